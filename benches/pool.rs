@@ -0,0 +1,42 @@
+//! Compares interning into a pre-sized [`Pool`] with interning into a pool
+//! that grows as items are added.
+//!
+//! Run with `cargo bench --bench pool`.
+
+use std::{hint::black_box, time::Instant};
+
+use resolvo::utils::Pool;
+use version_ranges::Ranges;
+
+const INTERNS: usize = 100_000;
+const ITERATIONS: u32 = 10;
+
+fn intern(pool: &Pool<Ranges<u32>>) {
+    for i in 0..INTERNS {
+        let name = pool.intern_package_name(format!("package-{i}"));
+        let version_set = pool.intern_version_set(name, Ranges::singleton(i as u32));
+        let solvable = pool.intern_solvable(name, i as u32);
+        let string = pool.intern_string(format!("extra-{i}"));
+        black_box((version_set, solvable, string));
+    }
+}
+
+fn bench(label: &str, create: impl Fn() -> Pool<Ranges<u32>>) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let pool = create();
+        intern(&pool);
+        black_box(pool);
+    }
+    println!(
+        "{label:<10} {:?} per {INTERNS} interns",
+        start.elapsed() / ITERATIONS
+    );
+}
+
+fn main() {
+    bench("default", Pool::new);
+    bench("pre-sized", || {
+        Pool::with_capacity(INTERNS, INTERNS, INTERNS, INTERNS)
+    });
+}
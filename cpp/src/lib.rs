@@ -102,19 +102,14 @@ pub struct ConditionalRequirement {
     pub requirement: Requirement,
 }
 
-impl From<resolvo::ConditionalRequirement> for ConditionalRequirement {
-    fn from(value: resolvo::ConditionalRequirement) -> Self {
-        Self {
-            condition: value.condition.into(),
-            requirement: value.requirement.into(),
-        }
-    }
-}
-
 impl From<ConditionalRequirement> for resolvo::ConditionalRequirement {
     fn from(value: ConditionalRequirement) -> Self {
+        let condition: Option<VersionSetId> = value.condition.into();
         Self {
-            condition: value.condition.into(),
+            conditions: condition
+                .map(|condition| resolvo::Condition::VersionSetId(condition.into()))
+                .into_iter()
+                .collect(),
             requirement: value.requirement.into(),
         }
     }
@@ -137,6 +132,21 @@ pub enum Requirement {
     /// cbindgen:derive-eq
     /// cbindgen:derive-neq
     Union(VersionSetUnionId),
+    /// Specifies a dependency on at least `n` solvables that each belong to any of the version
+    /// sets contained in the union. The solvables have to belong to different packages.
+    /// cbindgen:derive-eq
+    /// cbindgen:derive-neq
+    AtLeast {
+        /// The number of solvables that have to be selected.
+        n: usize,
+        /// The version sets the solvables have to match.
+        version_sets: VersionSetUnionId,
+    },
+    /// Specifies a dependency on exactly one solvable that belongs to any of the version sets
+    /// contained in the union.
+    /// cbindgen:derive-eq
+    /// cbindgen:derive-neq
+    ExactlyOne(VersionSetUnionId),
 }
 
 impl From<resolvo::Requirement> for crate::Requirement {
@@ -144,6 +154,11 @@ impl From<resolvo::Requirement> for crate::Requirement {
         match value {
             resolvo::Requirement::Single(id) => Requirement::Single(id.into()),
             resolvo::Requirement::Union(id) => Requirement::Union(id.into()),
+            resolvo::Requirement::AtLeast { n, version_sets } => Requirement::AtLeast {
+                n,
+                version_sets: version_sets.into(),
+            },
+            resolvo::Requirement::ExactlyOne(id) => Requirement::ExactlyOne(id.into()),
         }
     }
 }
@@ -153,6 +168,11 @@ impl From<crate::Requirement> for resolvo::Requirement {
         match value {
             Requirement::Single(id) => resolvo::Requirement::Single(id.into()),
             Requirement::Union(id) => resolvo::Requirement::Union(id.into()),
+            Requirement::AtLeast { n, version_sets } => resolvo::Requirement::AtLeast {
+                n,
+                version_sets: version_sets.into(),
+            },
+            Requirement::ExactlyOne(id) => resolvo::Requirement::ExactlyOne(id.into()),
         }
     }
 }
@@ -462,6 +482,21 @@ impl<'d> resolvo::Interner for &'d DependencyProvider {
         result
     }
 
+    fn display_condition(&self, condition: resolvo::Condition) -> impl Display + '_ {
+        match condition {
+            resolvo::Condition::VersionSetId(version_set) => format!(
+                "{} {}",
+                self.display_name(self.version_set_name(version_set)),
+                self.display_version_set(version_set)
+            ),
+            resolvo::Condition::Extra(extra) => self.display_string(extra).to_string(),
+            resolvo::Condition::Requirement(requirement) => requirement.display(self).to_string(),
+            resolvo::Condition::Marker(marker) => {
+                format!("marker '{}'", self.display_string(marker))
+            }
+        }
+    }
+
     fn version_set_name(&self, version_set: resolvo::VersionSetId) -> resolvo::NameId {
         unsafe { (self.version_set_name)(self.data, version_set.into()) }.into()
     }
@@ -512,23 +547,23 @@ impl<'d> resolvo::DependencyProvider for &'d DependencyProvider {
         };
         unsafe { (self.get_candidates)(self.data, name.into(), NonNull::from(&mut candidates)) };
 
+        let mut result =
+            resolvo::Candidates::new(candidates.candidates.into_iter().map(Into::into).collect());
         unsafe {
-            Some(resolvo::Candidates {
-                candidates: candidates.candidates.into_iter().map(Into::into).collect(),
-                favored: candidates.favored.as_ref().copied().map(Into::into),
-                locked: candidates.locked.as_ref().copied().map(Into::into),
-                hint_dependencies_available: candidates
-                    .hint_dependencies_available
-                    .into_iter()
-                    .map(Into::into)
-                    .collect(),
-                excluded: candidates
-                    .excluded
-                    .iter()
-                    .map(|excluded| (excluded.solvable.into(), excluded.reason.into()))
-                    .collect(),
-            })
+            result.favored = candidates.favored.as_ref().copied().map(Into::into);
+            result.locked = candidates.locked.as_ref().copied().map(Into::into);
         }
+        result.hint_dependencies_available = candidates
+            .hint_dependencies_available
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        result.excluded = candidates
+            .excluded
+            .iter()
+            .map(|excluded| (excluded.solvable.into(), excluded.reason.into()))
+            .collect();
+        Some(result)
     }
 
     async fn sort_candidates(
@@ -558,6 +593,7 @@ impl<'d> resolvo::DependencyProvider for &'d DependencyProvider {
                 .into_iter()
                 .map(Into::into)
                 .collect(),
+            conditional_constrains: Vec::new(),
         })
     }
 }
@@ -0,0 +1,190 @@
+//! Computes the number of packages a solvable pulls in, independently of the
+//! solver.
+
+use std::rc::Rc;
+
+use crate::{
+    internal::hash::{HashMap, HashSet},
+    runtime::{AsyncRuntime, NowOrNeverRuntime},
+    solver::filter_candidates,
+    Condition, ConditionalRequirement, Dependencies, DependencyProvider, NameId, SolvableId,
+    VersionSetId,
+};
+
+/// Returns the number of distinct packages that are transitively reachable
+/// from `solvable` through the requirements returned by
+/// [`DependencyProvider::get_dependencies`], not counting the package of
+/// `solvable` itself.
+///
+/// This is an upper bound on the number of packages that selecting
+/// `solvable` pulls into a solution: every candidate of a requirement is
+/// followed, not just the one the solver would pick. Requirements that are
+/// only active under a condition other than a holding marker are not
+/// followed, and neither are the dependencies of excluded candidates or of
+/// solvables whose dependencies are unknown.
+///
+/// Use a [`DependencyClosure`] to compute the sizes of several solvables, for
+/// instance all candidates of a package, which shares the work between them.
+///
+/// The futures of the `provider` are evaluated with the [`NowOrNeverRuntime`],
+/// so they must not yield.
+pub fn closure_size<D: DependencyProvider>(solvable: SolvableId, provider: &D) -> usize {
+    DependencyClosure::new(provider).size(solvable)
+}
+
+/// Computes the sizes of dependency closures, see [`closure_size`].
+///
+/// The closures of the solvables that are visited along the way are
+/// memoized, so the shared parts of the closures of different solvables are
+/// only computed once.
+pub struct DependencyClosure<'a, D: DependencyProvider> {
+    provider: &'a D,
+    closures: HashMap<SolvableId, Rc<HashSet<NameId>>>,
+    requirements: HashMap<SolvableId, Rc<Vec<VersionSetId>>>,
+    available: HashMap<NameId, Rc<Vec<SolvableId>>>,
+    candidates: HashMap<VersionSetId, Rc<Vec<SolvableId>>>,
+    in_progress: HashSet<SolvableId>,
+}
+
+impl<'a, D: DependencyProvider> DependencyClosure<'a, D> {
+    /// Constructs a new instance that computes the closures of the solvables
+    /// of the `provider`.
+    pub fn new(provider: &'a D) -> Self {
+        Self {
+            provider,
+            closures: HashMap::default(),
+            requirements: HashMap::default(),
+            available: HashMap::default(),
+            candidates: HashMap::default(),
+            in_progress: HashSet::default(),
+        }
+    }
+
+    /// Returns the size of the closure of `solvable`, see [`closure_size`].
+    pub fn size(&mut self, solvable: SolvableId) -> usize {
+        let name = self.name(solvable);
+        let (closure, _) = self.closure(solvable);
+        closure.iter().filter(|&&other| other != name).count()
+    }
+
+    /// Returns the names in the closure of `solvable`, including its own
+    /// name, and whether the closure is complete.
+    ///
+    /// The closure is incomplete if it reaches a solvable whose closure is
+    /// still being computed higher up, in which case it lacks whatever is
+    /// only reachable through that solvable. Only complete closures are
+    /// memoized. The closure of the solvable at the top is always complete,
+    /// because all the solvables being computed are on its path.
+    fn closure(&mut self, solvable: SolvableId) -> (Rc<HashSet<NameId>>, bool) {
+        if let Some(closure) = self.closures.get(&solvable) {
+            return (closure.clone(), true);
+        }
+        if !self.in_progress.insert(solvable) {
+            return (Rc::default(), false);
+        }
+
+        let mut closure = HashSet::default();
+        closure.insert(self.name(solvable));
+        let mut complete = true;
+
+        for &version_set in self.requirements(solvable).iter() {
+            closure.insert(
+                self.provider
+                    .canonical_name(self.provider.version_set_name(version_set)),
+            );
+            for &candidate in self.candidates(version_set).iter() {
+                let (candidate_closure, candidate_complete) = self.closure(candidate);
+                closure.extend(candidate_closure.iter().copied());
+                complete &= candidate_complete;
+            }
+        }
+
+        self.in_progress.remove(&solvable);
+        let closure = Rc::new(closure);
+        if complete {
+            self.closures.insert(solvable, closure.clone());
+        }
+        (closure, complete)
+    }
+
+    /// Returns the version sets of the active requirements of `solvable`.
+    fn requirements(&mut self, solvable: SolvableId) -> Rc<Vec<VersionSetId>> {
+        if let Some(requirements) = self.requirements.get(&solvable) {
+            return requirements.clone();
+        }
+
+        let requirements =
+            match NowOrNeverRuntime.block_on(self.provider.get_dependencies(solvable)) {
+                Dependencies::Known(dependencies) => dependencies
+                    .requirements
+                    .iter()
+                    .filter(|requirement| self.is_active(requirement))
+                    .flat_map(|requirement| requirement.requirement_version_sets(self.provider))
+                    .collect(),
+                Dependencies::Unknown(_) => Vec::new(),
+            };
+
+        let requirements = Rc::new(requirements);
+        self.requirements.insert(solvable, requirements.clone());
+        requirements
+    }
+
+    /// Returns the candidates that match `version_set` and are not excluded.
+    fn candidates(&mut self, version_set: VersionSetId) -> Rc<Vec<SolvableId>> {
+        if let Some(candidates) = self.candidates.get(&version_set) {
+            return candidates.clone();
+        }
+
+        let available = self.available(self.provider.version_set_name(version_set));
+        let candidates = Rc::new(NowOrNeverRuntime.block_on(filter_candidates(
+            self.provider,
+            &available,
+            version_set,
+            false,
+        )));
+        self.candidates.insert(version_set, candidates.clone());
+        candidates
+    }
+
+    /// Returns the candidates of the package with the given name that are not
+    /// excluded.
+    fn available(&mut self, name: NameId) -> Rc<Vec<SolvableId>> {
+        if let Some(available) = self.available.get(&name) {
+            return available.clone();
+        }
+
+        let available = match NowOrNeverRuntime.block_on(self.provider.get_candidates(name)) {
+            Some(candidates) => candidates
+                .candidates
+                .iter()
+                .copied()
+                .filter(|candidate| {
+                    !candidates
+                        .excluded
+                        .iter()
+                        .any(|&(excluded, _)| excluded == *candidate)
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let available = Rc::new(available);
+        self.available.insert(name, available.clone());
+        available
+    }
+
+    fn name(&self, solvable: SolvableId) -> NameId {
+        self.provider
+            .canonical_name(self.provider.solvable_name(solvable))
+    }
+
+    fn is_active(&self, requirement: &ConditionalRequirement) -> bool {
+        requirement
+            .conditions
+            .iter()
+            .all(|&condition| match condition {
+                Condition::Marker(marker) => self.provider.evaluate_marker(marker),
+                _ => false,
+            })
+    }
+}
@@ -1,9 +1,14 @@
 //! Types to examine why a problem was unsatisfiable, and to report the causes
 //! to the user.
 
-use std::{collections::HashSet, fmt, fmt::Formatter, hash::Hash, rc::Rc};
+use std::{
+    cmp::Reverse,
+    fmt,
+    fmt::{Display, Formatter},
+    hash::Hash,
+    rc::Rc,
+};
 
-use ahash::HashMap;
 use itertools::Itertools;
 use petgraph::{
     graph::{DiGraph, EdgeIndex, EdgeReference, NodeIndex},
@@ -14,8 +19,13 @@ use petgraph::{
 use crate::{
     internal::{
         arena::ArenaId,
-        id::{ClauseId, SolvableId, SolvableOrRootId, StringId, VersionSetId},
-    }, requirement::Condition, runtime::AsyncRuntime, solver::{clause::Clause, variable_map::VariableOrigin, Solver}, DependencyProvider, Interner, Requirement
+        hash::{HashMap, HashSet},
+        id::{ClauseId, NameId, SolvableId, SolvableOrRootId, StringId, VariableId, VersionSetId},
+    },
+    requirement::Condition,
+    runtime::AsyncRuntime,
+    solver::{clause::Clause, variable_map::VariableOrigin, Solver},
+    DependencyProvider, Interner, ProofStep, Requirement,
 };
 
 /// Represents the cause of the solver being unable to find a solution
@@ -23,15 +33,22 @@ use crate::{
 pub struct Conflict {
     /// The clauses involved in an unsatisfiable conflict
     clauses: Vec<ClauseId>,
+    /// The proof of unsatisfiability, if proof logging was enabled
+    pub(crate) proof: Vec<ProofStep>,
 }
 
 impl Conflict {
     pub(crate) fn default() -> Self {
         Self {
             clauses: Vec::new(),
+            proof: Vec::new(),
         }
     }
 
+    pub(crate) fn clauses(&self) -> &[ClauseId] {
+        &self.clauses
+    }
+
     pub(crate) fn add_clause(&mut self, clause_id: ClauseId) {
         if !self.clauses.contains(&clause_id) {
             self.clauses.push(clause_id);
@@ -51,6 +68,7 @@ impl Conflict {
         let root_node = Self::add_node(&mut graph, &mut nodes, SolvableOrRootId::root());
         let unresolved_node = graph.add_node(ConflictNode::UnresolvedDependency);
         let mut last_node_by_name = HashMap::default();
+        let mut rejected_candidates = HashMap::default();
 
         for clause_id in &self.clauses {
             let clause = &solver.clauses.kinds[clause_id.to_usize()];
@@ -74,6 +92,14 @@ impl Conflict {
                     );
                 }
                 Clause::Learnt(..) => unreachable!(),
+                // Raw clauses bypass provenance, so there is nothing to explain them with.
+                Clause::Raw(_) => (),
+                // An extra is only ever active through a requirement of the package that
+                // declares it, which explains the conflict instead.
+                Clause::Extra(..) => (),
+                // The counter of an at least requirement is explained by the
+                // [`Clause::AtLeast`] clause of the requirement.
+                Clause::AtLeastCounter(..) => (),
                 &Clause::Requires(package_id, version_set_id) => {
                     let solvable = package_id
                         .as_solvable_or_root(&solver.variable_map)
@@ -87,6 +113,9 @@ impl Conflict {
                         tracing::trace!(
                             "{package_id:?} requires {version_set_id:?}, which has no candidates"
                         );
+                        rejected_candidates
+                            .entry(version_set_id)
+                            .or_insert_with(|| Self::rejected_candidates(solver, version_set_id));
                         graph.add_edge(
                             package_node,
                             unresolved_node,
@@ -106,6 +135,63 @@ impl Conflict {
                         }
                     }
                 }
+                &Clause::AtLeast(package_id, requirement, _) => {
+                    let solvable = package_id
+                        .as_solvable_or_root(&solver.variable_map)
+                        .expect("only solvables can be excluded");
+                    let package_node = Self::add_node(&mut graph, &mut nodes, solvable);
+
+                    let candidates = solver.async_runtime.block_on(solver.cache.get_or_cache_sorted_candidates(requirement)).unwrap_or_else(|_| {
+                        unreachable!("The version set was used in the solver, so it must have been cached. Therefore cancellation is impossible here and we cannot get an `Err(...)`")
+                    });
+                    if candidates.len() < requirement.min_count() {
+                        tracing::trace!(
+                            "{package_id:?} requires {requirement:?}, which has too few candidates"
+                        );
+                        rejected_candidates
+                            .entry(requirement)
+                            .or_insert_with(|| Self::rejected_candidates(solver, requirement));
+                        graph.add_edge(
+                            package_node,
+                            unresolved_node,
+                            ConflictEdge::Requires(requirement),
+                        );
+                    } else {
+                        for &candidate_id in candidates {
+                            tracing::trace!("{package_id:?} requires {candidate_id:?}");
+
+                            let candidate_node =
+                                Self::add_node(&mut graph, &mut nodes, candidate_id.into());
+                            graph.add_edge(
+                                package_node,
+                                candidate_node,
+                                ConflictEdge::Requires(requirement),
+                            );
+                        }
+                    }
+                }
+                Clause::AtMostOne(_, requirement, literals) => {
+                    // The last two literals are the candidates that cannot both be installed.
+                    let [.., first, second] = literals.as_slice() else {
+                        unreachable!("an at most one clause has two candidates")
+                    };
+                    let first_solvable = first
+                        .variable()
+                        .as_solvable(&solver.variable_map)
+                        .expect("only solvables can be candidates");
+                    let second_solvable = second
+                        .variable()
+                        .as_solvable(&solver.variable_map)
+                        .expect("only solvables can be candidates");
+                    let first_node = Self::add_node(&mut graph, &mut nodes, first_solvable.into());
+                    let second_node =
+                        Self::add_node(&mut graph, &mut nodes, second_solvable.into());
+                    graph.add_edge(
+                        first_node,
+                        second_node,
+                        ConflictEdge::Conflict(ConflictCause::ExactlyOne(*requirement)),
+                    );
+                }
                 &Clause::Lock(locked, forbidden) => {
                     let locked_solvable = locked
                         .as_solvable(&solver.variable_map)
@@ -115,9 +201,40 @@ impl Conflict {
                         .expect("only solvables can be excluded");
                     let node2_id =
                         Self::add_node(&mut graph, &mut nodes, forbidden_solvable.into());
-                    let conflict = ConflictCause::Locked(locked_solvable);
+                    let conflict = if solver.root_base.contains(&locked_solvable) {
+                        ConflictCause::Base(locked_solvable)
+                    } else if solver.root_pins.contains(&locked_solvable) {
+                        ConflictCause::Pinned(locked_solvable)
+                    } else {
+                        ConflictCause::Locked(locked_solvable)
+                    };
                     graph.add_edge(root_node, node2_id, ConflictEdge::Conflict(conflict));
                 }
+                &Clause::Pin(pinned) => {
+                    let pinned_solvable = pinned
+                        .as_solvable(&solver.variable_map)
+                        .expect("only solvables can be pinned");
+                    let pinned_node =
+                        Self::add_node(&mut graph, &mut nodes, pinned_solvable.into());
+                    let conflict = if solver.root_base.contains(&pinned_solvable) {
+                        ConflictCause::Base(pinned_solvable)
+                    } else {
+                        ConflictCause::Pinned(pinned_solvable)
+                    };
+                    graph.add_edge(root_node, pinned_node, ConflictEdge::Conflict(conflict));
+                }
+                &Clause::Forbidden(forbidden) => {
+                    let forbidden_solvable = forbidden
+                        .as_solvable(&solver.variable_map)
+                        .expect("only solvables can be forbidden");
+                    let forbidden_node =
+                        Self::add_node(&mut graph, &mut nodes, forbidden_solvable.into());
+                    let conflict = match solver.prohibited_name(forbidden_solvable) {
+                        Some(name) => ConflictCause::Prohibited(name),
+                        None => ConflictCause::Forbidden(forbidden_solvable),
+                    };
+                    graph.add_edge(root_node, forbidden_node, ConflictEdge::Conflict(conflict));
+                }
                 &Clause::ForbidMultipleInstances(instance1_id, instance2_id, _) => {
                     let solvable1 = instance1_id
                         .as_solvable_or_root(&solver.variable_map)
@@ -150,17 +267,15 @@ impl Conflict {
                     let package_node = Self::add_node(&mut graph, &mut nodes, package_solvable);
                     let dep_node = Self::add_node(&mut graph, &mut nodes, dependency_solvable);
 
-                    graph.add_edge(
-                        package_node,
-                        dep_node,
-                        ConflictEdge::Conflict(ConflictCause::Constrains(version_set_id)),
-                    );
+                    let conflict = match solver.policy_pin(version_set_id) {
+                        Some((name, reason)) if package_solvable.is_root() => {
+                            ConflictCause::PolicyPin(name, version_set_id, reason)
+                        }
+                        _ => ConflictCause::Constrains(version_set_id),
+                    };
+                    graph.add_edge(package_node, dep_node, ConflictEdge::Conflict(conflict));
                 }
-                Clause::Conditional(
-                    package_id,
-                    condition_variables,
-                    requirement,
-                ) => {
+                Clause::Conditional(package_id, condition_variables, requirement) => {
                     let solvable = package_id
                         .as_solvable_or_root(&solver.variable_map)
                         .expect("only solvables can be excluded");
@@ -181,12 +296,18 @@ impl Conflict {
                         tracing::trace!(
                             "{package_id:?} conditionally requires {requirement:?}, which has no candidates"
                         );
+                        rejected_candidates
+                            .entry(*requirement)
+                            .or_insert_with(|| Self::rejected_candidates(solver, *requirement));
                         graph.add_edge(
                             package_node,
                             unresolved_node,
                             ConflictEdge::ConditionalRequires(
                                 *requirement,
-                                condition_variables.iter().map(|(_, condition)| *condition).collect(),
+                                condition_variables
+                                    .iter()
+                                    .map(|(_, condition)| *condition)
+                                    .collect(),
                             ),
                         );
                     } else {
@@ -203,7 +324,10 @@ impl Conflict {
                                 candidate_node,
                                 ConflictEdge::ConditionalRequires(
                                     *requirement,
-                                    condition_variables.iter().map(|(_, condition)| *condition).collect(),
+                                    condition_variables
+                                        .iter()
+                                        .map(|(_, condition)| *condition)
+                                        .collect(),
                                 ),
                             );
                         }
@@ -224,7 +348,7 @@ impl Conflict {
         };
 
         // Sanity check: all nodes are reachable from root
-        let mut visited_nodes = HashSet::new();
+        let mut visited_nodes = HashSet::default();
         let mut bfs = Bfs::new(&graph, root_node);
         while let Some(nx) = bfs.next(&graph) {
             visited_nodes.insert(nx);
@@ -235,9 +359,33 @@ impl Conflict {
             graph,
             root_node,
             unresolved_node,
+            rejected_candidates,
         }
     }
 
+    /// Returns the candidates of the packages referenced by `requirement` that
+    /// were available but did not match it.
+    fn rejected_candidates<D: DependencyProvider, RT: AsyncRuntime>(
+        solver: &Solver<D, RT>,
+        requirement: Requirement,
+    ) -> Vec<SolvableId> {
+        requirement
+            .version_sets(solver.provider())
+            .flat_map(|version_set_id| {
+                solver
+                    .async_runtime
+                    .block_on(
+                        solver
+                            .cache
+                            .get_or_cache_non_matching_candidates(version_set_id),
+                    )
+                    .map(|candidates| candidates.to_vec())
+                    .unwrap_or_default()
+            })
+            .unique()
+            .collect()
+    }
+
     fn add_node(
         graph: &mut DiGraph<ConflictNode, ConflictEdge>,
         nodes: &mut HashMap<SolvableOrRootId, NodeIndex>,
@@ -248,13 +396,465 @@ impl Conflict {
             .or_insert_with(|| graph.add_node(ConflictNode::Solvable(solvable_id)))
     }
 
+    /// Returns all the version sets that are referenced by the clauses
+    /// involved in the conflict. This includes the version sets of
+    /// requirements, of the conditions of conditional requirements, and of
+    /// constraints.
+    pub fn involved_version_sets<D: DependencyProvider, RT: AsyncRuntime>(
+        &self,
+        solver: &Solver<D, RT>,
+    ) -> std::collections::HashSet<VersionSetId> {
+        let mut version_sets = std::collections::HashSet::new();
+        for clause_id in &self.clauses {
+            match &solver.clauses.kinds[clause_id.to_usize()] {
+                Clause::Requires(_, requirement)
+                | Clause::AtLeast(_, requirement, _)
+                | Clause::AtMostOne(_, requirement, _) => {
+                    version_sets.extend(requirement.version_sets(solver.provider()));
+                }
+                Clause::Conditional(_, condition_variables, requirement) => {
+                    version_sets.extend(requirement.version_sets(solver.provider()));
+                    version_sets.extend(
+                        condition_variables
+                            .iter()
+                            .flat_map(|(_, condition)| condition.version_sets(solver.provider())),
+                    );
+                }
+                &Clause::Constrains(_, _, version_set_id) => {
+                    version_sets.insert(version_set_id);
+                }
+                _ => {}
+            }
+        }
+        version_sets
+    }
+
+    /// Returns a proof that the problem is unsatisfiable in the DRAT format.
+    ///
+    /// The proof refers to the clauses returned by [`Solver::cnf`] for the
+    /// solve that produced this conflict. Together they can be checked by an
+    /// independent proof checker like `drat-trim`. The proof is only recorded
+    /// if the solver was created with [`crate::SolverBuilder::with_proof_logging`],
+    /// otherwise it is empty.
+    pub fn drat_proof(&self) -> &[ProofStep] {
+        &self.proof
+    }
+
     /// Display a user-friendly error explaining the conflict
     pub fn display_user_friendly<'a, D: DependencyProvider, RT: AsyncRuntime>(
         &self,
         solver: &'a Solver<D, RT>,
     ) -> DisplayUnsat<'a, D> {
+        self.display_with_messages(solver, &DefaultConflictMessages)
+    }
+
+    /// Display a user-friendly error explaining the conflict, phrased with the
+    /// given [`ConflictMessages`]. This can be used to translate the error.
+    pub fn display_with_messages<
+        'a,
+        D: DependencyProvider,
+        RT: AsyncRuntime,
+        M: ConflictMessages,
+    >(
+        &self,
+        solver: &'a Solver<D, RT>,
+        messages: &'a M,
+    ) -> DisplayUnsat<'a, D, M> {
         let graph = self.graph(solver);
-        DisplayUnsat::new(graph, solver.provider())
+        DisplayUnsat::new(graph, solver.provider(), messages)
+    }
+
+    /// Returns the reasons for the conflict, one for every clause involved in
+    /// it. The reasons whose clauses were involved in the most conflicts while
+    /// solving come first, because they are the most relevant to resolve the
+    /// conflict. Among reasons with the same activity, the reasons that are
+    /// imposed by the problem itself, like its requirements, come first,
+    /// followed by the reasons that stem from the dependencies of solvables.
+    ///
+    /// The reasons are computed while iterating, so unlike
+    /// [`Self::display_user_friendly`] this does not process the whole
+    /// conflict if only the first few reasons are shown.
+    pub fn reasons<'a, D: DependencyProvider, RT: AsyncRuntime>(
+        &'a self,
+        solver: &'a Solver<D, RT>,
+    ) -> impl Iterator<Item = ConflictReason> + 'a {
+        let root = solver.variable_map.root();
+        let from_problem = |clause_id: ClauseId| match solver.clauses.kinds[clause_id.to_usize()] {
+            Clause::Requires(parent, _)
+            | Clause::AtLeast(parent, _, _)
+            | Clause::AtMostOne(parent, _, _)
+            | Clause::Conditional(parent, _, _)
+            | Clause::Constrains(parent, _, _) => parent == root,
+            Clause::Lock(..) | Clause::Pin(_) | Clause::Forbidden(_) => true,
+            _ => false,
+        };
+        let mut clauses = self.clauses.clone();
+        clauses.sort_by_key(|&clause_id| {
+            let activity = solver
+                .clause_activity
+                .get(&clause_id)
+                .copied()
+                .unwrap_or_default();
+            (Reverse(activity), !from_problem(clause_id))
+        });
+        clauses
+            .into_iter()
+            .filter_map(move |clause_id| Self::reason(solver, clause_id))
+    }
+
+    /// Returns changes to the problem that may resolve the conflict, each of
+    /// which refers to a requirement or constraint of the problem.
+    ///
+    /// A requirement of the problem on a single version set that is involved
+    /// in the conflict is suggested to be relaxed to include the candidate
+    /// that comes closest to matching it, see
+    /// [`crate::VersionSetBounds::closest_candidate`]. This requires the
+    /// interner to describe the bounds of the version set. Constraints of the
+    /// problem that are involved in the conflict are suggested to be removed,
+    /// except for policy pins (see [`crate::Problem::policy_pins`]), and depth
+    /// constraints (see [`crate::Problem::depth_constraints`]) to
+    /// be lifted for their package.
+    ///
+    /// Every suggestion removes one of the causes of the conflict, but the
+    /// problem may still be unsolvable for other reasons after applying it.
+    pub fn suggestions<D: DependencyProvider, RT: AsyncRuntime>(
+        &self,
+        solver: &Solver<D, RT>,
+    ) -> Vec<Suggestion> {
+        let interner = solver.provider();
+        let root = solver.variable_map.root();
+        let mut suggestions = Vec::new();
+        for clause_id in &self.clauses {
+            let suggestion = match solver.clauses.kinds[clause_id.to_usize()] {
+                Clause::Requires(parent, requirement)
+                | Clause::Conditional(parent, _, requirement)
+                    if parent == root =>
+                {
+                    let Requirement::Single(version_set) = requirement else {
+                        continue;
+                    };
+                    let Some(bounds) = interner.version_set_bounds(version_set) else {
+                        continue;
+                    };
+                    let rejected = Self::rejected_candidates(solver, requirement);
+                    let Some(candidate) = bounds.closest_candidate(&rejected, interner) else {
+                        continue;
+                    };
+                    Suggestion::RelaxRequirement {
+                        requirement,
+                        candidate,
+                    }
+                }
+                // Policy pins are enforced like constraints, but are not for the user to
+                // remove.
+                Clause::Constrains(parent, _, constraint)
+                    if parent == root
+                        && solver.root_constraints.contains(&constraint)
+                        && solver.policy_pin(constraint).is_none() =>
+                {
+                    Suggestion::RemoveConstraint(constraint)
+                }
+                Clause::Constrains(parent, _, constraint)
+                    if parent != root
+                        && solver
+                            .root_depth_constraints
+                            .iter()
+                            .any(|&(version_set, _)| version_set == constraint) =>
+                {
+                    Suggestion::LiftDepthConstraint {
+                        name: interner.version_set_name(constraint),
+                        constraint,
+                    }
+                }
+                _ => continue,
+            };
+            if !suggestions.contains(&suggestion) {
+                suggestions.push(suggestion);
+            }
+        }
+        suggestions
+    }
+
+    /// Returns the [`ConflictReason`] for a clause of the conflict.
+    fn reason<D: DependencyProvider, RT: AsyncRuntime>(
+        solver: &Solver<D, RT>,
+        clause_id: ClauseId,
+    ) -> Option<ConflictReason> {
+        let solvable = |variable: VariableId| {
+            variable
+                .as_solvable(&solver.variable_map)
+                .expect("only solvables can be involved in a conflict")
+        };
+        let candidates = |requirement: Requirement| {
+            solver
+                .async_runtime
+                .block_on(solver.cache.get_or_cache_sorted_candidates(requirement))
+                .map(<[SolvableId]>::to_vec)
+                .unwrap_or_default()
+        };
+
+        let reason = match &solver.clauses.kinds[clause_id.to_usize()] {
+            Clause::InstallRoot
+            | Clause::Learnt(_)
+            | Clause::Raw(_)
+            | Clause::Extra(..)
+            | Clause::AtLeastCounter(..) => return None,
+            &Clause::Requires(parent, requirement) | &Clause::AtLeast(parent, requirement, _) => {
+                ConflictReason::Requires {
+                    solvable: parent.as_solvable(&solver.variable_map),
+                    requirement,
+                    conditions: Vec::new(),
+                    candidates: candidates(requirement),
+                }
+            }
+            Clause::AtMostOne(parent, requirement, literals) => {
+                let [.., first, second] = literals.as_slice() else {
+                    unreachable!("an at most one clause has two candidates")
+                };
+                ConflictReason::AtMostOne {
+                    solvable: parent.as_solvable(&solver.variable_map),
+                    requirement: *requirement,
+                    candidates: (solvable(first.variable()), solvable(second.variable())),
+                }
+            }
+            Clause::Conditional(parent, condition_variables, requirement) => {
+                ConflictReason::Requires {
+                    solvable: parent.as_solvable(&solver.variable_map),
+                    requirement: *requirement,
+                    conditions: condition_variables
+                        .iter()
+                        .map(|&(_, condition)| condition)
+                        .collect(),
+                    candidates: candidates(*requirement),
+                }
+            }
+            &Clause::Constrains(parent, constrained, constraint) => ConflictReason::Constrains {
+                solvable: parent.as_solvable(&solver.variable_map),
+                constrained: solvable(constrained),
+                constraint,
+            },
+            &Clause::Excluded(excluded, reason) => ConflictReason::Excluded {
+                solvable: solvable(excluded),
+                reason,
+            },
+            &Clause::Pin(base) | &Clause::Lock(base, _)
+                if solver.root_base.contains(&solvable(base)) =>
+            {
+                ConflictReason::Base(solvable(base))
+            }
+            &Clause::Pin(pinned) => ConflictReason::Pinned(solvable(pinned)),
+            &Clause::Lock(locked, forbidden) => ConflictReason::Locked {
+                locked: solvable(locked),
+                forbidden: solvable(forbidden),
+            },
+            &Clause::Forbidden(forbidden) => match solver.prohibited_name(solvable(forbidden)) {
+                Some(name) => ConflictReason::Prohibited {
+                    solvable: solvable(forbidden),
+                    name,
+                },
+                None => ConflictReason::Forbidden(solvable(forbidden)),
+            },
+            &Clause::ForbidMultipleInstances(instance, other, _) => {
+                let VariableOrigin::ForbidMultiple(name) =
+                    solver.variable_map.origin(other.variable())
+                else {
+                    unreachable!("expected only forbid variables")
+                };
+                ConflictReason::ForbidMultipleInstances {
+                    solvable: solvable(instance),
+                    name,
+                }
+            }
+        };
+        Some(reason)
+    }
+}
+
+/// A single reason for a [`Conflict`], see [`Conflict::reasons`].
+///
+/// Reasons that refer to a solvable use `None` if the reason is imposed by the
+/// problem itself instead of by the dependencies of a solvable.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConflictReason {
+    /// A requirement that must be satisfied.
+    Requires {
+        /// The solvable that has the requirement.
+        solvable: Option<SolvableId>,
+        /// The requirement.
+        requirement: Requirement,
+        /// The conditions under which the requirement applies.
+        conditions: Vec<Condition>,
+        /// The candidates that satisfy the requirement, possibly none.
+        candidates: Vec<SolvableId>,
+    },
+    /// An "exactly one of" requirement that forbids installing two of its
+    /// candidates at the same time, see [`Requirement::ExactlyOne`].
+    AtMostOne {
+        /// The solvable that has the requirement.
+        solvable: Option<SolvableId>,
+        /// The requirement.
+        requirement: Requirement,
+        /// The candidates that cannot both be installed.
+        candidates: (SolvableId, SolvableId),
+    },
+    /// A constraint that forbids a solvable.
+    Constrains {
+        /// The solvable that has the constraint.
+        solvable: Option<SolvableId>,
+        /// The solvable that does not match the constraint.
+        constrained: SolvableId,
+        /// The constraint.
+        constraint: VersionSetId,
+    },
+    /// A solvable that was excluded by the dependency provider.
+    Excluded {
+        /// The excluded solvable.
+        solvable: SolvableId,
+        /// The reason why the solvable was excluded.
+        reason: StringId,
+    },
+    /// A solvable that is pinned by the problem.
+    Pinned(SolvableId),
+    /// A solvable of the base environment of the problem, see
+    /// [`crate::Problem::base`].
+    Base(SolvableId),
+    /// A locked solvable that forbids another solvable of the same package.
+    Locked {
+        /// The locked solvable.
+        locked: SolvableId,
+        /// The solvable that cannot be installed.
+        forbidden: SolvableId,
+    },
+    /// A solvable that is forbidden by the problem.
+    Forbidden(SolvableId),
+    /// A solvable of a package that the problem prohibits, see
+    /// [`crate::Problem::prohibited_names`].
+    Prohibited {
+        /// The solvable that cannot be installed.
+        solvable: SolvableId,
+        /// The name of the prohibited package.
+        name: NameId,
+    },
+    /// Only one solvable of a package can be installed.
+    ForbidMultipleInstances {
+        /// One of the solvables of the package.
+        solvable: SolvableId,
+        /// The name of the package.
+        name: NameId,
+    },
+}
+
+impl ConflictReason {
+    /// Returns an object that can be used to display the reason in a
+    /// user-friendly way.
+    pub fn display<'i>(&'i self, interner: &'i impl Interner) -> impl Display + 'i {
+        DisplayConflictReason {
+            reason: self,
+            interner,
+        }
+    }
+}
+
+struct DisplayConflictReason<'i, I: Interner> {
+    reason: &'i ConflictReason,
+    interner: &'i I,
+}
+
+impl<I: Interner> Display for DisplayConflictReason<'_, I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let interner = self.interner;
+        let subject = |solvable: Option<SolvableId>| match solvable {
+            Some(solvable) => interner.display_solvable(solvable).to_string(),
+            None => "the problem".to_string(),
+        };
+        match self.reason {
+            ConflictReason::Requires {
+                solvable,
+                requirement,
+                conditions,
+                candidates,
+            } => {
+                write!(
+                    f,
+                    "{} requires {}",
+                    subject(*solvable),
+                    requirement.display(interner)
+                )?;
+                if !conditions.is_empty() {
+                    write!(
+                        f,
+                        " if {}",
+                        conditions
+                            .iter()
+                            .map(|&condition| interner.display_condition(condition))
+                            .format(" and ")
+                    )?;
+                }
+                if candidates.is_empty() {
+                    write!(f, ", for which no candidates were found")?;
+                }
+                Ok(())
+            }
+            ConflictReason::AtMostOne {
+                solvable,
+                requirement,
+                candidates: (first, second),
+            } => write!(
+                f,
+                "{} requires {}, so {} cannot be installed alongside {}",
+                subject(*solvable),
+                requirement.display(interner),
+                interner.display_solvable(*first),
+                interner.display_solvable(*second)
+            ),
+            ConflictReason::Constrains {
+                solvable,
+                constrained,
+                constraint,
+            } => write!(
+                f,
+                "{} constrains {} {}, which excludes {}",
+                subject(*solvable),
+                interner.display_name(interner.version_set_name(*constraint)),
+                interner.display_version_set(*constraint),
+                interner.display_solvable(*constrained)
+            ),
+            ConflictReason::Excluded { solvable, reason } => write!(
+                f,
+                "{} is excluded because {}",
+                interner.display_solvable(*solvable),
+                interner.display_string(*reason)
+            ),
+            ConflictReason::Pinned(solvable) => {
+                write!(f, "{} is pinned", interner.display_solvable(*solvable))
+            }
+            ConflictReason::Base(solvable) => write!(
+                f,
+                "{} is part of the base environment",
+                interner.display_solvable(*solvable)
+            ),
+            ConflictReason::Locked { locked, forbidden } => write!(
+                f,
+                "{} is locked, which forbids {}",
+                interner.display_solvable(*locked),
+                interner.display_solvable(*forbidden)
+            ),
+            ConflictReason::Forbidden(solvable) => {
+                write!(f, "{} is forbidden", interner.display_solvable(*solvable))
+            }
+            ConflictReason::Prohibited { solvable, name } => write!(
+                f,
+                "{} is forbidden because {} is prohibited",
+                interner.display_solvable(*solvable),
+                interner.display_name(*name)
+            ),
+            ConflictReason::ForbidMultipleInstances { solvable, name } => write!(
+                f,
+                "{} cannot be installed alongside another version of {}",
+                interner.display_solvable(*solvable),
+                interner.display_name(*name)
+            ),
+        }
     }
 }
 
@@ -321,15 +921,99 @@ impl ConflictEdge {
     }
 }
 
+/// A change to the problem that may resolve a [`Conflict`], see
+/// [`Conflict::suggestions`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Suggestion {
+    /// Relax a requirement of the problem so that it includes `candidate`,
+    /// the candidate that comes closest to matching it.
+    RelaxRequirement {
+        /// The requirement of the problem.
+        requirement: Requirement,
+        /// The candidate the requirement should include.
+        candidate: SolvableId,
+    },
+    /// Remove a constraint of the problem.
+    RemoveConstraint(VersionSetId),
+    /// Lift a depth constraint of the problem for a package, see
+    /// [`crate::Problem::depth_constraints`], for instance to allow a
+    /// prerelease of a package deep in the dependency tree.
+    LiftDepthConstraint {
+        /// The name of the package.
+        name: NameId,
+        /// The depth constraint.
+        constraint: VersionSetId,
+    },
+}
+
+impl Suggestion {
+    /// Returns an object that can be used to display the suggestion in a
+    /// user-friendly way.
+    pub fn display<'i>(&'i self, interner: &'i impl Interner) -> impl Display + 'i {
+        DisplaySuggestion {
+            suggestion: self,
+            interner,
+        }
+    }
+}
+
+struct DisplaySuggestion<'i, I: Interner> {
+    suggestion: &'i Suggestion,
+    interner: &'i I,
+}
+
+impl<I: Interner> Display for DisplaySuggestion<'_, I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let interner = self.interner;
+        match *self.suggestion {
+            Suggestion::RelaxRequirement {
+                requirement,
+                candidate,
+            } => write!(
+                f,
+                "relax the requirement {} to include {}",
+                requirement.display(interner),
+                interner.display_solvable(candidate)
+            ),
+            Suggestion::RemoveConstraint(constraint) => write!(
+                f,
+                "remove the constraint {} {}",
+                interner.display_name(interner.version_set_name(constraint)),
+                interner.display_version_set(constraint)
+            ),
+            Suggestion::LiftDepthConstraint { name, constraint } => write!(
+                f,
+                "allow {} outside of {} deep in the dependency tree",
+                interner.display_name(name),
+                interner.display_version_set(constraint)
+            ),
+        }
+    }
+}
+
 /// Conflict causes
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub(crate) enum ConflictCause {
     /// The solvable is locked
     Locked(SolvableId),
+    /// The solvable is pinned
+    Pinned(SolvableId),
+    /// The solvable is part of the base environment
+    Base(SolvableId),
+    /// The solvable is forbidden by the problem
+    Forbidden(SolvableId),
+    /// The package of the solvable is prohibited by the problem
+    Prohibited(NameId),
     /// The target node is constrained by the specified version set
     Constrains(VersionSetId),
+    /// The package is pinned to the version set by a policy with the given
+    /// reason
+    PolicyPin(NameId, VersionSetId, StringId),
     /// It is forbidden to install multiple instances of the same dependency
     ForbidMultipleInstances,
+    /// The target node is another candidate of the "exactly one of"
+    /// requirement
+    ExactlyOne(Requirement),
     /// The node was excluded
     Excluded,
 }
@@ -342,8 +1026,14 @@ pub(crate) enum ConflictCause {
 /// - They all have the same name
 /// - They all have the same predecessor nodes
 /// - They all have the same successor nodes
+///
+/// When [`DisplayUnsat::merge_by_display`] is enabled, nodes with the same name
+/// and predecessors are also merged if the interner displays them the same.
 pub(crate) struct MergedConflictNode {
     pub ids: Vec<SolvableId>,
+    /// The solvables that are passed to [`Interner::display_merged_solvables`],
+    /// which omits solvables that display the same as one before them.
+    pub display_ids: Vec<SolvableId>,
 }
 
 /// Graph representation of [`Conflict`]
@@ -351,13 +1041,34 @@ pub(crate) struct MergedConflictNode {
 /// The root of the graph is the "root solvable". Note that not all the
 /// solvable's requirements are included in the graph, only those that are
 /// directly or indirectly involved in the conflict.
+#[derive(Clone, Debug)]
 pub struct ConflictGraph {
     graph: DiGraph<ConflictNode, ConflictEdge>,
     root_node: NodeIndex,
     unresolved_node: Option<NodeIndex>,
+    /// For requirements without candidates, the candidates that were available
+    /// for the package but did not match the requirement.
+    rejected_candidates: HashMap<Requirement, Vec<SolvableId>>,
 }
 
 impl ConflictGraph {
+    /// Display a user-friendly error explaining the conflict this graph
+    /// represents. See [`Conflict::display_user_friendly`].
+    pub fn display_user_friendly<'a, I: Interner>(&self, interner: &'a I) -> DisplayUnsat<'a, I> {
+        self.display_with_messages(interner, &DefaultConflictMessages)
+    }
+
+    /// Display a user-friendly error explaining the conflict this graph
+    /// represents, phrased with the given [`ConflictMessages`]. See
+    /// [`Conflict::display_with_messages`].
+    pub fn display_with_messages<'a, I: Interner, M: ConflictMessages>(
+        &self,
+        interner: &'a I,
+        messages: &'a M,
+    ) -> DisplayUnsat<'a, I, M> {
+        DisplayUnsat::new(self.clone(), interner, messages)
+    }
+
     /// Writes a graphviz graph that represents this instance to the specified
     /// output.
     pub fn graphviz(
@@ -369,7 +1080,7 @@ impl ConflictGraph {
         let graph = &self.graph;
 
         let merged_nodes = if simplify {
-            self.simplify(interner)
+            self.simplify(interner, false)
         } else {
             HashMap::default()
         };
@@ -390,7 +1101,7 @@ impl ConflictGraph {
                 }
             }
 
-            let mut added_edges = HashSet::new();
+            let mut added_edges = HashSet::default();
             for edge in graph.edges_directed(nx, Direction::Outgoing) {
                 let target = *graph.node_weight(edge.target()).unwrap();
 
@@ -413,7 +1124,8 @@ impl ConflictGraph {
                     ConflictEdge::ConditionalRequires(requirement, conditions) => {
                         format!(
                             "if {} then {}",
-                            conditions.iter()
+                            conditions
+                                .iter()
                                 .map(|c| interner.display_condition(*c).to_string())
                                 .collect::<Vec<_>>()
                                 .join(" and "),
@@ -423,10 +1135,24 @@ impl ConflictGraph {
                     ConflictEdge::Conflict(ConflictCause::Constrains(version_set_id)) => {
                         interner.display_version_set(*version_set_id).to_string()
                     }
+                    ConflictEdge::Conflict(ConflictCause::PolicyPin(_, version_set_id, _)) => {
+                        format!("policy {}", interner.display_version_set(*version_set_id))
+                    }
                     ConflictEdge::Conflict(ConflictCause::ForbidMultipleInstances)
                     | ConflictEdge::Conflict(ConflictCause::Locked(_)) => {
                         "already installed".to_string()
                     }
+                    ConflictEdge::Conflict(ConflictCause::ExactlyOne(requirement)) => {
+                        requirement.display(interner).to_string()
+                    }
+                    ConflictEdge::Conflict(ConflictCause::Pinned(_)) => "pinned".to_string(),
+                    ConflictEdge::Conflict(ConflictCause::Base(_)) => {
+                        "base environment".to_string()
+                    }
+                    ConflictEdge::Conflict(ConflictCause::Forbidden(_)) => "forbidden".to_string(),
+                    ConflictEdge::Conflict(ConflictCause::Prohibited(_)) => {
+                        "prohibited".to_string()
+                    }
                     ConflictEdge::Conflict(ConflictCause::Excluded) => "excluded".to_string(),
                 };
 
@@ -466,11 +1192,16 @@ impl ConflictGraph {
 
     /// Simplifies and collapses nodes so that these can be considered the same
     /// candidate
-    fn simplify(&self, interner: &impl Interner) -> HashMap<SolvableId, Rc<MergedConflictNode>> {
+    fn simplify(
+        &self,
+        interner: &impl Interner,
+        merge_by_display: bool,
+    ) -> HashMap<SolvableId, Rc<MergedConflictNode>> {
         let graph = &self.graph;
 
         // Gather information about nodes that can be merged
         let mut maybe_merge = HashMap::default();
+        let mut maybe_merge_by_display = HashMap::default();
         for node_id in graph.node_indices() {
             let candidate = match graph[node_id] {
                 ConflictNode::UnresolvedDependency | ConflictNode::Excluded(_) => continue,
@@ -503,6 +1234,16 @@ impl ConflictGraph {
                 .display_name(interner.solvable_name(solvable_id))
                 .to_string();
 
+            if merge_by_display {
+                let display = interner
+                    .display_merged_solvables(&[solvable_id])
+                    .to_string();
+                maybe_merge_by_display
+                    .entry((name.clone(), predecessors.clone(), display))
+                    .or_insert(Vec::new())
+                    .push(solvable_id);
+            }
+
             let entry = maybe_merge
                 .entry((name, predecessors, successors))
                 .or_insert(Vec::new());
@@ -511,22 +1252,45 @@ impl ConflictGraph {
         }
 
         let mut merged_candidates = HashMap::default();
-        for m in maybe_merge.into_values() {
-            if m.len() > 1 {
-                let m = Rc::new(MergedConflictNode {
-                    ids: m.into_iter().map(|(_, snd)| snd).collect(),
-                });
+        if merge_by_display {
+            let groups = maybe_merge
+                .into_values()
+                .map(|m| m.into_iter().map(|(_, snd)| snd).collect())
+                .chain(maybe_merge_by_display.into_values());
+            for ids in join_overlapping_groups(groups) {
+                let mut seen_displays = HashSet::default();
+                let display_ids = ids
+                    .iter()
+                    .copied()
+                    .filter(|&id| {
+                        seen_displays.insert(interner.display_merged_solvables(&[id]).to_string())
+                    })
+                    .collect();
+                let m = Rc::new(MergedConflictNode { ids, display_ids });
                 for &id in &m.ids {
                     merged_candidates.insert(id, m.clone());
                 }
             }
+        } else {
+            for m in maybe_merge.into_values() {
+                if m.len() > 1 {
+                    let ids: Vec<_> = m.into_iter().map(|(_, snd)| snd).collect();
+                    let m = Rc::new(MergedConflictNode {
+                        display_ids: ids.clone(),
+                        ids,
+                    });
+                    for &id in &m.ids {
+                        merged_candidates.insert(id, m.clone());
+                    }
+                }
+            }
         }
 
         merged_candidates
     }
 
     fn get_installable_set(&self) -> HashSet<NodeIndex> {
-        let mut installable = HashSet::new();
+        let mut installable = HashSet::default();
 
         // Definition: a package is installable if it does not have any outgoing
         // conflicting edges and if each of its dependencies has at least one
@@ -542,10 +1306,16 @@ impl ConflictGraph {
 
             // Determine any incoming "exclude" edges to the node. This would indicate that
             // the node is disabled for external reasons.
-            let excluding_edges = self
-                .graph
-                .edges_directed(nx, Direction::Incoming)
-                .any(|e| matches!(e.weight(), ConflictEdge::Conflict(ConflictCause::Excluded)));
+            let excluding_edges = self.graph.edges_directed(nx, Direction::Incoming).any(|e| {
+                matches!(
+                    e.weight(),
+                    ConflictEdge::Conflict(
+                        ConflictCause::Excluded
+                            | ConflictCause::Forbidden(_)
+                            | ConflictCause::Prohibited(_)
+                    )
+                )
+            });
             if excluding_edges {
                 // Nodes with incoming disabling edges aren't installable
                 continue;
@@ -593,7 +1363,7 @@ impl ConflictGraph {
         // Definition: a package is missing if it is not involved in any conflicts, yet
         // it is not installable
 
-        let mut missing = HashSet::new();
+        let mut missing = HashSet::default();
         match self.unresolved_node {
             None => return missing,
             Some(nx) => missing.insert(nx),
@@ -616,7 +1386,9 @@ impl ConflictGraph {
                 .graph
                 .edges_directed(nx, Direction::Outgoing)
                 .map(|e| match e.weight() {
-                    ConflictEdge::Requires(version_set_id) => ((version_set_id, vec![]), e.target()),
+                    ConflictEdge::Requires(version_set_id) => {
+                        ((version_set_id, vec![]), e.target())
+                    }
                     ConflictEdge::ConditionalRequires(reqs, conditions) => {
                         ((reqs, conditions.clone()), e.target())
                     }
@@ -711,19 +1483,276 @@ impl Indenter {
     }
 }
 
+/// The phrases that [`DisplayUnsat`] uses to explain a conflict.
+///
+/// Every method returns a complete phrase, so implementations are free to
+/// order the arguments as their language requires. The default
+/// implementations return the English phrases, see
+/// [`DefaultConflictMessages`]. The arguments are already formatted with the
+/// [`Interner`].
+pub trait ConflictMessages {
+    /// The heading above the requirements that cannot be installed together.
+    fn incompatible_packages(&self) -> String {
+        "The following packages are incompatible".to_string()
+    }
+
+    /// A top-level requirement for which no candidates were found.
+    fn no_candidates(&self, requirement: &dyn Display) -> String {
+        format!("No candidates were found for {requirement}.")
+    }
+
+    /// A nested requirement for which no candidates were found.
+    fn no_candidates_nested(&self, requirement: &dyn Display) -> String {
+        format!("{requirement}, for which no candidates were found.")
+    }
+
+    /// A top-level [`Requirement::AtLeast`] with fewer candidates than it
+    /// requires.
+    fn too_few_candidates(&self, requirement: &dyn Display) -> String {
+        format!("Not enough candidates were found for {requirement}.")
+    }
+
+    /// A nested [`Requirement::AtLeast`] with fewer candidates than it
+    /// requires.
+    fn too_few_candidates_nested(&self, requirement: &dyn Display) -> String {
+        format!("{requirement}, for which not enough candidates were found.")
+    }
+
+    /// The candidates of a package that do not match the requirement.
+    fn rejected_candidates(&self, candidates: &dyn Display) -> String {
+        format!("available, but not matching: {candidates}")
+    }
+
+    /// The candidate that comes closest to matching a requirement without
+    /// candidates, see [`Interner::version_set_bounds`].
+    fn closest_candidate(&self, candidate: &dyn Display) -> String {
+        format!("closest available: {candidate}")
+    }
+
+    /// A top-level requirement that can be installed, followed by its
+    /// candidates.
+    fn installable(&self, requirement: &dyn Display) -> String {
+        format!("{requirement} can be installed with any of the following options:")
+    }
+
+    /// A nested requirement that can be installed, followed by its candidates.
+    fn installable_nested(&self, requirement: &dyn Display) -> String {
+        format!("{requirement}, which can be installed with any of the following options:")
+    }
+
+    /// A top-level requirement that cannot be installed, followed by its
+    /// candidates.
+    fn not_installable(&self, requirement: &dyn Display) -> String {
+        format!("{requirement} cannot be installed because there are no viable options:")
+    }
+
+    /// A nested requirement that cannot be installed, followed by its
+    /// candidates.
+    fn not_installable_nested(&self, requirement: &dyn Display) -> String {
+        format!("{requirement}, which cannot be installed because there are no viable options:")
+    }
+
+    /// The separator between the conditions of a conditional requirement.
+    fn condition_separator(&self) -> String {
+        " and ".to_string()
+    }
+
+    /// A top-level conditional requirement for which no candidates were found.
+    fn conditional_no_candidates(
+        &self,
+        condition: &dyn Display,
+        requirement: &dyn Display,
+    ) -> String {
+        format!(
+            " the condition {condition} is true but no candidates were found for {requirement}."
+        )
+    }
+
+    /// A top-level conditional requirement that can be installed, followed by
+    /// its candidates.
+    fn conditional_installable(
+        &self,
+        condition: &dyn Display,
+        requirement: &dyn Display,
+    ) -> String {
+        format!(
+            "due to the condition {condition}, {requirement} can be installed with any of the following options:"
+        )
+    }
+
+    /// A top-level conditional requirement that cannot be installed, followed
+    /// by its candidates.
+    fn conditional_not_installable(
+        &self,
+        condition: &dyn Display,
+        requirement: &dyn Display,
+    ) -> String {
+        format!(
+            "The condition {condition} is true but {requirement} cannot be installed because there are no viable options:"
+        )
+    }
+
+    /// A candidate that is excluded by the dependency provider.
+    fn excluded(&self, candidate: &dyn Display, reason: &dyn Display) -> String {
+        format!("{candidate} is excluded because {reason}")
+    }
+
+    /// A candidate that is forbidden by the problem.
+    fn forbidden(&self, candidate: &dyn Display) -> String {
+        format!("{candidate} is forbidden")
+    }
+
+    /// A candidate of a package that is prohibited by the problem.
+    fn prohibited(&self, candidate: &dyn Display, name: &dyn Display) -> String {
+        format!("{candidate} is forbidden because {name} is prohibited")
+    }
+
+    /// A candidate that conflicts with another version of the same package.
+    fn conflicts_with_reported(&self, candidate: &dyn Display) -> String {
+        format!("{candidate}, which conflicts with the versions reported above.")
+    }
+
+    /// A candidate with constraints that cannot be met, followed by the
+    /// constraints.
+    fn would_constrain(&self, candidate: &dyn Display) -> String {
+        format!("{candidate} would constrain")
+    }
+
+    /// A constraint that conflicts with the given installable candidates.
+    fn constraint_conflicts(
+        &self,
+        name: &dyn Display,
+        version_set: &dyn Display,
+        conflicting: &dyn Display,
+    ) -> String {
+        format!(
+            "{name} {version_set}, which conflicts with any installable versions previously reported ({conflicting})"
+        )
+    }
+
+    /// A candidate with requirements that cannot be met, followed by the
+    /// requirements.
+    fn would_require(&self, candidate: &dyn Display) -> String {
+        format!("{candidate} would require")
+    }
+
+    /// A constraint of the problem that cannot be met.
+    fn constraint_cannot_be_fulfilled(
+        &self,
+        name: &dyn Display,
+        version_set: &dyn Display,
+    ) -> String {
+        format!("the constraint {name} {version_set} cannot be fulfilled")
+    }
+
+    /// A policy pin of the problem that cannot be met, together with the
+    /// reason for the policy.
+    fn policy_pin_cannot_be_fulfilled(
+        &self,
+        name: &dyn Display,
+        version_set: &dyn Display,
+        reason: &dyn Display,
+    ) -> String {
+        format!("{name} is pinned to {version_set} by policy ({reason}), which cannot be fulfilled")
+    }
+
+    /// A locked solvable while another version is required.
+    fn locked(&self, solvable: &dyn Display) -> String {
+        format!("{solvable} is locked, but another version is required as reported above")
+    }
+
+    /// A pinned solvable that cannot be installed.
+    fn pinned_not_installable(&self, solvable: &dyn Display) -> String {
+        format!("{solvable} is pinned, but cannot be installed")
+    }
+
+    /// A pinned solvable while another version is required.
+    fn pinned_other_version_required(&self, solvable: &dyn Display) -> String {
+        format!("{solvable} is pinned, but another version is required as reported above")
+    }
+
+    /// A solvable of the base environment that cannot be installed.
+    fn base_not_installable(&self, solvable: &dyn Display) -> String {
+        format!("{solvable} is part of the base environment, but cannot be installed")
+    }
+
+    /// A solvable of the base environment while another version is required.
+    fn incompatible_with_base(&self, solvable: &dyn Display) -> String {
+        format!(
+            "another version than {solvable} is required as reported above, which is \
+             incompatible with base environment"
+        )
+    }
+}
+
+/// The English [`ConflictMessages`] that are used by
+/// [`Conflict::display_user_friendly`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultConflictMessages;
+
+impl ConflictMessages for DefaultConflictMessages {}
+
+/// Joins groups of solvables that share a solvable until no two groups
+/// overlap, and drops the groups of a single solvable.
+///
+/// The result does not depend on the order of the groups: the solvables of
+/// every joined group are sorted, and so are the groups themselves.
+fn join_overlapping_groups(
+    groups: impl IntoIterator<Item = Vec<SolvableId>>,
+) -> Vec<Vec<SolvableId>> {
+    fn find(parents: &mut HashMap<SolvableId, SolvableId>, mut id: SolvableId) -> SolvableId {
+        while let Some(&parent) = parents.get(&id).filter(|&&parent| parent != id) {
+            id = parent;
+        }
+        id
+    }
+
+    let mut parents = HashMap::default();
+    for group in groups {
+        let Some((&first, rest)) = group.split_first() else {
+            continue;
+        };
+        parents.entry(first).or_insert(first);
+        for &id in rest {
+            parents.entry(id).or_insert(id);
+            let (a, b) = (find(&mut parents, first), find(&mut parents, id));
+            parents.insert(a.max(b), a.min(b));
+        }
+    }
+
+    let mut joined: HashMap<SolvableId, Vec<SolvableId>> = HashMap::default();
+    for id in parents.keys().copied().collect_vec() {
+        let root = find(&mut parents, id);
+        joined.entry(root).or_default().push(id);
+    }
+
+    joined
+        .into_values()
+        .filter(|ids| ids.len() > 1)
+        .map(|ids| ids.into_iter().sorted().collect())
+        .sorted()
+        .collect()
+}
+
 /// A struct implementing [`fmt::Display`] that generates a user-friendly
 /// representation of a conflict graph
-pub struct DisplayUnsat<'i, I: Interner> {
+///
+/// The top-level requirements summarize the conflict and display their
+/// package names with [`Interner::display_name_short`], the details below
+/// them use [`Interner::display_name`]. The phrases are provided by the
+/// [`ConflictMessages`].
+pub struct DisplayUnsat<'i, I: Interner, M: ConflictMessages = DefaultConflictMessages> {
     graph: ConflictGraph,
     merged_candidates: HashMap<SolvableId, Rc<MergedConflictNode>>,
     installable_set: HashSet<NodeIndex>,
     missing_set: HashSet<NodeIndex>,
     interner: &'i I,
+    messages: &'i M,
 }
 
-impl<'i, I: Interner> DisplayUnsat<'i, I> {
-    pub(crate) fn new(graph: ConflictGraph, interner: &'i I) -> Self {
-        let merged_candidates = graph.simplify(interner);
+impl<'i, I: Interner, M: ConflictMessages> DisplayUnsat<'i, I, M> {
+    pub(crate) fn new(graph: ConflictGraph, interner: &'i I, messages: &'i M) -> Self {
+        let merged_candidates = graph.simplify(interner, false);
         let installable_set = graph.get_installable_set();
         let missing_set = graph.get_missing_set();
 
@@ -733,7 +1762,75 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
             installable_set,
             missing_set,
             interner,
+            messages,
+        }
+    }
+
+    /// Sets whether candidates of the same package that are displayed the
+    /// same by [`Interner::display_merged_solvables`] are merged into a single
+    /// entry, for instance the builds of a single version. Off by default.
+    ///
+    /// Merged candidates that fail for different reasons are explained by the
+    /// reasons of the first of them only, which makes the output shorter but
+    /// less complete.
+    pub fn merge_by_display(mut self, merge_by_display: bool) -> Self {
+        self.merged_candidates = self.graph.simplify(self.interner, merge_by_display);
+        self
+    }
+
+    /// Lists the candidates that were available for a requirement without
+    /// matching candidates, so the user can see which versions were rejected.
+    fn fmt_rejected_candidates(
+        &self,
+        f: &mut Formatter<'_>,
+        requirement: &Requirement,
+        indenter: &Indenter,
+    ) -> fmt::Result {
+        let Some(rejected) = self
+            .graph
+            .rejected_candidates
+            .get(requirement)
+            .filter(|rejected| !rejected.is_empty())
+        else {
+            return Ok(());
+        };
+
+        // Suggest the closest candidate if the interner can describe the bounds of the
+        // requirement.
+        let closest = match *requirement {
+            Requirement::Single(version_set) => self
+                .interner
+                .version_set_bounds(version_set)
+                .and_then(|bounds| bounds.closest_candidate(rejected, self.interner)),
+            Requirement::Union(_) | Requirement::AtLeast { .. } | Requirement::ExactlyOne(_) => {
+                None
+            }
+        };
+
+        let order = if closest.is_some() {
+            ChildOrder::HasRemainingSiblings
+        } else {
+            ChildOrder::Last
+        };
+        let indent = indenter.push_level_with_order(order).get_indent();
+        let displayed = rejected
+            .iter()
+            .map(|&s| self.interner.display_solvable(s))
+            .format(", ");
+        writeln!(
+            f,
+            "{indent}{}",
+            self.messages.rejected_candidates(&displayed)
+        )?;
+
+        if let Some(closest) = closest {
+            let indent = indenter
+                .push_level_with_order(ChildOrder::Last)
+                .get_indent();
+            let closest = self.interner.display_solvable(closest);
+            writeln!(f, "{indent}{}", self.messages.closest_candidate(&closest))?;
         }
+        Ok(())
     }
 
     fn fmt_graph(
@@ -749,9 +1846,8 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
         }
 
         let graph = &self.graph.graph;
-        println!("graph {:?}", graph);
         let installable_nodes = &self.installable_set;
-        let mut reported: HashSet<SolvableOrRootId> = HashSet::new();
+        let mut reported: HashSet<SolvableOrRootId> = HashSet::default();
 
         // Note: we are only interested in requires edges here
         let indenter = Indenter::new(top_level_indent);
@@ -772,7 +1868,6 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
             .map(|((version_set_id, condition), edges)| {
                 (
                     if !condition.is_empty() {
-                        println!("conditional requirement");
                         DisplayOp::ConditionalRequirement((version_set_id, condition), edges)
                     } else {
                         DisplayOp::Requirement(version_set_id, edges)
@@ -800,27 +1895,37 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                         installable_nodes.contains(&target)
                     });
 
-                    let req = requirement.display(self.interner).to_string();
+                    // Top level requirements summarize the conflict, so use the short names.
+                    let req = if top_level {
+                        requirement.display_short(self.interner).to_string()
+                    } else {
+                        requirement.display(self.interner).to_string()
+                    };
 
                     let target_nx = graph.edge_endpoints(edges[0]).unwrap().1;
                     let missing =
                         edges.len() == 1 && graph[target_nx] == ConflictNode::UnresolvedDependency;
                     if missing {
                         // No candidates for requirement
-                        if top_level {
-                            writeln!(f, "{indent}No candidates were found for {req}.")?;
+                        if requirement.min_count() > 1 {
+                            let message = if top_level {
+                                self.messages.too_few_candidates(&req)
+                            } else {
+                                self.messages.too_few_candidates_nested(&req)
+                            };
+                            writeln!(f, "{indent}{message}")?;
+                        } else if top_level {
+                            writeln!(f, "{indent}{}", self.messages.no_candidates(&req))?;
                         } else {
-                            writeln!(f, "{indent}{req}, for which no candidates were found.",)?;
+                            writeln!(f, "{indent}{}", self.messages.no_candidates_nested(&req))?;
                         }
+                        self.fmt_rejected_candidates(f, &requirement, &indenter)?;
                     } else if installable {
                         // Package can be installed (only mentioned for top-level requirements)
                         if top_level {
-                            writeln!(
-                                f,
-                                "{indent}{req} can be installed with any of the following options:"
-                            )?;
+                            writeln!(f, "{indent}{}", self.messages.installable(&req))?;
                         } else {
-                            writeln!(f, "{indent}{req}, which can be installed with any of the following options:")?;
+                            writeln!(f, "{indent}{}", self.messages.installable_nested(&req))?;
                         }
 
                         let children: Vec<_> = edges
@@ -838,7 +1943,7 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
 
                         // TODO: this is an utterly ugly hack that should be burnt to ashes
                         let mut deduplicated_children = Vec::new();
-                        let mut merged_and_seen = HashSet::new();
+                        let mut merged_and_seen = HashSet::default();
                         for child in children {
                             let (DisplayOp::Candidate(child_node), _) = child else {
                                 unreachable!()
@@ -871,9 +1976,9 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                         // Package cannot be installed (the conflicting requirement is further down
                         // the tree)
                         if top_level {
-                            writeln!(f, "{indent}{req} cannot be installed because there are no viable options:")?;
+                            writeln!(f, "{indent}{}", self.messages.not_installable(&req))?;
                         } else {
-                            writeln!(f, "{indent}{req}, which cannot be installed because there are no viable options:")?;
+                            writeln!(f, "{indent}{}", self.messages.not_installable_nested(&req))?;
                         }
 
                         let children: Vec<_> = edges
@@ -888,7 +1993,7 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
 
                         // TODO: this is an utterly ugly hack that should be burnt to ashes
                         let mut deduplicated_children = Vec::new();
-                        let mut merged_and_seen = HashSet::new();
+                        let mut merged_and_seen = HashSet::default();
                         for child in children {
                             let (DisplayOp::Candidate(child_node), _) = child else {
                                 unreachable!()
@@ -930,7 +2035,7 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                     {
                         reported.extend(merged.ids.iter().copied().map(SolvableOrRootId::from));
                         self.interner
-                            .display_merged_solvables(&merged.ids)
+                            .display_merged_solvables(&merged.display_ids)
                             .to_string()
                     } else if let Some(solvable_id) = solvable_id.solvable() {
                         self.interner
@@ -951,9 +2056,28 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                             }
                             _ => None,
                         });
+                    let forbidden = graph
+                        .edges_directed(candidate, Direction::Incoming)
+                        .any(|e| {
+                            matches!(
+                                e.weight(),
+                                ConflictEdge::Conflict(ConflictCause::Forbidden(_))
+                            )
+                        });
+                    let prohibited = graph
+                        .edges_directed(candidate, Direction::Incoming)
+                        .find_map(|e| match e.weight() {
+                            &ConflictEdge::Conflict(ConflictCause::Prohibited(name)) => Some(name),
+                            _ => None,
+                        });
                     let already_installed = graph.edges(candidate).any(|e| {
-                        e.weight()
-                            == &ConflictEdge::Conflict(ConflictCause::ForbidMultipleInstances)
+                        matches!(
+                            e.weight(),
+                            ConflictEdge::Conflict(
+                                ConflictCause::ForbidMultipleInstances
+                                    | ConflictCause::ExactlyOne(_)
+                            )
+                        )
                     });
                     let constrains_conflict = graph.edges(candidate).any(|e| {
                         matches!(
@@ -964,17 +2088,20 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                     let is_leaf = graph.edges(candidate).next().is_none();
 
                     if let Some(excluded_reason) = excluded {
-                        writeln!(
-                            f,
-                            "{indent}{version} is excluded because {reason}",
-                            reason = self.interner.display_string(excluded_reason),
-                        )?;
+                        let reason = self.interner.display_string(excluded_reason);
+                        writeln!(f, "{indent}{}", self.messages.excluded(&version, &reason))?;
+                    } else if forbidden {
+                        writeln!(f, "{indent}{}", self.messages.forbidden(&version))?;
+                    } else if let Some(name) = prohibited {
+                        let name = self.interner.display_name(name);
+                        writeln!(f, "{indent}{}", self.messages.prohibited(&version, &name))?;
                     } else if is_leaf {
                         writeln!(f, "{indent}{version}")?;
                     } else if already_installed {
                         writeln!(
                             f,
-                            "{indent}{version}, which conflicts with the versions reported above."
+                            "{indent}{}",
+                            self.messages.conflicts_with_reported(&version)
                         )?;
                     } else if constrains_conflict {
                         let mut version_sets = graph
@@ -988,7 +2115,7 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                             .dedup()
                             .peekable();
 
-                        writeln!(f, "{indent}{version} would constrain",)?;
+                        writeln!(f, "{indent}{}", self.messages.would_constrain(&version))?;
 
                         let mut indenter = indenter.push_level();
                         while let Some(&version_set_id) = version_sets.next() {
@@ -1001,13 +2128,30 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                                 indenter.set_last();
                             }
                             let indent = indenter.get_indent();
+                            let conflicting = graph
+                                .edges(candidate)
+                                .filter(|e| {
+                                    e.weight()
+                                        == &ConflictEdge::Conflict(ConflictCause::Constrains(
+                                            version_set_id,
+                                        ))
+                                })
+                                .filter_map(|e| graph[e.target()].solvable())
+                                .unique()
+                                .map(|s| self.interner.display_solvable(s))
+                                .format(", ");
                             writeln!(
                                 f,
-                                "{indent}{name} {version_set}, which conflicts with any installable versions previously reported",
+                                "{indent}{}",
+                                self.messages.constraint_conflicts(
+                                    &name,
+                                    &version_set,
+                                    &conflicting
+                                )
                             )?;
                         }
                     } else {
-                        writeln!(f, "{indent}{version} would require",)?;
+                        writeln!(f, "{indent}{}", self.messages.would_require(&version))?;
                         let mut requirements = graph
                             .edges(candidate)
                             .chunk_by(|e| e.weight().clone().requires_or_conditional())
@@ -1052,8 +2196,17 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                         installable_nodes.contains(&target)
                     });
 
-                    let req = requirement.display(self.interner).to_string();
-                    let condition = condition.iter().map(|c| self.interner.display_condition(*c).to_string()).collect::<Vec<_>>().join(" and ");
+                    // Top level requirements summarize the conflict, so use the short names.
+                    let req = if top_level {
+                        requirement.display_short(self.interner).to_string()
+                    } else {
+                        requirement.display(self.interner).to_string()
+                    };
+                    let condition = condition
+                        .iter()
+                        .map(|c| self.interner.display_condition(*c).to_string())
+                        .collect::<Vec<_>>()
+                        .join(&self.messages.condition_separator());
 
                     let target_nx = graph.edge_endpoints(edges[0]).unwrap().1;
                     let missing =
@@ -1061,19 +2214,25 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                     if missing {
                         // No candidates for requirement
                         if top_level {
-                            writeln!(f, "{indent} the condition {condition} is true but no candidates were found for {req}.")?;
+                            writeln!(
+                                f,
+                                "{indent}{}",
+                                self.messages.conditional_no_candidates(&condition, &req)
+                            )?;
                         } else {
-                            writeln!(f, "{indent}{req}, for which no candidates were found.",)?;
+                            writeln!(f, "{indent}{}", self.messages.no_candidates_nested(&req))?;
                         }
+                        self.fmt_rejected_candidates(f, &requirement, &indenter)?;
                     } else if installable {
                         // Package can be installed (only mentioned for top-level requirements)
                         if top_level {
                             writeln!(
                                 f,
-                                "{indent}due to the condition {condition}, {req} can be installed with any of the following options:"
+                                "{indent}{}",
+                                self.messages.conditional_installable(&condition, &req)
                             )?;
                         } else {
-                            writeln!(f, "{indent}{req}, which can be installed with any of the following options:")?;
+                            writeln!(f, "{indent}{}", self.messages.installable_nested(&req))?;
                         }
 
                         let children: Vec<_> = edges
@@ -1091,7 +2250,7 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
 
                         // TODO: this is an utterly ugly hack that should be burnt to ashes
                         let mut deduplicated_children = Vec::new();
-                        let mut merged_and_seen = HashSet::new();
+                        let mut merged_and_seen = HashSet::default();
                         for child in children {
                             let (DisplayOp::Candidate(child_node), _) = child else {
                                 unreachable!()
@@ -1124,9 +2283,13 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
                         // Package cannot be installed (the conflicting requirement is further down
                         // the tree)
                         if top_level {
-                            writeln!(f, "{indent}The condition {condition} is true but {req} cannot be installed because there are no viable options:")?;
+                            writeln!(
+                                f,
+                                "{indent}{}",
+                                self.messages.conditional_not_installable(&condition, &req)
+                            )?;
                         } else {
-                            writeln!(f, "{indent}{req}, which cannot be installed because there are no viable options:")?;
+                            writeln!(f, "{indent}{}", self.messages.not_installable_nested(&req))?;
                         }
 
                         let children: Vec<_> = edges
@@ -1141,7 +2304,7 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
 
                         // TODO: this is an utterly ugly hack that should be burnt to ashes
                         let mut deduplicated_children = Vec::new();
-                        let mut merged_and_seen = HashSet::new();
+                        let mut merged_and_seen = HashSet::default();
                         for child in children {
                             let (DisplayOp::Candidate(child_node), _) = child else {
                                 unreachable!()
@@ -1177,7 +2340,7 @@ impl<'i, I: Interner> DisplayUnsat<'i, I> {
     }
 }
 
-impl<'i, I: Interner> fmt::Display for DisplayUnsat<'i, I> {
+impl<'i, I: Interner, M: ConflictMessages> fmt::Display for DisplayUnsat<'i, I, M> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let (top_level_missing, top_level_conflicts): (Vec<_>, _) = self
             .graph
@@ -1190,7 +2353,7 @@ impl<'i, I: Interner> fmt::Display for DisplayUnsat<'i, I> {
         }
 
         if !top_level_conflicts.is_empty() {
-            writeln!(f, "The following packages are incompatible")?;
+            writeln!(f, "{}", self.messages.incompatible_packages())?;
             self.fmt_graph(f, &top_level_conflicts, true)?;
 
             // Conflicts caused by locked dependencies
@@ -1212,26 +2375,66 @@ impl<'i, I: Interner> fmt::Display for DisplayUnsat<'i, I> {
                 // The only possible conflict at the root level is a Locked conflict
                 match conflict {
                     &ConflictCause::Constrains(version_set_id) => {
+                        let name = self
+                            .interner
+                            .display_name_short(self.interner.version_set_name(version_set_id));
+                        let version_set = self.interner.display_version_set(version_set_id);
                         writeln!(
                             f,
-                            "{indent}the constraint {name} {version_set} cannot be fulfilled",
-                            name = self
-                                .interner
-                                .display_name(self.interner.version_set_name(version_set_id)),
-                            version_set = self.interner.display_version_set(version_set_id),
+                            "{indent}{}",
+                            self.messages
+                                .constraint_cannot_be_fulfilled(&name, &version_set)
                         )?;
                     }
-                    &ConflictCause::ForbidMultipleInstances => {
-                        unreachable!()
-                    }
-                    &ConflictCause::Locked(solvable_id) => {
+                    &ConflictCause::PolicyPin(name, version_set_id, reason) => {
+                        let name = self.interner.display_name_short(name);
+                        let version_set = self.interner.display_version_set(version_set_id);
+                        let reason = self.interner.display_string(reason);
                         writeln!(
                             f,
-                            "{indent}{} is locked, but another version is required as reported above",
-                            self.interner.display_merged_solvables(&[solvable_id]),
+                            "{indent}{}",
+                            self.messages.policy_pin_cannot_be_fulfilled(
+                                &name,
+                                &version_set,
+                                &reason
+                            )
                         )?;
                     }
-                    ConflictCause::Excluded => continue,
+                    &ConflictCause::ForbidMultipleInstances | &ConflictCause::ExactlyOne(_) => {
+                        unreachable!()
+                    }
+                    &ConflictCause::Locked(solvable_id) => {
+                        let locked = self.interner.display_merged_solvables(&[solvable_id]);
+                        writeln!(f, "{indent}{}", self.messages.locked(&locked))?;
+                    }
+                    &ConflictCause::Pinned(solvable_id) => {
+                        let pinned = self.interner.display_merged_solvables(&[solvable_id]);
+                        if self.graph.graph[e.target()].solvable() == Some(solvable_id) {
+                            writeln!(
+                                f,
+                                "{indent}{}",
+                                self.messages.pinned_not_installable(&pinned)
+                            )?;
+                        } else {
+                            writeln!(
+                                f,
+                                "{indent}{}",
+                                self.messages.pinned_other_version_required(&pinned)
+                            )?;
+                        }
+                    }
+                    &ConflictCause::Base(solvable_id) => {
+                        let base = self.interner.display_merged_solvables(&[solvable_id]);
+                        if self.graph.graph[e.target()].solvable() == Some(solvable_id) {
+                            writeln!(f, "{indent}{}", self.messages.base_not_installable(&base))?;
+                        } else {
+                            writeln!(f, "{indent}{}", self.messages.incompatible_with_base(&base))?;
+                        }
+                    }
+                    // Forbidden solvables are reported where they are required
+                    ConflictCause::Excluded
+                    | ConflictCause::Forbidden(_)
+                    | ConflictCause::Prohibited(_) => continue,
                 };
             }
         }
@@ -0,0 +1,304 @@
+//! Generates synthetic problems to benchmark and stress test the solver.
+//!
+//! [`generate_problem`] builds a pool with dependencies between its packages
+//! from a seed. The same seed and [`GenParams`] always produce the same
+//! problem, so generated problems can be used to compare the performance of
+//! different versions of the solver.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+use version_ranges::Ranges;
+
+use crate::{
+    utils::Pool, Candidates, Condition, ConditionalRequirement, Dependencies, DependencyProvider,
+    Interner, KnownDependencies, NameId, SolvableId, SolverCache, StringId, VersionSetId,
+    VersionSetUnionId,
+};
+
+/// The parameters of the problems created by [`generate_problem`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GenParams {
+    /// The number of package names in the pool.
+    pub names: usize,
+
+    /// The number of versions of every package.
+    pub versions_per_name: usize,
+
+    /// The number of packages that every solvable depends on.
+    pub fan_out: usize,
+
+    /// The probability between `0.0` and `1.0` that a dependency does not
+    /// allow the lowest version of a package, and that a solvable constrains
+    /// another package.
+    ///
+    /// Every dependency allows the lowest version of a package at a density
+    /// of `0.0`, and there are no constraints, so the generated problems are
+    /// always solvable. Higher densities make conflicts more likely.
+    pub conflict_density: f64,
+
+    /// The number of requirements of the problem.
+    pub requirements: usize,
+}
+
+impl Default for GenParams {
+    fn default() -> Self {
+        Self {
+            names: 100,
+            versions_per_name: 5,
+            fan_out: 3,
+            conflict_density: 0.1,
+            requirements: 10,
+        }
+    }
+}
+
+/// The pool of a generated problem, together with the dependencies of its
+/// solvables.
+///
+/// It implements [`DependencyProvider`], so it can be passed to
+/// [`crate::Solver::new`] directly. Displaying it lists every solvable with
+/// its dependencies, which makes it easy to compare generated problems.
+pub struct GeneratedPool {
+    /// The pool that contains the names, solvables and version sets.
+    pub pool: Pool<Ranges<u32>>,
+    names: Vec<NameId>,
+    candidates: HashMap<NameId, Vec<SolvableId>>,
+    dependencies: HashMap<SolvableId, KnownDependencies>,
+}
+
+/// Generates a problem from a seed.
+///
+/// The pool contains [`GenParams::names`] packages with
+/// [`GenParams::versions_per_name`] versions each. A package only depends on
+/// packages that come after it, so there are no dependency cycles. Returns
+/// the pool, the requirements and the constraints of the problem.
+pub fn generate_problem(
+    seed: u64,
+    params: GenParams,
+) -> (
+    GeneratedPool,
+    Vec<ConditionalRequirement>,
+    Vec<VersionSetId>,
+) {
+    let mut rng = SplitMix64(seed);
+    let pool = Pool::new();
+    let versions = params.versions_per_name.max(1) as u32;
+
+    let names = (0..params.names)
+        .map(|index| pool.intern_package_name(format!("package-{index}")))
+        .collect::<Vec<_>>();
+    let candidates = names
+        .iter()
+        .map(|&name| {
+            let solvables = (1..=versions)
+                .map(|version| pool.intern_solvable(name, version))
+                .collect();
+            (name, solvables)
+        })
+        .collect::<HashMap<_, _>>();
+
+    // A random range of versions, which includes the lowest version unless the
+    // range is meant to be able to conflict.
+    let version_range = |rng: &mut SplitMix64, may_conflict: bool| {
+        let low = if may_conflict {
+            1 + rng.below(versions as u64) as u32
+        } else {
+            1
+        };
+        let high = low + 1 + rng.below((versions - low + 1) as u64) as u32;
+        Ranges::between(low, high)
+    };
+
+    let mut dependencies = HashMap::new();
+    for (index, &name) in names.iter().enumerate() {
+        let later_names = &names[index + 1..];
+        for &solvable in &candidates[&name] {
+            let mut known = KnownDependencies::default();
+            if !later_names.is_empty() {
+                for _ in 0..params.fan_out {
+                    let dependency = later_names[rng.below(later_names.len() as u64) as usize];
+                    let may_conflict = rng.chance(params.conflict_density);
+                    let range = version_range(&mut rng, may_conflict);
+                    known
+                        .requirements
+                        .push(pool.intern_version_set(dependency, range).into());
+                }
+                if rng.chance(params.conflict_density) {
+                    let constrained = later_names[rng.below(later_names.len() as u64) as usize];
+                    let range = version_range(&mut rng, true);
+                    known
+                        .constrains
+                        .push(pool.intern_version_set(constrained, range));
+                }
+            }
+            dependencies.insert(solvable, known);
+        }
+    }
+
+    let mut requirements = Vec::with_capacity(params.requirements);
+    let mut constraints = Vec::new();
+    if !names.is_empty() {
+        for _ in 0..params.requirements {
+            let name = names[rng.below(names.len() as u64) as usize];
+            requirements.push(pool.intern_version_set(name, Ranges::full()).into());
+            if rng.chance(params.conflict_density) {
+                let range = version_range(&mut rng, true);
+                constraints.push(pool.intern_version_set(name, range));
+            }
+        }
+    }
+
+    let generated = GeneratedPool {
+        pool,
+        names,
+        candidates,
+        dependencies,
+    };
+    (generated, requirements, constraints)
+}
+
+/// A small pseudo random number generator, so that the generated problems do
+/// not depend on the implementation of an external crate.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`, `bound` must not be zero.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Returns `true` with the given probability.
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+impl Display for GeneratedPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for name in &self.names {
+            for &solvable in &self.candidates[name] {
+                writeln!(f, "{}", self.display_solvable(solvable))?;
+                let dependencies = &self.dependencies[&solvable];
+                for requirement in &dependencies.requirements {
+                    writeln!(f, "  requires {}", requirement.requirement.display(self))?;
+                }
+                for &constraint in &dependencies.constrains {
+                    writeln!(
+                        f,
+                        "  constrains {} {}",
+                        self.display_name(self.version_set_name(constraint)),
+                        self.display_version_set(constraint)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Interner for GeneratedPool {
+    fn display_solvable(&self, solvable: SolvableId) -> impl Display + '_ {
+        let solvable = self.pool.resolve_solvable(solvable);
+        format!("{}={}", self.display_name(solvable.name), solvable.record)
+    }
+
+    fn display_name(&self, name: NameId) -> impl Display + '_ {
+        self.pool.resolve_package_name(name)
+    }
+
+    fn display_condition(&self, condition: Condition) -> impl Display + '_ {
+        match condition {
+            Condition::Extra(extra) => self.display_string(extra).to_string(),
+            Condition::VersionSetId(version_set) => format!(
+                "{} {}",
+                self.display_name(self.version_set_name(version_set)),
+                self.display_version_set(version_set)
+            ),
+            Condition::Requirement(requirement) => requirement.display(self).to_string(),
+            Condition::Marker(marker) => format!("marker '{}'", self.display_string(marker)),
+        }
+    }
+
+    fn display_version_set(&self, version_set: VersionSetId) -> impl Display + '_ {
+        self.pool.resolve_version_set(version_set)
+    }
+
+    fn display_string(&self, string_id: StringId) -> impl Display + '_ {
+        self.pool.resolve_string(string_id)
+    }
+
+    fn version_set_name(&self, version_set: VersionSetId) -> NameId {
+        self.pool.resolve_version_set_package_name(version_set)
+    }
+
+    fn solvable_name(&self, solvable: SolvableId) -> NameId {
+        self.pool.resolve_solvable(solvable).name
+    }
+
+    fn version_sets_in_union(
+        &self,
+        version_set_union: VersionSetUnionId,
+    ) -> impl Iterator<Item = VersionSetId> {
+        self.pool.resolve_version_set_union(version_set_union)
+    }
+
+    fn version_set_subset(&self, subset: VersionSetId, superset: VersionSetId) -> Option<bool> {
+        let subset = self.pool.resolve_version_set(subset);
+        let superset = self.pool.resolve_version_set(superset);
+        Some(subset.subset_of(superset))
+    }
+}
+
+impl DependencyProvider for GeneratedPool {
+    async fn filter_candidates(
+        &self,
+        candidates: &[SolvableId],
+        version_set: VersionSetId,
+        inverse: bool,
+    ) -> Vec<SolvableId> {
+        let range = self.pool.resolve_version_set(version_set);
+        candidates
+            .iter()
+            .copied()
+            .filter(|&s| range.contains(&self.pool.resolve_solvable(s).record) != inverse)
+            .collect()
+    }
+
+    async fn get_candidates(&self, name: NameId) -> Option<Candidates> {
+        let candidates = self.candidates.get(&name)?;
+        Some(Candidates {
+            candidates: candidates.clone(),
+            hint_dependencies_available: candidates.clone(),
+            ..Candidates::default()
+        })
+    }
+
+    async fn sort_candidates(&self, _solver: &SolverCache<Self>, solvables: &mut [SolvableId]) {
+        // Try the highest versions first.
+        solvables.sort_by_key(|&s| std::cmp::Reverse(self.pool.resolve_solvable(s).record));
+    }
+
+    async fn get_dependencies(&self, solvable: SolvableId) -> Dependencies {
+        Dependencies::Known(
+            self.dependencies
+                .get(&solvable)
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+
+    fn known_names(&self) -> impl Iterator<Item = NameId> + '_ {
+        self.names.iter().copied()
+    }
+}
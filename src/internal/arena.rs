@@ -58,6 +58,17 @@ impl<TId: ArenaId, TValue> Arena<TId, TValue> {
         }
     }
 
+    /// Reserves capacity for at least `additional` more values, so that they can be allocated
+    /// without growing the arena.
+    pub fn reserve(&mut self, additional: usize) {
+        let n = self.len.get() + additional;
+        let n_chunks = (n + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let chunks = self.chunks.get_mut();
+        if n_chunks > chunks.len() {
+            chunks.resize_with(n_chunks, || Vec::with_capacity(CHUNK_SIZE));
+        }
+    }
+
     /// Returns the size of the arena
     ///
     /// This is useful for using the size of previous typed arenas to build new typed arenas with
@@ -3,9 +3,11 @@ use std::cell::UnsafeCell;
 use std::collections::HashMap;
 use std::hash::{BuildHasher, Hash};
 
+use crate::internal::hash::DefaultHashBuilder;
+
 /// An insert only map where items can only be returned by cloning the values. This ensures that the
 /// map can safely be used in an immutable context.
-pub struct FrozenCopyMap<K, V, S = ahash::RandomState> {
+pub struct FrozenCopyMap<K, V, S = DefaultHashBuilder> {
     map: UnsafeCell<HashMap<K, V, S>>,
 }
 
@@ -29,6 +31,21 @@ impl<K: Eq + Hash, V: Clone, S: BuildHasher> FrozenCopyMap<K, V, S> {
     }
 }
 
+impl<K: Eq + Hash, V, S: BuildHasher + Default> FrozenCopyMap<K, V, S> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            map: UnsafeCell::new(HashMap::with_capacity_and_hasher(
+                capacity,
+                Default::default(),
+            )),
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.get_mut().reserve(additional)
+    }
+}
+
 impl<K: Eq + Hash, V, S: Default> Default for FrozenCopyMap<K, V, S> {
     fn default() -> Self {
         Self {
@@ -0,0 +1,24 @@
+//! The hash maps and sets used throughout the crate.
+//!
+//! By default, the collections use the randomly seeded hashers of [`ahash`].
+//! When the `deterministic` feature is enabled, they use the hasher of the
+//! standard library with fixed keys instead. The iteration order of a
+//! collection then only depends on its contents, which makes the output of the
+//! solver reproducible between runs. This comes at a small performance cost,
+//! because that hasher is slower than [`ahash`], and it gives up the protection
+//! against inputs that are crafted to cause many hash collisions.
+
+/// The [`std::hash::BuildHasher`] of the collections in this crate.
+#[cfg(not(feature = "deterministic"))]
+pub type DefaultHashBuilder = ahash::RandomState;
+
+/// The [`std::hash::BuildHasher`] of the collections in this crate.
+#[cfg(feature = "deterministic")]
+pub type DefaultHashBuilder =
+    std::hash::BuildHasherDefault<std::collections::hash_map::DefaultHasher>;
+
+/// A [`std::collections::HashMap`] that uses the [`DefaultHashBuilder`].
+pub type HashMap<K, V> = std::collections::HashMap<K, V, DefaultHashBuilder>;
+
+/// A [`std::collections::HashSet`] that uses the [`DefaultHashBuilder`].
+pub type HashSet<K> = std::collections::HashSet<K, DefaultHashBuilder>;
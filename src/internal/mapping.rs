@@ -60,7 +60,9 @@ impl<TId: ArenaId, TValue> Mapping<TId, TValue> {
                 .resize_with(chunk + 1, || std::array::from_fn(|_| None));
         }
         let previous_value = self.chunks[chunk][offset].replace(value);
-        self.len += 1;
+        if previous_value.is_none() {
+            self.len += 1;
+        }
         self.max = self.max.max(idx);
         previous_value
     }
@@ -182,7 +184,9 @@ impl<'a, TId: ArenaId, TValue> Iterator for MappingIter<'a, TId, TValue> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if self.offset >= self.mapping.len {
+            // Ids can be unset, so the last id is the maximum id rather than the
+            // number of values.
+            if self.mapping.len == 0 || self.offset > self.mapping.max {
                 return None;
             }
 
@@ -282,6 +286,21 @@ mod tests {
         assert_eq!(mapping.slots(), VALUES_PER_CHUNK * 2);
     }
 
+    #[test]
+    pub fn test_iter_sparse_mapping() {
+        let mut mapping = Mapping::<Id, usize>::new();
+        mapping.insert(Id::from_usize(3), 30);
+        mapping.insert(Id::from_usize(3), 31);
+        mapping.insert(Id::from_usize(7), 70);
+        assert_eq!(mapping.len(), 2);
+
+        let values = mapping.iter().map(|(id, &value)| (id.id, value));
+        assert_eq!(values.collect::<Vec<_>>(), vec![(3, 31), (7, 70)]);
+
+        mapping.unset(Id::from_usize(3));
+        assert_eq!(mapping.iter().count(), 1);
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     pub fn test_serde() {
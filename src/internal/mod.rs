@@ -1,5 +1,6 @@
 pub mod arena;
 pub mod frozen_copy_map;
+pub mod hash;
 pub mod id;
 pub mod mapping;
 pub mod small_vec;
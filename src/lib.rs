@@ -10,27 +10,41 @@
 
 #![deny(missing_docs)]
 
+mod closure;
 pub mod conflict;
+#[cfg(feature = "gen")]
+pub mod gen;
 pub(crate) mod internal;
 mod requirement;
 pub mod runtime;
 pub mod snapshot;
 mod solver;
 pub mod utils;
+mod verify;
 
 use std::{
     any::Any,
+    cmp::Ordering,
     fmt::{Debug, Display},
 };
 
+pub use closure::{closure_size, DependencyClosure};
 pub use internal::{
     id::{NameId, SolvableId, StringId, VersionSetId, VersionSetUnionId},
     mapping::Mapping,
 };
 use itertools::Itertools;
-use requirement::Condition;
-pub use requirement::{ConditionalRequirement, Requirement};
-pub use solver::{Problem, Solver, SolverCache, UnsolvableOrCancelled};
+pub use requirement::{
+    Condition, ConditionError, ConditionExpr, ConditionExprError, ConditionalConstraint,
+    ConditionalRequirement, DisplayConditionalRequirement, Requirement, RequirementGroup,
+};
+pub use solver::{
+    BacktrackEvent, Cnf, DifficultyScore, Downgrade, ExclusionReason, FrozenSolveError,
+    MultiPlatformSolution, Objective, PartialResult, PortableClause, PortableLiteral, Problem,
+    ProofStep, RemovalImpact, SolveOutcome, Solver, SolverBuilder, SolverCache, SolverStats,
+    UnknownDependencies, UnsolvableOrCancelled, WatchStats,
+};
+pub use verify::{verify_solution, ValidationError};
 
 /// An object that is used by the solver to query certain properties of
 /// different internalized objects.
@@ -74,6 +88,16 @@ pub trait Interner {
     /// user-friendly way.
     fn display_name(&self, name: NameId) -> impl Display + '_;
 
+    /// Returns an object that can be used to display the given name in
+    /// compact contexts, for instance the summary lines of an error message.
+    /// Providers with namespaced names (e.g. `channel/pkg`) can use this to
+    /// only display `pkg`.
+    ///
+    /// The default implementation displays the same as [`Self::display_name`].
+    fn display_name_short(&self, name: NameId) -> impl Display + '_ {
+        self.display_name(name)
+    }
+
     /// Returns an object that can used to display a [`Condition`] where a condition is either a [`Extra(StringId)`] or a [`VersionSetId`]
     fn display_condition(&self, condition: Condition) -> impl Display + '_;
 
@@ -94,14 +118,211 @@ pub trait Interner {
     /// Returns the name of the package for the given solvable.
     fn solvable_name(&self, solvable: SolvableId) -> NameId;
 
+    /// Returns the canonical name for the given name.
+    ///
+    /// Names that are aliases of each other should map to the same canonical
+    /// name. The solver uses the canonical name to look up candidates for a
+    /// requirement or constraint and to ensure that at most one solvable is
+    /// selected across all aliases. The candidates of the canonical name are
+    /// passed to [`DependencyProvider::filter_candidates`] together with the
+    /// original version set.
+    ///
+    /// By default, every name is its own canonical name.
+    fn canonical_name(&self, name: NameId) -> NameId {
+        name
+    }
+
     /// Returns the version sets comprising the given union.
     ///
     /// The implementor must take care that the order in which the version sets
-    /// are returned is deterministic.
+    /// are returned is deterministic, for instance by ordering them by their
+    /// [`VersionSetId`] like [`utils::Pool`] does.
     fn version_sets_in_union(
         &self,
         version_set_union: VersionSetUnionId,
     ) -> impl Iterator<Item = VersionSetId>;
+
+    /// Returns the priority of a solvable, for instance a build number.
+    ///
+    /// After [`DependencyProvider::sort_candidates`], the solver orders
+    /// consecutive candidates that compare equal according to
+    /// [`Self::compare_solvables`] (e.g. because they have the same version)
+    /// by descending priority. The order of the provider is kept otherwise.
+    /// The default implementation returns `0` for every solvable.
+    fn solvable_priority(&self, _solvable: SolvableId) -> i64 {
+        0
+    }
+
+    /// Compares the versions of two solvables of the same package, or returns
+    /// `None` if they cannot be compared. The default implementation only
+    /// knows that a solvable is equal to itself.
+    fn compare_solvables(&self, a: SolvableId, b: SolvableId) -> Option<Ordering> {
+        (a == b).then_some(Ordering::Equal)
+    }
+
+    /// Returns the priority of the channel a solvable comes from, where a
+    /// lower number means a higher priority.
+    ///
+    /// The solver tries candidates from channels with a higher priority
+    /// first, even if a channel with a lower priority has a newer version.
+    /// Within a channel the candidates keep the order of
+    /// [`DependencyProvider::sort_candidates`]. The default implementation
+    /// returns `0` for every solvable.
+    fn solvable_channel_priority(&self, _solvable: SolvableId) -> u32 {
+        0
+    }
+
+    /// Returns whether the given string exists, see
+    /// [`ConditionalRequirement::validate`]. The default implementation
+    /// assumes every string exists.
+    fn contains_string(&self, _string_id: StringId) -> bool {
+        true
+    }
+
+    /// Returns whether the given version set exists, see
+    /// [`ConditionalRequirement::validate`]. The default implementation
+    /// assumes every version set exists.
+    fn contains_version_set(&self, _version_set: VersionSetId) -> bool {
+        true
+    }
+
+    /// Returns whether the given version set union exists, see
+    /// [`ConditionalRequirement::validate`]. The default implementation
+    /// assumes every version set union exists.
+    fn contains_version_set_union(&self, _version_set_union: VersionSetUnionId) -> bool {
+        true
+    }
+
+    /// Returns whether every version that matches `subset` also matches
+    /// `superset`, or `None` if this cannot be determined. Both version sets
+    /// are for the same package.
+    ///
+    /// This is used by [`utils::find_redundant_requirements`]. The default
+    /// implementation returns `None`.
+    fn version_set_subset(&self, _subset: VersionSetId, _superset: VersionSetId) -> Option<bool> {
+        None
+    }
+
+    /// Returns whether `solvable` matches `version_set` when metadata of the
+    /// version is taken into account that [`DependencyProvider::filter_candidates`]
+    /// does not look at, or `None` to leave the decision to it. The solvable
+    /// belongs to the package of the version set.
+    ///
+    /// Some ecosystems have version components that affect matching but not
+    /// ordering, like PEP 440 local versions or SemVer build metadata. Two
+    /// solvables can then share a version while only one of them matches a
+    /// version set. A provider can implement the rules for such components
+    /// here instead of in every [`DependencyProvider::filter_candidates`]
+    /// call, the solver applies them to the candidates it filtered. The
+    /// default implementation returns `None` for every solvable.
+    fn matches_with_metadata(
+        &self,
+        _solvable: SolvableId,
+        _version_set: VersionSetId,
+    ) -> Option<bool> {
+        None
+    }
+
+    /// Returns the version sets of virtual packages that `solvable` provides,
+    /// like `python-abi 3.11` for a python interpreter.
+    ///
+    /// A solvable that provides a version set of a virtual package satisfies
+    /// every requirement on that package whose version set is a superset of
+    /// it, see [`Self::version_set_subset`]. Constraints on the virtual
+    /// package apply to the solvable in the same way. To be considered by the
+    /// solver, the providing solvables have to be returned as candidates of
+    /// the virtual package by [`DependencyProvider::get_candidates`]. The
+    /// default implementation returns no version sets.
+    fn provided_version_sets(&self, _solvable: SolvableId) -> impl Iterator<Item = VersionSetId> {
+        std::iter::empty()
+    }
+
+    /// Returns all the version sets that exist for the package with the given
+    /// name, for instance to show every constraint seen on a package when
+    /// explaining a conflict.
+    ///
+    /// The default implementation returns no version sets, [`utils::Pool`]
+    /// implements this as [`utils::Pool::version_sets_for_name`].
+    fn version_sets_for_name(&self, _name: NameId) -> impl Iterator<Item = VersionSetId> {
+        std::iter::empty()
+    }
+
+    /// Returns the bounds of the versions that match the version set, or
+    /// `None` if they cannot be described. Either bound can be left out if
+    /// the version set is unbounded on that side.
+    ///
+    /// [`conflict::DisplayUnsat`] uses this together with
+    /// [`Self::compare_to_bound`] to suggest the closest available candidate
+    /// for a requirement without candidates, see
+    /// [`VersionSetBounds::closest_candidate`]. The default implementation
+    /// returns `None`.
+    fn version_set_bounds(&self, _version_set: VersionSetId) -> Option<VersionSetBounds> {
+        None
+    }
+
+    /// Compares the version of a solvable to a bound, or returns `None` if
+    /// they cannot be compared. The default implementation compares to
+    /// [`VersionBound::Solvable`] bounds with [`Self::compare_solvables`] and
+    /// cannot compare to [`VersionBound::Version`] bounds.
+    fn compare_to_bound(&self, solvable: SolvableId, bound: VersionBound) -> Option<Ordering> {
+        match bound {
+            VersionBound::Solvable(other) => self.compare_solvables(solvable, other),
+            VersionBound::Version(_) => None,
+        }
+    }
+}
+
+/// A bound of [`VersionSetBounds`].
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum VersionBound {
+    /// The bound is the version of a solvable.
+    Solvable(SolvableId),
+    /// The bound is a version for which there might not be a solvable, like
+    /// `2.0` in `>=2.0`.
+    Version(StringId),
+}
+
+/// The bounds of the versions that match a version set, see
+/// [`Interner::version_set_bounds`].
+#[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
+pub struct VersionSetBounds {
+    /// The lowest version that matches the version set, if any.
+    pub lower: Option<VersionBound>,
+    /// The highest version that matches the version set, if any.
+    pub upper: Option<VersionBound>,
+}
+
+impl VersionSetBounds {
+    /// Returns the candidate that comes closest to matching the version set,
+    /// out of `candidates` that do not match it.
+    ///
+    /// The highest candidate at or below the lower bound is preferred over the
+    /// lowest candidate at or above the upper bound. Candidates that the
+    /// interner cannot compare to the bounds (see
+    /// [`Interner::compare_to_bound`]) are never suggested.
+    pub fn closest_candidate(
+        &self,
+        candidates: &[SolvableId],
+        interner: &impl Interner,
+    ) -> Option<SolvableId> {
+        let compare = |a: SolvableId, b: SolvableId| {
+            interner.compare_solvables(a, b).unwrap_or(Ordering::Equal)
+        };
+        let outside = |bound: Option<VersionBound>, side: Ordering| {
+            candidates.iter().copied().filter(move |&candidate| {
+                bound.is_some_and(|bound| {
+                    matches!(
+                        interner.compare_to_bound(candidate, bound),
+                        Some(ordering) if ordering != side.reverse()
+                    )
+                })
+            })
+        };
+
+        outside(self.lower, Ordering::Less)
+            .max_by(|&a, &b| compare(a, b))
+            .or_else(|| outside(self.upper, Ordering::Greater).min_by(|&a, &b| compare(a, b)))
+    }
 }
 
 /// Defines implementation specific behavior for the solver and a way for the
@@ -120,6 +341,9 @@ pub trait DependencyProvider: Sized + Interner {
 
     /// Obtains a list of solvables that should be considered when a package
     /// with the given name is requested.
+    ///
+    /// For a virtual package the candidates are the solvables of other
+    /// packages that provide it, see [`Interner::provided_version_sets`].
     async fn get_candidates(&self, name: NameId) -> Option<Candidates>;
 
     /// Sort the specified solvables based on which solvable to try first. The
@@ -128,9 +352,66 @@ pub trait DependencyProvider: Sized + Interner {
     /// tried. This continues until a solution is found.
     async fn sort_candidates(&self, solver: &SolverCache<Self>, solvables: &mut [SolvableId]);
 
+    /// Sorts the specified solvables like [`Self::sort_candidates`], given the
+    /// order in which the provider sorted the candidates of the same package
+    /// during a previous solve, see [`SolverBuilder::with_previous_sorts`].
+    ///
+    /// Providers can use the previous order to break ties between candidates,
+    /// so that repeated solves of similar problems keep selecting the same
+    /// candidates. `previous` is `None` if no order is known for the package.
+    /// The default implementation ignores the previous order and calls
+    /// [`Self::sort_candidates`].
+    async fn sort_candidates_with_previous(
+        &self,
+        solver: &SolverCache<Self>,
+        solvables: &mut [SolvableId],
+        _previous: Option<&PreviousSort>,
+    ) {
+        self.sort_candidates(solver, solvables).await
+    }
+
+    /// Returns a score of a candidate, higher scoring candidates are tried
+    /// first.
+    ///
+    /// Scores are a softer alternative to [`Self::sort_candidates`]: after
+    /// the candidates of a requirement have been sorted, they are reordered
+    /// by descending score with a stable sort, so candidates with equal scores
+    /// keep their sorted order. The default implementation scores every
+    /// candidate `0.0`, which leaves the sorted order as is. A favored or
+    /// locked candidate is still tried before all others.
+    ///
+    /// Scores only decide which candidate of a requirement the solver tries
+    /// first. Which requirement the solver decides on next is still
+    /// determined by the activity of the packages, which grows with the
+    /// number of conflicts a package is involved in.
+    fn score_candidate(&self, _solvable: SolvableId) -> f64 {
+        0.0
+    }
+
     /// Returns the dependencies for the specified solvable.
     async fn get_dependencies(&self, solvable: SolvableId) -> Dependencies;
 
+    /// Returns the dependencies for multiple solvables at once. The returned
+    /// [`Vec`] must contain the dependencies of each of the `solvables`, in
+    /// the same order.
+    ///
+    /// The solver calls this method when it is about to consider a list of
+    /// candidates of a package for which the dependencies are cheaply
+    /// available (see [`Candidates::hint_dependencies_available`]).
+    /// Providers that can retrieve the dependencies of multiple solvables
+    /// more efficiently in bulk can override this method.
+    ///
+    /// The default implementation concurrently calls
+    /// [`DependencyProvider::get_dependencies`] for every solvable.
+    async fn get_dependencies_batch(&self, solvables: &[SolvableId]) -> Vec<Dependencies> {
+        futures::future::join_all(
+            solvables
+                .iter()
+                .map(|&solvable| self.get_dependencies(solvable)),
+        )
+        .await
+    }
+
     /// Whether the solver should stop the dependency resolution algorithm.
     ///
     /// This method gets called at the beginning of each unit propagation round
@@ -141,11 +422,82 @@ pub trait DependencyProvider: Sized + Interner {
     fn should_cancel_with_value(&self) -> Option<Box<dyn Any>> {
         None
     }
+
+    /// Returns all the package names known to the provider that have at least
+    /// one candidate.
+    ///
+    /// The solver itself never calls this method, it is intended for tools
+    /// built on top of the provider, like index views or autocompletion. The
+    /// default implementation returns no names.
+    fn known_names(&self) -> impl Iterator<Item = NameId> + '_ {
+        std::iter::empty()
+    }
+
+    /// Returns more candidates for the package with the given name, after the
+    /// candidates returned so far were marked with
+    /// [`Candidates::more_available`]. The returned candidates are added to
+    /// the ones returned before, so they should not include those. Set
+    /// [`Candidates::more_available`] again if there are even more candidates.
+    ///
+    /// The solver only calls this method when the problem is unsolvable with
+    /// the candidates it has. The [`Candidates::favored`] and
+    /// [`Candidates::locked`] solvables of the returned candidates are
+    /// ignored. The default implementation returns `None`, which means there
+    /// are no more candidates.
+    async fn get_more_candidates(&self, _name: NameId) -> Option<Candidates> {
+        None
+    }
+
+    /// Returns the reason why a candidate is not available for the given
+    /// platform, or `None` if it is. This is only called when solving for
+    /// several platforms at once, see [`Solver::solve_multi_platform`]. The
+    /// candidates that are not available are excluded like the ones in
+    /// [`Candidates::excluded`].
+    ///
+    /// The default implementation returns `None`, which means every candidate
+    /// is available for every platform.
+    fn excluded_on_platform(&self, _solvable: SolvableId, _platform: StringId) -> Option<StringId> {
+        None
+    }
+
+    /// Evaluates a [`Condition::Marker`] against the environment the solution
+    /// is for. Requirements with a marker that does not hold are dropped
+    /// before solving.
+    ///
+    /// The default implementation returns `false`, so requirements with a
+    /// marker are never active.
+    fn evaluate_marker(&self, _marker: StringId) -> bool {
+        false
+    }
+}
+
+/// The order in which the candidates of a package were sorted during a
+/// previous solve, see [`DependencyProvider::sort_candidates_with_previous`].
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct PreviousSort {
+    /// The candidates in the order in which
+    /// [`DependencyProvider::sort_candidates_with_previous`] sorted them, with
+    /// ties broken by [`Interner::solvable_priority`] if there was no previous
+    /// order. If different sets of candidates of the package were sorted, the
+    /// most recently sorted candidates come first.
+    pub order: Vec<SolvableId>,
+}
+
+impl PreviousSort {
+    /// Returns the position of the solvable in the previous order, or `None`
+    /// if it was not sorted before.
+    pub fn position(&self, solvable: SolvableId) -> Option<usize> {
+        self.order.iter().position(|&s| s == solvable)
+    }
 }
 
 /// A list of candidate solvables for a specific package. This is returned from
 /// [`DependencyProvider::get_candidates`].
+///
+/// New fields may be added in the future, so outside of this crate the struct
+/// has to be created with [`Candidates::new`] or [`Candidates::default`].
 #[derive(Default, Clone, Debug)]
+#[non_exhaustive]
 pub struct Candidates {
     /// A list of all solvables for the package.
     pub candidates: Vec<SolvableId>,
@@ -183,6 +535,56 @@ pub struct Candidates {
     /// consider these solvables when forming a solution but will use
     /// them in the error message if no solution could be found.
     pub excluded: Vec<(SolvableId, StringId)>,
+
+    /// Whether there are more candidates for the package than the ones
+    /// returned so far. The solver only requests them with
+    /// [`DependencyProvider::get_more_candidates`] if no solution can be found
+    /// with the candidates it has. This allows a provider to return the best
+    /// candidates of a package first and only materialize the others when
+    /// they are needed, see also [`SolverBuilder::with_candidate_limit`].
+    pub more_available: bool,
+
+    /// The dependencies of some of the candidates, if the provider already
+    /// has them at hand. The solver caches them together with the candidates
+    /// and never calls [`DependencyProvider::get_dependencies`] for these
+    /// solvables, which saves a round-trip to the provider. Unlike
+    /// [`Candidates::hint_dependencies_available`] the dependencies do not
+    /// have to be requested at all.
+    ///
+    /// If the dependencies of a solvable are already cached, for instance
+    /// because the solvable is a candidate of another package as well, the
+    /// cached dependencies are kept.
+    pub prefetched: Vec<(SolvableId, Dependencies)>,
+}
+
+impl Candidates {
+    /// Creates a list of candidates for a package without any further
+    /// information about them.
+    pub fn new(candidates: Vec<SolvableId>) -> Self {
+        Self {
+            candidates,
+            ..Self::default()
+        }
+    }
+
+    /// Sets whether there are more candidates for the package, see
+    /// [`Candidates::more_available`].
+    ///
+    /// Returns the [`Candidates`] for further mutation.
+    pub fn with_more_available(self, more_available: bool) -> Self {
+        Self {
+            more_available,
+            ..self
+        }
+    }
+
+    /// Sets the dependencies of some of the candidates, see
+    /// [`Candidates::prefetched`].
+    ///
+    /// Returns the [`Candidates`] for further mutation.
+    pub fn with_prefetched(self, prefetched: Vec<(SolvableId, Dependencies)>) -> Self {
+        Self { prefetched, ..self }
+    }
 }
 
 /// Holds information about the dependencies of a package.
@@ -224,4 +626,28 @@ pub struct KnownDependencies {
         serde(default, skip_serializing_if = "Vec::is_empty")
     )]
     pub constrains: Vec<VersionSetId>,
+
+    /// Defines constraints like `constrains`, that only apply when their
+    /// conditions hold, e.g. to constrain a package only on some platforms.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub conditional_constrains: Vec<ConditionalConstraint>,
+}
+
+impl KnownDependencies {
+    /// Returns the `constrains` and the `conditional_constrains` whose
+    /// conditions hold, see [`ConditionalConstraint::is_active`].
+    pub fn active_constrains<'a>(
+        &'a self,
+        provider: &'a impl DependencyProvider,
+    ) -> impl Iterator<Item = VersionSetId> + 'a {
+        self.constrains.iter().copied().chain(
+            self.conditional_constrains
+                .iter()
+                .filter(|constraint| constraint.is_active(provider))
+                .map(|constraint| constraint.version_set),
+        )
+    }
 }
@@ -1,7 +1,8 @@
-use crate::{Interner, StringId, VersionSetId, VersionSetUnionId};
+use crate::{DependencyProvider, Interner, StringId, VersionSetId, VersionSetUnionId};
 use itertools::Itertools;
 use std::fmt::Display;
 
+/// A condition under which a [`ConditionalRequirement`] becomes active.
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Condition {
@@ -9,6 +10,17 @@ pub enum Condition {
     VersionSetId(VersionSetId),
     /// An extra which if enabled, requires further dependencies to be met.
     Extra(StringId),
+    /// A condition that is met if a solvable that satisfies the requirement is
+    /// selected. For a [`Requirement::Union`] this is the case if a solvable
+    /// matching any of the version sets of the union is selected.
+    Requirement(Requirement),
+    /// A condition on the environment the solution is for, like the operating
+    /// system, rather than on the solution itself. The string is the marker
+    /// expression, which is evaluated with
+    /// [`crate::DependencyProvider::evaluate_marker`] when the clauses of the
+    /// requirement are generated. If the marker does not hold, the requirement
+    /// is dropped entirely.
+    Marker(StringId),
 }
 
 impl From<VersionSetId> for Condition {
@@ -28,10 +40,329 @@ impl From<Condition> for VersionSetId {
         match value {
             Condition::VersionSetId(id) => id,
             Condition::Extra(_) => panic!("Cannot convert Extra to VersionSetId"),
+            Condition::Requirement(_) => panic!("Cannot convert Requirement to VersionSetId"),
+            Condition::Marker(_) => panic!("Cannot convert Marker to VersionSetId"),
         }
     }
 }
 
+impl Condition {
+    /// Returns the version sets of which a matching solvable must be selected
+    /// for the condition to be met. Returns nothing for an extra or a marker.
+    pub(crate) fn version_sets(
+        self,
+        interner: &impl Interner,
+    ) -> impl Iterator<Item = VersionSetId> + '_ {
+        match self {
+            Condition::VersionSetId(version_set) => {
+                itertools::Either::Left(Some(version_set).into_iter())
+            }
+            Condition::Extra(_) | Condition::Marker(_) => itertools::Either::Left(None.into_iter()),
+            Condition::Requirement(requirement) => itertools::Either::Right(
+                requirement.version_sets(interner).collect_vec().into_iter(),
+            ),
+        }
+    }
+
+    /// Returns a condition that is met if all of the given conditions are met.
+    ///
+    /// A single condition is returned as is.
+    pub fn all(conditions: impl IntoIterator<Item = impl Into<ConditionExpr>>) -> ConditionExpr {
+        let mut conditions = conditions.into_iter().map(Into::into).collect::<Vec<_>>();
+        if conditions.len() == 1 {
+            conditions.pop().unwrap()
+        } else {
+            ConditionExpr::All(conditions)
+        }
+    }
+
+    /// Returns a condition that is met if any of the given conditions is met.
+    ///
+    /// A single condition is returned as is.
+    pub fn any(conditions: impl IntoIterator<Item = impl Into<ConditionExpr>>) -> ConditionExpr {
+        let mut conditions = conditions.into_iter().map(Into::into).collect::<Vec<_>>();
+        if conditions.len() == 1 {
+            conditions.pop().unwrap()
+        } else {
+            ConditionExpr::Any(conditions)
+        }
+    }
+
+    /// Returns a condition that is met if the given condition is not met.
+    ///
+    /// A double negation is removed. The solver can only express conditions
+    /// that are met, so [`Requirement::when`] returns an error if a negation
+    /// is not cancelled out by another negation.
+    pub fn not(condition: impl Into<ConditionExpr>) -> ConditionExpr {
+        let mut condition = condition.into();
+        match &mut condition {
+            ConditionExpr::Not(negated) => {
+                std::mem::replace(&mut **negated, ConditionExpr::All(Vec::new()))
+            }
+            _ => ConditionExpr::Not(Box::new(condition)),
+        }
+    }
+}
+
+/// A combination of [`Condition`]s, built with [`Condition::all`],
+/// [`Condition::any`] and [`Condition::not`].
+///
+/// Use [`Requirement::when`] to turn a requirement and a condition into
+/// [`ConditionalRequirement`]s:
+///
+/// ```
+/// # use resolvo::{Condition, Requirement, StringId, VersionSetId};
+/// let requirement = Requirement::from(VersionSetId(0));
+/// let (a, b, c) = (VersionSetId(1), VersionSetId(2), StringId(0));
+///
+/// // The requirement is active if `c` and either `a` or `b` are met.
+/// let conditional_requirements = requirement
+///     .when(Condition::all([Condition::any([a, b]), c.into()]))
+///     .unwrap();
+/// assert_eq!(conditional_requirements.len(), 2);
+/// assert_eq!(
+///     conditional_requirements[0].conditions,
+///     vec![Condition::VersionSetId(a), Condition::Extra(c)]
+/// );
+/// assert_eq!(
+///     conditional_requirements[1].conditions,
+///     vec![Condition::VersionSetId(b), Condition::Extra(c)]
+/// );
+/// ```
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ConditionExpr {
+    /// A single condition.
+    Condition(Condition),
+    /// Met if all of the conditions are met.
+    All(Vec<ConditionExpr>),
+    /// Met if any of the conditions is met.
+    Any(Vec<ConditionExpr>),
+    /// Met if the condition is not met.
+    Not(Box<ConditionExpr>),
+}
+
+impl<T: Into<Condition>> From<T> for ConditionExpr {
+    fn from(value: T) -> Self {
+        ConditionExpr::Condition(value.into())
+    }
+}
+
+impl Drop for ConditionExpr {
+    fn drop(&mut self) {
+        // Dropping the nested conditions recursively could exhaust the stack
+        // for deeply nested conditions, so they are unnested one at a time.
+        let mut nested = Vec::new();
+        self.take_nested(&mut nested);
+        while let Some(mut condition) = nested.pop() {
+            condition.take_nested(&mut nested);
+        }
+    }
+}
+
+impl ConditionExpr {
+    /// Moves the conditions directly nested in this condition into `nested`.
+    fn take_nested(&mut self, nested: &mut Vec<ConditionExpr>) {
+        match self {
+            ConditionExpr::Condition(_) => {}
+            ConditionExpr::All(conditions) | ConditionExpr::Any(conditions) => {
+                nested.append(conditions)
+            }
+            ConditionExpr::Not(condition) => nested.push(std::mem::replace(
+                &mut **condition,
+                ConditionExpr::All(Vec::new()),
+            )),
+        }
+    }
+
+    /// Returns an error if the combinators of this condition are nested more
+    /// than `max_depth` levels deep. This does not recurse, so it is safe to
+    /// call on arbitrarily deep conditions.
+    fn check_depth(&self, max_depth: usize) -> Result<(), ConditionExprError> {
+        let mut stack = vec![(self, 0)];
+        while let Some((condition, depth)) = stack.pop() {
+            if depth > max_depth {
+                return Err(ConditionExprError::TooDeep { max_depth });
+            }
+            match condition {
+                ConditionExpr::Condition(_) => {}
+                ConditionExpr::All(conditions) | ConditionExpr::Any(conditions) => {
+                    stack.extend(conditions.iter().map(|condition| (condition, depth + 1)))
+                }
+                ConditionExpr::Not(condition) => stack.push((condition, depth + 1)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the condition as a disjunction of conjunctions of
+    /// [`Condition`]s.
+    ///
+    /// Returns an error if a [`Condition`] is negated, the solver can only
+    /// express conditions that are met, or if the disjunction would consist
+    /// of more than `max_terms` conjunctions. The depth of the condition must
+    /// have been checked before, this recurses once per combinator.
+    fn disjunctive_normal_form(
+        &self,
+        negated: bool,
+        max_terms: usize,
+    ) -> Result<Vec<Vec<Condition>>, ConditionExprError> {
+        match (self, negated) {
+            (ConditionExpr::Condition(condition), false) => Ok(vec![vec![*condition]]),
+            (ConditionExpr::Condition(condition), true) => {
+                Err(ConditionExprError::Negated(*condition))
+            }
+            (ConditionExpr::Not(condition), negated) => {
+                condition.disjunctive_normal_form(!negated, max_terms)
+            }
+            (ConditionExpr::All(conditions), false) | (ConditionExpr::Any(conditions), true) => {
+                conditions
+                    .iter()
+                    .try_fold(vec![Vec::new()], |terms, condition| {
+                        let condition_terms =
+                            condition.disjunctive_normal_form(negated, max_terms)?;
+                        if terms.len().saturating_mul(condition_terms.len()) > max_terms {
+                            return Err(ConditionExprError::TooManyTerms { max_terms });
+                        }
+                        Ok(terms
+                            .iter()
+                            .cartesian_product(&condition_terms)
+                            .map(|(term, condition_term)| {
+                                term.iter().chain(condition_term).copied().collect()
+                            })
+                            .collect())
+                    })
+            }
+            (ConditionExpr::Any(conditions), false) | (ConditionExpr::All(conditions), true) => {
+                let mut terms = Vec::new();
+                for condition in conditions {
+                    terms.extend(condition.disjunctive_normal_form(negated, max_terms)?);
+                    if terms.len() > max_terms {
+                        return Err(ConditionExprError::TooManyTerms { max_terms });
+                    }
+                }
+                Ok(terms)
+            }
+        }
+    }
+
+    /// Returns the [`ConditionalRequirement`]s that make `requirement` active
+    /// when this condition is met, one for every conjunction of the
+    /// disjunctive normal form of the condition.
+    pub(crate) fn conditional_requirements(
+        &self,
+        requirement: Requirement,
+        max_depth: usize,
+        max_terms: usize,
+    ) -> Result<Vec<ConditionalRequirement>, ConditionExprError> {
+        self.check_depth(max_depth)?;
+        let terms = self.disjunctive_normal_form(false, max_terms)?;
+        if terms.is_empty() {
+            return Err(ConditionExprError::NeverMet);
+        }
+        Ok(terms
+            .into_iter()
+            .map(|conditions| ConditionalRequirement::new(conditions, requirement))
+            .collect())
+    }
+}
+
+/// The default maximum nesting depth of the combinators of a
+/// [`ConditionExpr`].
+pub(crate) const DEFAULT_MAX_CONDITION_DEPTH: usize = 64;
+
+/// The default maximum number of [`ConditionalRequirement`]s a
+/// [`ConditionExpr`] may expand to.
+pub(crate) const DEFAULT_MAX_CONDITION_TERMS: usize = 1024;
+
+/// A [`ConditionExpr`] that cannot be turned into [`ConditionalRequirement`]s,
+/// see [`Solver::conditional_requirements`](crate::Solver::conditional_requirements).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConditionExprError {
+    /// A condition is negated after applying De Morgan's laws. The solver
+    /// cannot express that a condition is not met.
+    Negated(Condition),
+    /// The condition can never be met, for instance because it is a
+    /// [`Condition::any`] of no conditions. The requirement would never
+    /// be active.
+    NeverMet,
+    /// The combinators of the condition are nested deeper than the maximum
+    /// depth, see [`SolverBuilder::with_max_condition_depth`](crate::SolverBuilder::with_max_condition_depth).
+    TooDeep {
+        /// The maximum depth that was exceeded.
+        max_depth: usize,
+    },
+    /// The condition expands to more conditional requirements than the
+    /// maximum, see [`SolverBuilder::with_max_condition_terms`](crate::SolverBuilder::with_max_condition_terms).
+    TooManyTerms {
+        /// The maximum number of conditional requirements that was exceeded.
+        max_terms: usize,
+    },
+}
+
+impl Display for ConditionExprError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionExprError::Negated(_) => {
+                write!(f, "negated conditions are not supported by the solver")
+            }
+            ConditionExprError::NeverMet => write!(f, "the condition can never be met"),
+            ConditionExprError::TooDeep { max_depth } => write!(
+                f,
+                "the condition is nested deeper than the maximum depth of {max_depth}"
+            ),
+            ConditionExprError::TooManyTerms { max_terms } => write!(
+                f,
+                "the condition expands to more than {max_terms} conditional requirements"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConditionExprError {}
+
+/// An id referenced by a [`ConditionalRequirement`] that does not exist, see
+/// [`ConditionalRequirement::validate`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConditionError {
+    /// A condition refers to an extra that does not exist.
+    UnknownExtra(StringId),
+    /// A condition refers to a marker that does not exist.
+    UnknownMarker(StringId),
+    /// A condition or the requirement refers to a version set that does not
+    /// exist.
+    UnknownVersionSet(VersionSetId),
+    /// A condition or the requirement refers to a version set union that does
+    /// not exist.
+    UnknownVersionSetUnion(VersionSetUnionId),
+}
+
+impl Display for ConditionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionError::UnknownExtra(string_id) => {
+                write!(f, "the extra with id {} does not exist", string_id.0)
+            }
+            ConditionError::UnknownMarker(string_id) => {
+                write!(f, "the marker with id {} does not exist", string_id.0)
+            }
+            ConditionError::UnknownVersionSet(version_set) => {
+                write!(
+                    f,
+                    "the version set with id {} does not exist",
+                    version_set.0
+                )
+            }
+            ConditionError::UnknownVersionSetUnion(version_set_union) => write!(
+                f,
+                "the version set union with id {} does not exist",
+                version_set_union.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConditionError {}
+
 /// Specifies a conditional requirement, where the requirement is only active when the condition is met.
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -50,6 +381,62 @@ impl ConditionalRequirement {
             requirement,
         }
     }
+
+    /// Checks that the conditions and the requirement only refer to strings,
+    /// version sets and version set unions that exist in the interner, see
+    /// [`Interner::contains_string`], [`Interner::contains_version_set`] and
+    /// [`Interner::contains_version_set_union`].
+    ///
+    /// The solver assumes that all ids are valid, so this can be used to
+    /// report a stale id before solving, instead of failing while solving.
+    pub fn validate(&self, interner: &impl Interner) -> Result<(), ConditionError> {
+        let validate_requirement = |requirement: Requirement| match requirement {
+            Requirement::Single(version_set) => validate_version_set(interner, version_set),
+            Requirement::Union(version_set_union)
+            | Requirement::ExactlyOne(version_set_union)
+            | Requirement::AtLeast {
+                version_sets: version_set_union,
+                ..
+            } => {
+                if !interner.contains_version_set_union(version_set_union) {
+                    return Err(ConditionError::UnknownVersionSetUnion(version_set_union));
+                }
+                interner
+                    .version_sets_in_union(version_set_union)
+                    .try_for_each(|version_set| validate_version_set(interner, version_set))
+            }
+        };
+
+        for &condition in &self.conditions {
+            match condition {
+                Condition::VersionSetId(version_set) => {
+                    validate_version_set(interner, version_set)?
+                }
+                Condition::Extra(extra) => {
+                    if !interner.contains_string(extra) {
+                        return Err(ConditionError::UnknownExtra(extra));
+                    }
+                }
+                Condition::Marker(marker) => {
+                    if !interner.contains_string(marker) {
+                        return Err(ConditionError::UnknownMarker(marker));
+                    }
+                }
+                Condition::Requirement(requirement) => validate_requirement(requirement)?,
+            }
+        }
+        validate_requirement(self.requirement)
+    }
+
+    /// Returns the canonical form of this requirement in which the conditions
+    /// are sorted and deduplicated. Requirements with the same canonical form
+    /// are semantically identical.
+    pub(crate) fn canonicalize(mut self) -> Self {
+        self.conditions.sort_unstable();
+        self.conditions.dedup();
+        self
+    }
+
     /// Returns the version sets that satisfy the requirement.
     pub fn requirement_version_sets<'i>(
         &'i self,
@@ -72,6 +459,93 @@ impl ConditionalRequirement {
     pub fn into_condition_and_requirement(self) -> (Vec<Condition>, Requirement) {
         (self.conditions, self.requirement)
     }
+
+    /// Returns an object that implements `Display` for the requirement, for
+    /// instance to list the dependencies of a solvable in a resolved tree.
+    ///
+    /// The conditions are left out unless they are enabled with
+    /// [`DisplayConditionalRequirement::show_conditions`].
+    pub fn display<'i, I: Interner>(
+        &'i self,
+        interner: &'i I,
+    ) -> DisplayConditionalRequirement<'i, I> {
+        DisplayConditionalRequirement {
+            interner,
+            requirement: self,
+            show_conditions: false,
+        }
+    }
+}
+
+/// A struct implementing [`Display`] for a [`ConditionalRequirement`], see
+/// [`ConditionalRequirement::display`].
+pub struct DisplayConditionalRequirement<'i, I: Interner> {
+    interner: &'i I,
+    requirement: &'i ConditionalRequirement,
+    show_conditions: bool,
+}
+
+impl<'i, I: Interner> DisplayConditionalRequirement<'i, I> {
+    /// Sets whether the conditions of the requirement are displayed after
+    /// it, like `foo >=1 (if gui)`, with [`Interner::display_condition`]. A
+    /// requirement without conditions is displayed the same either way. Off
+    /// by default.
+    pub fn show_conditions(mut self, show_conditions: bool) -> Self {
+        self.show_conditions = show_conditions;
+        self
+    }
+}
+
+impl<'i, I: Interner> Display for DisplayConditionalRequirement<'i, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.requirement.requirement.display(self.interner))?;
+        if self.show_conditions && !self.requirement.conditions.is_empty() {
+            write!(
+                f,
+                " (if {})",
+                self.requirement
+                    .conditions
+                    .iter()
+                    .map(|&condition| self.interner.display_condition(condition))
+                    .format(" and ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Specifies a constraint that only applies when its conditions are met, see
+/// [`crate::KnownDependencies::conditional_constrains`].
+///
+/// Only [`Condition::Marker`]s are supported. They are evaluated with
+/// [`DependencyProvider::evaluate_marker`] when the clauses of the constraint
+/// are generated, and the constraint is dropped unless all of them hold. A
+/// constraint with any other kind of condition never applies.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConditionalConstraint {
+    /// The conditions that must be met for the constraint to apply.
+    pub conditions: Vec<Condition>,
+    /// The version set that solvables of its package must match.
+    pub version_set: VersionSetId,
+}
+
+impl ConditionalConstraint {
+    /// Creates a new conditional constraint.
+    pub fn new(conditions: Vec<Condition>, version_set: VersionSetId) -> Self {
+        Self {
+            conditions,
+            version_set,
+        }
+    }
+
+    /// Returns true if all the conditions of the constraint hold.
+    pub fn is_active(&self, provider: &impl DependencyProvider) -> bool {
+        self.conditions.iter().all(|&condition| match condition {
+            Condition::Marker(marker) => provider.evaluate_marker(marker),
+            _ => false,
+        })
+    }
 }
 
 impl From<Requirement> for ConditionalRequirement {
@@ -110,6 +584,43 @@ impl From<(VersionSetId, Vec<Condition>)> for ConditionalRequirement {
     }
 }
 
+/// Requirements that are either all part of a solution or not at all, see
+/// [`crate::Problem::requirement_groups`].
+///
+/// Unlike the [`Condition`]s of a [`ConditionalRequirement`], whether a group
+/// is active does not depend on the rest of the solution. The solver
+/// activates a group if all of its requirements can be satisfied together.
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequirementGroup {
+    /// The requirements that are activated together.
+    pub requirements: Vec<ConditionalRequirement>,
+}
+
+impl RequirementGroup {
+    /// Creates a new group of requirements.
+    pub fn new(requirements: Vec<ConditionalRequirement>) -> Self {
+        Self { requirements }
+    }
+}
+
+impl From<Vec<ConditionalRequirement>> for RequirementGroup {
+    fn from(requirements: Vec<ConditionalRequirement>) -> Self {
+        Self { requirements }
+    }
+}
+
+fn validate_version_set(
+    interner: &impl Interner,
+    version_set: VersionSetId,
+) -> Result<(), ConditionError> {
+    if interner.contains_version_set(version_set) {
+        Ok(())
+    } else {
+        Err(ConditionError::UnknownVersionSet(version_set))
+    }
+}
+
 /// Specifies the dependency of a solvable on a set of version sets.
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -121,6 +632,32 @@ pub enum Requirement {
     /// This variant is typically used for requirements that can be satisfied by two or more
     /// version sets belonging to _different_ packages.
     Union(VersionSetUnionId),
+    /// Specifies a dependency on at least `n` solvables that each belong to any of the version
+    /// sets contained in the union, for instance at least two mirrors out of a set of mirrors.
+    /// Because at most one solvable of a package can be selected, the solvables have to belong
+    /// to different packages.
+    ///
+    /// The requirement is encoded by counting the selected candidates, which takes a number of
+    /// auxiliary variables and clauses that grows with the product of `n` and the number of
+    /// candidates. If `n` is zero the requirement is always satisfied, and if `n` exceeds the
+    /// number of candidates it cannot be satisfied. As a [`Condition::Requirement`] it is met
+    /// like a [`Requirement::Union`], by a single matching solvable.
+    AtLeast {
+        /// The number of solvables that have to be selected.
+        n: usize,
+        /// The version sets the solvables have to match.
+        version_sets: VersionSetUnionId,
+    },
+    /// Specifies a dependency on exactly one solvable that belongs to any of the version sets
+    /// contained in the union, for instance exactly one out of a set of mutually exclusive
+    /// implementations.
+    ///
+    /// The requirement is encoded like a [`Requirement::Union`], to select at least one of the
+    /// candidates, together with a clause for every pair of candidates of different packages
+    /// that forbids selecting both. If none of the candidates can be selected, the requirement
+    /// cannot be satisfied. As a [`Condition::Requirement`] it is met like a
+    /// [`Requirement::Union`].
+    ExactlyOne(VersionSetUnionId),
 }
 
 impl Default for Requirement {
@@ -142,11 +679,46 @@ impl From<VersionSetUnionId> for Requirement {
 }
 
 impl Requirement {
+    /// Returns the [`ConditionalRequirement`]s that make this requirement
+    /// active when `condition` is met.
+    ///
+    /// A [`ConditionalRequirement`] is only active if all of its conditions
+    /// are met, so a condition that is met if any of several conditions is
+    /// met results in multiple [`ConditionalRequirement`]s.
+    ///
+    /// Returns an error if a [`Condition`] is negated after applying De
+    /// Morgan's laws, because the solver cannot express that a condition is
+    /// not met, or if the condition can never be met. Conditions are subject
+    /// to the default limits on their depth and size, use
+    /// [`Solver::conditional_requirements`](crate::Solver::conditional_requirements)
+    /// to apply the limits configured for a solver instead.
+    pub fn when(
+        self,
+        condition: impl Into<ConditionExpr>,
+    ) -> Result<Vec<ConditionalRequirement>, ConditionExprError> {
+        condition.into().conditional_requirements(
+            self,
+            DEFAULT_MAX_CONDITION_DEPTH,
+            DEFAULT_MAX_CONDITION_TERMS,
+        )
+    }
+
     /// Returns an object that implements `Display` for the requirement.
     pub fn display<'i>(&'i self, interner: &'i impl Interner) -> impl Display + '_ {
         DisplayRequirement {
             interner,
             requirement: self,
+            short_names: false,
+        }
+    }
+
+    /// Returns an object that implements `Display` for the requirement, using
+    /// [`Interner::display_name_short`] to display the package names.
+    pub(crate) fn display_short<'i>(&'i self, interner: &'i impl Interner) -> impl Display + '_ {
+        DisplayRequirement {
+            interner,
+            requirement: self,
+            short_names: true,
         }
     }
 
@@ -158,9 +730,21 @@ impl Requirement {
             Requirement::Single(version_set) => {
                 itertools::Either::Left(std::iter::once(version_set))
             }
-            Requirement::Union(version_set_union) => {
-                itertools::Either::Right(interner.version_sets_in_union(version_set_union))
-            }
+            Requirement::Union(version_set_union)
+            | Requirement::ExactlyOne(version_set_union)
+            | Requirement::AtLeast {
+                version_sets: version_set_union,
+                ..
+            } => itertools::Either::Right(interner.version_sets_in_union(version_set_union)),
+        }
+    }
+
+    /// Returns the number of solvables that have to be selected to satisfy
+    /// the requirement.
+    pub(crate) fn min_count(&self) -> usize {
+        match *self {
+            Requirement::AtLeast { n, .. } => n,
+            Requirement::Single(_) | Requirement::Union(_) | Requirement::ExactlyOne(_) => 1,
         }
     }
 }
@@ -168,32 +752,53 @@ impl Requirement {
 pub(crate) struct DisplayRequirement<'i, I: Interner> {
     interner: &'i I,
     requirement: &'i Requirement,
+    short_names: bool,
+}
+
+impl<'i, I: Interner> DisplayRequirement<'i, I> {
+    fn version_set_to_string(&self, version_set: VersionSetId) -> String {
+        let name = self.interner.version_set_name(version_set);
+        let version_set = self.interner.display_version_set(version_set);
+        if self.short_names {
+            format!("{} {}", self.interner.display_name_short(name), version_set)
+        } else {
+            format!("{} {}", self.interner.display_name(name), version_set)
+        }
+    }
+
+    fn union_to_string(&self, version_set_union: VersionSetUnionId) -> String {
+        // Sort the version sets so the output does not depend on the order in which
+        // the interner returns them.
+        self.interner
+            .version_sets_in_union(version_set_union)
+            .sorted()
+            .map(|version_set| self.version_set_to_string(version_set))
+            .join(" | ")
+    }
 }
 
 impl<'i, I: Interner> Display for DisplayRequirement<'i, I> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self.requirement {
-            Requirement::Single(version_set) => write!(
-                f,
-                "{} {}",
-                self.interner
-                    .display_name(self.interner.version_set_name(version_set)),
-                self.interner.display_version_set(version_set)
-            ),
+            Requirement::Single(version_set) => {
+                write!(f, "{}", self.version_set_to_string(version_set))
+            }
             Requirement::Union(version_set_union) => {
-                let formatted_version_sets = self
-                    .interner
-                    .version_sets_in_union(version_set_union)
-                    .format_with(" | ", |version_set, f| {
-                        f(&format_args!(
-                            "{} {}",
-                            self.interner
-                                .display_name(self.interner.version_set_name(version_set)),
-                            self.interner.display_version_set(version_set)
-                        ))
-                    });
-
-                write!(f, "{}", formatted_version_sets)
+                write!(f, "{}", self.union_to_string(version_set_union))
+            }
+            Requirement::AtLeast { n, version_sets } => {
+                write!(
+                    f,
+                    "at least {n} of ({})",
+                    self.union_to_string(version_sets)
+                )
+            }
+            Requirement::ExactlyOne(version_set_union) => {
+                write!(
+                    f,
+                    "exactly one of ({})",
+                    self.union_to_string(version_set_union)
+                )
             }
         }
     }
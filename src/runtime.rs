@@ -25,13 +25,45 @@
 //! [`crate::DependencyProvider`] using the `async-std` executor.
 
 use futures::FutureExt;
-use std::future::Future;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 /// A trait to wrap an async runtime.
 pub trait AsyncRuntime {
     /// Runs the given future on the current thread, blocking until it is complete, and yielding its
     /// resolved result.
     fn block_on<F: Future>(&self, f: F) -> F::Output;
+
+    /// Returns a future that yields execution back to the executor once, allowing other tasks to
+    /// make progress. This is used by [`crate::Solver::solve_async`] to avoid monopolizing the
+    /// executor during a long solve.
+    ///
+    /// The default implementation works with any executor.
+    fn yield_now(&self) -> impl Future<Output = ()> {
+        YieldNow { yielded: false }
+    }
+}
+
+/// A future that returns [`Poll::Pending`] exactly once, immediately waking itself.
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
 }
 
 /// The simplest runtime possible evaluates and consumes the future, returning the resulting
@@ -56,6 +88,10 @@ impl AsyncRuntime for tokio::runtime::Handle {
     fn block_on<F: Future>(&self, f: F) -> F::Output {
         self.block_on(f)
     }
+
+    fn yield_now(&self) -> impl Future<Output = ()> {
+        tokio::task::yield_now()
+    }
 }
 
 #[cfg(feature = "tokio")]
@@ -63,6 +99,10 @@ impl AsyncRuntime for tokio::runtime::Runtime {
     fn block_on<F: Future>(&self, f: F) -> F::Output {
         self.block_on(f)
     }
+
+    fn yield_now(&self) -> impl Future<Output = ()> {
+        tokio::task::yield_now()
+    }
 }
 
 /// An implementation of [`AsyncRuntime`] that spawns and awaits any passed future on the current
@@ -75,4 +115,8 @@ impl AsyncRuntime for AsyncStdRuntime {
     fn block_on<F: Future>(&self, f: F) -> F::Output {
         async_std::task::block_on(f)
     }
+
+    fn yield_now(&self) -> impl Future<Output = ()> {
+        async_std::task::yield_now()
+    }
 }
@@ -4,10 +4,18 @@
 //! reused to solve dependencies.
 //!
 //! The [`DependencySnapshot`] can be serialized to disk if the `serde` feature
-//! is enabled.
+//! is enabled, or encoded in a compact binary format with
+//! [`DependencySnapshot::to_binary`].
 //!
 //! The [`DependencySnapshot`] implements the [`DependencyProvider`] trait,
 //! allowing it to be used as a dependency provider for the solver.
+//!
+//! A [`ProblemSnapshot`] combines a [`DependencySnapshot`] with the
+//! requirements of a problem, which [`reduce_problem`] can reduce to a minimal
+//! reproduction of an issue.
+
+mod binary;
+mod reduce;
 
 use std::{any::Any, collections::VecDeque, fmt::Display, time::SystemTime};
 
@@ -15,9 +23,14 @@ use ahash::HashSet;
 use futures::FutureExt;
 
 use crate::{
-    internal::arena::ArenaId, requirement::Condition, Candidates, Dependencies, DependencyProvider, Interner, Mapping, NameId, Requirement, SolvableId, SolverCache, StringId, VersionSetId, VersionSetUnionId
+    internal::arena::ArenaId, requirement::Condition, Candidates, ConditionalRequirement,
+    Dependencies, DependencyProvider, Interner, KnownDependencies, Mapping, NameId, Requirement,
+    SolvableId, SolverCache, StringId, VersionSetId, VersionSetUnionId,
 };
 
+pub use binary::{BinarySnapshotError, BINARY_FORMAT_VERSION, BINARY_MAGIC};
+pub use reduce::{reduce_problem, ProblemSnapshot};
+
 /// A single solvable in a [`DependencySnapshot`].
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -205,7 +218,10 @@ impl DependencySnapshot {
                         queue.push_back(Element::Package(name));
                     };
 
-                    let dependencies = cache.get_or_cache_dependencies(solvable_id).await?;
+                    let dependencies = evaluate_markers(
+                        cache.get_or_cache_dependencies(solvable_id).await?,
+                        cache.provider(),
+                    );
                     match &dependencies {
                         Dependencies::Unknown(reason) => {
                             if seen.insert(Element::String(*reason)) {
@@ -220,8 +236,10 @@ impl DependencySnapshot {
                             }
 
                             for req in deps.requirements.iter() {
-                                let (conditions, requirement) = req.clone().into_condition_and_requirement();
+                                let (conditions, requirement) =
+                                    req.clone().into_condition_and_requirement();
 
+                                let mut requirements = vec![requirement];
                                 for condition in conditions {
                                     match condition {
                                         Condition::Extra(string_id) => {
@@ -231,33 +249,48 @@ impl DependencySnapshot {
                                         }
                                         Condition::VersionSetId(version_set_id) => {
                                             if seen.insert(Element::VersionSet(version_set_id)) {
-                                                queue.push_back(Element::VersionSet(version_set_id));
+                                                queue
+                                                    .push_back(Element::VersionSet(version_set_id));
                                             }
                                         }
-                                    }
-                                }
-
-                                match requirement {
-                                    Requirement::Single(version_set) => {
-                                        if seen.insert(Element::VersionSet(version_set)) {
-                                            queue.push_back(Element::VersionSet(version_set));
+                                        Condition::Requirement(requirement) => {
+                                            requirements.push(requirement);
                                         }
+                                        // Markers are evaluated before the snapshot is taken.
+                                        Condition::Marker(_) => {}
                                     }
-                                    Requirement::Union(version_set_union_id) => {
-                                        let version_sets: HashSet<_> = cache
-                                            .provider()
-                                            .version_sets_in_union(version_set_union_id)
-                                            .collect();
+                                }
 
-                                        for &version_set in version_sets.iter() {
+                                for requirement in requirements {
+                                    match requirement {
+                                        Requirement::Single(version_set) => {
                                             if seen.insert(Element::VersionSet(version_set)) {
                                                 queue.push_back(Element::VersionSet(version_set));
                                             }
                                         }
+                                        Requirement::Union(version_set_union_id)
+                                        | Requirement::ExactlyOne(version_set_union_id)
+                                        | Requirement::AtLeast {
+                                            version_sets: version_set_union_id,
+                                            ..
+                                        } => {
+                                            let version_sets: HashSet<_> = cache
+                                                .provider()
+                                                .version_sets_in_union(version_set_union_id)
+                                                .collect();
+
+                                            for &version_set in version_sets.iter() {
+                                                if seen.insert(Element::VersionSet(version_set)) {
+                                                    queue.push_back(Element::VersionSet(
+                                                        version_set,
+                                                    ));
+                                                }
+                                            }
 
-                                        result
-                                            .version_set_unions
-                                            .insert(version_set_union_id, version_sets);
+                                            result
+                                                .version_set_unions
+                                                .insert(version_set_union_id, version_sets);
+                                        }
                                     }
                                 }
                             }
@@ -268,7 +301,7 @@ impl DependencySnapshot {
                         display: cache.provider().display_solvable(solvable_id).to_string(),
                         name,
                         order: 0,
-                        dependencies: dependencies.clone(),
+                        dependencies,
                         hint_dependencies_available: cache
                             .are_dependencies_available_for(solvable_id),
                     };
@@ -337,6 +370,45 @@ impl DependencySnapshot {
     }
 }
 
+/// Evaluates the markers of the requirements and constraints in
+/// `dependencies`. Requirements with a marker that does not hold are dropped,
+/// the markers that do hold are removed from the conditions. Conditional
+/// constraints are turned into plain constraints if they apply.
+fn evaluate_markers(
+    dependencies: &Dependencies,
+    provider: &impl DependencyProvider,
+) -> Dependencies {
+    let Dependencies::Known(deps) = dependencies else {
+        return dependencies.clone();
+    };
+
+    let requirements = deps
+        .requirements
+        .iter()
+        .filter_map(|req| {
+            let (conditions, requirement) = req.clone().into_condition_and_requirement();
+            let mut remaining = Vec::with_capacity(conditions.len());
+            for condition in conditions {
+                match condition {
+                    Condition::Marker(marker) => {
+                        if !provider.evaluate_marker(marker) {
+                            return None;
+                        }
+                    }
+                    condition => remaining.push(condition),
+                }
+            }
+            Some(ConditionalRequirement::new(remaining, requirement))
+        })
+        .collect();
+
+    Dependencies::Known(KnownDependencies {
+        requirements,
+        constrains: deps.active_constrains(provider).collect(),
+        conditional_constrains: Vec::new(),
+    })
+}
+
 /// Provides a [`DependencyProvider`] implementation for a
 /// [`DependencySnapshot`].
 pub struct SnapshotProvider<'s> {
@@ -374,7 +446,7 @@ impl<'s> SnapshotProvider<'s> {
     /// Adds another requirement that matches any version of a package.
     /// If you use "*" as the matcher, it will match any version of the package.
     pub fn add_package_requirement(&mut self, name: NameId, matcher: &str) -> VersionSetId {
-        let id = self.snapshot.version_sets.max() + self.additional_version_sets.len();
+        let id = self.first_additional_version_set() + self.additional_version_sets.len();
         let package = self.package(name);
 
         let matching_candidates = package
@@ -414,11 +486,22 @@ impl<'s> SnapshotProvider<'s> {
             .expect("missing string")
     }
 
+    /// Returns the id of the first version set added with
+    /// [`Self::add_package_requirement`], which follows the last version set of
+    /// the snapshot.
+    fn first_additional_version_set(&self) -> usize {
+        if self.snapshot.version_sets.is_empty() {
+            0
+        } else {
+            self.snapshot.version_sets.max() + 1
+        }
+    }
+
     fn version_set(&self, version_set: VersionSetId) -> &VersionSet {
         let idx = version_set.to_usize();
-        let max_idx = self.snapshot.version_sets.max();
-        if idx >= max_idx {
-            &self.additional_version_sets[idx - max_idx]
+        let first_additional = self.first_additional_version_set();
+        if idx >= first_additional {
+            &self.additional_version_sets[idx - first_additional]
         } else {
             self.snapshot
                 .version_sets
@@ -448,7 +531,13 @@ impl<'s> Interner for SnapshotProvider<'s> {
     fn display_condition(&self, condition: Condition) -> impl Display + '_ {
         match condition {
             Condition::Extra(string_id) => format!("{}", self.display_string(string_id)),
-            Condition::VersionSetId(version_set_id) => format!("{} {}", self.display_name(self.version_set_name(version_set_id)), self.display_version_set(version_set_id)),
+            Condition::VersionSetId(version_set_id) => format!(
+                "{} {}",
+                self.display_name(self.version_set_name(version_set_id)),
+                self.display_version_set(version_set_id)
+            ),
+            Condition::Requirement(requirement) => requirement.display(self).to_string(),
+            Condition::Marker(marker) => format!("marker '{}'", self.display_string(marker)),
         }
     }
 
@@ -471,6 +560,24 @@ impl<'s> Interner for SnapshotProvider<'s> {
             .iter()
             .copied()
     }
+
+    fn version_sets_for_name(&self, name: NameId) -> impl Iterator<Item = VersionSetId> {
+        let first_additional = self.first_additional_version_set();
+        self.snapshot
+            .version_sets
+            .iter()
+            .map(|(id, version_set)| (id, version_set.name))
+            .chain(self.additional_version_sets.iter().enumerate().map(
+                move |(idx, version_set)| {
+                    (
+                        VersionSetId::from_usize(first_additional + idx),
+                        version_set.name,
+                    )
+                },
+            ))
+            .filter(move |&(_, version_set_name)| version_set_name == name)
+            .map(|(id, _)| id)
+    }
 }
 
 impl<'s> DependencyProvider for SnapshotProvider<'s> {
@@ -495,6 +602,8 @@ impl<'s> DependencyProvider for SnapshotProvider<'s> {
             favored: None,
             locked: None,
             excluded: package.excluded.clone(),
+            more_available: false,
+            prefetched: Vec::new(),
             hint_dependencies_available: package
                 .solvables
                 .iter()
@@ -512,6 +621,14 @@ impl<'s> DependencyProvider for SnapshotProvider<'s> {
         self.solvable(solvable).dependencies.clone()
     }
 
+    fn known_names(&self) -> impl Iterator<Item = NameId> + '_ {
+        self.snapshot
+            .packages
+            .iter()
+            .filter(|(_, package)| !package.solvables.is_empty())
+            .map(|(name, _)| name)
+    }
+
     fn should_cancel_with_value(&self) -> Option<Box<dyn Any>> {
         if let Some(stop_time) = &self.stop_time {
             if SystemTime::now() > *stop_time {
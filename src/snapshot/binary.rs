@@ -0,0 +1,420 @@
+//! A compact binary encoding of a [`DependencySnapshot`].
+//!
+//! The encoding starts with [`BINARY_MAGIC`] and a format version byte, followed by
+//! the contents of the snapshot. Integers are encoded as LEB128 variable
+//! length integers, strings and collections are prefixed with their length.
+
+use std::fmt::{self, Display, Formatter};
+
+use ahash::HashSet;
+
+use super::{DependencySnapshot, Package, Solvable, VersionSet};
+use crate::{
+    internal::arena::ArenaId, Condition, ConditionalRequirement, Dependencies, KnownDependencies,
+    Mapping, Requirement, StringId, VersionSetId, VersionSetUnionId,
+};
+
+/// The bytes every binary snapshot starts with.
+pub const BINARY_MAGIC: [u8; 4] = *b"RSVS";
+
+/// The version of the binary format that is written by
+/// [`DependencySnapshot::to_binary`].
+pub const BINARY_FORMAT_VERSION: u8 = 1;
+
+/// An error that occurred while reading a binary snapshot, see
+/// [`DependencySnapshot::from_binary`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BinarySnapshotError {
+    /// The data does not start with [`BINARY_MAGIC`], so it is not a binary snapshot.
+    InvalidMagic,
+    /// The snapshot was written in a format version that is not supported.
+    UnsupportedVersion(u8),
+    /// The data ended before the snapshot was complete.
+    UnexpectedEnd,
+    /// The data is not a valid snapshot.
+    Invalid(&'static str),
+}
+
+impl Display for BinarySnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BinarySnapshotError::InvalidMagic => write!(f, "the data is not a binary snapshot"),
+            BinarySnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "the snapshot has format version {version}, but only version {BINARY_FORMAT_VERSION} is supported"
+            ),
+            BinarySnapshotError::UnexpectedEnd => write!(f, "the snapshot is truncated"),
+            BinarySnapshotError::Invalid(reason) => write!(f, "the snapshot is invalid: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for BinarySnapshotError {}
+
+impl DependencySnapshot {
+    /// Encodes the snapshot in a compact binary format, which is much smaller
+    /// and faster to read than JSON. Use [`Self::from_binary`] to decode it.
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut writer = Writer(Vec::new());
+        writer.0.extend_from_slice(&BINARY_MAGIC);
+        writer.0.push(BINARY_FORMAT_VERSION);
+
+        writer.mapping(&self.solvables, |writer, solvable| {
+            writer.str(&solvable.display);
+            writer.id(solvable.name);
+            writer.int(solvable.order);
+            writer.dependencies(&solvable.dependencies);
+            writer.bool(solvable.hint_dependencies_available);
+        });
+        writer.mapping(&self.version_set_unions, |writer, version_sets| {
+            writer.id_set(version_sets);
+        });
+        writer.mapping(&self.version_sets, |writer, version_set| {
+            writer.id(version_set.name);
+            writer.str(&version_set.display);
+            writer.id_set(&version_set.matching_candidates);
+        });
+        writer.mapping(&self.packages, |writer, package| {
+            writer.str(&package.name);
+            writer.ids(&package.solvables);
+            writer.len(package.excluded.len());
+            for &(solvable, reason) in &package.excluded {
+                writer.id(solvable);
+                writer.id(reason);
+            }
+        });
+        writer.mapping(&self.strings, |writer, string| writer.str(string));
+
+        writer.0
+    }
+
+    /// Decodes a snapshot that was encoded with [`Self::to_binary`].
+    ///
+    /// Returns an error if the data is not a binary snapshot, or if it was
+    /// written in a different version of the format.
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, BinarySnapshotError> {
+        let mut reader = Reader(bytes);
+        if reader.bytes(BINARY_MAGIC.len()).ok() != Some(&BINARY_MAGIC[..]) {
+            return Err(BinarySnapshotError::InvalidMagic);
+        }
+        let version = reader.byte()?;
+        if version != BINARY_FORMAT_VERSION {
+            return Err(BinarySnapshotError::UnsupportedVersion(version));
+        }
+
+        let snapshot = DependencySnapshot {
+            solvables: reader.mapping(|reader| {
+                Ok(Solvable {
+                    display: reader.string()?,
+                    name: reader.id()?,
+                    order: reader.int()?,
+                    dependencies: reader.dependencies()?,
+                    hint_dependencies_available: reader.bool()?,
+                })
+            })?,
+            version_set_unions: reader.mapping(Reader::id_set)?,
+            version_sets: reader.mapping(|reader| {
+                Ok(VersionSet {
+                    name: reader.id()?,
+                    display: reader.string()?,
+                    matching_candidates: reader.id_set()?,
+                })
+            })?,
+            packages: reader.mapping(|reader| {
+                Ok(Package {
+                    name: reader.string()?,
+                    solvables: reader.ids()?,
+                    excluded: reader.vec(|reader| Ok((reader.id()?, reader.id()?)))?,
+                })
+            })?,
+            strings: reader.mapping(Reader::string)?,
+        };
+
+        if !reader.0.is_empty() {
+            return Err(BinarySnapshotError::Invalid("trailing data"));
+        }
+        Ok(snapshot)
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn int(&mut self, mut value: u32) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn len(&mut self, len: usize) {
+        self.int(u32::try_from(len).expect("the snapshot is too large to encode"));
+    }
+
+    fn id(&mut self, id: impl ArenaId) {
+        self.len(id.to_usize());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.0.push(value as u8);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn ids<I: ArenaId + Copy>(&mut self, ids: &[I]) {
+        self.len(ids.len());
+        for &id in ids {
+            self.id(id);
+        }
+    }
+
+    /// Writes the ids in ascending order, so the output does not depend on the
+    /// iteration order of the set.
+    fn id_set<I: ArenaId + Copy>(&mut self, ids: &HashSet<I>) {
+        let mut ids = ids.iter().copied().collect::<Vec<_>>();
+        ids.sort_unstable_by_key(|id| id.to_usize());
+        self.ids(&ids);
+    }
+
+    fn mapping<K: ArenaId, V>(
+        &mut self,
+        mapping: &Mapping<K, V>,
+        mut write: impl FnMut(&mut Self, &V),
+    ) {
+        self.len(mapping.len());
+        for (id, value) in mapping.iter() {
+            self.id(id);
+            write(self, value);
+        }
+    }
+
+    fn requirement(&mut self, requirement: Requirement) {
+        match requirement {
+            Requirement::Single(version_set) => {
+                self.0.push(0);
+                self.id(version_set);
+            }
+            Requirement::Union(version_set_union) => {
+                self.0.push(1);
+                self.id(version_set_union);
+            }
+            Requirement::AtLeast { n, version_sets } => {
+                self.0.push(2);
+                self.len(n);
+                self.id(version_sets);
+            }
+            Requirement::ExactlyOne(version_set_union) => {
+                self.0.push(3);
+                self.id(version_set_union);
+            }
+        }
+    }
+
+    fn dependencies(&mut self, dependencies: &Dependencies) {
+        match dependencies {
+            Dependencies::Known(known) => {
+                self.0.push(0);
+                self.len(known.requirements.len());
+                for requirement in &known.requirements {
+                    self.len(requirement.conditions.len());
+                    for &condition in &requirement.conditions {
+                        match condition {
+                            Condition::VersionSetId(version_set) => {
+                                self.0.push(0);
+                                self.id(version_set);
+                            }
+                            Condition::Extra(extra) => {
+                                self.0.push(1);
+                                self.id(extra);
+                            }
+                            Condition::Requirement(requirement) => {
+                                self.0.push(2);
+                                self.requirement(requirement);
+                            }
+                            Condition::Marker(marker) => {
+                                self.0.push(3);
+                                self.id(marker);
+                            }
+                        }
+                    }
+                    self.requirement(requirement.requirement);
+                }
+                self.ids(&known.constrains);
+            }
+            &Dependencies::Unknown(reason) => {
+                self.0.push(1);
+                self.id(reason);
+            }
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], BinarySnapshotError> {
+        if self.0.len() < len {
+            return Err(BinarySnapshotError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, BinarySnapshotError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn int(&mut self) -> Result<u32, BinarySnapshotError> {
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = self.byte()?;
+            value |= u32::from(byte & 0x7f)
+                .checked_shl(shift)
+                .filter(|&bits| bits >> shift == u32::from(byte & 0x7f))
+                .ok_or(BinarySnapshotError::Invalid("integer overflow"))?;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(BinarySnapshotError::Invalid("integer overflow"))
+    }
+
+    fn len(&mut self) -> Result<usize, BinarySnapshotError> {
+        Ok(self.int()? as usize)
+    }
+
+    fn id<I: ArenaId>(&mut self) -> Result<I, BinarySnapshotError> {
+        Ok(I::from_usize(self.len()?))
+    }
+
+    fn bool(&mut self) -> Result<bool, BinarySnapshotError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(BinarySnapshotError::Invalid("invalid boolean")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, BinarySnapshotError> {
+        let len = self.len()?;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| BinarySnapshotError::Invalid("invalid UTF-8 in string"))
+    }
+
+    fn vec<T>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> Result<T, BinarySnapshotError>,
+    ) -> Result<Vec<T>, BinarySnapshotError> {
+        let len = self.len()?;
+        // Every element takes at least one byte, so this does not allocate more
+        // than the size of the input.
+        let mut values = Vec::with_capacity(len.min(self.0.len()));
+        for _ in 0..len {
+            values.push(read(self)?);
+        }
+        Ok(values)
+    }
+
+    fn ids<I: ArenaId>(&mut self) -> Result<Vec<I>, BinarySnapshotError> {
+        self.vec(Self::id)
+    }
+
+    fn id_set<I: ArenaId + Eq + std::hash::Hash>(
+        &mut self,
+    ) -> Result<HashSet<I>, BinarySnapshotError> {
+        Ok(self.ids()?.into_iter().collect())
+    }
+
+    fn mapping<K: ArenaId, V>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> Result<V, BinarySnapshotError>,
+    ) -> Result<Mapping<K, V>, BinarySnapshotError> {
+        let len = self.len()?;
+        let mut mapping = Mapping::new();
+        for _ in 0..len {
+            let id: K = self.id()?;
+            let value = read(self)?;
+            mapping.insert(id, value);
+        }
+        Ok(mapping)
+    }
+
+    fn requirement(&mut self) -> Result<Requirement, BinarySnapshotError> {
+        match self.byte()? {
+            0 => Ok(Requirement::Single(self.id::<VersionSetId>()?)),
+            1 => Ok(Requirement::Union(self.id::<VersionSetUnionId>()?)),
+            2 => Ok(Requirement::AtLeast {
+                n: self.len()?,
+                version_sets: self.id::<VersionSetUnionId>()?,
+            }),
+            3 => Ok(Requirement::ExactlyOne(self.id::<VersionSetUnionId>()?)),
+            _ => Err(BinarySnapshotError::Invalid("invalid requirement")),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Condition, BinarySnapshotError> {
+        match self.byte()? {
+            0 => Ok(Condition::VersionSetId(self.id()?)),
+            1 => Ok(Condition::Extra(self.id::<StringId>()?)),
+            2 => Ok(Condition::Requirement(self.requirement()?)),
+            3 => Ok(Condition::Marker(self.id::<StringId>()?)),
+            _ => Err(BinarySnapshotError::Invalid("invalid condition")),
+        }
+    }
+
+    fn dependencies(&mut self) -> Result<Dependencies, BinarySnapshotError> {
+        match self.byte()? {
+            0 => Ok(Dependencies::Known(KnownDependencies {
+                requirements: self.vec(|reader| {
+                    Ok(ConditionalRequirement {
+                        conditions: reader.vec(Self::condition)?,
+                        requirement: reader.requirement()?,
+                    })
+                })?,
+                constrains: self.ids()?,
+                conditional_constrains: Vec::new(),
+            })),
+            1 => Ok(Dependencies::Unknown(self.id()?)),
+            _ => Err(BinarySnapshotError::Invalid("invalid dependencies")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_int_round_trip() {
+        for value in [0, 1, 127, 128, 300, 16_383, 16_384, u32::MAX] {
+            let mut writer = Writer(Vec::new());
+            writer.int(value);
+            assert_eq!(Reader(&writer.0).int(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_rejects_other_versions() {
+        let mut bytes = DependencySnapshot::default().to_binary();
+        assert!(DependencySnapshot::from_binary(&bytes).is_ok());
+
+        bytes[BINARY_MAGIC.len()] = BINARY_FORMAT_VERSION + 1;
+        let error = DependencySnapshot::from_binary(&bytes).err().unwrap();
+        assert_eq!(error, BinarySnapshotError::UnsupportedVersion(2));
+        assert_eq!(
+            error.to_string(),
+            "the snapshot has format version 2, but only version 1 is supported"
+        );
+
+        assert_eq!(
+            DependencySnapshot::from_binary(b"{}").err(),
+            Some(BinarySnapshotError::InvalidMagic)
+        );
+    }
+}
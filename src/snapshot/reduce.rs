@@ -0,0 +1,316 @@
+//! Reduces a [`ProblemSnapshot`] to a minimal problem that still reproduces an
+//! issue, see [`reduce_problem`].
+
+use std::any::Any;
+
+use ahash::HashSet;
+
+use super::{DependencySnapshot, Solvable};
+use crate::{
+    ConditionalRequirement, Dependencies, DependencyProvider, Problem, SolvableId, Solver,
+    UnsolvableOrCancelled, VersionSetId,
+};
+
+/// A problem together with a [`DependencySnapshot`] of the packages it is
+/// solved against. This makes a solve reproducible without the original
+/// [`DependencyProvider`], which is useful for bug reports.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProblemSnapshot {
+    /// The packages the problem is solved against.
+    pub dependencies: DependencySnapshot,
+
+    /// The requirements of the problem.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub requirements: Vec<ConditionalRequirement>,
+
+    /// The constraints of the problem.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub constraints: Vec<VersionSetId>,
+}
+
+impl ProblemSnapshot {
+    /// Captures the problem with the given `requirements` and `constraints`
+    /// and everything of the `provider` that is reachable from it, see
+    /// [`DependencySnapshot::from_provider`].
+    pub fn from_provider(
+        provider: impl DependencyProvider,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+    ) -> Result<Self, Box<dyn Any>> {
+        let version_sets = requirements
+            .iter()
+            .flat_map(|requirement| {
+                requirement
+                    .conditions
+                    .iter()
+                    .flat_map(|condition| condition.version_sets(&provider))
+                    .chain(requirement.requirement_version_sets(&provider))
+                    .collect::<Vec<_>>()
+            })
+            .chain(constraints.iter().copied())
+            .collect::<Vec<_>>();
+        let dependencies = DependencySnapshot::from_provider(provider, [], version_sets, [])?;
+        Ok(Self {
+            dependencies,
+            requirements,
+            constraints,
+        })
+    }
+
+    /// Solves the problem against the snapshot.
+    pub fn solve(&self) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        let mut solver = Solver::new(self.dependencies.provider());
+        solver.solve(
+            Problem::new()
+                .requirements(self.requirements.clone())
+                .constraints(self.constraints.clone()),
+        )
+    }
+
+    /// Removes the given solvables from the candidates of their packages.
+    fn without_solvables(&self, removed: &HashSet<SolvableId>) -> Self {
+        let mut result = self.clone();
+        let dependencies = &mut result.dependencies;
+        for &solvable in removed {
+            dependencies.solvables.unset(solvable);
+        }
+        let names = dependencies
+            .packages
+            .iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        for name in names {
+            let package = dependencies
+                .packages
+                .get_mut(name)
+                .expect("missing package");
+            package
+                .solvables
+                .retain(|solvable| !removed.contains(solvable));
+            package
+                .excluded
+                .retain(|(solvable, _)| !removed.contains(solvable));
+        }
+        let version_sets = dependencies
+            .version_sets
+            .iter()
+            .map(|(version_set, _)| version_set)
+            .collect::<Vec<_>>();
+        for version_set in version_sets {
+            dependencies
+                .version_sets
+                .get_mut(version_set)
+                .expect("missing version set")
+                .matching_candidates
+                .retain(|solvable| !removed.contains(solvable));
+        }
+        result
+    }
+}
+
+/// Reduces `problem` to a smaller problem for which `predicate` still holds,
+/// for instance a problem that is still unsolvable or that still makes the
+/// solver panic. This turns a problem that triggers a bug into a minimal
+/// reproduction of it.
+///
+/// The requirements and constraints of the problem, the candidates of the
+/// packages and the requirements and constraints of the candidates are
+/// removed with delta debugging until none of them can be removed without
+/// `predicate` failing. The result is minimal in that sense, it is not
+/// necessarily the smallest problem for which `predicate` holds.
+///
+/// `predicate` should hold for `problem`, otherwise `problem` is returned
+/// as is. Every attempt solves a problem, so this can take a while for large
+/// snapshots.
+pub fn reduce_problem(
+    problem: ProblemSnapshot,
+    predicate: impl Fn(&ProblemSnapshot) -> bool,
+) -> ProblemSnapshot {
+    if !predicate(&problem) {
+        return problem;
+    }
+
+    let mut problem = problem;
+    loop {
+        let before = size(&problem);
+
+        problem = reduce_items(
+            problem,
+            &predicate,
+            |problem| problem.requirements.clone(),
+            |problem, requirements| problem.requirements = requirements,
+        );
+        problem = reduce_items(
+            problem,
+            &predicate,
+            |problem| problem.constraints.clone(),
+            |problem, constraints| problem.constraints = constraints,
+        );
+
+        let mut solvables = candidates(&problem);
+        solvables.sort();
+        let kept = delta_debug(solvables.clone(), |kept| {
+            predicate(&problem.without_solvables(&removed(&solvables, kept)))
+        });
+        problem = problem.without_solvables(&removed(&solvables, &kept));
+
+        for solvable in candidates(&problem) {
+            problem = reduce_items(
+                problem,
+                &predicate,
+                |problem| known_dependencies(problem, solvable).0,
+                |problem, requirements| {
+                    set_known_dependencies(problem, solvable, Some(requirements), None)
+                },
+            );
+            problem = reduce_items(
+                problem,
+                &predicate,
+                |problem| known_dependencies(problem, solvable).1,
+                |problem, constrains| {
+                    set_known_dependencies(problem, solvable, None, Some(constrains))
+                },
+            );
+        }
+
+        if size(&problem) == before {
+            return problem;
+        }
+    }
+}
+
+/// Reduces the items that `get` returns from `problem` while `predicate`
+/// holds.
+fn reduce_items<T: Clone>(
+    problem: ProblemSnapshot,
+    predicate: &impl Fn(&ProblemSnapshot) -> bool,
+    get: impl Fn(&ProblemSnapshot) -> Vec<T>,
+    set: impl Fn(&mut ProblemSnapshot, Vec<T>),
+) -> ProblemSnapshot {
+    let items = get(&problem);
+    if items.is_empty() {
+        return problem;
+    }
+    let kept = delta_debug(items, |kept| {
+        let mut candidate = problem.clone();
+        set(&mut candidate, kept.to_vec());
+        predicate(&candidate)
+    });
+    let mut problem = problem;
+    set(&mut problem, kept);
+    problem
+}
+
+/// Returns a subset of `items` for which `test` holds and from which no
+/// chunk can be removed without `test` failing, with the complement based
+/// variant of the `ddmin` algorithm. `test` is assumed to hold for `items`.
+fn delta_debug<T: Clone>(mut items: Vec<T>, test: impl Fn(&[T]) -> bool) -> Vec<T> {
+    let mut chunks = 2;
+    while !items.is_empty() {
+        let chunk_size = items.len().div_ceil(chunks);
+        let complement = (0..items.len()).step_by(chunk_size).find_map(|start| {
+            let end = (start + chunk_size).min(items.len());
+            let complement = [&items[..start], &items[end..]].concat();
+            test(&complement).then_some(complement)
+        });
+        match complement {
+            Some(complement) => {
+                items = complement;
+                chunks = (chunks - 1).max(2);
+            }
+            None if chunks >= items.len() => break,
+            None => chunks = (chunks * 2).min(items.len()),
+        }
+    }
+    items
+}
+
+/// Returns the candidates of all the packages in the problem.
+fn candidates(problem: &ProblemSnapshot) -> Vec<SolvableId> {
+    problem
+        .dependencies
+        .packages
+        .iter()
+        .flat_map(|(_, package)| package.solvables.iter().copied())
+        .collect()
+}
+
+/// Returns the solvables in `all` that are not in `kept`.
+fn removed(all: &[SolvableId], kept: &[SolvableId]) -> HashSet<SolvableId> {
+    let kept = kept.iter().collect::<HashSet<_>>();
+    all.iter()
+        .copied()
+        .filter(|solvable| !kept.contains(solvable))
+        .collect()
+}
+
+/// Returns the requirements and constraints of a solvable, which are empty if
+/// its dependencies are unknown.
+fn known_dependencies(
+    problem: &ProblemSnapshot,
+    solvable: SolvableId,
+) -> (Vec<ConditionalRequirement>, Vec<VersionSetId>) {
+    match problem.dependencies.solvables.get(solvable) {
+        Some(Solvable {
+            dependencies: Dependencies::Known(known),
+            ..
+        }) => (known.requirements.clone(), known.constrains.clone()),
+        _ => (Vec::new(), Vec::new()),
+    }
+}
+
+/// Replaces the requirements and/or constraints of a solvable with known
+/// dependencies.
+fn set_known_dependencies(
+    problem: &mut ProblemSnapshot,
+    solvable: SolvableId,
+    requirements: Option<Vec<ConditionalRequirement>>,
+    constrains: Option<Vec<VersionSetId>>,
+) {
+    if let Some(Solvable {
+        dependencies: Dependencies::Known(known),
+        ..
+    }) = problem.dependencies.solvables.get_mut(solvable)
+    {
+        if let Some(requirements) = requirements {
+            known.requirements = requirements;
+        }
+        if let Some(constrains) = constrains {
+            known.constrains = constrains;
+        }
+    }
+}
+
+/// Returns the number of items that [`reduce_problem`] can remove.
+fn size(problem: &ProblemSnapshot) -> usize {
+    let dependencies = candidates(problem)
+        .into_iter()
+        .map(|solvable| {
+            let (requirements, constrains) = known_dependencies(problem, solvable);
+            requirements.len() + constrains.len()
+        })
+        .sum::<usize>();
+    problem.requirements.len()
+        + problem.constraints.len()
+        + candidates(problem).len()
+        + dependencies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_debug_finds_minimal_subset() {
+        let items = (0..20).collect::<Vec<_>>();
+        let kept = delta_debug(items, |items| items.contains(&3) && items.contains(&17));
+        assert_eq!(kept, vec![3, 17]);
+    }
+}
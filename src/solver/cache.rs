@@ -1,6 +1,5 @@
-use std::{any::Any, cell::RefCell, rc::Rc};
+use std::{any::Any, cell::RefCell, cmp::Ordering, rc::Rc};
 
-use ahash::HashMap;
 use bitvec::vec::BitVec;
 use elsa::FrozenMap;
 use event_listener::Event;
@@ -9,11 +8,86 @@ use crate::{
     internal::{
         arena::{Arena, ArenaId},
         frozen_copy_map::FrozenCopyMap,
+        hash::{DefaultHashBuilder, HashMap, HashSet},
         id::{CandidatesId, DependenciesId},
     },
-    Candidates, Dependencies, DependencyProvider, NameId, Requirement, SolvableId, VersionSetId,
+    Candidates, Dependencies, DependencyProvider, NameId, PreviousSort, Requirement, SolvableId,
+    VersionSetId,
 };
 
+/// Filters the candidates with [`DependencyProvider::filter_candidates`], and
+/// then applies [`crate::Interner::matches_with_metadata`] on top of that. The
+/// candidates keep their order.
+///
+/// Candidates of other packages that provide the package of the version set
+/// are matched by their provided version sets instead, see
+/// [`crate::Interner::provided_version_sets`].
+pub(crate) async fn filter_candidates<D: DependencyProvider>(
+    provider: &D,
+    candidates: &[SolvableId],
+    version_set: VersionSetId,
+    inverse: bool,
+) -> Vec<SolvableId> {
+    let name = provider.canonical_name(provider.version_set_name(version_set));
+    let is_provider =
+        |candidate: SolvableId| provider.canonical_name(provider.solvable_name(candidate)) != name;
+
+    let filtered = if candidates.iter().any(|&candidate| is_provider(candidate)) {
+        let own_candidates = candidates
+            .iter()
+            .copied()
+            .filter(|&candidate| !is_provider(candidate))
+            .collect::<Vec<_>>();
+        provider
+            .filter_candidates(&own_candidates, version_set, inverse)
+            .await
+    } else {
+        provider
+            .filter_candidates(candidates, version_set, inverse)
+            .await
+    };
+
+    let overrides = candidates
+        .iter()
+        .filter_map(|&candidate| {
+            if is_provider(candidate) {
+                let provides = provides_matching(provider, candidate, version_set);
+                return Some((candidate, provides != inverse));
+            }
+            provider
+                .matches_with_metadata(candidate, version_set)
+                .map(|matches| (candidate, matches != inverse))
+        })
+        .collect::<HashMap<_, _>>();
+    if overrides.is_empty() {
+        return filtered;
+    }
+
+    let filtered = filtered.into_iter().collect::<HashSet<_>>();
+    candidates
+        .iter()
+        .copied()
+        .filter(|candidate| match overrides.get(candidate) {
+            Some(&included) => included,
+            None => filtered.contains(candidate),
+        })
+        .collect()
+}
+
+/// Returns whether `solvable` provides a version set of the package of
+/// `version_set` that is a subset of it.
+fn provides_matching<D: DependencyProvider>(
+    provider: &D,
+    solvable: SolvableId,
+    version_set: VersionSetId,
+) -> bool {
+    let name = provider.canonical_name(provider.version_set_name(version_set));
+    provider.provided_version_sets(solvable).any(|provided| {
+        provider.canonical_name(provider.version_set_name(provided)) == name
+            && provider.version_set_subset(provided, version_set) == Some(true)
+    })
+}
+
 /// Keeps a cache of previously computed and/or requested information about
 /// solvables and version sets.
 pub struct SolverCache<D: DependencyProvider> {
@@ -25,26 +99,47 @@ pub struct SolverCache<D: DependencyProvider> {
     package_name_to_candidates_in_flight: RefCell<HashMap<NameId, Rc<Event>>>,
 
     /// A mapping of `VersionSetId` to the candidates that match that set.
-    version_set_candidates: FrozenMap<VersionSetId, Vec<SolvableId>, ahash::RandomState>,
+    version_set_candidates: FrozenMap<VersionSetId, Vec<SolvableId>, DefaultHashBuilder>,
 
     /// A mapping of `VersionSetId` to the candidates that do not match that set
     /// (only candidates of the package indicated by the version set are
     /// included).
-    version_set_inverse_candidates: FrozenMap<VersionSetId, Vec<SolvableId>, ahash::RandomState>,
+    version_set_inverse_candidates: FrozenMap<VersionSetId, Vec<SolvableId>, DefaultHashBuilder>,
 
     /// A mapping of [`Requirement`] to a sorted list of candidates that fulfill
     /// that requirement.
-    requirement_to_sorted_candidates: FrozenMap<Requirement, Vec<SolvableId>, ahash::RandomState>,
+    requirement_to_sorted_candidates: FrozenMap<Requirement, Vec<SolvableId>, DefaultHashBuilder>,
 
     /// A mapping from a solvable to a list of dependencies
     solvable_dependencies: Arena<DependenciesId, Dependencies>,
     solvable_to_dependencies: FrozenCopyMap<SolvableId, DependenciesId>,
 
+    /// A mapping from a package name to the constraints on it in the
+    /// dependencies retrieved so far, see [`SolverCache::constraints_on`].
+    constraints_on_name: RefCell<HashMap<NameId, Vec<(SolvableId, VersionSetId)>>>,
+
     /// A mapping that indicates that the dependencies for a particular solvable
     /// can cheaply be retrieved from the dependency provider. This
     /// information is provided by the DependencyProvider when the
     /// candidates for a package are requested.
     hint_dependencies_available: RefCell<BitVec>,
+
+    /// The orders in which the candidates of packages were sorted during a
+    /// previous solve, see [`crate::SolverBuilder::with_previous_sorts`].
+    pub(crate) previous_sorts: HashMap<NameId, PreviousSort>,
+
+    /// The orders in which the candidates of packages were sorted by this
+    /// cache, see [`crate::Solver::candidate_sorts`].
+    sorts: RefCell<HashMap<NameId, PreviousSort>>,
+
+    /// Whether the favored candidate of a package is tried first, see
+    /// [`Candidates::favored`].
+    pub(crate) favor_candidates: bool,
+
+    /// Whether candidates are tried in the reverse order of
+    /// [`DependencyProvider::sort_candidates`], see
+    /// [`crate::SolverBuilder::with_objective`].
+    pub(crate) minimize_versions: bool,
 }
 
 impl<D: DependencyProvider> SolverCache<D> {
@@ -60,7 +155,12 @@ impl<D: DependencyProvider> SolverCache<D> {
             requirement_to_sorted_candidates: Default::default(),
             solvable_dependencies: Default::default(),
             solvable_to_dependencies: Default::default(),
+            constraints_on_name: Default::default(),
             hint_dependencies_available: Default::default(),
+            previous_sorts: Default::default(),
+            sorts: Default::default(),
+            favor_candidates: true,
+            minimize_versions: false,
         }
     }
 
@@ -69,6 +169,50 @@ impl<D: DependencyProvider> SolverCache<D> {
         &self.provider
     }
 
+    /// Returns true if the [`DependencyProvider`] has more candidates for the
+    /// package than the ones in the cache, see [`Candidates::more_available`].
+    pub(crate) fn more_candidates_available(&self, package_name: NameId) -> bool {
+        self.package_name_to_candidates
+            .get_copy(&package_name)
+            .is_some_and(|candidates_id| self.candidates[candidates_id].more_available)
+    }
+
+    /// Adds candidates returned by [`DependencyProvider::get_more_candidates`]
+    /// to the cached candidates of a package. The cached candidates of version
+    /// sets and requirements are cleared because they might be incomplete now.
+    pub(crate) fn add_candidates(&mut self, package_name: NameId, more: Candidates) {
+        let Some(candidates_id) = self.package_name_to_candidates.get_copy(&package_name) else {
+            return;
+        };
+
+        self.set_dependencies_available(&more.hint_dependencies_available);
+        self.insert_prefetched(more.prefetched);
+        let candidates = &mut self.candidates[candidates_id];
+        candidates.candidates.extend(more.candidates);
+        candidates
+            .hint_dependencies_available
+            .extend(more.hint_dependencies_available);
+        candidates.excluded.extend(more.excluded);
+        candidates.more_available = more.more_available;
+
+        self.version_set_candidates = Default::default();
+        self.version_set_inverse_candidates = Default::default();
+        self.requirement_to_sorted_candidates = Default::default();
+    }
+
+    /// Records that the dependencies of the given solvables are cheaply
+    /// available, see [`Candidates::hint_dependencies_available`].
+    fn set_dependencies_available(&self, solvables: &[SolvableId]) {
+        let mut hint_dependencies_available = self.hint_dependencies_available.borrow_mut();
+        for hint_candidate in solvables {
+            let idx = hint_candidate.to_usize();
+            if hint_dependencies_available.len() <= idx {
+                hint_dependencies_available.resize(idx + 1, false);
+            }
+            hint_dependencies_available.set(idx, true)
+        }
+    }
+
     /// Returns the candidates for the package with the given name. This will
     /// either ask the [`DependencyProvider`] for the entries or a cached
     /// value.
@@ -113,7 +257,7 @@ impl<D: DependencyProvider> SolverCache<D> {
                             .insert(package_name, Rc::new(Event::new()));
 
                         // Otherwise we have to get them from the DependencyProvider
-                        let candidates = self
+                        let mut candidates = self
                             .provider
                             .get_candidates(package_name)
                             .await
@@ -121,17 +265,8 @@ impl<D: DependencyProvider> SolverCache<D> {
 
                         // Store information about which solvables dependency information is easy to
                         // retrieve.
-                        {
-                            let mut hint_dependencies_available =
-                                self.hint_dependencies_available.borrow_mut();
-                            for hint_candidate in candidates.hint_dependencies_available.iter() {
-                                let idx = hint_candidate.to_usize();
-                                if hint_dependencies_available.len() <= idx {
-                                    hint_dependencies_available.resize(idx + 1, false);
-                                }
-                                hint_dependencies_available.set(idx, true)
-                            }
-                        }
+                        self.set_dependencies_available(&candidates.hint_dependencies_available);
+                        self.insert_prefetched(std::mem::take(&mut candidates.prefetched));
 
                         // Allocate an ID so we can refer to the candidates from everywhere
                         let candidates_id = self.candidates.alloc(candidates);
@@ -169,7 +304,9 @@ impl<D: DependencyProvider> SolverCache<D> {
         match self.version_set_candidates.get(&version_set_id) {
             Some(candidates) => Ok(candidates),
             None => {
-                let package_name_id = self.provider.version_set_name(version_set_id);
+                let package_name_id = self
+                    .provider
+                    .canonical_name(self.provider.version_set_name(version_set_id));
 
                 tracing::trace!(
                     "Getting matching candidates for package: {}",
@@ -179,10 +316,13 @@ impl<D: DependencyProvider> SolverCache<D> {
                 let candidates = self.get_or_cache_candidates(package_name_id).await?;
                 tracing::trace!("Got {:?} matching candidates", candidates.candidates.len());
 
-                let matching_candidates = self
-                    .provider
-                    .filter_candidates(&candidates.candidates, version_set_id, false)
-                    .await;
+                let matching_candidates = filter_candidates(
+                    &self.provider,
+                    &candidates.candidates,
+                    version_set_id,
+                    false,
+                )
+                .await;
 
                 tracing::trace!(
                     "Filtered {:?} matching candidates",
@@ -207,7 +347,9 @@ impl<D: DependencyProvider> SolverCache<D> {
         match self.version_set_inverse_candidates.get(&version_set_id) {
             Some(candidates) => Ok(candidates),
             None => {
-                let package_name_id = self.provider.version_set_name(version_set_id);
+                let package_name_id = self
+                    .provider
+                    .canonical_name(self.provider.version_set_name(version_set_id));
 
                 tracing::trace!(
                     "Getting NON-matching candidates for package: {:?}",
@@ -220,13 +362,9 @@ impl<D: DependencyProvider> SolverCache<D> {
                     candidates.candidates.len()
                 );
 
-                let matching_candidates: Vec<SolvableId> = self
-                    .provider
-                    .filter_candidates(&candidates.candidates, version_set_id, true)
-                    .await
-                    .into_iter()
-                    .map(Into::into)
-                    .collect();
+                let matching_candidates =
+                    filter_candidates(&self.provider, &candidates.candidates, version_set_id, true)
+                        .await;
 
                 tracing::trace!(
                     "Filtered {:?} matching candidates",
@@ -255,31 +393,32 @@ impl<D: DependencyProvider> SolverCache<D> {
                 self.get_or_cache_sorted_candidates_for_version_set(version_set_id)
                     .await
             }
-            Requirement::Union(version_set_union_id) => {
-                match self.requirement_to_sorted_candidates.get(&requirement) {
-                    Some(candidates) => Ok(candidates),
-                    None => {
-                        let sorted_candidates = futures::future::try_join_all(
-                            self.provider()
-                                .version_sets_in_union(version_set_union_id)
-                                .map(|version_set_id| {
-                                    self.get_or_cache_sorted_candidates_for_version_set(
-                                        version_set_id,
-                                    )
-                                }),
-                        )
-                        .await?
-                        .into_iter()
-                        .flatten()
-                        .copied()
-                        .collect();
-
-                        Ok(self
-                            .requirement_to_sorted_candidates
-                            .insert(requirement, sorted_candidates))
-                    }
+            Requirement::Union(version_set_union_id)
+            | Requirement::ExactlyOne(version_set_union_id)
+            | Requirement::AtLeast {
+                version_sets: version_set_union_id,
+                ..
+            } => match self.requirement_to_sorted_candidates.get(&requirement) {
+                Some(candidates) => Ok(candidates),
+                None => {
+                    let sorted_candidates = futures::future::try_join_all(
+                        self.provider()
+                            .version_sets_in_union(version_set_union_id)
+                            .map(|version_set_id| {
+                                self.get_or_cache_sorted_candidates_for_version_set(version_set_id)
+                            }),
+                    )
+                    .await?
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .collect();
+
+                    Ok(self
+                        .requirement_to_sorted_candidates
+                        .insert(requirement, sorted_candidates))
                 }
-            }
+            },
         }
     }
 
@@ -294,7 +433,9 @@ impl<D: DependencyProvider> SolverCache<D> {
             return Ok(candidates);
         }
 
-        let package_name_id = self.provider.version_set_name(version_set_id);
+        let package_name_id = self
+            .provider
+            .canonical_name(self.provider.version_set_name(version_set_id));
         tracing::trace!(
             "Getting sorted matching candidates for package: {:?}",
             self.provider.display_name(package_name_id).to_string()
@@ -305,25 +446,100 @@ impl<D: DependencyProvider> SolverCache<D> {
             .await?;
         let candidates = self.get_or_cache_candidates(package_name_id).await?;
 
-        // Sort all the candidates in order in which they should be tried by the solver.
         let mut sorted_candidates = Vec::with_capacity(matching_candidates.len());
         sorted_candidates.extend_from_slice(matching_candidates);
+        self.sort_in_solver_order(package_name_id, &mut sorted_candidates, candidates.favored)
+            .await;
+
+        Ok(self
+            .requirement_to_sorted_candidates
+            .insert(requirement, sorted_candidates))
+    }
+
+    /// Sorts the candidates of a package in the order in which they should be
+    /// tried by the solver, with the `favored` candidate first.
+    pub(crate) async fn sort_in_solver_order(
+        &self,
+        name: NameId,
+        candidates: &mut [SolvableId],
+        favored: Option<SolvableId>,
+    ) {
+        let previous = self.previous_sorts.get(&name);
         self.provider
-            .sort_candidates(self, &mut sorted_candidates)
+            .sort_candidates_with_previous(self, candidates, previous)
             .await;
 
+        // The priority breaks ties between candidates that compare equal, unless the
+        // provider was given a previous order to break them with.
+        if previous.is_none() {
+            self.sort_ties_by_priority(candidates);
+        }
+        self.record_sort(name, candidates);
+
+        if self.minimize_versions {
+            candidates.reverse();
+        }
+        candidates.sort_by(|&a, &b| {
+            self.provider
+                .score_candidate(b)
+                .total_cmp(&self.provider.score_candidate(a))
+        });
+
+        // Candidates from channels with a higher priority are always tried first.
+        candidates.sort_by_key(|&s| self.provider.solvable_channel_priority(s));
+
         // If we have a solvable that we favor, we sort that to the front. This ensures
         // that the version that is favored is picked first.
-        if let Some(favored_id) = candidates.favored {
-            if let Some(pos) = sorted_candidates.iter().position(|&s| s == favored_id) {
+        if let Some(favored_id) = favored.filter(|_| self.favor_candidates) {
+            if let Some(pos) = candidates.iter().position(|&s| s == favored_id) {
                 // Move the element at `pos` to the front of the array
-                sorted_candidates[0..=pos].rotate_right(1);
+                candidates[0..=pos].rotate_right(1);
             }
         }
+    }
 
-        Ok(self
-            .requirement_to_sorted_candidates
-            .insert(requirement, sorted_candidates))
+    /// Orders consecutive candidates that compare equal according to
+    /// [`crate::Interner::compare_solvables`] by descending
+    /// [`crate::Interner::solvable_priority`]. All other candidates keep their
+    /// order.
+    fn sort_ties_by_priority(&self, candidates: &mut [SolvableId]) {
+        let mut start = 0;
+        while start < candidates.len() {
+            let first = candidates[start];
+            let end = start
+                + 1
+                + candidates[start + 1..]
+                    .iter()
+                    .take_while(|&&s| {
+                        self.provider.compare_solvables(first, s) == Some(Ordering::Equal)
+                    })
+                    .count();
+            candidates[start..end]
+                .sort_by_key(|&s| std::cmp::Reverse(self.provider.solvable_priority(s)));
+            start = end;
+        }
+    }
+
+    /// Records the order in which the provider and the priorities sorted
+    /// candidates of a package. Candidates that were sorted before but are not
+    /// part of `candidates` keep their previous order after them.
+    fn record_sort(&self, name: NameId, candidates: &[SolvableId]) {
+        let mut sorts = self.sorts.borrow_mut();
+        let sort = sorts.entry(name).or_default();
+        let mut order = candidates.to_vec();
+        order.extend(
+            sort.order
+                .iter()
+                .copied()
+                .filter(|solvable| !candidates.contains(solvable)),
+        );
+        sort.order = order;
+    }
+
+    /// Returns the orders in which the candidates of packages were sorted so
+    /// far, see [`crate::Solver::candidate_sorts`].
+    pub(crate) fn sorts(&self) -> HashMap<NameId, PreviousSort> {
+        self.sorts.borrow().clone()
     }
 
     /// Returns the dependencies of a solvable. Requests the solvables from the
@@ -346,16 +562,113 @@ impl<D: DependencyProvider> SolverCache<D> {
                 }
 
                 let dependencies = self.provider.get_dependencies(solvable_id).await;
-                let dependencies_id = self.solvable_dependencies.alloc(dependencies);
-                self.solvable_to_dependencies
-                    .insert_copy(solvable_id, dependencies_id);
-                dependencies_id
+                self.insert_dependencies(solvable_id, dependencies)
             }
         };
 
         Ok(&self.solvable_dependencies[dependencies_id])
     }
 
+    /// Ensures the dependencies of all the given solvables are cached,
+    /// requesting the dependencies that are not cached yet from the
+    /// [`DependencyProvider`] with a single call to
+    /// [`DependencyProvider::get_dependencies_batch`].
+    ///
+    /// If the provider has requested the solving process to be cancelled, the
+    /// cancellation value will be returned as an `Err(...)`.
+    pub async fn get_or_cache_dependencies_batch(
+        &self,
+        solvable_ids: &[SolvableId],
+    ) -> Result<(), Box<dyn Any>> {
+        let missing = solvable_ids
+            .iter()
+            .copied()
+            .filter(|solvable_id| {
+                self.solvable_to_dependencies
+                    .get_copy(solvable_id)
+                    .is_none()
+            })
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        // Since getting the dependencies from the provider is a potentially blocking
+        // operation, we want to check beforehand whether we should cancel the solving
+        // process
+        if let Some(value) = self.provider.should_cancel_with_value() {
+            return Err(value);
+        }
+
+        let dependencies = self.provider.get_dependencies_batch(&missing).await;
+        assert_eq!(
+            dependencies.len(),
+            missing.len(),
+            "get_dependencies_batch must return the dependencies of every requested solvable"
+        );
+
+        for (solvable_id, dependencies) in missing.into_iter().zip(dependencies) {
+            self.insert_dependencies(solvable_id, dependencies);
+        }
+
+        Ok(())
+    }
+
+    /// Caches the dependencies that were returned together with candidates,
+    /// see [`Candidates::prefetched`].
+    fn insert_prefetched(&self, prefetched: Vec<(SolvableId, Dependencies)>) {
+        for (solvable_id, dependencies) in prefetched {
+            if self
+                .solvable_to_dependencies
+                .get_copy(&solvable_id)
+                .is_none()
+            {
+                self.insert_dependencies(solvable_id, dependencies);
+            }
+        }
+    }
+
+    /// Caches the dependencies of a solvable and records its constraints.
+    fn insert_dependencies(
+        &self,
+        solvable_id: SolvableId,
+        dependencies: Dependencies,
+    ) -> DependenciesId {
+        if let Dependencies::Known(known) = &dependencies {
+            let mut constraints_on_name = self.constraints_on_name.borrow_mut();
+            for version_set in known.active_constrains(&self.provider) {
+                let name = self
+                    .provider
+                    .canonical_name(self.provider.version_set_name(version_set));
+                constraints_on_name
+                    .entry(name)
+                    .or_default()
+                    .push((solvable_id, version_set));
+            }
+        }
+
+        let dependencies_id = self.solvable_dependencies.alloc(dependencies);
+        self.solvable_to_dependencies
+            .insert_copy(solvable_id, dependencies_id);
+        dependencies_id
+    }
+
+    /// Returns the constraints on the package with the given name, together
+    /// with the solvable that imposes each constraint.
+    ///
+    /// Only the dependencies that have been retrieved from the
+    /// [`DependencyProvider`] so far are taken into account, which after
+    /// solving includes the dependencies of every solvable the solver
+    /// considered. This can be used to explain why the versions of a package
+    /// are capped.
+    pub fn constraints_on(&self, name: NameId) -> Vec<(SolvableId, VersionSetId)> {
+        self.constraints_on_name
+            .borrow()
+            .get(&self.provider.canonical_name(name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Returns true if the dependencies for the given solvable are "cheaply"
     /// available. This means either the dependency provider indicated that
     /// the dependencies for a solvable are available or the dependencies
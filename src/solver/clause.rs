@@ -11,6 +11,7 @@ use itertools::Itertools;
 use crate::{
     internal::{
         arena::{Arena, ArenaId},
+        hash::DefaultHashBuilder,
         id::{ClauseId, LearntClauseId, StringId, VersionSetId},
     },
     requirement::Condition,
@@ -68,6 +69,44 @@ pub(crate) enum Clause {
     ///
     /// In SAT terms: (¬A ∨ ¬B)
     ForbidMultipleInstances(VariableId, Literal, NameId),
+    /// Requires that at least `n` of the candidates of a
+    /// [`Requirement::AtLeast`] are installed
+    ///
+    /// Usage: in addition to the [`Clause::Requires`] or
+    /// [`Clause::Conditional`] clause of the requirement, count the
+    /// candidates with [`Clause::AtLeastCounter`] clauses and generate one
+    /// [`Clause::AtLeast`] that requires the counter to reach `n`.
+    ///
+    /// In SAT terms: (¬A ∨ ¬C1 ∨ ... ∨ ¬Cm ∨ S), where A is the solvable, C1
+    /// to Cm are the conditions of the requirement, and S is the counter
+    /// variable that can only be true if at least `n` candidates are
+    /// installed. If there are fewer than `n` candidates, S is left out.
+    AtLeast(VariableId, Requirement, Vec<Literal>),
+    /// Counts the installed candidates of a [`Requirement::AtLeast`]
+    ///
+    /// Usage: the candidates B1 to Bk of the requirement are counted with
+    /// counter variables S(i, j), which can only be true if at least `j` of
+    /// the candidates B1 to Bi are installed. For every counter variable
+    /// generate the clauses S(i, j) → S(i-1, j) ∨ Bi and S(i, j) → S(i-1, j) ∨
+    /// S(i-1, j-1), leaving out the counter variables that are trivially true
+    /// or false.
+    ///
+    /// In SAT terms: (¬S ∨ L1 ∨ L2), where S is a counter variable and L1 and
+    /// L2 are a candidate or the counter variables for one candidate less.
+    AtLeastCounter(VariableId, Vec<Literal>),
+    /// Forbids installing two of the candidates of a
+    /// [`Requirement::ExactlyOne`] at the same time
+    ///
+    /// Usage: in addition to the [`Clause::Requires`] or
+    /// [`Clause::Conditional`] clause of the requirement, which ensures that
+    /// at least one candidate is installed, generate one
+    /// [`Clause::AtMostOne`] for every pair of candidates of different
+    /// packages.
+    ///
+    /// In SAT terms: (¬A ∨ ¬C1 ∨ ... ∨ ¬Cn ∨ ¬B1 ∨ ¬B2), where A is the
+    /// solvable, C1 to Cn are the conditions of the requirement, and B1 and B2
+    /// are the candidates of the pair.
+    AtMostOne(VariableId, Requirement, Vec<Literal>),
     /// Forbids packages that do not satisfy a solvable's constrains
     ///
     /// Usage: for each constrains relationship in a package, determine all the
@@ -93,6 +132,19 @@ pub(crate) enum Clause {
     /// assertion (¬B), but that would require additional logic in the
     /// solver.
     Lock(VariableId, VariableId),
+    /// Forces a pinned package to be installed
+    ///
+    /// Usage: generate one [`Clause::Pin`] for each solvable that was pinned
+    /// in the [`crate::Problem`]. The other candidates of the pinned package
+    /// are forbidden through [`Clause::Lock`] clauses.
+    ///
+    /// In SAT terms: (¬root ∨ A)
+    Pin(VariableId),
+    /// Forbids a package that was explicitly forbidden in the
+    /// [`crate::Problem`]
+    ///
+    /// In SAT terms: (¬root ∨ ¬A)
+    Forbidden(VariableId),
     /// A clause learnt during solving
     ///
     /// The learnt clause id can be used to retrieve the clause's literals,
@@ -103,6 +155,19 @@ pub(crate) enum Clause {
     /// A clause that forbids a package from being installed for an external
     /// reason.
     Excluded(VariableId, StringId),
+
+    /// A clause that was added as is through [`crate::Problem::raw_clauses`]
+    ///
+    /// In SAT terms: (¬root ∨ L1 ∨ L2 ∨ ... ∨ Ln), where L1 to Ln are the
+    /// literals of the clause.
+    Raw(Vec<Literal>),
+
+    /// Ties an extra of a package to the package itself: the extra can only
+    /// be active if the package is installed.
+    ///
+    /// In SAT terms: (¬E ∨ A), where E is the extra and A the solvable that
+    /// declares it.
+    Extra(VariableId, VariableId),
 }
 
 impl Clause {
@@ -220,6 +285,110 @@ impl Clause {
         )
     }
 
+    fn pin(pinned_candidate: VariableId) -> (Self, Option<[Literal; 2]>) {
+        (
+            Clause::Pin(pinned_candidate),
+            Some([VariableId::root().negative(), pinned_candidate.positive()]),
+        )
+    }
+
+    fn forbidden(forbidden_candidate: VariableId) -> (Self, Option<[Literal; 2]>) {
+        (
+            Clause::Forbidden(forbidden_candidate),
+            Some([
+                VariableId::root().negative(),
+                forbidden_candidate.negative(),
+            ]),
+        )
+    }
+
+    fn raw(literals: Vec<Literal>) -> (Self, Option<[Literal; 2]>) {
+        let watched_literals = literals
+            .first()
+            .map(|&literal| [VariableId::root().negative(), literal]);
+        (Clause::Raw(literals), watched_literals)
+    }
+
+    fn at_least(
+        parent: VariableId,
+        requirement: Requirement,
+        literals: Vec<Literal>,
+        decision_tracker: &DecisionTracker,
+    ) -> (Self, Option<[Literal; 2]>, bool) {
+        // It only makes sense to introduce the clause when the parent solvable is
+        // undecided or going to be installed
+        assert_ne!(decision_tracker.assigned_value(parent), Some(false));
+
+        let (watched_literals, conflict) = Self::implication(parent, &literals, decision_tracker);
+        (
+            Clause::AtLeast(parent, requirement, literals),
+            watched_literals,
+            conflict,
+        )
+    }
+
+    fn at_most_one(
+        parent: VariableId,
+        requirement: Requirement,
+        literals: Vec<Literal>,
+        decision_tracker: &DecisionTracker,
+    ) -> (Self, Option<[Literal; 2]>, bool) {
+        // It only makes sense to introduce the clause when the parent solvable is
+        // undecided or going to be installed
+        assert_ne!(decision_tracker.assigned_value(parent), Some(false));
+
+        let (watched_literals, conflict) = Self::implication(parent, &literals, decision_tracker);
+        (
+            Clause::AtMostOne(parent, requirement, literals),
+            watched_literals,
+            conflict,
+        )
+    }
+
+    fn at_least_counter(
+        counter: VariableId,
+        literals: Vec<Literal>,
+        decision_tracker: &DecisionTracker,
+    ) -> (Self, Option<[Literal; 2]>, bool) {
+        let (watched_literals, conflict) = Self::implication(counter, &literals, decision_tracker);
+        (
+            Clause::AtLeastCounter(counter, literals),
+            watched_literals,
+            conflict,
+        )
+    }
+
+    /// Returns the watches of a clause (¬A ∨ L1 ∨ ... ∨ Ln), and whether it
+    /// conflicts with the current decisions.
+    fn implication(
+        variable: VariableId,
+        literals: &[Literal],
+        decision_tracker: &DecisionTracker,
+    ) -> (Option<[Literal; 2]>, bool) {
+        let watched_literal = literals
+            .iter()
+            .find(|literal| literal.eval(decision_tracker.map()) != Some(false))
+            .or(literals.first())
+            .copied();
+        // If all the literals are assigned to false, the clause conflicts with the
+        // current decisions, like a [`Clause::Requires`] without candidates that can
+        // be installed.
+        let conflict = watched_literal
+            .is_some_and(|literal| literal.eval(decision_tracker.map()) == Some(false));
+
+        (
+            watched_literal.map(|literal| [variable.negative(), literal]),
+            conflict,
+        )
+    }
+
+    fn extra(solvable: VariableId, extra: VariableId) -> (Self, Option<[Literal; 2]>) {
+        (
+            Clause::Extra(solvable, extra),
+            Some([extra.negative(), solvable.positive()]),
+        )
+    }
+
     fn learnt(
         learnt_clause_id: LearntClauseId,
         literals: &[Literal],
@@ -280,7 +449,7 @@ impl Clause {
         requirements_to_sorted_candidates: &FrozenMap<
             Requirement,
             Vec<Vec<VariableId>>,
-            ahash::RandomState,
+            DefaultHashBuilder,
         >,
         init: C,
         mut visit: F,
@@ -312,6 +481,23 @@ impl Clause {
             Clause::Lock(_, s) => [s.negative(), VariableId::root().negative()]
                 .into_iter()
                 .try_fold(init, visit),
+            Clause::Pin(s) => [VariableId::root().negative(), s.positive()]
+                .into_iter()
+                .try_fold(init, visit),
+            Clause::Forbidden(s) => [VariableId::root().negative(), s.negative()]
+                .into_iter()
+                .try_fold(init, visit),
+            Clause::Raw(literals) => iter::once(VariableId::root().negative())
+                .chain(literals.iter().copied())
+                .try_fold(init, visit),
+            Clause::AtLeast(variable, _, literals)
+            | Clause::AtLeastCounter(variable, literals)
+            | Clause::AtMostOne(variable, _, literals) => iter::once(variable.negative())
+                .chain(literals.iter().copied())
+                .try_fold(init, visit),
+            Clause::Extra(solvable, extra) => [extra.negative(), solvable.positive()]
+                .into_iter()
+                .try_fold(init, visit),
             Clause::Conditional(package_id, condition_variables, requirement) => {
                 iter::once(package_id.negative())
                     .chain(condition_variables.iter().map(|c| c.0.negative()))
@@ -335,7 +521,7 @@ impl Clause {
         requirements_to_sorted_candidates: &FrozenMap<
             Requirement,
             Vec<Vec<VariableId>>,
-            ahash::RandomState,
+            DefaultHashBuilder,
         >,
         mut visit: impl FnMut(Literal),
     ) {
@@ -448,6 +634,85 @@ impl WatchedLiterals {
         (Self::from_kind_and_initial_watches(watched_literals), kind)
     }
 
+    pub fn pin(pinned_candidate: VariableId) -> (Option<Self>, Clause) {
+        let (kind, watched_literals) = Clause::pin(pinned_candidate);
+        (Self::from_kind_and_initial_watches(watched_literals), kind)
+    }
+
+    pub fn forbidden(forbidden_candidate: VariableId) -> (Option<Self>, Clause) {
+        let (kind, watched_literals) = Clause::forbidden(forbidden_candidate);
+        (Self::from_kind_and_initial_watches(watched_literals), kind)
+    }
+
+    pub fn raw(literals: Vec<Literal>) -> (Option<Self>, Clause) {
+        let (kind, watched_literals) = Clause::raw(literals);
+        (Self::from_kind_and_initial_watches(watched_literals), kind)
+    }
+
+    /// Shorthand method to construct a [`Clause::AtLeast`] without requiring
+    /// complicated arguments.
+    ///
+    /// The returned boolean value is true when adding the clause resulted in a
+    /// conflict.
+    pub fn at_least(
+        parent: VariableId,
+        requirement: Requirement,
+        literals: Vec<Literal>,
+        decision_tracker: &DecisionTracker,
+    ) -> (Option<Self>, bool, Clause) {
+        let (kind, watched_literals, conflict) =
+            Clause::at_least(parent, requirement, literals, decision_tracker);
+        (
+            Self::from_kind_and_initial_watches(watched_literals),
+            conflict,
+            kind,
+        )
+    }
+
+    /// Shorthand method to construct a [`Clause::AtLeastCounter`] without
+    /// requiring complicated arguments.
+    ///
+    /// The returned boolean value is true when adding the clause resulted in a
+    /// conflict.
+    pub fn at_least_counter(
+        counter: VariableId,
+        literals: Vec<Literal>,
+        decision_tracker: &DecisionTracker,
+    ) -> (Option<Self>, bool, Clause) {
+        let (kind, watched_literals, conflict) =
+            Clause::at_least_counter(counter, literals, decision_tracker);
+        (
+            Self::from_kind_and_initial_watches(watched_literals),
+            conflict,
+            kind,
+        )
+    }
+
+    /// Shorthand method to construct a [`Clause::AtMostOne`] without
+    /// requiring complicated arguments.
+    ///
+    /// The returned boolean value is true when adding the clause resulted in a
+    /// conflict.
+    pub fn at_most_one(
+        parent: VariableId,
+        requirement: Requirement,
+        literals: Vec<Literal>,
+        decision_tracker: &DecisionTracker,
+    ) -> (Option<Self>, bool, Clause) {
+        let (kind, watched_literals, conflict) =
+            Clause::at_most_one(parent, requirement, literals, decision_tracker);
+        (
+            Self::from_kind_and_initial_watches(watched_literals),
+            conflict,
+            kind,
+        )
+    }
+
+    pub fn extra(solvable: VariableId, extra: VariableId) -> (Option<Self>, Clause) {
+        let (kind, watched_literals) = Clause::extra(solvable, extra);
+        (Self::from_kind_and_initial_watches(watched_literals), kind)
+    }
+
     pub fn forbid_multiple(
         candidate: VariableId,
         other_candidate: Literal,
@@ -513,7 +778,7 @@ impl WatchedLiterals {
         requirement_to_sorted_candidates: &FrozenMap<
             Requirement,
             Vec<Vec<VariableId>>,
-            ahash::RandomState,
+            DefaultHashBuilder,
         >,
         decision_map: &DecisionMap,
         for_watch_index: usize,
@@ -523,7 +788,11 @@ impl WatchedLiterals {
         match clause {
             Clause::InstallRoot => unreachable!(),
             Clause::Excluded(_, _) => unreachable!(),
-            Clause::Constrains(..) | Clause::ForbidMultipleInstances(..) | Clause::Lock(..) => {
+            Clause::Constrains(..)
+            | Clause::ForbidMultipleInstances(..)
+            | Clause::Lock(..)
+            | Clause::Pin(..)
+            | Clause::Forbidden(..) => {
                 // We cannot move the watches in these clauses.
                 None
             }
@@ -603,6 +872,17 @@ impl Literal {
         VariableId::from_usize(self.to_usize() >> 1)
     }
 
+    /// Returns the literal as it is written in the DIMACS format: the index of
+    /// the variable plus one, negated if the literal is negated.
+    pub(crate) fn to_dimacs(self) -> i32 {
+        let variable = self.variable().to_usize() as i32 + 1;
+        if self.negate() {
+            -variable
+        } else {
+            variable
+        }
+    }
+
     /// Evaluates the literal, or returns `None` if no value has been assigned
     /// to the solvable
     #[inline(always)]
@@ -689,6 +969,85 @@ impl<'i, I: Interner> Display for ClauseDisplay<'i, I> {
                     other,
                 )
             }
+            Clause::Pin(pinned) => {
+                write!(
+                    f,
+                    "Pin({}({:?}))",
+                    pinned.display(self.variable_map, self.interner),
+                    pinned,
+                )
+            }
+            Clause::Forbidden(forbidden) => {
+                write!(
+                    f,
+                    "Forbidden({}({:?}))",
+                    forbidden.display(self.variable_map, self.interner),
+                    forbidden,
+                )
+            }
+            Clause::AtLeast(parent, requirement, literals)
+            | Clause::AtMostOne(parent, requirement, literals) => {
+                write!(
+                    f,
+                    "{}({}({:?}), {}, [{}])",
+                    if matches!(self.kind, Clause::AtLeast(..)) {
+                        "AtLeast"
+                    } else {
+                        "AtMostOne"
+                    },
+                    parent.display(self.variable_map, self.interner),
+                    parent,
+                    requirement.display(self.interner),
+                    literals
+                        .iter()
+                        .map(|literal| format!(
+                            "{}{}",
+                            if literal.negate() { "¬" } else { "" },
+                            literal.variable().display(self.variable_map, self.interner)
+                        ))
+                        .join(", ")
+                )
+            }
+            Clause::AtLeastCounter(counter, literals) => {
+                write!(
+                    f,
+                    "AtLeastCounter({}({:?}), [{}])",
+                    counter.display(self.variable_map, self.interner),
+                    counter,
+                    literals
+                        .iter()
+                        .map(|literal| format!(
+                            "{}{}",
+                            if literal.negate() { "¬" } else { "" },
+                            literal.variable().display(self.variable_map, self.interner)
+                        ))
+                        .join(", ")
+                )
+            }
+            Clause::Extra(solvable, extra) => {
+                write!(
+                    f,
+                    "Extra({}({:?}), {}({:?}))",
+                    solvable.display(self.variable_map, self.interner),
+                    solvable,
+                    extra.display(self.variable_map, self.interner),
+                    extra,
+                )
+            }
+            Clause::Raw(literals) => {
+                write!(
+                    f,
+                    "Raw({})",
+                    literals
+                        .iter()
+                        .map(|literal| format!(
+                            "{}{}",
+                            if literal.negate() { "¬" } else { "" },
+                            literal.variable().display(self.variable_map, self.interner)
+                        ))
+                        .join(", ")
+                )
+            }
             Clause::Conditional(package_id, condition_variables, requirement) => {
                 write!(
                     f,
@@ -0,0 +1,84 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{internal::hash::HashMap, SolvableId};
+
+/// The clauses that encode a problem in conjunctive normal form, see
+/// [`crate::Solver::export_cnf`] and [`crate::Solver::cnf`].
+///
+/// Like in the DIMACS format, variables are numbered from `1` and a literal is
+/// a variable that is negated if it is negative. A clause is satisfied if at
+/// least one of its literals is true. The [`Display`] implementation writes the
+/// clauses in the DIMACS format, so they can be fed to other SAT solvers.
+///
+/// Variable `1` is the root of the problem, which is required to be true.
+/// Besides the solvables, some variables are only used to make the encoding
+/// more compact. These do not map to a solvable.
+#[derive(Debug, Clone, Default)]
+pub struct Cnf {
+    pub(crate) variables: Vec<Option<SolvableId>>,
+    pub(crate) solvable_variables: HashMap<SolvableId, u32>,
+    pub(crate) clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    /// Returns the number of variables.
+    pub fn num_variables(&self) -> usize {
+        self.variables.len()
+    }
+
+    /// Returns the clauses, each as a list of literals.
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// Returns the solvable that the given variable represents, if any.
+    pub fn solvable(&self, variable: u32) -> Option<SolvableId> {
+        let idx = (variable as usize).checked_sub(1)?;
+        self.variables.get(idx).copied().flatten()
+    }
+
+    /// Returns the variable that represents the given solvable, or `None` if
+    /// the solvable is not part of the problem.
+    pub fn variable(&self, solvable: SolvableId) -> Option<u32> {
+        self.solvable_variables.get(&solvable).copied()
+    }
+}
+
+impl Display for Cnf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "p cnf {} {}", self.variables.len(), self.clauses.len())?;
+        for clause in &self.clauses {
+            for literal in clause {
+                write!(f, "{literal} ")?;
+            }
+            writeln!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+/// A step of a proof of unsatisfiability in the DRAT format, see
+/// [`crate::conflict::Conflict::drat_proof`].
+///
+/// The literals use the same variable numbering as the [`Cnf`] of the solve
+/// that produced the proof. The [`Display`] implementation writes the step
+/// as a line of a DRAT proof file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofStep {
+    /// Adds a clause that follows from the clauses before it by unit
+    /// propagation. A proof ends by adding the empty clause.
+    Add(Vec<i32>),
+}
+
+impl Display for ProofStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofStep::Add(literals) => {
+                for literal in literals {
+                    write!(f, "{literal} ")?;
+                }
+                write!(f, "0")
+            }
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::internal::id::{ClauseId, VariableId};
+use crate::internal::{
+    arena::ArenaId,
+    id::{ClauseId, VariableId},
+};
 use crate::solver::{decision::Decision, decision_map::DecisionMap};
 
 /// Tracks the assignments to solvables, keeping a log that can be used to backtrack, and a map that
@@ -7,6 +10,10 @@ pub(crate) struct DecisionTracker {
     map: DecisionMap,
     stack: Vec<Decision>,
     propagate_index: usize,
+    /// The clause that implied the assignment of every variable, indexed by
+    /// variable. The first assignment at every level is a decision and was not
+    /// implied.
+    implied_by: Vec<Option<ClauseId>>,
 }
 
 impl DecisionTracker {
@@ -15,6 +22,7 @@ impl DecisionTracker {
             map: DecisionMap::new(),
             stack: Vec::new(),
             propagate_index: 0,
+            implied_by: Vec::new(),
         }
     }
 
@@ -22,6 +30,7 @@ impl DecisionTracker {
         self.map = DecisionMap::new();
         self.stack = Vec::new();
         self.propagate_index = 0;
+        self.implied_by = Vec::new();
     }
 
     #[inline(always)]
@@ -41,6 +50,27 @@ impl DecisionTracker {
         self.map.level(variable_id)
     }
 
+    /// Returns the clause that implied the assignment of the specified
+    /// variable, or `None` if the variable is unassigned or its assignment is
+    /// the decision of its level.
+    pub(crate) fn implied_by(&self, variable_id: VariableId) -> Option<ClauseId> {
+        self.implied_by
+            .get(variable_id.to_usize())
+            .copied()
+            .flatten()
+    }
+
+    /// Replaces the clause that caused every assignment with the result of
+    /// `map`, after the clauses were renumbered.
+    pub(crate) fn map_derived_from(&mut self, mut map: impl FnMut(ClauseId) -> ClauseId) {
+        for decision in &mut self.stack {
+            decision.derived_from = map(decision.derived_from);
+        }
+        for clause_id in self.implied_by.iter_mut().flatten() {
+            *clause_id = map(*clause_id);
+        }
+    }
+
     // Find the clause that caused the assignment of the specified solvable. If no assignment has
     // been made to the solvable than `None` is returned.
     pub(crate) fn find_clause_for_assignment(&self, variable_id: VariableId) -> Option<ClauseId> {
@@ -58,7 +88,12 @@ impl DecisionTracker {
     pub(crate) fn try_add_decision(&mut self, decision: Decision, level: u32) -> Result<bool, ()> {
         match self.map.value(decision.variable) {
             None => {
+                let implied = self
+                    .stack
+                    .last()
+                    .is_some_and(|last| self.map.level(last.variable) == level);
                 self.map.set(decision.variable, decision.value, level);
+                self.set_implied_by(decision.variable, implied.then_some(decision.derived_from));
                 self.stack.push(decision);
                 Ok(true)
             }
@@ -85,6 +120,7 @@ impl DecisionTracker {
     pub(crate) fn undo_last(&mut self) -> (Decision, u32) {
         let decision = self.stack.pop().unwrap();
         self.map.reset(decision.variable);
+        self.set_implied_by(decision.variable, None);
 
         self.propagate_index = self.stack.len();
 
@@ -92,6 +128,17 @@ impl DecisionTracker {
         (decision, self.map.level(top_decision.variable))
     }
 
+    fn set_implied_by(&mut self, variable_id: VariableId, clause_id: Option<ClauseId>) {
+        let index = variable_id.to_usize();
+        if index >= self.implied_by.len() {
+            if clause_id.is_none() {
+                return;
+            }
+            self.implied_by.resize(index + 1, None);
+        }
+        self.implied_by[index] = clause_id;
+    }
+
     /// Returns the next decision in the log for which unit propagation still needs to run
     ///
     /// Side-effect: the decision will be marked as propagated
@@ -0,0 +1,40 @@
+use crate::SolvableId;
+
+/// Describes a backjump of the solver after it learnt a clause from a
+/// conflict, see [`crate::SolverBuilder::on_backtrack`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacktrackEvent {
+    /// The decision level at which the conflict was encountered.
+    pub from_level: u32,
+
+    /// The decision level the solver jumped back to.
+    pub to_level: u32,
+
+    /// The solvables whose assignments were undone by the backjump, in the
+    /// order in which they were assigned.
+    pub undone: Vec<SolvableId>,
+}
+
+/// The reason the solver eliminated a candidate, see
+/// [`crate::SolverBuilder::on_candidate_excluded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExclusionReason {
+    /// The candidate does not match a constraint of the problem or of another
+    /// selected solvable.
+    Constrained,
+
+    /// The candidate conflicts with the dependencies of the other selected
+    /// solvables, for instance because another version of the package is
+    /// selected or because none of the candidates of one of its requirements
+    /// can be selected.
+    DependencyConflict,
+
+    /// The candidate was forbidden by the caller, either directly through
+    /// [`crate::Problem::forbidden`] or because another candidate of the
+    /// package was pinned or locked.
+    ForbiddenByCaller,
+
+    /// The candidate was excluded by the dependency provider, for instance
+    /// because its dependencies are unknown.
+    Excluded,
+}
@@ -1,53 +1,69 @@
-use std::{any::Any, fmt::Display, future::ready, ops::ControlFlow};
+use std::{
+    any::Any,
+    collections::VecDeque,
+    fmt::Display,
+    future::{ready, Future},
+    ops::ControlFlow,
+    task::Poll,
+};
 
-use ahash::{HashMap, HashSet};
+pub(crate) use cache::filter_candidates;
 pub use cache::SolverCache;
 use clause::{Clause, Literal, WatchedLiterals};
+pub use cnf::{Cnf, ProofStep};
 use decision::Decision;
 use decision_tracker::DecisionTracker;
 use elsa::FrozenMap;
+pub use events::{BacktrackEvent, ExclusionReason};
 use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
 use indexmap::IndexMap;
 use itertools::Itertools;
-use variable_map::{SolvableOrStringId, VariableMap};
+pub use stats::{DifficultyScore, SolverStats, WatchStats};
+use variable_map::{SolvableOrStringId, VariableMap, VariableOrigin};
+pub use warm_start::{PortableClause, PortableLiteral};
 use watch_map::WatchMap;
 
 use crate::{
-    conflict::Conflict,
+    conflict::{Conflict, ConflictGraph},
     internal::{
         arena::{Arena, ArenaId},
+        hash::{DefaultHashBuilder, HashMap, HashSet},
         id::{ClauseId, LearntClauseId, NameId, SolvableId, SolvableOrRootId, VariableId},
         mapping::Mapping,
     },
-    requirement::{Condition, ConditionalRequirement},
+    requirement::{
+        Condition, ConditionExpr, ConditionExprError, ConditionalRequirement, RequirementGroup,
+        DEFAULT_MAX_CONDITION_DEPTH, DEFAULT_MAX_CONDITION_TERMS,
+    },
     runtime::{AsyncRuntime, NowOrNeverRuntime},
     solver::binary_encoding::AtMostOnceTracker,
-    Candidates, Dependencies, DependencyProvider, KnownDependencies, Requirement, StringId,
-    VersionSetId,
+    utils::stable_hash,
+    Candidates, Dependencies, DependencyProvider, KnownDependencies, PreviousSort, Requirement,
+    StringId, VersionSetId,
 };
 
 mod binary_encoding;
 mod cache;
 pub(crate) mod clause;
+mod cnf;
 mod decision;
 mod decision_map;
 mod decision_tracker;
+mod events;
+mod stats;
 pub(crate) mod variable_map;
+mod warm_start;
 mod watch_map;
 
 #[derive(Default)]
 struct AddClauseOutput {
     new_requires_clauses: Vec<(VariableId, Requirement, ClauseId)>,
-    new_conditional_clauses: Vec<(
-        VariableId,
-        Vec<(VariableId, Condition)>,
-        Requirement,
-        ClauseId,
-    )>,
+    new_conditional_clauses: Vec<(VariableId, ConditionVariables, Requirement, ClauseId)>,
     conflicting_clauses: Vec<ClauseId>,
     negative_assertions: Vec<(VariableId, ClauseId)>,
     clauses_to_watch: Vec<ClauseId>,
     new_names: Vec<NameId>,
+    candidates_truncated: bool,
 }
 
 /// Describes the problem that is to be solved by the solver.
@@ -58,9 +74,21 @@ struct AddClauseOutput {
 ///
 /// This struct follows the builder pattern and can have its fields set by one
 /// of the available setter methods.
+#[derive(Clone)]
 pub struct Problem<S> {
     requirements: Vec<ConditionalRequirement>,
     constraints: Vec<VersionSetId>,
+    pins: Vec<SolvableId>,
+    base: Vec<SolvableId>,
+    keep: Vec<SolvableId>,
+    forbidden: Vec<SolvableId>,
+    prohibited_names: Vec<NameId>,
+    policy_pins: Vec<(NameId, VersionSetId, StringId)>,
+    depth_constraints: Vec<(VersionSetId, u32)>,
+    raw_clauses: Vec<Vec<(SolvableId, bool)>>,
+    must_upgrade: Vec<SolvableId>,
+    recommends: Vec<ConditionalRequirement>,
+    requirement_groups: Vec<RequirementGroup>,
     soft_requirements: S,
 }
 
@@ -77,6 +105,17 @@ impl Problem<std::iter::Empty<SolvableId>> {
         Self {
             requirements: Default::default(),
             constraints: Default::default(),
+            pins: Default::default(),
+            base: Default::default(),
+            keep: Default::default(),
+            forbidden: Default::default(),
+            prohibited_names: Default::default(),
+            policy_pins: Default::default(),
+            depth_constraints: Default::default(),
+            raw_clauses: Default::default(),
+            must_upgrade: Default::default(),
+            recommends: Default::default(),
+            requirement_groups: Default::default(),
             soft_requirements: Default::default(),
         }
     }
@@ -107,6 +146,205 @@ impl<S: IntoIterator<Item = SolvableId>> Problem<S> {
         }
     }
 
+    /// Sets the solvables that are pinned.
+    ///
+    /// A pinned solvable _must_ be included in the solution, and all other
+    /// candidates of its package are forbidden. Any requirement on the package
+    /// can therefore only be satisfied by the pinned solvable; if it does not
+    /// match such a requirement the problem is unsolvable and the pin is
+    /// reported as the cause of the conflict. At most one solvable should be
+    /// pinned per package.
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn pins(self, pins: Vec<SolvableId>) -> Self {
+        Self { pins, ..self }
+    }
+
+    /// Sets the solvables of a base environment that the solution is layered
+    /// on top of.
+    ///
+    /// Like pinned solvables, the solvables of the base environment are
+    /// always included in the solution and the other candidates of their
+    /// packages are forbidden, so the dependencies of the problem are
+    /// satisfied from the base environment where possible and only the
+    /// packages the base environment lacks are added. A conflict with the
+    /// base environment is reported as such, instead of as a conflict with a
+    /// pin.
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn base(self, base: Vec<SolvableId>) -> Self {
+        Self { base, ..self }
+    }
+
+    /// Sets the solvables that are currently installed and should be kept.
+    ///
+    /// Kept solvables are included in the solution, unless that makes the
+    /// problem unsolvable. In that case the solver removes the kept solvable
+    /// involved in the conflict that comes last in `keep`, and tries again.
+    /// The removed solvables can be retrieved with
+    /// [`Solver::removed_solvables`]. Unlike [`Self::pins`], keeping a
+    /// solvable does not forbid the other candidates of its package.
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn keep(self, keep: Vec<SolvableId>) -> Self {
+        Self { keep, ..self }
+    }
+
+    /// Sets the solvables that must not be part of the solution, for instance
+    /// known bad builds.
+    ///
+    /// Unlike solvables excluded by the [`DependencyProvider`], forbidden
+    /// solvables only apply to this problem.
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn forbidden_solvables(self, forbidden: Vec<SolvableId>) -> Self {
+        Self { forbidden, ..self }
+    }
+
+    /// Sets the names of the packages that must not be installed, for
+    /// instance because they are being uninstalled.
+    ///
+    /// All candidates of these packages are forbidden, so any requirement
+    /// that can only be satisfied by one of them makes the problem
+    /// unsolvable. Unlike [`Problem::forbidden_solvables`], a conflict
+    /// explains that the package itself is prohibited.
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn prohibited_names(self, prohibited_names: Vec<NameId>) -> Self {
+        Self {
+            prohibited_names,
+            ..self
+        }
+    }
+
+    /// Sets the packages that are pinned by policy, for instance to the
+    /// versions approved by an organization. Every entry consists of the name
+    /// of the package, the version set the package is restricted to, and the
+    /// reason for the policy.
+    ///
+    /// The version set is enforced like a constraint passed to
+    /// [`Problem::constraints`], but a conflict caused by it cites the reason
+    /// in [`crate::conflict::Conflict::display_user_friendly`].
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn policy_pins(self, policy_pins: Vec<(NameId, VersionSetId, StringId)>) -> Self {
+        Self {
+            policy_pins,
+            ..self
+        }
+    }
+
+    /// Sets constraints that only apply to packages deep in the dependency
+    /// tree, for instance to allow prereleases of the packages that are
+    /// required directly but not of their dependencies.
+    ///
+    /// Every entry consists of a version set and a depth. The packages
+    /// required by the problem are at depth 1, their dependencies at depth
+    /// 2, and so on. The version set constrains its package like a
+    /// constraint of every solvable that requires the package, as long as
+    /// the package is deeper than the given depth. A package that is reached
+    /// through several requirements is at the shallowest depth, so it is not
+    /// constrained if it is required close enough to the roots.
+    ///
+    /// The depth of a package is computed while the clauses are generated,
+    /// so it is the shallowest depth among the requirements the solver has
+    /// seen at that point. The requirements of the problem are always seen
+    /// first.
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn depth_constraints(self, depth_constraints: Vec<(VersionSetId, u32)>) -> Self {
+        Self {
+            depth_constraints,
+            ..self
+        }
+    }
+
+    /// Sets clauses that are added to the problem as is, to express
+    /// constraints that cannot be expressed otherwise, like implications
+    /// between solvables or installing at most `k` out of a set of
+    /// solvables.
+    ///
+    /// Every clause is a disjunction of literals: at least one of the
+    /// literals must hold in the solution. A literal `(solvable, true)` holds
+    /// if the solvable is part of the solution, `(solvable, false)` holds if
+    /// it is not. An empty clause makes the problem unsolvable.
+    ///
+    /// Raw clauses bypass the provenance the solver keeps for all other
+    /// clauses, so a conflict caused by a raw clause is not explained in
+    /// [`crate::conflict::Conflict::display_user_friendly`] or
+    /// [`crate::conflict::Conflict::reasons`].
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn raw_clauses(self, raw_clauses: Vec<Vec<(SolvableId, bool)>>) -> Self {
+        Self {
+            raw_clauses,
+            ..self
+        }
+    }
+
+    /// Sets the solvables that must be upgraded, for instance to force an
+    /// upgrade of a package that is currently installed.
+    ///
+    /// The solution will not contain a given solvable or any other candidate
+    /// of its package that is not newer than it. A candidate is newer if
+    /// [`DependencyProvider::sort_candidates`] orders it before the given
+    /// solvable. This does not require the package to be part of the
+    /// solution, combine it with a requirement to enforce that.
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn must_upgrade(self, must_upgrade: Vec<SolvableId>) -> Self {
+        Self {
+            must_upgrade,
+            ..self
+        }
+    }
+
+    /// Sets the weak requirements of the problem.
+    ///
+    /// Like soft requirements, recommends are only included in the solution
+    /// if they can be satisfied alongside the rest of the solution. If a
+    /// recommend cannot be satisfied, it is skipped without causing a
+    /// conflict. Recommends are considered in order once the requirements
+    /// and soft requirements have been solved, the first candidate of a
+    /// recommend that can be installed is selected. A recommend whose version
+    /// set conditions are not satisfied by the solution is skipped.
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn recommends(self, recommends: Vec<ConditionalRequirement>) -> Self {
+        Self { recommends, ..self }
+    }
+
+    /// Sets the groups of requirements that are either all satisfied by the
+    /// solution, or not taken into account at all.
+    ///
+    /// A group is activated if all of its requirements can be satisfied
+    /// together with the rest of the problem. Otherwise the whole group is
+    /// excluded, and none of its requirements causes a solvable to be
+    /// selected. Every group is represented by a variable that activates its
+    /// requirements, and the solver tries to activate the groups in order
+    /// before making any other decision, so groups that come first are
+    /// activated preferably. The excluded groups can be retrieved with
+    /// [`Solver::excluded_requirement_groups`].
+    ///
+    /// Returns the [`Problem`] for further mutation or to pass to
+    /// [`Solver::solve`].
+    pub fn requirement_groups(self, requirement_groups: Vec<RequirementGroup>) -> Self {
+        Self {
+            requirement_groups,
+            ..self
+        }
+    }
+
     /// Sets the additional requirements that the solver should _try_ and
     /// fulfill once it has found a solution to the main problem.
     ///
@@ -129,9 +367,40 @@ impl<S: IntoIterator<Item = SolvableId>> Problem<S> {
         Problem {
             requirements: self.requirements,
             constraints: self.constraints,
+            pins: self.pins,
+            base: self.base,
+            keep: self.keep,
+            forbidden: self.forbidden,
+            prohibited_names: self.prohibited_names,
+            policy_pins: self.policy_pins,
+            depth_constraints: self.depth_constraints,
+            raw_clauses: self.raw_clauses,
+            must_upgrade: self.must_upgrade,
+            recommends: self.recommends,
+            requirement_groups: self.requirement_groups,
             soft_requirements,
         }
     }
+
+    /// Collects the soft requirements, so the problem can be cloned.
+    fn collect_soft_requirements(self) -> Problem<Vec<SolvableId>> {
+        Problem {
+            requirements: self.requirements,
+            constraints: self.constraints,
+            pins: self.pins,
+            keep: self.keep,
+            forbidden: self.forbidden,
+            recommends: self.recommends,
+            must_upgrade: self.must_upgrade,
+            requirement_groups: self.requirement_groups,
+            raw_clauses: self.raw_clauses,
+            base: self.base,
+            prohibited_names: self.prohibited_names,
+            policy_pins: self.policy_pins,
+            depth_constraints: self.depth_constraints,
+            soft_requirements: self.soft_requirements.into_iter().collect(),
+        }
+    }
 }
 
 #[derive(Default)]
@@ -151,24 +420,111 @@ impl Clauses {
 
 type RequirementCandidateVariables = Vec<Vec<VariableId>>;
 
+/// The variables of the conditions of a conditional requirement, together
+/// with the condition each variable represents.
+type ConditionVariables = Vec<(VariableId, Condition)>;
+
+/// The state of an attempt of [`Solver::solve_anytime`] that ran out of
+/// budget.
+#[derive(Debug, Clone)]
+pub struct PartialResult {
+    /// The number of conflicts the attempt was allowed to encounter.
+    pub conflict_budget: usize,
+
+    /// The solvables that were selected when the attempt was stopped. They do
+    /// not necessarily satisfy all requirements, nor are they necessarily
+    /// part of the final solution.
+    pub solvables: Vec<SolvableId>,
+
+    /// The decision level the attempt was at when it was stopped, which shows
+    /// how deep the search was at that moment. Level 1 is the root level.
+    pub decision_level: u32,
+
+    /// The number of assignments on the trail when the attempt was stopped,
+    /// including the ones that are propagated from decisions.
+    pub trail_length: usize,
+
+    /// The statistics of the attempt.
+    pub stats: SolverStats,
+}
+
+/// The result of [`Solver::solve_detailed`], a solution together with the
+/// details of how it was reached.
+#[derive(Debug, Clone)]
+pub struct SolveOutcome {
+    /// The solvables that were selected, like [`Solver::solve`] returns them.
+    pub solution: Vec<SolvableId>,
+
+    /// The conditions of conditional requirements that hold in the solution,
+    /// see [`Solver::active_conditions`].
+    pub active_conditions: Vec<(Option<SolvableId>, Condition)>,
+
+    /// The solvable that was selected for every package in the solution, by
+    /// canonical name, see [`Solver::selected_for`].
+    pub selected: std::collections::HashMap<NameId, SolvableId>,
+
+    /// The statistics of the solve, see [`Solver::stats`].
+    pub stats: SolverStats,
+}
+
+/// The reason a solve is cancelled when it comes across a solvable whose
+/// dependencies are unknown, see [`SolverBuilder::with_strict_unknown_dependencies`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownDependencies {
+    /// The solvable whose dependencies are unknown.
+    pub solvable: SolvableId,
+
+    /// The reason the dependencies are unknown, as reported by
+    /// [`Dependencies::Unknown`].
+    pub reason: StringId,
+}
+
+/// What the solver steers its search towards, see [`SolverBuilder::with_objective`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Objective {
+    /// Prefer the candidates that [`DependencyProvider::sort_candidates`]
+    /// sorts first, which are usually the newest versions.
+    #[default]
+    MaximizeVersions,
+
+    /// Prefer the candidates that [`DependencyProvider::sort_candidates`]
+    /// sorts last, which are usually the oldest versions.
+    MinimizeVersions,
+}
+
+/// The result of [`Solver::solve_multi_platform`].
+#[derive(Debug)]
+pub struct MultiPlatformSolution {
+    /// The solution or the reason there is none for every platform, in the
+    /// order the platforms were given.
+    pub solutions: Vec<(StringId, Result<Vec<SolvableId>, UnsolvableOrCancelled>)>,
+
+    /// The solvables that are part of the solution of every platform that
+    /// could be solved, in the order of the first solution.
+    pub shared: Vec<SolvableId>,
+}
+
+/// The reason a solve is cancelled once it exceeds its conflict budget.
+struct ConflictBudgetExhausted;
+
 /// Drives the SAT solving process.
 pub struct Solver<D: DependencyProvider, RT: AsyncRuntime = NowOrNeverRuntime> {
     pub(crate) async_runtime: RT,
     pub(crate) cache: SolverCache<D>,
 
     pub(crate) clauses: Clauses,
-    requires_clauses: IndexMap<VariableId, Vec<(Requirement, ClauseId)>, ahash::RandomState>,
+    requires_clauses: IndexMap<VariableId, Vec<(Requirement, ClauseId)>, DefaultHashBuilder>,
     conditional_clauses: IndexMap<
-        (VariableId, Vec<(VariableId, Condition)>),
+        (VariableId, ConditionVariables),
         Vec<(Requirement, ClauseId)>,
-        ahash::RandomState,
+        DefaultHashBuilder,
     >,
     watches: WatchMap,
 
     /// A mapping from requirements to the variables that represent the
     /// candidates.
     requirement_to_sorted_candidates:
-        FrozenMap<Requirement, RequirementCandidateVariables, ahash::RandomState>,
+        FrozenMap<Requirement, RequirementCandidateVariables, DefaultHashBuilder>,
 
     pub(crate) variable_map: VariableMap,
 
@@ -178,6 +534,20 @@ pub struct Solver<D: DependencyProvider, RT: AsyncRuntime = NowOrNeverRuntime> {
     learnt_why: Mapping<LearntClauseId, Vec<ClauseId>>,
     learnt_clause_ids: Vec<ClauseId>,
 
+    /// The number of conflicts every clause was involved in, see
+    /// [`Conflict::reasons`].
+    pub(crate) clause_activity: HashMap<ClauseId, u32>,
+
+    /// Whether the learnt clauses that were kept from the previous solve (see
+    /// [`Solver::solve_current`]) or imported (see
+    /// [`Solver::import_learnt_clauses`]) were used during the current solve.
+    warm_start_clauses_used: Vec<bool>,
+
+    /// The learnt clauses that were imported with
+    /// [`Solver::import_learnt_clauses`], as the solvables and whether they
+    /// are negated.
+    imported_clauses: Vec<Vec<(SolvableId, bool)>>,
+
     clauses_added_for_package: HashSet<NameId>,
     clauses_added_for_solvable: HashSet<SolvableOrRootId>,
     forbidden_clauses_added: HashMap<NameId, AtMostOnceTracker<VariableId>>,
@@ -188,7 +558,63 @@ pub struct Solver<D: DependencyProvider, RT: AsyncRuntime = NowOrNeverRuntime> {
     root_requirements: Vec<ConditionalRequirement>,
 
     /// Additional constraints imposed by the root.
-    root_constraints: Vec<VersionSetId>,
+    pub(crate) root_constraints: Vec<VersionSetId>,
+    pub(crate) root_pins: Vec<SolvableId>,
+
+    /// The solvables of the base environment, which are pinned as well, see
+    /// [`Problem::base`].
+    pub(crate) root_base: Vec<SolvableId>,
+
+    /// Installed solvables that should be kept in the solution if possible.
+    root_keep: Vec<SolvableId>,
+
+    /// Solvables that must not be part of the solution.
+    root_forbidden: Vec<SolvableId>,
+
+    /// The packages of which no solvable may be part of the solution, see
+    /// [`Problem::prohibited_names`]. Their candidates are part of
+    /// `root_forbidden`.
+    pub(crate) root_prohibited_names: Vec<NameId>,
+
+    /// The packages that are pinned by policy, see [`Problem::policy_pins`].
+    /// Their version sets are part of `root_constraints`.
+    root_policy_pins: Vec<(NameId, VersionSetId, StringId)>,
+
+    /// The constraints that only apply beyond a depth, see
+    /// [`Problem::depth_constraints`].
+    pub(crate) root_depth_constraints: Vec<(VersionSetId, u32)>,
+
+    /// The shallowest depth at which every package was required so far,
+    /// see [`Problem::depth_constraints`].
+    package_depths: HashMap<NameId, u32>,
+
+    /// Clauses that are added to the problem as is, see
+    /// [`Problem::raw_clauses`].
+    root_raw_clauses: Vec<Vec<(SolvableId, bool)>>,
+
+    /// Requirements added with [`Solver::add_requirement`] that have not been
+    /// solved for yet.
+    pending_requirements: Vec<ConditionalRequirement>,
+
+    /// Constraints added with [`Solver::add_constraint`] that have not been
+    /// solved for yet.
+    pending_constraints: Vec<VersionSetId>,
+
+    /// The kept solvables that had to be removed to find a solution.
+    removed_solvables: Vec<SolvableId>,
+
+    /// The problem that was solved last, see [`Solver::solve_variant`].
+    last_problem: Problem<Vec<SolvableId>>,
+    /// The groups of requirements that are activated together, see
+    /// [`Problem::requirement_groups`].
+    root_groups: Vec<RequirementGroup>,
+
+    /// The variables that activate the requirements of the `root_groups`.
+    group_variables: Vec<VariableId>,
+
+    /// The indices of the requirement groups that had to be excluded to find a
+    /// solution.
+    excluded_groups: Vec<usize>,
 
     /// Activity score per package.
     name_activity: Vec<f32>,
@@ -201,15 +627,388 @@ pub struct Solver<D: DependencyProvider, RT: AsyncRuntime = NowOrNeverRuntime> {
     /// the activity scores of each package are multiplied when a conflict is
     /// detected.
     activity_decay: f32,
+
+    /// The number of conflicts after which [`Solver::solve_async`] yields
+    /// back to the async runtime.
+    yield_interval: usize,
+
+    /// The number of conflicts encountered since the solver last yielded to
+    /// the async runtime.
+    conflicts_since_yield: usize,
+
+    /// The maximum nesting depth of a [`ConditionExpr`] passed to
+    /// [`Solver::conditional_requirements`].
+    max_condition_depth: usize,
+
+    /// The maximum number of conditional requirements a [`ConditionExpr`]
+    /// passed to [`Solver::conditional_requirements`] may expand to.
+    max_condition_terms: usize,
+    /// The maximum number of candidates per version set that are considered
+    /// initially, see [`SolverBuilder::with_candidate_limit`].
+    candidate_limit: Option<usize>,
+
+    /// The number of candidates per version set that are considered in the
+    /// current attempt to solve the problem.
+    candidate_window: Option<usize>,
+
+    /// Whether candidates were left out because of the `candidate_window`.
+    candidates_truncated: bool,
+
+    /// Called whenever the solver backjumps after a conflict.
+    on_backtrack: Option<Box<dyn FnMut(BacktrackEvent)>>,
+
+    /// Called whenever propagation eliminates a candidate.
+    on_candidate_excluded: Option<Box<dyn FnMut(SolvableId, ExclusionReason)>>,
+
+    /// Whether decisions are made without the heuristics, see
+    /// [`SolverBuilder::with_first_fit`].
+    first_fit: bool,
+
+    /// Whether learnt clauses are minimized, see
+    /// [`SolverBuilder::with_clause_minimization`].
+    clause_minimization: bool,
+
+    /// The number of conflicts after which solving is stopped, see
+    /// [`Solver::solve_anytime`].
+    conflict_budget: Option<usize>,
+
+    /// The platform that is solved for, see [`Solver::solve_multi_platform`].
+    platform: Option<StringId>,
+
+    /// Whether solving is stopped at the first solvable whose dependencies
+    /// are unknown, see [`SolverBuilder::with_strict_unknown_dependencies`].
+    strict_unknown_dependencies: bool,
+
+    /// Whether only the satisfiability of the problem is determined, so that
+    /// neither a solution nor a [`Conflict`] has to be built, see
+    /// [`Solver::is_satisfiable`].
+    satisfiability_only: bool,
+
+    /// Whether the learnt clauses are recorded in `proof`.
+    proof_logging: bool,
+
+    /// The clauses learnt during the current solve, if `proof_logging` is
+    /// enabled.
+    proof: Vec<ProofStep>,
+
+    /// Statistics collected during the last solve.
+    stats: SolverStats,
+
+    /// The number of literals propagated by each clause during the last
+    /// solve, indexed by clause id.
+    #[cfg(feature = "watch-stats")]
+    clause_propagations: Vec<u32>,
 }
 
+/// The default number of conflicts after which [`Solver::solve_async`] yields
+/// back to the async runtime.
+const DEFAULT_YIELD_INTERVAL: usize = 100;
+
 impl<D: DependencyProvider> Solver<D, NowOrNeverRuntime> {
     /// Creates a single threaded block solver, using the provided
     /// [`DependencyProvider`].
     pub fn new(provider: D) -> Self {
+        SolverBuilder::new().build(provider)
+    }
+}
+
+/// Configures the options of a [`Solver`] and creates the solver with
+/// [`SolverBuilder::build`]. This is the only place where the solver is
+/// configured, [`Solver::new`] is a shorthand for building with the default
+/// options.
+#[must_use]
+pub struct SolverBuilder<RT: AsyncRuntime = NowOrNeverRuntime> {
+    runtime: RT,
+    max_condition_depth: usize,
+    max_condition_terms: usize,
+    activity_add: f32,
+    activity_decay: f32,
+    yield_interval: usize,
+    candidate_limit: Option<usize>,
+    first_fit: bool,
+    objective: Objective,
+    previous_sorts: std::collections::HashMap<NameId, PreviousSort>,
+    clause_minimization: bool,
+    strict_unknown_dependencies: bool,
+    proof_logging: bool,
+    on_backtrack: Option<Box<dyn FnMut(BacktrackEvent)>>,
+    on_candidate_excluded: Option<Box<dyn FnMut(SolvableId, ExclusionReason)>>,
+}
+
+impl Default for SolverBuilder {
+    fn default() -> Self {
+        Self {
+            runtime: NowOrNeverRuntime,
+            max_condition_depth: DEFAULT_MAX_CONDITION_DEPTH,
+            max_condition_terms: DEFAULT_MAX_CONDITION_TERMS,
+            activity_add: 1.0,
+            activity_decay: 0.95,
+            yield_interval: DEFAULT_YIELD_INTERVAL,
+            candidate_limit: None,
+            first_fit: false,
+            objective: Objective::default(),
+            previous_sorts: Default::default(),
+            clause_minimization: true,
+            strict_unknown_dependencies: false,
+            proof_logging: false,
+            on_backtrack: None,
+            on_candidate_excluded: None,
+        }
+    }
+}
+
+impl SolverBuilder {
+    /// Creates a builder with the default options and a single threaded block
+    /// runtime.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<RT: AsyncRuntime> SolverBuilder<RT> {
+    /// Set the runtime of the solver to `runtime`.
+    pub fn with_runtime<RT2: AsyncRuntime>(self, runtime: RT2) -> SolverBuilder<RT2> {
+        SolverBuilder {
+            runtime,
+            max_condition_depth: self.max_condition_depth,
+            max_condition_terms: self.max_condition_terms,
+            activity_add: self.activity_add,
+            activity_decay: self.activity_decay,
+            yield_interval: self.yield_interval,
+            candidate_limit: self.candidate_limit,
+            first_fit: self.first_fit,
+            objective: self.objective,
+            previous_sorts: self.previous_sorts,
+            clause_minimization: self.clause_minimization,
+            strict_unknown_dependencies: self.strict_unknown_dependencies,
+            proof_logging: self.proof_logging,
+            on_backtrack: self.on_backtrack,
+            on_candidate_excluded: self.on_candidate_excluded,
+        }
+    }
+
+    /// Configure the maximum nesting depth of the combinators of a
+    /// [`ConditionExpr`] passed to [`Solver::conditional_requirements`].
+    /// Deeper conditions are rejected with [`ConditionExprError::TooDeep`],
+    /// which protects against conditions from untrusted package metadata
+    /// exhausting the stack. Defaults to `64`.
+    pub fn with_max_condition_depth(self, max_depth: usize) -> Self {
+        Self {
+            max_condition_depth: max_depth,
+            ..self
+        }
+    }
+
+    /// Configure the maximum number of conditional requirements a
+    /// [`ConditionExpr`] passed to [`Solver::conditional_requirements`] may
+    /// expand to. Every combination of the alternatives of a
+    /// [`ConditionExpr::Any`] nested in a [`ConditionExpr::All`] becomes a
+    /// separate conditional requirement, so this number grows exponentially
+    /// with the size of the condition. Conditions that expand to more are
+    /// rejected with [`ConditionExprError::TooManyTerms`]. Defaults to
+    /// `1024`.
+    pub fn with_max_condition_terms(self, max_terms: usize) -> Self {
+        Self {
+            max_condition_terms: max_terms,
+            ..self
+        }
+    }
+
+    /// Configure activity add and decay parameters. This enables tweaking
+    /// these parameters.
+    pub fn with_activity_params(self, add: f32, decay: f32) -> Self {
+        Self {
+            activity_add: add,
+            activity_decay: decay,
+            ..self
+        }
+    }
+
+    /// Configure the number of conflicts after which [`Solver::solve_async`]
+    /// yields back to the async runtime, giving other tasks on the same
+    /// executor the chance to make progress. A value of `0` yields after
+    /// every decision. Defaults to `100`.
+    ///
+    /// This setting has no effect on [`Solver::solve`], which never yields.
+    pub fn with_yield_interval(self, conflicts: usize) -> Self {
+        Self {
+            yield_interval: conflicts,
+            ..self
+        }
+    }
+
+    /// Only consider the first `limit` candidates of every version set, in the
+    /// order established by [`DependencyProvider::sort_candidates`]. The
+    /// other candidates are not added to the problem, which keeps the number
+    /// of variables small for packages with many versions.
+    ///
+    /// If the problem turns out to be unsolvable while candidates were left
+    /// out, the limit is doubled and the problem is solved again, until either
+    /// a solution is found or all candidates have been considered. This
+    /// ensures that a limit never makes a solvable problem unsolvable. Soft
+    /// requirements and recommends do not cause the limit to be raised.
+    ///
+    /// This pairs with providers that return only the best candidates of a
+    /// package and set [`Candidates::more_available`]: once all the
+    /// candidates the solver has are considered, it requests more with
+    /// [`DependencyProvider::get_more_candidates`] before giving up.
+    ///
+    /// A limit of `0` is treated as `1`. By default all candidates are
+    /// considered.
+    pub fn with_candidate_limit(self, limit: usize) -> Self {
+        Self {
+            candidate_limit: Some(limit.max(1)),
+            ..self
+        }
+    }
+
+    /// Makes the solver settle for the first solution it comes across, instead
+    /// of steering the search towards a preferable one.
+    ///
+    /// By default, the solver first decides on the requirement with the fewest
+    /// candidates and the packages that were involved in the most conflicts,
+    /// and it tries the candidate that the [`DependencyProvider`] favors (see
+    /// [`Candidates::favored`]) first. In first-fit mode the solver simply
+    /// picks the first candidate of the first unsatisfied requirement, in the
+    /// order of [`DependencyProvider::sort_candidates`], and does not keep
+    /// track of conflicting packages. This makes every decision cheaper, but
+    /// the solution may be of lower quality, e.g. because favored candidates
+    /// are not preserved, and problems with many conflicts may need more
+    /// backtracking.
+    pub fn with_first_fit(self, first_fit: bool) -> Self {
+        Self { first_fit, ..self }
+    }
+
+    /// Sets what the solver steers its search towards. By default, it prefers
+    /// the newest versions, see [`Objective::MaximizeVersions`].
+    ///
+    /// With [`Objective::MinimizeVersions`] the candidates of every
+    /// requirement are tried in the reverse order of
+    /// [`DependencyProvider::sort_candidates`], so the solver starts from the
+    /// oldest candidates and only moves on to newer ones when older ones turn
+    /// out to conflict. This is useful to test that the lower bounds of
+    /// requirements are correct. Candidates with a higher
+    /// [`DependencyProvider::score_candidate`] and the favored candidate of a
+    /// package (see [`Candidates::favored`]) are still tried first, and a
+    /// [`SolverBuilder::with_candidate_limit`] keeps the oldest candidates.
+    ///
+    /// Once a solution is found, the solver iteratively tightens it towards
+    /// older candidates: package by package, in the order of the solution, it
+    /// solves the problem again with the selected candidate forbidden, and
+    /// keeps the result as long as a solution exists and no package moved to
+    /// a newer candidate. This moves packages away from candidates that were
+    /// only selected because they were tried first. It solves the problem at
+    /// least once more for every package in the solution, which can be
+    /// expensive for large problems.
+    pub fn with_objective(self, objective: Objective) -> Self {
+        Self { objective, ..self }
+    }
+
+    /// Sets the orders in which the candidates of packages were sorted during
+    /// a previous solve, as returned by [`Solver::candidate_sorts`].
+    ///
+    /// The order of a package is passed to
+    /// [`DependencyProvider::sort_candidates_with_previous`] whenever its
+    /// candidates are sorted, so the provider can keep the order of
+    /// candidates it considers equal stable across solves. Ties between the
+    /// candidates of such a package are not broken by
+    /// [`crate::Interner::solvable_priority`] again. The orders refer to
+    /// solvables by id, so they only apply to a provider that assigns the same
+    /// ids to the same solvables.
+    pub fn with_previous_sorts(
+        self,
+        previous_sorts: std::collections::HashMap<NameId, PreviousSort>,
+    ) -> Self {
+        Self {
+            previous_sorts,
+            ..self
+        }
+    }
+
+    /// Sets whether the clauses that are learnt from conflicts are minimized.
+    /// Minimization is enabled by default.
+    ///
+    /// A literal is removed from a learnt clause if its assignment was implied
+    /// by other literals of the clause, directly or through other implied
+    /// assignments. Shorter clauses propagate sooner and explain conflicts
+    /// more tightly, at the cost of some extra work per conflict.
+    pub fn with_clause_minimization(self, clause_minimization: bool) -> Self {
+        Self {
+            clause_minimization,
+            ..self
+        }
+    }
+
+    /// Makes solvables whose dependencies are unknown a hard error, instead of
+    /// silently excluding them from the solution.
+    ///
+    /// By default, a solvable for which [`DependencyProvider::get_dependencies`]
+    /// returns [`Dependencies::Unknown`] is excluded and the solver looks for a
+    /// solution without it. In strict mode, solving is stopped as soon as the
+    /// solver comes across such a solvable, and
+    /// [`UnsolvableOrCancelled::Cancelled`] is returned with an
+    /// [`UnknownDependencies`] that names the solvable and the reason.
+    pub fn with_strict_unknown_dependencies(self, strict: bool) -> Self {
+        Self {
+            strict_unknown_dependencies: strict,
+            ..self
+        }
+    }
+
+    /// Records the clauses that are learnt while solving, so that a proof of
+    /// unsatisfiability can be obtained from an unsolvable problem, see
+    /// [`Conflict::drat_proof`].
+    ///
+    /// Proof logging is disabled by default, because it costs memory and time.
+    pub fn with_proof_logging(self) -> Self {
+        Self {
+            proof_logging: true,
+            ..self
+        }
+    }
+
+    /// Registers a callback that is invoked every time the solver backjumps
+    /// after learning a clause from a conflict. The callback receives the
+    /// levels the solver jumped from and to, and the solvables whose
+    /// assignments were undone.
+    ///
+    /// Without a callback no bookkeeping is done for backjumps.
+    pub fn on_backtrack(self, on_backtrack: impl FnMut(BacktrackEvent) + 'static) -> Self {
+        Self {
+            on_backtrack: Some(Box::new(on_backtrack)),
+            ..self
+        }
+    }
+
+    /// Registers a callback that is invoked every time propagation assigns
+    /// `false` to a candidate, together with the reason it was eliminated.
+    /// This shows which candidates were considered but rejected, which
+    /// complements the explanation of a [`Conflict`].
+    ///
+    /// A candidate can be reported more than once, because assignments are
+    /// undone when the solver backtracks and the candidate may be eliminated
+    /// again afterwards. Candidates that the solver decides against without
+    /// being forced to are not reported.
+    pub fn on_candidate_excluded(
+        self,
+        on_candidate_excluded: impl FnMut(SolvableId, ExclusionReason) + 'static,
+    ) -> Self {
         Self {
-            cache: SolverCache::new(provider),
-            async_runtime: NowOrNeverRuntime,
+            on_candidate_excluded: Some(Box::new(on_candidate_excluded)),
+            ..self
+        }
+    }
+
+    /// Creates a solver with the configured options, using the provided
+    /// [`DependencyProvider`].
+    pub fn build<D: DependencyProvider>(self, provider: D) -> Solver<D, RT> {
+        let mut cache = SolverCache::new(provider);
+        cache.favor_candidates = !self.first_fit;
+        cache.minimize_versions = self.objective == Objective::MinimizeVersions;
+        cache.previous_sorts = self.previous_sorts.into_iter().collect();
+        Solver {
+            cache,
+            async_runtime: self.runtime,
             clauses: Clauses::default(),
             variable_map: VariableMap::default(),
             requires_clauses: Default::default(),
@@ -218,17 +1017,56 @@ impl<D: DependencyProvider> Solver<D, NowOrNeverRuntime> {
             watches: WatchMap::new(),
             negative_assertions: Default::default(),
             learnt_clauses: Arena::new(),
+            warm_start_clauses_used: Vec::new(),
+            imported_clauses: Vec::new(),
             learnt_why: Mapping::new(),
+            clause_activity: Default::default(),
             learnt_clause_ids: Vec::new(),
             decision_tracker: DecisionTracker::new(),
             root_requirements: Default::default(),
             root_constraints: Default::default(),
+            root_pins: Default::default(),
+            root_base: Default::default(),
+            root_keep: Default::default(),
+            root_forbidden: Default::default(),
+            root_prohibited_names: Default::default(),
+            root_policy_pins: Default::default(),
+            root_depth_constraints: Default::default(),
+            package_depths: Default::default(),
+            root_raw_clauses: Default::default(),
+            pending_requirements: Default::default(),
+            pending_constraints: Default::default(),
+            removed_solvables: Default::default(),
+            last_problem: Problem::new().soft_requirements(Vec::new()),
+            root_groups: Default::default(),
+            group_variables: Default::default(),
+            excluded_groups: Default::default(),
             clauses_added_for_package: Default::default(),
             clauses_added_for_solvable: Default::default(),
             forbidden_clauses_added: Default::default(),
             name_activity: Default::default(),
-            activity_add: 1.0,
-            activity_decay: 0.95,
+            activity_add: self.activity_add,
+            activity_decay: self.activity_decay,
+            yield_interval: self.yield_interval,
+            conflicts_since_yield: 0,
+            max_condition_depth: self.max_condition_depth,
+            max_condition_terms: self.max_condition_terms,
+            candidate_limit: self.candidate_limit,
+            candidate_window: None,
+            candidates_truncated: false,
+            on_backtrack: self.on_backtrack,
+            on_candidate_excluded: self.on_candidate_excluded,
+            first_fit: self.first_fit,
+            clause_minimization: self.clause_minimization,
+            conflict_budget: None,
+            platform: None,
+            strict_unknown_dependencies: self.strict_unknown_dependencies,
+            satisfiability_only: false,
+            proof_logging: self.proof_logging,
+            proof: Vec::new(),
+            stats: SolverStats::default(),
+            #[cfg(feature = "watch-stats")]
+            clause_propagations: Vec::new(),
         }
     }
 }
@@ -242,156 +1080,2400 @@ pub enum UnsolvableOrCancelled {
     Cancelled(Box<dyn Any>),
 }
 
-impl From<Conflict> for UnsolvableOrCancelled {
-    fn from(value: Conflict) -> Self {
-        UnsolvableOrCancelled::Unsolvable(value)
+impl From<Conflict> for UnsolvableOrCancelled {
+    fn from(value: Conflict) -> Self {
+        UnsolvableOrCancelled::Unsolvable(value)
+    }
+}
+
+impl From<Box<dyn Any>> for UnsolvableOrCancelled {
+    fn from(value: Box<dyn Any>) -> Self {
+        UnsolvableOrCancelled::Cancelled(value)
+    }
+}
+
+/// A package of which an older solvable was selected than the newest one
+/// that is available, see [`Solver::downgrades`].
+#[derive(Clone, Debug)]
+pub struct Downgrade {
+    /// The name of the package.
+    pub name: NameId,
+    /// The newest available solvable of the package.
+    pub from: SolvableId,
+    /// The solvable that was selected instead.
+    pub to: SolvableId,
+    /// Explains why `from` could not be selected.
+    pub reason: ConflictGraph,
+}
+
+/// The effect of removing a requirement from a problem, see
+/// [`Solver::removal_impact`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemovalImpact {
+    /// The solvables that are no longer installed, because no other
+    /// solvable of their package is installed without the requirement.
+    pub removed: Vec<SolvableId>,
+
+    /// The packages of which another solvable is installed without the
+    /// requirement, as the solvable that was installed before and the one
+    /// that is installed instead.
+    pub changed: Vec<(SolvableId, SolvableId)>,
+}
+
+/// The reason why [`Solver::solve_frozen`] failed.
+#[derive(Debug)]
+pub enum FrozenSolveError {
+    /// The problem is only unsolvable because of the solvables that were not
+    /// allowed to change. Allowing one of the packages in `names` to change
+    /// may make the problem solvable.
+    Frozen {
+        /// The names of the frozen packages that are part of the conflict.
+        names: Vec<NameId>,
+        /// The conflict that includes the frozen solvables.
+        conflict: Conflict,
+    },
+    /// The problem is unsolvable regardless of the frozen solvables, or
+    /// solving was cancelled.
+    Unsolvable(UnsolvableOrCancelled),
+}
+
+impl From<UnsolvableOrCancelled> for FrozenSolveError {
+    fn from(value: UnsolvableOrCancelled) -> Self {
+        FrozenSolveError::Unsolvable(value)
+    }
+}
+
+/// An error during the propagation step
+#[derive(Debug)]
+pub(crate) enum PropagationError {
+    Conflict(VariableId, bool, ClauseId),
+    Cancelled(Box<dyn Any>),
+}
+
+impl Display for PropagationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropagationError::Conflict(solvable, value, clause) => {
+                write!(
+                    f,
+                    "conflict while propagating solvable {:?}, value {} caused by clause {:?}",
+                    solvable, value, clause
+                )
+            }
+            PropagationError::Cancelled(_) => {
+                write!(f, "propagation was cancelled")
+            }
+        }
+    }
+}
+
+impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
+    /// Returns the dependency provider used by this instance.
+    pub fn provider(&self) -> &D {
+        self.cache.provider()
+    }
+
+    /// Returns the cache of the information retrieved from the dependency
+    /// provider by this instance.
+    pub fn cache(&self) -> &SolverCache<D> {
+        &self.cache
+    }
+
+    /// Set the runtime of the solver to `runtime`.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_runtime`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_runtime` instead")]
+    pub fn with_runtime<RT2: AsyncRuntime>(self, runtime: RT2) -> Solver<D, RT2> {
+        Solver {
+            async_runtime: runtime,
+            cache: self.cache,
+            clauses: self.clauses,
+            variable_map: self.variable_map,
+            requires_clauses: self.requires_clauses,
+            conditional_clauses: self.conditional_clauses,
+            requirement_to_sorted_candidates: self.requirement_to_sorted_candidates,
+            watches: self.watches,
+            negative_assertions: self.negative_assertions,
+            learnt_clauses: self.learnt_clauses,
+            warm_start_clauses_used: self.warm_start_clauses_used,
+            imported_clauses: self.imported_clauses,
+            learnt_why: self.learnt_why,
+            clause_activity: self.clause_activity,
+            learnt_clause_ids: self.learnt_clause_ids,
+            clauses_added_for_package: self.clauses_added_for_package,
+            clauses_added_for_solvable: self.clauses_added_for_solvable,
+            forbidden_clauses_added: self.forbidden_clauses_added,
+            decision_tracker: self.decision_tracker,
+            root_requirements: self.root_requirements,
+            root_constraints: self.root_constraints,
+            root_pins: self.root_pins,
+            root_base: self.root_base,
+            root_keep: self.root_keep,
+            root_forbidden: self.root_forbidden,
+            root_prohibited_names: self.root_prohibited_names,
+            root_policy_pins: self.root_policy_pins,
+            root_depth_constraints: self.root_depth_constraints,
+            package_depths: self.package_depths,
+            root_raw_clauses: self.root_raw_clauses,
+            pending_requirements: self.pending_requirements,
+            pending_constraints: self.pending_constraints,
+            removed_solvables: self.removed_solvables,
+            last_problem: self.last_problem,
+            root_groups: self.root_groups,
+            group_variables: self.group_variables,
+            excluded_groups: self.excluded_groups,
+            name_activity: self.name_activity,
+            activity_add: self.activity_add,
+            activity_decay: self.activity_decay,
+            yield_interval: self.yield_interval,
+            conflicts_since_yield: self.conflicts_since_yield,
+            max_condition_depth: self.max_condition_depth,
+            max_condition_terms: self.max_condition_terms,
+            candidate_limit: self.candidate_limit,
+            candidate_window: self.candidate_window,
+            candidates_truncated: self.candidates_truncated,
+            on_backtrack: self.on_backtrack,
+            on_candidate_excluded: self.on_candidate_excluded,
+            first_fit: self.first_fit,
+            clause_minimization: self.clause_minimization,
+            conflict_budget: self.conflict_budget,
+            platform: self.platform,
+            strict_unknown_dependencies: self.strict_unknown_dependencies,
+            satisfiability_only: self.satisfiability_only,
+            proof_logging: self.proof_logging,
+            proof: self.proof,
+            stats: self.stats,
+            #[cfg(feature = "watch-stats")]
+            clause_propagations: self.clause_propagations,
+        }
+    }
+
+    /// Configure activity add and decay parameters.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_activity_params`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_activity_params` instead")]
+    pub fn with_activity_params(self, add: f32, decay: f32) -> Self {
+        Self {
+            activity_add: add,
+            activity_decay: decay,
+            ..self
+        }
+    }
+
+    /// Configure the number of conflicts after which [`Solver::solve_async`]
+    /// yields back to the async runtime.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_yield_interval`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_yield_interval` instead")]
+    pub fn with_yield_interval(self, conflicts: usize) -> Self {
+        Self {
+            yield_interval: conflicts,
+            ..self
+        }
+    }
+
+    /// Returns the [`ConditionalRequirement`]s that make `requirement` active
+    /// when `condition` is met.
+    ///
+    /// A [`ConditionalRequirement`] is only active if all of its conditions
+    /// are met, so a condition that is met if any of several conditions is
+    /// met results in multiple [`ConditionalRequirement`]s.
+    ///
+    /// Returns an error if a [`Condition`] is negated after applying De
+    /// Morgan's laws, because the solver cannot express that a condition is
+    /// not met, or if the condition can never be met. Conditions that exceed
+    /// the limits configured with [`SolverBuilder::with_max_condition_depth`]
+    /// and [`SolverBuilder::with_max_condition_terms`] are rejected as well.
+    pub fn conditional_requirements(
+        &self,
+        requirement: Requirement,
+        condition: &ConditionExpr,
+    ) -> Result<Vec<ConditionalRequirement>, ConditionExprError> {
+        condition.conditional_requirements(
+            requirement,
+            self.max_condition_depth,
+            self.max_condition_terms,
+        )
+    }
+
+    /// Only consider the first `limit` candidates of every version set.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_candidate_limit`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_candidate_limit` instead")]
+    pub fn with_candidate_limit(self, limit: usize) -> Self {
+        Self {
+            candidate_limit: Some(limit.max(1)),
+            ..self
+        }
+    }
+
+    /// Makes the solver settle for the first solution it comes across.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_first_fit`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_first_fit` instead")]
+    pub fn with_first_fit(mut self, first_fit: bool) -> Self {
+        self.first_fit = first_fit;
+        self.cache.favor_candidates = !first_fit;
+        self
+    }
+
+    /// Sets what the solver steers its search towards.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_objective`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_objective` instead")]
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.cache.minimize_versions = objective == Objective::MinimizeVersions;
+        self
+    }
+
+    /// Returns the orders in which the candidates of packages were sorted so
+    /// far, which can be passed to [`SolverBuilder::with_previous_sorts`] of
+    /// the solver of a next solve.
+    pub fn candidate_sorts(&self) -> std::collections::HashMap<NameId, PreviousSort> {
+        self.cache.sorts().into_iter().collect()
+    }
+
+    /// Sets whether the clauses that are learnt from conflicts are minimized.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_clause_minimization`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_clause_minimization` instead")]
+    pub fn with_clause_minimization(self, clause_minimization: bool) -> Self {
+        Self {
+            clause_minimization,
+            ..self
+        }
+    }
+
+    /// Makes solvables whose dependencies are unknown a hard error.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_strict_unknown_dependencies`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_strict_unknown_dependencies` instead")]
+    pub fn with_strict_unknown_dependencies(self, strict: bool) -> Self {
+        Self {
+            strict_unknown_dependencies: strict,
+            ..self
+        }
+    }
+
+    /// Records the clauses that are learnt while solving.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::with_proof_logging`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::with_proof_logging` instead")]
+    pub fn with_proof_logging(self) -> Self {
+        Self {
+            proof_logging: true,
+            ..self
+        }
+    }
+
+    /// Registers a callback that is invoked every time the solver backjumps.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::on_backtrack`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::on_backtrack` instead")]
+    pub fn on_backtrack(self, on_backtrack: impl FnMut(BacktrackEvent) + 'static) -> Self {
+        Self {
+            on_backtrack: Some(Box::new(on_backtrack)),
+            ..self
+        }
+    }
+
+    /// Registers a callback that is invoked every time propagation assigns
+    /// `false` to a candidate.
+    ///
+    /// Deprecated in favor of [`SolverBuilder::on_candidate_excluded`], which configures the
+    /// solver before it is created.
+    #[must_use]
+    #[deprecated(note = "use `SolverBuilder::on_candidate_excluded` instead")]
+    pub fn on_candidate_excluded(
+        self,
+        on_candidate_excluded: impl FnMut(SolvableId, ExclusionReason) + 'static,
+    ) -> Self {
+        Self {
+            on_candidate_excluded: Some(Box::new(on_candidate_excluded)),
+            ..self
+        }
+    }
+
+    /// Solves the given [`Problem`].
+    ///
+    /// The solver first solves for the root requirements and constraints, and
+    /// then tries to include in the solution as many of the soft
+    /// requirements as it can. Each soft requirement is subject to all the
+    /// clauses and decisions introduced for all the previously decided
+    /// solvables in the solution.
+    ///
+    /// Unless the corresponding package has been requested by a version set in
+    /// another solvable's clauses, each soft requirement is _not_ subject
+    /// to the package-level clauses introduced in
+    /// [`DependencyProvider::get_candidates`] since the solvables have been
+    /// requested specifically (not through a version set) in the solution.
+    ///
+    /// # Returns
+    ///
+    /// If a solution was found, returns a [`Vec`] of the solvables included in
+    /// the solution.
+    ///
+    /// If no solution to the _root_ requirements and constraints was found,
+    /// returns a [`Conflict`] wrapped in a
+    /// [`UnsolvableOrCancelled::Unsolvable`], which provides ways to
+    /// inspect the causes and report them to the user. If a soft requirement is
+    /// unsolvable, it is simply not included in the solution.
+    ///
+    /// If the solution process is cancelled (see
+    /// [`DependencyProvider::should_cancel_with_value`]), returns an
+    /// [`UnsolvableOrCancelled::Cancelled`] containing the cancellation value.
+    ///
+    /// A problem without any (soft) requirements, pins, kept solvables or
+    /// recommends is trivially solved by installing nothing: an empty
+    /// solution is returned without querying the provider or generating any
+    /// clauses, even if the problem has constraints.
+    pub fn solve(
+        &mut self,
+        problem: Problem<impl IntoIterator<Item = SolvableId>>,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        self.solve_inner(problem, false)
+            .now_or_never()
+            .expect("bug: the solver should only yield when solving cooperatively")
+    }
+
+    /// Solves the requirements and constraints with a growing budget of
+    /// conflicts, so that an interactive application can show progress and
+    /// stop solving at any time.
+    ///
+    /// An attempt stops before its next decision once it has encountered as
+    /// many conflicts as its budget allows. The first attempt has a budget of
+    /// a single conflict, every next attempt doubles the budget. Each time an
+    /// attempt runs out of budget, `callback` is called with the solvables
+    /// that were selected at that moment. If it returns
+    /// [`ControlFlow::Break`], solving stops and
+    /// [`UnsolvableOrCancelled::Cancelled`] is returned with the last
+    /// [`PartialResult`] as its value. Otherwise the next attempt starts from
+    /// scratch, reusing the information that was retrieved from the
+    /// [`DependencyProvider`].
+    ///
+    /// Once an attempt finishes within its budget, its solution or conflict is
+    /// returned.
+    pub fn solve_anytime(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+        mut callback: impl FnMut(&PartialResult) -> ControlFlow<()>,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        let mut budget = 1;
+        loop {
+            self.conflict_budget = Some(budget);
+            let problem = Problem::new()
+                .requirements(requirements.clone())
+                .constraints(constraints.clone());
+            let result = self.solve(problem);
+            self.conflict_budget = None;
+
+            match result {
+                Err(UnsolvableOrCancelled::Cancelled(reason))
+                    if reason.is::<ConflictBudgetExhausted>() =>
+                {
+                    let partial = PartialResult {
+                        conflict_budget: budget,
+                        solvables: self.chosen_solvables().collect(),
+                        decision_level: self.decision_level(),
+                        trail_length: self.decision_tracker.stack().count(),
+                        stats: self.stats(),
+                    };
+                    if callback(&partial).is_break() {
+                        return Err(UnsolvableOrCancelled::Cancelled(Box::new(partial)));
+                    }
+                    budget = budget.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Determines whether the requirements and constraints can be satisfied,
+    /// without extracting a solution.
+    ///
+    /// This returns `Ok(true)` exactly when [`Solver::solve`] would return a
+    /// solution for the same requirements and constraints, but it neither
+    /// collects the solution nor analyzes the [`Conflict`] if there is none.
+    /// The limits of the solver, like [`SolverBuilder::with_candidate_limit`], are
+    /// respected in the same way.
+    ///
+    /// If the solution process is cancelled (see
+    /// [`DependencyProvider::should_cancel_with_value`]), returns an
+    /// [`UnsolvableOrCancelled::Cancelled`] containing the cancellation value.
+    pub fn is_satisfiable(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+    ) -> Result<bool, UnsolvableOrCancelled> {
+        let problem = Problem::new()
+            .requirements(requirements)
+            .constraints(constraints);
+        self.satisfiability_only = true;
+        let result = self
+            .solve_inner(problem, false)
+            .now_or_never()
+            .expect("bug: the solver should only yield when solving cooperatively");
+        self.satisfiability_only = false;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(UnsolvableOrCancelled::Unsolvable(_)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Determines whether the requirements `a` and `b` can be satisfied at
+    /// the same time, by solving the problem that consists of just these two
+    /// requirements.
+    ///
+    /// Returns `Ok(true)` if they have a common solution. Otherwise returns an
+    /// [`UnsolvableOrCancelled::Unsolvable`] with the [`Conflict`] that
+    /// explains why they are mutually exclusive, or why either of them cannot
+    /// be satisfied at all. Like [`Solver::solve`], this replaces the problem
+    /// the solver was previously used for.
+    pub fn are_compatible(
+        &mut self,
+        a: Requirement,
+        b: Requirement,
+    ) -> Result<bool, UnsolvableOrCancelled> {
+        self.solve(Problem::new().requirements(vec![a.into(), b.into()]))
+            .map(|_| true)
+    }
+
+    /// Solves the requirements and constraints like [`Solver::solve`], and
+    /// returns the solution together with the conditions that hold in it, the
+    /// solvable that was selected for every package and the statistics of the
+    /// solve.
+    ///
+    /// This collects the same information as calling
+    /// [`Solver::active_conditions`], [`Solver::selected_for`] and
+    /// [`Solver::stats`] after [`Solver::solve`], in a single pass over the
+    /// decisions.
+    pub fn solve_detailed(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+    ) -> Result<SolveOutcome, UnsolvableOrCancelled> {
+        let solution = self.solve(
+            Problem::new()
+                .requirements(requirements)
+                .constraints(constraints),
+        )?;
+        let selected = solution
+            .iter()
+            .map(|&solvable| (self.canonical_solvable_name(solvable), solvable))
+            .collect();
+        Ok(SolveOutcome {
+            active_conditions: self.active_conditions(),
+            selected,
+            stats: self.stats(),
+            solution,
+        })
+    }
+
+    /// Solves the requirements and constraints and returns a canonical
+    /// solution, which does not depend on the heuristics of the solver.
+    ///
+    /// The canonical solution is the first of all the solutions of the
+    /// problem in the following order. Solutions are compared package by
+    /// package, in the order of the package names (see
+    /// [`Interner::display_name`], with ties broken by [`NameId`]). For a
+    /// single package, a solution without the package comes first, followed
+    /// by the solutions with one of its candidates, in the order of
+    /// [`DependencyProvider::sort_candidates`]. The favored candidates and the
+    /// [`Objective`] of the solver do not influence this order.
+    ///
+    /// The order is searched package by package: the first package of the
+    /// current solution that is not fixed yet is kept out of the solution
+    /// (see [`Problem::prohibited_names`]) if possible, and otherwise pinned
+    /// (see [`Problem::pins`]) to the first of its candidates with which the
+    /// problem is still solvable. A package that comes before the packages
+    /// that are fixed already and is not part of the current solution is
+    /// kept out of it.
+    ///
+    /// This solves the problem again for every option that is tried, so at
+    /// least once for every package of the solution, which is very expensive
+    /// for large problems. Afterwards the state of the solver, e.g. its
+    /// [`Solver::stats`], reflects the last of these solves.
+    pub fn solve_canonical(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        let problem = |pins: &[SolvableId], prohibited: &[NameId]| {
+            Problem::new()
+                .requirements(requirements.clone())
+                .constraints(constraints.clone())
+                .pins(pins.to_vec())
+                .prohibited_names(prohibited.to_vec())
+        };
+        let key =
+            |solver: &Self, name: NameId| (solver.provider().display_name(name).to_string(), name);
+
+        let mut pins = Vec::new();
+        let mut prohibited = Vec::new();
+        let mut fixed = HashSet::default();
+        let mut solution = self.solve(problem(&pins, &prohibited))?;
+        loop {
+            let Some(name) = solution
+                .iter()
+                .map(|&solvable| self.canonical_solvable_name(solvable))
+                .filter(|name| !fixed.contains(name))
+                .min_by_key(|&name| key(self, name))
+            else {
+                return Ok(solution);
+            };
+            fixed.insert(name);
+
+            // Keep the package out of the solution if possible, otherwise try
+            // its candidates in order.
+            let candidates = self
+                .async_runtime
+                .block_on(self.viable_candidates(name))
+                .map_err(UnsolvableOrCancelled::Cancelled)?;
+            solution = 'options: {
+                for option in std::iter::once(None).chain(candidates.into_iter().map(Some)) {
+                    loop {
+                        let mut trial_pins = pins.clone();
+                        let mut trial_prohibited = prohibited.clone();
+                        match option {
+                            None => trial_prohibited.push(name),
+                            Some(candidate) => trial_pins.push(candidate),
+                        }
+                        let trial = match self.solve(problem(&trial_pins, &trial_prohibited)) {
+                            Ok(trial) => trial,
+                            Err(UnsolvableOrCancelled::Unsolvable(_)) => break,
+                            Err(err) => return Err(err),
+                        };
+
+                        // A package that comes before this one is not part of the
+                        // current solution, so it has to stay out.
+                        let earlier = trial
+                            .iter()
+                            .map(|&solvable| self.canonical_solvable_name(solvable))
+                            .filter(|other| !fixed.contains(other))
+                            .find(|&other| key(self, other) < key(self, name));
+                        match earlier {
+                            Some(other) => {
+                                fixed.insert(other);
+                                prohibited.push(other);
+                            }
+                            None => {
+                                pins = trial_pins;
+                                prohibited = trial_prohibited;
+                                break 'options trial;
+                            }
+                        }
+                    }
+                }
+                unreachable!("the current solution contains a candidate of the package")
+            };
+        }
+    }
+
+    /// Solves the given [`Problem`] from within an async context.
+    ///
+    /// This behaves exactly like [`Solver::solve`], except that the futures
+    /// returned by the [`DependencyProvider`] are awaited directly instead of
+    /// through the solver's [`AsyncRuntime`], and that the solver
+    /// periodically yields back to the executor (see
+    /// [`SolverBuilder::with_yield_interval`]). This prevents a long running solve
+    /// from starving other tasks that run on the same executor.
+    pub async fn solve_async(
+        &mut self,
+        problem: Problem<impl IntoIterator<Item = SolvableId>>,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        self.solve_inner(problem, true).await
+    }
+
+    /// Solves several independent sets of root requirements and constraints
+    /// against the same [`DependencyProvider`].
+    ///
+    /// Every set is solved as if it were passed to [`Solver::solve`] on its
+    /// own, but the candidates and dependencies retrieved from the provider
+    /// are cached and reused across the sets. Learnt clauses are derived from
+    /// the root requirements of a set, so they are not carried over to the
+    /// next set.
+    ///
+    /// The results are returned in the same order as the sets.
+    pub fn solve_many(
+        &mut self,
+        root_sets: impl IntoIterator<Item = (Vec<ConditionalRequirement>, Vec<VersionSetId>)>,
+    ) -> Vec<Result<Vec<SolvableId>, UnsolvableOrCancelled>> {
+        root_sets
+            .into_iter()
+            .map(|(requirements, constraints)| {
+                self.solve(
+                    Problem::new()
+                        .requirements(requirements)
+                        .constraints(constraints),
+                )
+            })
+            .collect()
+    }
+
+    /// Solves the same requirements and constraints for several platforms,
+    /// for instance to create a lock file that covers multiple operating
+    /// systems.
+    ///
+    /// Every platform is solved as if it were passed to [`Solver::solve`] on
+    /// its own, except that the candidates that are not available for the
+    /// platform are excluded, see [`DependencyProvider::excluded_on_platform`].
+    /// The candidates and dependencies retrieved from the provider are cached
+    /// and reused across the platforms, like with [`Solver::solve_many`].
+    ///
+    /// Besides the solution of every platform, the returned
+    /// [`MultiPlatformSolution`] contains the solvables that the solutions
+    /// have in common.
+    pub fn solve_multi_platform(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+        platforms: &[StringId],
+    ) -> MultiPlatformSolution {
+        let solutions = platforms
+            .iter()
+            .map(|&platform| {
+                self.platform = Some(platform);
+                let result = self.solve(
+                    Problem::new()
+                        .requirements(requirements.clone())
+                        .constraints(constraints.clone()),
+                );
+                self.platform = None;
+                (platform, result)
+            })
+            .collect::<Vec<_>>();
+
+        let mut solved = solutions
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok());
+        let shared = match solved.next() {
+            Some(first) => {
+                let others = solved
+                    .map(|solution| solution.iter().collect::<HashSet<_>>())
+                    .collect::<Vec<_>>();
+                first
+                    .iter()
+                    .copied()
+                    .filter(|solvable| others.iter().all(|other| other.contains(solvable)))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        MultiPlatformSolution { solutions, shared }
+    }
+
+    /// Solves the requirements and constraints on top of a base environment,
+    /// see [`Problem::base`].
+    pub fn solve_over_base(
+        &mut self,
+        base: &[SolvableId],
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        self.solve(
+            Problem::new()
+                .requirements(requirements)
+                .constraints(constraints)
+                .base(base.to_vec()),
+        )
+    }
+
+    /// Solves the requirements and constraints while keeping a previous
+    /// solution in place, except for the packages in `allowed_to_change`.
+    ///
+    /// Every solvable in `previous` whose package is not in
+    /// `allowed_to_change` is pinned, see [`Problem::pins`], so only the
+    /// packages that are allowed to change can be updated, added or removed.
+    ///
+    /// If the problem is unsolvable because of a pinned solvable, the names of
+    /// the frozen packages that are part of the conflict are returned in
+    /// [`FrozenSolveError::Frozen`].
+    pub fn solve_frozen(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+        previous: &[SolvableId],
+        allowed_to_change: &[NameId],
+    ) -> Result<Vec<SolvableId>, FrozenSolveError> {
+        let allowed_to_change = allowed_to_change
+            .iter()
+            .map(|&name| self.provider().canonical_name(name))
+            .collect::<HashSet<_>>();
+        let frozen = previous
+            .iter()
+            .copied()
+            .filter(|&solvable| {
+                !allowed_to_change.contains(&self.canonical_solvable_name(solvable))
+            })
+            .collect::<Vec<_>>();
+        let frozen_set = frozen.iter().copied().collect::<HashSet<_>>();
+
+        let conflict = match self.solve(
+            Problem::new()
+                .requirements(requirements)
+                .constraints(constraints)
+                .pins(frozen),
+        ) {
+            Ok(solution) => return Ok(solution),
+            Err(UnsolvableOrCancelled::Unsolvable(conflict)) => conflict,
+            Err(err) => return Err(err.into()),
+        };
+
+        // A pin excludes the other candidates of its package through lock clauses,
+        // so those are the clauses that show up in a conflict.
+        let mut names = Vec::new();
+        for &clause_id in conflict.clauses() {
+            let (Clause::Pin(variable) | Clause::Lock(variable, _)) =
+                self.clauses.kinds[clause_id.to_usize()]
+            else {
+                continue;
+            };
+            let Some(solvable) = variable.as_solvable(&self.variable_map) else {
+                continue;
+            };
+            if frozen_set.contains(&solvable) {
+                let name = self.canonical_solvable_name(solvable);
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        if names.is_empty() {
+            Err(UnsolvableOrCancelled::Unsolvable(conflict).into())
+        } else {
+            Err(FrozenSolveError::Frozen { names, conflict })
+        }
+    }
+
+    /// Adds a requirement to the problem that was last solved. The requirement
+    /// is taken into account by the next call to [`Solver::solve_current`].
+    pub fn add_requirement(&mut self, requirement: ConditionalRequirement) {
+        self.pending_requirements.push(requirement);
+    }
+
+    /// Adds a constraint to the problem that was last solved. The constraint
+    /// is taken into account by the next call to [`Solver::solve_current`].
+    pub fn add_constraint(&mut self, constraint: VersionSetId) {
+        self.pending_constraints.push(constraint);
+    }
+
+    /// Solves the problem that was last solved, extended with the requirements
+    /// and constraints added through [`Solver::add_requirement`] and
+    /// [`Solver::add_constraint`].
+    ///
+    /// Instead of starting from scratch like [`Solver::solve`], only the
+    /// clauses for the added requirements and constraints are generated. All
+    /// other clauses, including the clauses learnt while solving the previous
+    /// problem, are kept. Soft requirements and recommends of the previous
+    /// problem are not solved for again.
+    ///
+    /// If no problem was solved before, this solves a problem consisting of
+    /// only the added requirements and constraints.
+    pub fn solve_current(&mut self) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        self.solve_current_inner()
+            .now_or_never()
+            .expect("bug: the solver should only yield when solving cooperatively")
+    }
+
+    /// Implementation of [`Solver::solve_current`].
+    async fn solve_current_inner(&mut self) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        let requirements = std::mem::take(&mut self.pending_requirements);
+        let constraints = std::mem::take(&mut self.pending_constraints);
+
+        // Without the clauses of a previous problem there is nothing to continue from.
+        if self.clauses.kinds.is_empty() {
+            let mut all_requirements = std::mem::take(&mut self.root_requirements);
+            all_requirements.extend(requirements);
+            let mut all_constraints = std::mem::take(&mut self.root_constraints);
+            all_constraints.extend(constraints);
+            let problem = Problem::new()
+                .requirements(all_requirements)
+                .constraints(all_constraints);
+            return self.solve_inner(problem, false).await;
+        }
+
+        self.conflicts_since_yield = 0;
+        self.stats = SolverStats::default();
+        #[cfg(feature = "watch-stats")]
+        self.clause_propagations.clear();
+        self.decision_tracker.clear();
+        self.warm_start_clauses_used = vec![false; self.learnt_clauses.len()];
+
+        // Let the root generate clauses again, but only for the added requirements and
+        // constraints. The clauses of the previous problem are still in place.
+        self.clauses_added_for_solvable
+            .remove(&SolvableOrRootId::root());
+        self.last_problem
+            .requirements
+            .extend(requirements.iter().cloned());
+        self.last_problem
+            .constraints
+            .extend(constraints.iter().copied());
+        let previous_requirements = std::mem::replace(&mut self.root_requirements, requirements);
+        let previous_constraints = std::mem::replace(&mut self.root_constraints, constraints);
+        let previous_pins = std::mem::take(&mut self.root_pins);
+        let previous_keep = std::mem::take(&mut self.root_keep);
+        let previous_forbidden = std::mem::take(&mut self.root_forbidden);
+        let previous_groups = std::mem::take(&mut self.root_groups);
+        let previous_raw_clauses = std::mem::take(&mut self.root_raw_clauses);
+
+        let result = self.run_sat(SolvableOrRootId::root(), false).await;
+
+        let requirements = std::mem::replace(&mut self.root_requirements, previous_requirements);
+        self.root_requirements.extend(requirements);
+        let constraints = std::mem::replace(&mut self.root_constraints, previous_constraints);
+        self.root_constraints.extend(constraints);
+        self.root_pins = previous_pins;
+        self.root_keep = previous_keep;
+        self.root_forbidden = previous_forbidden;
+        self.root_groups = previous_groups;
+        self.root_raw_clauses = previous_raw_clauses;
+
+        let solved = result?;
+        assert!(
+            solved,
+            "bug: Since root is the first requested solvable, \
+                  should have returned Err instead of Ok(false) if root is unsolvable"
+        );
+        self.record_excluded_groups();
+        Ok(self.chosen_solvables().collect())
+    }
+
+    /// Removes the clauses that can no longer affect the outcome of
+    /// [`Solver::solve_current`], and releases the memory they use.
+    ///
+    /// A solver that is used for many incremental solves keeps the clauses of
+    /// every problem it solved, as well as every clause it learnt. This
+    /// removes the clauses that are satisfied by an assignment at the root
+    /// level of the last solve, which holds in every subsequent incremental
+    /// solve as well, and the learnt clauses that are subsumed by another
+    /// learnt clause. The clauses that caused an assignment of the last solve
+    /// or that explain a learnt clause are kept, so subsequent solves find
+    /// the same solutions and conflicts as without compaction.
+    ///
+    /// Clauses are renumbered, so a [`Conflict`] of a previous solve must not
+    /// be used afterwards.
+    pub fn compact(&mut self) {
+        if self.clauses.kinds.is_empty() {
+            return;
+        }
+
+        let learnt = self
+            .learnt_clause_ids
+            .iter()
+            .map(
+                |&clause_id| match self.clauses.kinds[clause_id.to_usize()] {
+                    Clause::Learnt(learnt_id) => (clause_id, learnt_id),
+                    _ => unreachable!("bug: learnt clause id does not refer to a learnt clause"),
+                },
+            )
+            .collect_vec();
+
+        let mut removed = vec![false; self.clauses.kinds.len()];
+        let mut kept_why = Vec::new();
+        {
+            // The clauses that caused an assignment are needed to derive it again.
+            let reasons = self
+                .decision_tracker
+                .stack()
+                .map(|decision| decision.derived_from)
+                .collect::<HashSet<_>>();
+            let decision_map = self.decision_tracker.map();
+            let satisfied_at_root = |clause: &Clause| {
+                let mut satisfied = false;
+                clause.visit_literals(
+                    &self.learnt_clauses,
+                    &self.requirement_to_sorted_candidates,
+                    |literal| {
+                        satisfied |= literal.eval(decision_map) == Some(true)
+                            && decision_map.level(literal.variable()) <= 1;
+                    },
+                );
+                satisfied
+            };
+
+            for (index, &(clause_id, learnt_id)) in learnt.iter().enumerate() {
+                if reasons.contains(&clause_id) {
+                    continue;
+                }
+                let literals = &self.learnt_clauses[learnt_id];
+                let subsumed = learnt.iter().enumerate().any(
+                    |(other_index, &(other_clause_id, other_learnt_id))| {
+                        let other = &self.learnt_clauses[other_learnt_id];
+                        other_index != index
+                            && !removed[other_clause_id.to_usize()]
+                            && other.iter().all(|literal| literals.contains(literal))
+                            && (other.len() < literals.len() || other_index < index)
+                    },
+                );
+                removed[clause_id.to_usize()] =
+                    subsumed || satisfied_at_root(&self.clauses.kinds[clause_id.to_usize()]);
+            }
+
+            // The causes of the removed learnt clauses become the causes of the
+            // learnt clauses that were derived from them.
+            let mut why_of_removed = HashMap::<ClauseId, Vec<ClauseId>>::default();
+            let mut referenced = HashSet::default();
+            for &(clause_id, learnt_id) in &learnt {
+                let why = self
+                    .learnt_why
+                    .get(learnt_id)
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|cause| {
+                        why_of_removed
+                            .get(cause)
+                            .cloned()
+                            .unwrap_or_else(|| vec![*cause])
+                    })
+                    .unique()
+                    .collect_vec();
+                if removed[clause_id.to_usize()] {
+                    why_of_removed.insert(clause_id, why);
+                } else {
+                    referenced.extend(why.iter().copied());
+                    kept_why.push(why);
+                }
+            }
+
+            for (index, clause) in self.clauses.kinds.iter().enumerate() {
+                let clause_id = ClauseId::from_usize(index);
+                if matches!(
+                    clause,
+                    Clause::Constrains(..) | Clause::ForbidMultipleInstances(..) | Clause::Lock(..)
+                ) && !reasons.contains(&clause_id)
+                    && !referenced.contains(&clause_id)
+                    && satisfied_at_root(clause)
+                {
+                    removed[index] = true;
+                }
+            }
+        }
+
+        let mut next_id = 0;
+        let remap = removed
+            .iter()
+            .map(|&is_removed| {
+                (!is_removed).then(|| {
+                    next_id += 1;
+                    ClauseId::from_usize(next_id - 1)
+                })
+            })
+            .collect_vec();
+        let map_id = |clause_id: ClauseId| {
+            remap[clause_id.to_usize()].expect("bug: a removed clause is still referenced")
+        };
+
+        // Rebuild the clauses and the learnt clauses without the removed ones.
+        let old_clauses = std::mem::take(&mut self.clauses);
+        let old_learnt_clauses = std::mem::replace(&mut self.learnt_clauses, Arena::new());
+        let mut kept_why = kept_why.into_iter();
+        self.learnt_why = Mapping::new();
+        self.learnt_clause_ids.clear();
+        for (index, (kind, watched_literals)) in old_clauses
+            .kinds
+            .into_iter()
+            .zip(old_clauses.watched_literals)
+            .enumerate()
+        {
+            if removed[index] {
+                continue;
+            }
+            let kind = match kind {
+                Clause::Learnt(learnt_id) => {
+                    let new_learnt_id = self
+                        .learnt_clauses
+                        .alloc(old_learnt_clauses[learnt_id].clone());
+                    let why = kept_why
+                        .next()
+                        .expect("bug: missing causes of learnt clause");
+                    self.learnt_why
+                        .insert(new_learnt_id, why.into_iter().map(map_id).collect());
+                    self.learnt_clause_ids
+                        .push(ClauseId::from_usize(self.clauses.kinds.len()));
+                    Clause::Learnt(new_learnt_id)
+                }
+                kind => kind,
+            };
+            self.clauses.alloc(watched_literals, kind);
+        }
+
+        for (_, clause_id) in self
+            .requires_clauses
+            .values_mut()
+            .chain(self.conditional_clauses.values_mut())
+            .flatten()
+        {
+            *clause_id = map_id(*clause_id);
+        }
+        for (_, clause_id) in &mut self.negative_assertions {
+            *clause_id = map_id(*clause_id);
+        }
+        self.clause_activity = std::mem::take(&mut self.clause_activity)
+            .into_iter()
+            .filter_map(|(clause_id, activity)| Some((remap[clause_id.to_usize()]?, activity)))
+            .collect();
+        self.decision_tracker.map_derived_from(map_id);
+
+        self.watches = WatchMap::new();
+        for (index, watched_literals) in self.clauses.watched_literals.iter_mut().enumerate() {
+            if let Some(watched_literals) = watched_literals {
+                self.watches
+                    .start_watching(watched_literals, ClauseId::from_usize(index));
+            }
+        }
+
+        self.clauses.kinds.shrink_to_fit();
+        self.clauses.watched_literals.shrink_to_fit();
+        self.learnt_clause_ids.shrink_to_fit();
+        self.negative_assertions.shrink_to_fit();
+        self.warm_start_clauses_used.clear();
+        #[cfg(feature = "watch-stats")]
+        self.clause_propagations.clear();
+    }
+
+    /// Returns the clauses that were learnt during the last solve, in a form
+    /// that does not depend on the ids of the solvables, so that they can be
+    /// imported into a solver whose pool was built anew, see
+    /// [`Solver::import_learnt_clauses`].
+    ///
+    /// The solvables are identified by hashes of how they and their packages
+    /// are displayed by the [`Interner`](crate::Interner). Clauses that refer
+    /// to variables other than solvables, like the helper variables of the
+    /// encoding, are left out.
+    pub fn export_learnt_clauses(&self) -> Vec<PortableClause> {
+        let interner = self.provider();
+        self.learnt_clauses
+            .iter()
+            .filter_map(|(_, literals)| {
+                let literals = literals
+                    .iter()
+                    .map(|literal| {
+                        let solvable = literal.variable().as_solvable(&self.variable_map)?;
+                        let name = interner
+                            .display_name(interner.solvable_name(solvable))
+                            .to_string();
+                        let display = interner.display_solvable(solvable).to_string();
+                        Some(PortableLiteral {
+                            name: stable_hash([&name]),
+                            solvable: stable_hash([&name, &display]),
+                            negate: literal.negate(),
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(PortableClause { literals })
+            })
+            .collect()
+    }
+
+    /// Imports clauses that were exported with
+    /// [`Solver::export_learnt_clauses`], possibly by another process, and
+    /// adds them to every subsequent [`Solver::solve`]. This replaces the
+    /// clauses of a previous import. Returns the number of clauses that were
+    /// imported.
+    ///
+    /// The solvables of the clauses are looked up among the candidates of the
+    /// packages in [`DependencyProvider::known_names`] by how they are
+    /// displayed, so the clauses apply even if the ids changed. Clauses that
+    /// refer to a solvable that no longer exists, or that is excluded, are
+    /// discarded.
+    ///
+    /// The clauses only speed up a solve if they were learnt from the same
+    /// dependencies, and they may steer it towards a different solution than
+    /// it would otherwise find. If the problem turns out to be unsolvable with
+    /// the imported clauses, for instance because the dependencies changed in
+    /// the meantime, it is solved again without them, so they never make a
+    /// solvable problem unsolvable. Imported clauses are not used when proof
+    /// logging is enabled, see [`Solver::with_proof_logging`]. How many of
+    /// them were used is counted in [`SolverStats::warm_start_clauses_used`].
+    ///
+    /// Returns an `Err` if the provider cancelled (see
+    /// [`DependencyProvider::should_cancel_with_value`]).
+    pub fn import_learnt_clauses(
+        &mut self,
+        clauses: &[PortableClause],
+    ) -> Result<usize, Box<dyn Any>> {
+        let names = clauses
+            .iter()
+            .flat_map(|clause| clause.literals.iter().map(|literal| literal.name))
+            .collect::<HashSet<_>>();
+
+        let mut solvables = HashMap::default();
+        let interner = self.cache.provider();
+        for name in interner.known_names() {
+            let display_name = interner.display_name(name).to_string();
+            if !names.contains(&stable_hash([&display_name])) {
+                continue;
+            }
+            let candidates = self
+                .async_runtime
+                .block_on(self.cache.get_or_cache_candidates(name))?;
+            // Excluded candidates can never be selected, so the clauses that refer
+            // to them are pointless.
+            for &candidate in &candidates.candidates {
+                if candidates
+                    .excluded
+                    .iter()
+                    .any(|&(excluded, _)| excluded == candidate)
+                {
+                    continue;
+                }
+                let display = interner.display_solvable(candidate).to_string();
+                solvables.insert(stable_hash([&display_name, &display]), candidate);
+            }
+        }
+
+        self.imported_clauses = clauses
+            .iter()
+            .filter(|clause| !clause.literals.is_empty())
+            .filter_map(|clause| {
+                clause
+                    .literals
+                    .iter()
+                    .map(|literal| Some((*solvables.get(&literal.solvable)?, literal.negate)))
+                    .collect()
+            })
+            .collect();
+        Ok(self.imported_clauses.len())
+    }
+
+    /// Adds the clauses of [`Solver::import_learnt_clauses`] as learnt
+    /// clauses, before any other clause is learnt.
+    fn add_imported_clauses(&mut self) {
+        for index in 0..self.imported_clauses.len() {
+            let mut literals = Vec::with_capacity(self.imported_clauses[index].len());
+            for &(solvable, negate) in &self.imported_clauses[index] {
+                let name = self.cache.provider().solvable_name(solvable);
+                if self.name_activity.len() <= name.to_usize() {
+                    self.name_activity.resize(name.to_usize() + 1, 0.0);
+                }
+                let variable = self.variable_map.intern_solvable_or_root(solvable.into());
+                literals.push(Literal::new(variable, negate));
+
+                // An imported clause can select a solvable before any requirement on its
+                // package is processed, so immediately forbid installing other solvables
+                // of the same package alongside it, like for pinned solvables.
+                let name_id = self.cache.provider().canonical_name(name);
+                let (clauses, watches) = (&mut self.clauses, &mut self.watches);
+                let variable_map = &mut self.variable_map;
+                self.forbidden_clauses_added
+                    .entry(name_id)
+                    .or_default()
+                    .add(
+                        variable,
+                        |a, b, positive| {
+                            let (watched_literals, kind) = WatchedLiterals::forbid_multiple(
+                                a,
+                                if positive { b.positive() } else { b.negative() },
+                                name_id,
+                            );
+                            let clause_id = clauses.alloc(watched_literals, kind);
+                            if let Some(watched_literals) =
+                                clauses.watched_literals[clause_id.to_usize()].as_mut()
+                            {
+                                watches.start_watching(watched_literals, clause_id);
+                            }
+                        },
+                        || variable_map.alloc_forbid_multiple_variable(name_id),
+                    );
+            }
+
+            let learnt_id = self.learnt_clauses.alloc(literals.clone());
+            self.learnt_why.insert(learnt_id, Vec::new());
+            let (watched_literals, kind) = WatchedLiterals::learnt(learnt_id, &literals);
+            let clause_id = self.clauses.alloc(watched_literals, kind);
+            self.learnt_clause_ids.push(clause_id);
+            if let Some(watched_literals) =
+                self.clauses.watched_literals[clause_id.to_usize()].as_mut()
+            {
+                self.watches.start_watching(watched_literals, clause_id);
+            }
+        }
+        self.warm_start_clauses_used = vec![false; self.imported_clauses.len()];
+    }
+
+    /// Implementation of [`Solver::solve`] and [`Solver::solve_async`]. If
+    /// `cooperative` is `false` the returned future never yields.
+    async fn solve_inner(
+        &mut self,
+        problem: Problem<impl IntoIterator<Item = SolvableId>>,
+        cooperative: bool,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        let problem = problem.collect_soft_requirements();
+        if !self.cache.minimize_versions {
+            return self.solve_once(problem, cooperative).await;
+        }
+        let solution = self.solve_once(problem.clone(), cooperative).await?;
+        let result = self
+            .tighten_versions(problem.clone(), solution, cooperative)
+            .await;
+        self.last_problem = problem;
+        result
+    }
+
+    /// Moves the selected candidates of a solution towards the candidates that
+    /// [`DependencyProvider::sort_candidates`] sorts last, see
+    /// [`Objective::MinimizeVersions`].
+    ///
+    /// The packages are visited in the order of the solution. For every
+    /// package, the problem is solved again with its selected candidate
+    /// forbidden, for as long as that still leads to a solution. During every
+    /// attempt, the candidates sorted before the selected candidate of any
+    /// package are forbidden too, so no package ever moves to a newer
+    /// candidate. Afterwards the state of the solver reflects the returned
+    /// solution.
+    async fn tighten_versions(
+        &mut self,
+        problem: Problem<Vec<SolvableId>>,
+        mut solution: Vec<SolvableId>,
+        cooperative: bool,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        let mut forbidden = problem.forbidden.clone();
+        let mut visited = HashSet::default();
+        let mut state_is_current = true;
+        while let Some(name) = solution
+            .iter()
+            .map(|&solvable| self.provider().solvable_name(solvable))
+            .find(|name| !visited.contains(name))
+        {
+            visited.insert(name);
+            while let Some(&selected) = solution
+                .iter()
+                .find(|&&solvable| self.provider().solvable_name(solvable) == name)
+            {
+                let mut attempt = forbidden.clone();
+                attempt.push(selected);
+                attempt.extend(self.newer_candidates(&solution, cooperative).await?);
+
+                let mut variant = problem.clone();
+                variant.forbidden = attempt.clone();
+                match self.solve_once(variant, cooperative).await {
+                    Ok(tightened) => {
+                        tracing::debug!(
+                            "moved {} away from {}",
+                            self.provider().display_name(name),
+                            selected.display(self.provider())
+                        );
+                        forbidden = attempt;
+                        solution = tightened;
+                        state_is_current = true;
+                    }
+                    Err(UnsolvableOrCancelled::Unsolvable(_)) => {
+                        state_is_current = false;
+                        break;
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        // Bring the state of the solver back in line with the solution. This
+        // might find a different solution, but none of its candidates is newer.
+        if !state_is_current {
+            let mut variant = problem;
+            variant.forbidden = forbidden;
+            variant
+                .forbidden
+                .extend(self.newer_candidates(&solution, cooperative).await?);
+            solution = self.solve_once(variant, cooperative).await?;
+        }
+        Ok(solution)
+    }
+
+    /// Returns the candidates that are sorted before the selected candidates
+    /// of a solution, see [`Solver::candidates_newer_than`].
+    async fn newer_candidates(
+        &self,
+        solution: &[SolvableId],
+        cooperative: bool,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        let mut newer = Vec::new();
+        for &solvable in solution {
+            let query = self.candidates_newer_than(solvable);
+            let candidates = if cooperative {
+                query.await
+            } else {
+                self.async_runtime.block_on(query)
+            }
+            .map_err(UnsolvableOrCancelled::Cancelled)?;
+            newer.extend(candidates);
+        }
+        Ok(newer)
+    }
+
+    /// Solves the problem once, without moving the selected candidates
+    /// towards older ones afterwards.
+    async fn solve_once(
+        &mut self,
+        problem: Problem<Vec<SolvableId>>,
+        cooperative: bool,
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        self.last_problem = problem.clone();
+
+        self.conflicts_since_yield = 0;
+        self.stats = SolverStats::default();
+        #[cfg(feature = "watch-stats")]
+        self.clause_propagations.clear();
+        self.removed_solvables.clear();
+        self.excluded_groups.clear();
+        self.warm_start_clauses_used.clear();
+        self.root_requirements = problem.requirements;
+        self.root_groups = problem.requirement_groups;
+        self.root_constraints = problem.constraints;
+        self.root_pins = problem.pins;
+        self.root_base = problem.base;
+        self.root_pins.extend(self.root_base.iter().copied());
+        self.root_keep = problem.keep;
+        self.root_forbidden = problem.forbidden;
+        self.root_prohibited_names = problem.prohibited_names;
+        self.root_policy_pins = problem.policy_pins;
+        for &(_, version_set, _) in &self.root_policy_pins {
+            if !self.root_constraints.contains(&version_set) {
+                self.root_constraints.push(version_set);
+            }
+        }
+        self.root_depth_constraints = problem.depth_constraints;
+        self.root_raw_clauses = problem.raw_clauses;
+        self.candidate_window = self.candidate_limit;
+
+        // Without any requirements nothing has to be installed. Constraints only
+        // forbid solvables, so they cannot cause anything to be selected either.
+        let mut soft_requirements = problem.soft_requirements.into_iter().peekable();
+        if self.root_requirements.is_empty()
+            && self.root_groups.is_empty()
+            && self.root_pins.is_empty()
+            && self.root_keep.is_empty()
+            && self.root_raw_clauses.is_empty()
+            && problem.recommends.is_empty()
+            && soft_requirements.peek().is_none()
+        {
+            self.reset();
+            return Ok(Vec::new());
+        }
+
+        // Forbid all the candidates of the prohibited packages.
+        for index in 0..self.root_prohibited_names.len() {
+            let query = self.candidates_of(self.root_prohibited_names[index]);
+            let candidates = if cooperative {
+                query.await
+            } else {
+                self.async_runtime.block_on(query)
+            }
+            .map_err(UnsolvableOrCancelled::Cancelled)?;
+            self.root_forbidden.extend(candidates);
+        }
+
+        // Forbid the candidates that would not be an upgrade.
+        for &solvable in &problem.must_upgrade {
+            let query = self.candidates_not_newer_than(solvable);
+            let not_newer = if cooperative {
+                query.await
+            } else {
+                self.async_runtime.block_on(query)
+            }
+            .map_err(UnsolvableOrCancelled::Cancelled)?;
+            self.root_forbidden.extend(not_newer);
+        }
+
+        let mut use_imported_clauses = !self.imported_clauses.is_empty() && !self.proof_logging;
+        loop {
+            self.reset();
+
+            // The first clause will always be the install root clause. Here we verify that
+            // this is indeed the case.
+            let root_clause = {
+                let (state, kind) = WatchedLiterals::root();
+                self.clauses.alloc(state, kind)
+            };
+            assert_eq!(root_clause, ClauseId::install_root());
+
+            self.group_variables = (0..self.root_groups.len())
+                .map(|idx| self.variable_map.alloc_requirement_group_variable(idx))
+                .collect();
+
+            if use_imported_clauses {
+                self.add_imported_clauses();
+            }
+
+            match self.run_sat(SolvableOrRootId::root(), cooperative).await {
+                Ok(solved) => {
+                    assert!(
+                        solved,
+                        "bug: Since root is the first requested solvable, \
+                  should have returned Err instead of Ok(false) if root is unsolvable"
+                    );
+                    break;
+                }
+                Err(UnsolvableOrCancelled::Unsolvable(conflict)) => {
+                    // Imported clauses might be stale, and they cannot explain the conflict
+                    // because their causes are unknown, so try again without them.
+                    if use_imported_clauses {
+                        tracing::debug!("no solution with the imported clauses, dropping them");
+                        use_imported_clauses = false;
+                        continue;
+                    }
+
+                    // Candidates outside of the window might still lead to a solution, so
+                    // widen the window and try again.
+                    if self.candidates_truncated {
+                        let window = self
+                            .candidate_window
+                            .expect("bug: candidates can only be truncated with a window");
+                        self.candidate_window = Some(window.saturating_mul(2));
+                        tracing::debug!(
+                            "no solution with {window} candidates per version set, widening the \
+                             candidate window to {}",
+                            window.saturating_mul(2)
+                        );
+                        continue;
+                    }
+
+                    // The provider might have more candidates for the packages that were
+                    // considered, which might still lead to a solution.
+                    if self
+                        .request_more_candidates(cooperative)
+                        .await
+                        .map_err(UnsolvableOrCancelled::Cancelled)?
+                    {
+                        continue;
+                    }
+
+                    // If keeping one of the installed solvables caused the conflict, remove
+                    // it and try again. Solvables that come first in the list are kept
+                    // preferably.
+                    let Some(idx) = self.kept_solvable_in_conflict(&conflict) else {
+                        return Err(UnsolvableOrCancelled::Unsolvable(conflict));
+                    };
+                    let removed = self.root_keep.remove(idx);
+                    tracing::debug!(
+                        "removing kept solvable {} to resolve a conflict",
+                        removed.display(self.provider())
+                    );
+                    self.removed_solvables.push(removed);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        self.record_excluded_groups();
+
+        for additional in soft_requirements {
+            let additional_var = self
+                .variable_map
+                .intern_solvable_or_string(additional.into());
+
+            if self
+                .decision_tracker
+                .assigned_value(additional_var)
+                .is_none()
+            {
+                self.run_sat(additional.into(), cooperative).await?;
+            }
+        }
+
+        for recommend in problem.recommends {
+            self.solve_recommend(recommend, cooperative).await?;
+        }
+
+        if self.satisfiability_only {
+            return Ok(Vec::new());
+        }
+        Ok(self.chosen_solvables().collect())
+    }
+
+    /// Tries to extend the current solution with a candidate of a weak
+    /// requirement, see [`Problem::recommends`].
+    async fn solve_recommend(
+        &mut self,
+        recommend: ConditionalRequirement,
+        cooperative: bool,
+    ) -> Result<(), UnsolvableOrCancelled> {
+        if !markers_hold(&recommend, self.provider()) {
+            return Ok(());
+        }
+
+        let chosen = self.chosen_solvables().collect::<HashSet<_>>();
+
+        for &condition in &recommend.conditions {
+            if let Condition::Extra(_) | Condition::Marker(_) = condition {
+                continue;
+            }
+            let mut met = false;
+            for version_set in condition.version_sets(self.provider()).collect_vec() {
+                let matching = self
+                    .run_cache_query(
+                        self.cache.get_or_cache_matching_candidates(version_set),
+                        cooperative,
+                    )
+                    .await?;
+                met |= matching.iter().any(|candidate| chosen.contains(candidate));
+            }
+            if !met {
+                return Ok(());
+            }
+        }
+
+        let candidates = self
+            .run_cache_query(
+                self.cache
+                    .get_or_cache_sorted_candidates(recommend.requirement),
+                cooperative,
+            )
+            .await?;
+        if candidates
+            .iter()
+            .any(|candidate| chosen.contains(candidate))
+        {
+            return Ok(());
+        }
+
+        // A candidate that is not part of any requirement might not be covered
+        // by the clauses that forbid multiple solvables of the same package,
+        // so skip candidates of which another version is already installed.
+        let chosen_names = chosen
+            .iter()
+            .map(|&solvable| self.provider().solvable_name(solvable))
+            .collect::<HashSet<_>>();
+        for candidate in candidates {
+            if chosen_names.contains(&self.provider().solvable_name(candidate)) {
+                continue;
+            }
+
+            let candidate_var = self
+                .variable_map
+                .intern_solvable_or_string(candidate.into());
+            if self
+                .decision_tracker
+                .assigned_value(candidate_var)
+                .is_none()
+                && self.run_sat(candidate.into(), cooperative).await?
+            {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a query against the [`SolverCache`] and returns an owned copy of
+    /// the resulting solvables.
+    ///
+    /// If `cooperative` is `false` the query is run by blocking on the async
+    /// runtime.
+    async fn run_cache_query<'a>(
+        &'a self,
+        query: impl Future<Output = Result<&'a [SolvableId], Box<dyn Any>>>,
+        cooperative: bool,
+    ) -> Result<Vec<SolvableId>, Box<dyn Any>> {
+        let result = if cooperative {
+            query.await
+        } else {
+            self.async_runtime.block_on(query)
+        };
+        result.map(<[SolvableId]>::to_vec)
+    }
+
+    /// Clears all the state that was built up while solving a problem, except
+    /// for the cached information from the [`DependencyProvider`].
+    fn reset(&mut self) {
+        self.clauses = Clauses::default();
+        self.requires_clauses = Default::default();
+        self.conditional_clauses = Default::default();
+        self.watches = WatchMap::new();
+        self.requirement_to_sorted_candidates = FrozenMap::default();
+        self.variable_map = VariableMap::default();
+        self.negative_assertions.clear();
+        self.learnt_clauses.clear();
+        self.learnt_why = Mapping::new();
+        self.learnt_clause_ids.clear();
+        self.clause_activity.clear();
+        self.clauses_added_for_package.clear();
+        self.clauses_added_for_solvable.clear();
+        self.forbidden_clauses_added.clear();
+        self.package_depths.clear();
+        self.decision_tracker.clear();
+        self.name_activity.clear();
+        self.candidates_truncated = false;
+        self.proof.clear();
+    }
+
+    /// Returns the index in `root_keep` of the last kept solvable whose
+    /// [`Clause::Pin`] is part of the conflict, if any.
+    fn kept_solvable_in_conflict(&self, conflict: &Conflict) -> Option<usize> {
+        let pinned_in_conflict = conflict
+            .clauses()
+            .iter()
+            .filter_map(
+                |&clause_id| match self.clauses.kinds[clause_id.to_usize()] {
+                    Clause::Pin(variable) => variable.as_solvable(&self.variable_map),
+                    _ => None,
+                },
+            )
+            .collect::<HashSet<_>>();
+        self.root_keep
+            .iter()
+            .rposition(|solvable| pinned_in_conflict.contains(solvable))
+    }
+
+    /// Records the requirement groups whose variable could not be set to true
+    /// as excluded.
+    fn record_excluded_groups(&mut self) {
+        self.excluded_groups = self
+            .group_variables
+            .iter()
+            .positions(|&variable| self.decision_tracker.assigned_value(variable) == Some(false))
+            .collect();
+    }
+
+    /// Returns the solvables passed to [`Problem::keep`] that had to be
+    /// removed from the solution during the last solve, because keeping them
+    /// made the problem unsolvable.
+    pub fn removed_solvables(&self) -> &[SolvableId] {
+        &self.removed_solvables
+    }
+
+    /// Returns an iterator over the solvables that were selected during the
+    /// last successful solve, without collecting them into a new `Vec`.
+    ///
+    /// The iterator yields the same solvables, in the same order, as the
+    /// solution returned by the solve. Its result is unspecified if the last
+    /// solve failed.
+    pub fn solution_iter(&self) -> impl Iterator<Item = SolvableId> + '_ {
+        self.chosen_solvables()
+    }
+
+    /// Returns the solvable that was selected for the package with the given
+    /// name during the last solve, if any. Aliases are resolved with
+    /// [`Interner::canonical_name`].
+    pub fn selected_for(&self, name: NameId) -> Option<SolvableId> {
+        let name = self.provider().canonical_name(name);
+        self.chosen_solvables()
+            .find(|&solvable| self.canonical_solvable_name(solvable) == name)
+    }
+
+    /// Returns the candidates of the package with the given name that were
+    /// ruled out during the last solve, together with the reason each of
+    /// them was eliminated, in the order in which the solver eliminated
+    /// them.
+    ///
+    /// Returns an empty `Vec` if no candidate of the package was selected.
+    /// Candidates that the solver never had to consider are not included.
+    pub fn eliminated_candidates(&self, name: NameId) -> Vec<(SolvableId, ExclusionReason)> {
+        let name = self.provider().canonical_name(name);
+        if self.selected_for(name).is_none() {
+            return Vec::new();
+        }
+
+        self.decision_tracker
+            .stack()
+            .filter(|decision| !decision.value)
+            .filter_map(|decision| {
+                let solvable = decision.variable.as_solvable(&self.variable_map)?;
+                (self.canonical_solvable_name(solvable) == name).then(|| {
+                    let clause = &self.clauses.kinds[decision.derived_from.to_usize()];
+                    (solvable, exclusion_reason(clause))
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the conditions of conditional requirements that held during
+    /// the last solve, together with the solvable that has the requirement,
+    /// or `None` for the requirements of the problem itself. Only the
+    /// requirements of solvables that were selected are considered.
+    pub fn active_conditions(&self) -> Vec<(Option<SolvableId>, Condition)> {
+        self.conditional_clauses
+            .keys()
+            .filter(|(parent, condition_variables)| {
+                self.decision_tracker.assigned_value(*parent) == Some(true)
+                    && condition_variables.iter().all(|(variable, _)| {
+                        self.decision_tracker.assigned_value(*variable) == Some(true)
+                    })
+            })
+            .flat_map(|(parent, condition_variables)| {
+                let parent = parent.as_solvable(&self.variable_map);
+                condition_variables
+                    .iter()
+                    .map(move |&(_, condition)| (parent, condition))
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Returns the requirements of `solvable` that were active during the
+    /// last solve, each together with the selected solvable that satisfies
+    /// it, in the order of [`DependencyProvider::get_dependencies`].
+    ///
+    /// A requirement with several version sets, like a [`Requirement::Union`],
+    /// is paired with the selected solvable of the first version set that has
+    /// one. Returns an empty `Vec` if `solvable` was not selected or its
+    /// dependencies are unknown.
+    pub fn satisfied_dependencies(
+        &self,
+        solvable: SolvableId,
+    ) -> Vec<(ConditionalRequirement, SolvableId)> {
+        let selected: HashSet<_> = self.chosen_solvables().collect();
+        if !selected.contains(&solvable) {
+            return Vec::new();
+        }
+        let Ok(Dependencies::Known(dependencies)) = self
+            .async_runtime
+            .block_on(self.cache.get_or_cache_dependencies(solvable))
+        else {
+            return Vec::new();
+        };
+
+        let selected_matching = |version_set: VersionSetId| {
+            self.async_runtime
+                .block_on(self.cache.get_or_cache_matching_candidates(version_set))
+                .ok()?
+                .iter()
+                .copied()
+                .find(|candidate| selected.contains(candidate))
+        };
+        let condition_holds = |condition: Condition| {
+            match condition {
+            Condition::Marker(marker) => self.provider().evaluate_marker(marker),
+            Condition::Extra(extra) => self.decision_tracker.stack().any(|decision| {
+                decision.value
+                    && matches!(
+                        self.variable_map.origin(decision.variable),
+                        VariableOrigin::Extra(parent, string) if parent == solvable && string == extra
+                    )
+            }),
+            Condition::VersionSetId(_) | Condition::Requirement(_) => condition
+                .version_sets(self.provider())
+                .any(|version_set| selected_matching(version_set).is_some()),
+        }
+        };
+
+        dependencies
+            .requirements
+            .iter()
+            .filter(|requirement| requirement.conditions.iter().copied().all(condition_holds))
+            .filter_map(|requirement| {
+                requirement
+                    .requirement_version_sets(self.provider())
+                    .find_map(selected_matching)
+                    .map(|satisfied_by| (requirement.clone(), satisfied_by))
+            })
+            .collect()
     }
-}
 
-impl From<Box<dyn Any>> for UnsolvableOrCancelled {
-    fn from(value: Box<dyn Any>) -> Self {
-        UnsolvableOrCancelled::Cancelled(value)
+    /// Returns the name of the package of `solvable` if the package is
+    /// prohibited, see [`Problem::prohibited_names`].
+    pub(crate) fn prohibited_name(&self, solvable: SolvableId) -> Option<NameId> {
+        let name = self.canonical_solvable_name(solvable);
+        self.root_prohibited_names
+            .iter()
+            .any(|&prohibited| self.provider().canonical_name(prohibited) == name)
+            .then_some(name)
     }
-}
 
-/// An error during the propagation step
-#[derive(Debug)]
-pub(crate) enum PropagationError {
-    Conflict(VariableId, bool, ClauseId),
-    Cancelled(Box<dyn Any>),
-}
+    /// Returns the name and the reason of the policy pin that restricts a
+    /// package to `version_set`, see [`Problem::policy_pins`].
+    pub(crate) fn policy_pin(&self, version_set: VersionSetId) -> Option<(NameId, StringId)> {
+        self.root_policy_pins
+            .iter()
+            .find(|&&(_, pinned, _)| pinned == version_set)
+            .map(|&(name, _, reason)| (name, reason))
+    }
 
-impl Display for PropagationError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            PropagationError::Conflict(solvable, value, clause) => {
-                write!(
-                    f,
-                    "conflict while propagating solvable {:?}, value {} caused by clause {:?}",
-                    solvable, value, clause
-                )
+    fn canonical_solvable_name(&self, solvable: SolvableId) -> NameId {
+        self.provider()
+            .canonical_name(self.provider().solvable_name(solvable))
+    }
+
+    /// Returns the indices of the groups passed to
+    /// [`Problem::requirement_groups`] that were excluded during the last
+    /// solve, because their requirements could not all be satisfied.
+    pub fn excluded_requirement_groups(&self) -> &[usize] {
+        &self.excluded_groups
+    }
+
+    /// Returns the packages in `solution` for which the newest available
+    /// candidate could not be selected, together with the reason why.
+    ///
+    /// The newest candidate of a package is the first candidate that is not
+    /// excluded, according to [`DependencyProvider::sort_candidates`]. For
+    /// every package where this is not the selected solvable, the last solved
+    /// problem is solved again with the newest candidate pinned. The conflict
+    /// that results is stored as a [`ConflictGraph`], because the clauses
+    /// a [`Conflict`] refers to do not outlive the next solve. Packages that
+    /// are pinned by the problem, or for which the newest candidate turns out
+    /// to be installable after all, are not reported.
+    ///
+    /// Because of these additional solves the state of the solver, e.g. its
+    /// [`Solver::stats`], no longer reflects the last call to
+    /// [`Solver::solve`] afterwards. Returns an `Err` if the provider cancelled
+    /// (see [`DependencyProvider::should_cancel_with_value`]).
+    pub fn downgrades(&mut self, solution: &[SolvableId]) -> Result<Vec<Downgrade>, Box<dyn Any>> {
+        let mut downgrades = Vec::new();
+        for &to in solution {
+            if self.last_problem.pins.contains(&to) {
+                continue;
             }
-            PropagationError::Cancelled(_) => {
-                write!(f, "propagation was cancelled")
+
+            let name = self
+                .provider()
+                .canonical_name(self.provider().solvable_name(to));
+            let from = match self.async_runtime.block_on(self.newest_candidate(name))? {
+                Some(from) if from != to => from,
+                _ => continue,
+            };
+
+            match self.solve_variant(|problem| problem.pins.push(from)) {
+                Ok(_) => {}
+                Err(UnsolvableOrCancelled::Unsolvable(conflict)) => {
+                    downgrades.push(Downgrade {
+                        name,
+                        from,
+                        to,
+                        reason: conflict.graph(self),
+                    });
+                }
+                Err(UnsolvableOrCancelled::Cancelled(value)) => return Err(value),
             }
         }
+        Ok(downgrades)
     }
-}
 
-impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
-    /// Returns the dependency provider used by this instance.
-    pub fn provider(&self) -> &D {
-        self.cache.provider()
+    /// Solves a variant of the problem that was solved last, which `modify`
+    /// derives from a copy of that problem. Everything else about the last
+    /// problem, e.g. its soft requirements and recommends, is preserved.
+    ///
+    /// The last problem itself is kept, so that further variants of it can be
+    /// solved. The rest of the state of the solver reflects the variant
+    /// afterwards.
+    fn solve_variant(
+        &mut self,
+        modify: impl FnOnce(&mut Problem<Vec<SolvableId>>),
+    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
+        let last_problem = self.last_problem.clone();
+        let mut problem = last_problem.clone();
+        modify(&mut problem);
+        let result = self.solve(problem);
+        self.last_problem = last_problem;
+        result
     }
 
-    /// Set the runtime of the solver to `runtime`.
-    #[must_use]
-    pub fn with_runtime<RT2: AsyncRuntime>(self, runtime: RT2) -> Solver<D, RT2> {
-        Solver {
-            async_runtime: runtime,
-            cache: self.cache,
-            clauses: self.clauses,
-            variable_map: self.variable_map,
-            requires_clauses: self.requires_clauses,
-            conditional_clauses: self.conditional_clauses,
-            requirement_to_sorted_candidates: self.requirement_to_sorted_candidates,
-            watches: self.watches,
-            negative_assertions: self.negative_assertions,
-            learnt_clauses: self.learnt_clauses,
-            learnt_why: self.learnt_why,
-            learnt_clause_ids: self.learnt_clause_ids,
-            clauses_added_for_package: self.clauses_added_for_package,
-            clauses_added_for_solvable: self.clauses_added_for_solvable,
-            forbidden_clauses_added: self.forbidden_clauses_added,
-            decision_tracker: self.decision_tracker,
-            root_requirements: self.root_requirements,
-            root_constraints: self.root_constraints,
-            name_activity: self.name_activity,
-            activity_add: self.activity_add,
-            activity_decay: self.activity_decay,
+    /// Returns the solvables that the solver chose during the last solve, and
+    /// that could have been chosen differently.
+    ///
+    /// The solver selects most solvables because it has to: they are required
+    /// and no other candidate is left. The remaining solvables are selected by
+    /// a decision. For every such decision, the last solved problem is solved
+    /// again with the decided solvable forbidden. If a solution still exists,
+    /// the decision was a real choice and the solvable is returned. The
+    /// solvables are returned in the order they were decided.
+    ///
+    /// This solves the problem once more for every decision, which can be
+    /// expensive for large problems. Afterwards the state of the solver, e.g.
+    /// its [`Solver::stats`], no longer reflects the last call to
+    /// [`Solver::solve`]. Returns an `Err` if the provider cancelled (see
+    /// [`DependencyProvider::should_cancel_with_value`]).
+    pub fn pivotal_decisions(&mut self) -> Result<Vec<SolvableId>, Box<dyn Any>> {
+        // The first assignment at every level is a decision, all other
+        // assignments are derived from it.
+        let mut decisions = Vec::new();
+        let mut previous_level = 0;
+        for decision in self.decision_tracker.stack() {
+            let level = self.decision_tracker.level(decision.variable);
+            if level == previous_level {
+                continue;
+            }
+            previous_level = level;
+            if decision.value {
+                decisions.extend(decision.variable.as_solvable(&self.variable_map));
+            }
+        }
+
+        let mut pivotal = Vec::new();
+        for decision in decisions {
+            match self.solve_variant(|problem| problem.forbidden.push(decision)) {
+                Ok(_) => pivotal.push(decision),
+                Err(UnsolvableOrCancelled::Unsolvable(_)) => {}
+                Err(UnsolvableOrCancelled::Cancelled(value)) => return Err(value),
+            }
         }
+        Ok(pivotal)
     }
 
-    /// Configure activity andd and decay parameters. This enables tweaking
-    /// these parameters.
-    #[must_use]
-    pub fn with_activity_params(self, add: f32, decay: f32) -> Self {
-        Self {
-            activity_add: add,
-            activity_decay: decay,
-            ..self
+    /// Returns the indices into [`Problem::requirements`] of the requirements
+    /// of the last solved problem that could be removed without changing its
+    /// solution, e.g. because another requirement pulls in the same package
+    /// anyway.
+    ///
+    /// The last solved problem is solved again without each of its
+    /// requirements in turn, a requirement is redundant if that results in
+    /// the same solution. Everything else about the last problem, e.g. its
+    /// requirement groups and soft requirements, is left unchanged. This is
+    /// only meaningful if the last solve succeeded.
+    ///
+    /// This solves the problem once more for every requirement, which can be
+    /// expensive for large problems. Afterwards the state of the solver, e.g.
+    /// its [`Solver::stats`], no longer reflects the last call to
+    /// [`Solver::solve`]. Returns an `Err` if the provider cancelled (see
+    /// [`DependencyProvider::should_cancel_with_value`]).
+    pub fn redundant_requirements(&mut self) -> Result<Vec<usize>, Box<dyn Any>> {
+        let solution = self.chosen_solvables().sorted().collect_vec();
+
+        let mut redundant = Vec::new();
+        for index in 0..self.last_problem.requirements.len() {
+            match self.solve_variant(|problem| {
+                problem.requirements.remove(index);
+            }) {
+                Ok(mut other) => {
+                    other.sort();
+                    if other == solution {
+                        redundant.push(index);
+                    }
+                }
+                Err(UnsolvableOrCancelled::Unsolvable(_)) => {}
+                Err(UnsolvableOrCancelled::Cancelled(value)) => return Err(value),
+            }
         }
+        Ok(redundant)
     }
 
-    /// Solves the given [`Problem`].
+    /// Returns the packages that are part of every solution of the given
+    /// requirements and constraints, no matter how the solver breaks ties.
     ///
-    /// The solver first solves for the root requirements and constraints, and
-    /// then tries to include in the solution as many of the soft
-    /// requirements as it can. Each soft requirement is subject to all the
-    /// clauses and decisions introduced for all the previously decided
-    /// solvables in the solution.
+    /// Up to `sample` different solutions are enumerated, and the names of
+    /// the packages they select are intersected. Every next solution has to
+    /// leave out at least one solvable of each of the previous ones. At
+    /// least one solution is always computed. If the problem has more than
+    /// `sample` solutions the result is an approximation: a package that is
+    /// returned might still be missing from a solution that was not
+    /// enumerated.
     ///
-    /// Unless the corresponding package has been requested by a version set in
-    /// another solvable's clauses, each soft requirement is _not_ subject
-    /// to the package-level clauses introduced in
-    /// [`DependencyProvider::get_candidates`] since the solvables have been
-    /// requested specifically (not through a version set) in the solution.
+    /// Like [`Solver::solve`], this replaces the problem the solver was
+    /// previously used for. Returns the [`Conflict`] if the problem has no
+    /// solution at all.
+    pub fn invariant_packages(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+        sample: usize,
+    ) -> Result<std::collections::HashSet<NameId>, UnsolvableOrCancelled> {
+        let mut invariant: Option<std::collections::HashSet<NameId>> = None;
+        let mut previous_solutions = Vec::new();
+        for _ in 0..sample.max(1) {
+            let problem = Problem::new()
+                .requirements(requirements.clone())
+                .constraints(constraints.clone())
+                .raw_clauses(previous_solutions.clone());
+            let solution = match self.solve(problem) {
+                Ok(solution) => solution,
+                // All solutions have been enumerated.
+                Err(UnsolvableOrCancelled::Unsolvable(_)) if invariant.is_some() => break,
+                Err(err) => return Err(err),
+            };
+
+            let names = solution
+                .iter()
+                .map(|&solvable| self.canonical_solvable_name(solvable))
+                .collect::<std::collections::HashSet<_>>();
+            invariant = Some(match invariant {
+                Some(invariant) => invariant.intersection(&names).copied().collect(),
+                None => names,
+            });
+
+            // Forbid selecting the same solvables again.
+            previous_solutions.push(
+                solution
+                    .into_iter()
+                    .map(|solvable| (solvable, false))
+                    .collect(),
+            );
+        }
+
+        Ok(invariant.unwrap_or_default())
+    }
+
+    /// Determines what changes if the requirement at `drop_index` is removed
+    /// from the given requirements, see [`RemovalImpact`].
     ///
-    /// # Returns
+    /// The problem is solved with and without the requirement, and the
+    /// solutions are compared by package. The solvables are listed in the
+    /// order of the solution with the requirement. Like [`Solver::solve`],
+    /// this replaces the problem the solver was previously used for. Returns
+    /// the [`Conflict`] if either problem has no solution.
     ///
-    /// If a solution was found, returns a [`Vec`] of the solvables included in
-    /// the solution.
+    /// # Panics
     ///
-    /// If no solution to the _root_ requirements and constraints was found,
-    /// returns a [`Conflict`] wrapped in a
-    /// [`UnsolvableOrCancelled::Unsolvable`], which provides ways to
-    /// inspect the causes and report them to the user. If a soft requirement is
-    /// unsolvable, it is simply not included in the solution.
+    /// Panics if `drop_index` is out of bounds.
+    pub fn removal_impact(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+        drop_index: usize,
+    ) -> Result<RemovalImpact, UnsolvableOrCancelled> {
+        let mut remaining = requirements.clone();
+        remaining.remove(drop_index);
+
+        let before = self.solve(
+            Problem::new()
+                .requirements(requirements)
+                .constraints(constraints.clone()),
+        )?;
+        let after = self
+            .solve(
+                Problem::new()
+                    .requirements(remaining)
+                    .constraints(constraints),
+            )?
+            .into_iter()
+            .map(|solvable| (self.canonical_solvable_name(solvable), solvable))
+            .collect::<HashMap<_, _>>();
+
+        let mut impact = RemovalImpact::default();
+        for solvable in before {
+            match after.get(&self.canonical_solvable_name(solvable)) {
+                None => impact.removed.push(solvable),
+                Some(&other) if other != solvable => impact.changed.push((solvable, other)),
+                Some(_) => {}
+            }
+        }
+        Ok(impact)
+    }
+
+    /// Returns the first candidate of the package that is not excluded,
+    /// according to the order of [`DependencyProvider::sort_candidates`].
+    async fn newest_candidate(&self, name: NameId) -> Result<Option<SolvableId>, Box<dyn Any>> {
+        Ok(self.viable_candidates(name).await?.first().copied())
+    }
+
+    /// Returns the candidates of the package that are not excluded, in the
+    /// order of [`DependencyProvider::sort_candidates`].
+    async fn viable_candidates(&self, name: NameId) -> Result<Vec<SolvableId>, Box<dyn Any>> {
+        let candidates = self.cache.get_or_cache_candidates(name).await?;
+        let mut viable = candidates
+            .candidates
+            .iter()
+            .copied()
+            .filter(|&candidate| {
+                !candidates
+                    .excluded
+                    .iter()
+                    .any(|&(excluded, _)| excluded == candidate)
+            })
+            .collect_vec();
+        self.provider()
+            .sort_candidates(&self.cache, &mut viable)
+            .await;
+        Ok(viable)
+    }
+
+    /// Returns all the candidates of the package with the given name.
+    async fn candidates_of(&self, name: NameId) -> Result<Vec<SolvableId>, Box<dyn Any>> {
+        let name = self.provider().canonical_name(name);
+        Ok(self
+            .cache
+            .get_or_cache_candidates(name)
+            .await?
+            .candidates
+            .clone())
+    }
+
+    /// Returns the given solvable and the candidates of its package that come
+    /// after it in the order of [`DependencyProvider::sort_candidates`].
+    async fn candidates_not_newer_than(
+        &self,
+        solvable: SolvableId,
+    ) -> Result<Vec<SolvableId>, Box<dyn Any>> {
+        let name = self.provider().solvable_name(solvable);
+        let mut candidates = self
+            .cache
+            .get_or_cache_candidates(name)
+            .await?
+            .candidates
+            .clone();
+        self.provider()
+            .sort_candidates(&self.cache, &mut candidates)
+            .await;
+        match candidates
+            .iter()
+            .position(|&candidate| candidate == solvable)
+        {
+            Some(position) => Ok(candidates.split_off(position)),
+            None => Ok(vec![solvable]),
+        }
+    }
+
+    /// Returns the candidates of the package of the given solvable that come
+    /// before it in the order of [`DependencyProvider::sort_candidates`].
+    async fn candidates_newer_than(
+        &self,
+        solvable: SolvableId,
+    ) -> Result<Vec<SolvableId>, Box<dyn Any>> {
+        let name = self.provider().solvable_name(solvable);
+        let mut candidates = self
+            .cache
+            .get_or_cache_candidates(name)
+            .await?
+            .candidates
+            .clone();
+        self.provider()
+            .sort_candidates(&self.cache, &mut candidates)
+            .await;
+        let position = candidates
+            .iter()
+            .position(|&candidate| candidate == solvable)
+            .unwrap_or(0);
+        candidates.truncate(position);
+        Ok(candidates)
+    }
+
+    /// Returns the names of the packages that still had to be decided when the
+    /// last solve stopped. These are the packages for which candidates were
+    /// considered, but none of them was selected and at least one of them was
+    /// not assigned yet.
     ///
-    /// If the solution process is cancelled (see
-    /// [`DependencyProvider::should_cancel_with_value`]), returns an
-    /// [`UnsolvableOrCancelled::Cancelled`] containing the cancellation value.
-    pub fn solve(
+    /// This is mostly useful after a solve was cancelled (see
+    /// [`DependencyProvider::should_cancel_with_value`]) to show the work that
+    /// remained. The names are ordered by their [`NameId`].
+    pub fn undecided_names(&self) -> Vec<NameId> {
+        let mut decided = HashSet::default();
+        let mut undecided = HashSet::default();
+        for (variable, solvable) in self.variable_map.solvables() {
+            let name = self.provider().solvable_name(solvable);
+            match self.decision_tracker.assigned_value(variable) {
+                Some(true) => {
+                    decided.insert(name);
+                }
+                Some(false) => {}
+                None => {
+                    undecided.insert(name);
+                }
+            }
+        }
+        undecided.difference(&decided).copied().sorted().collect()
+    }
+
+    /// Estimates how hard it is to solve the given requirements and
+    /// constraints, see [`DifficultyScore`].
+    ///
+    /// This generates the clauses for every solvable that could be part of a
+    /// solution, but does not search for a solution. The candidates and
+    /// dependencies retrieved from the [`DependencyProvider`] are cached for
+    /// subsequent solves. Returns an `Err` if the provider cancelled (see
+    /// [`DependencyProvider::should_cancel_with_value`]).
+    pub fn difficulty_estimate(
         &mut self,
-        problem: Problem<impl IntoIterator<Item = SolvableId>>,
-    ) -> Result<Vec<SolvableId>, UnsolvableOrCancelled> {
-        self.decision_tracker.clear();
-        self.negative_assertions.clear();
-        self.learnt_clauses.clear();
-        self.learnt_why = Mapping::new();
-        self.clauses = Clauses::default();
-        self.root_requirements = problem.requirements;
-        self.root_constraints = problem.constraints;
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+    ) -> Result<DifficultyScore, Box<dyn Any>> {
+        self.add_all_clauses(requirements, constraints)?;
+
+        let mut num_literals = 0;
+        let mut num_constraint_clauses = 0;
+        for clause in &self.clauses.kinds[1..] {
+            clause.visit_literals(
+                &self.learnt_clauses,
+                &self.requirement_to_sorted_candidates,
+                |_| num_literals += 1,
+            );
+            if matches!(
+                clause,
+                Clause::Constrains(..)
+                    | Clause::ForbidMultipleInstances(..)
+                    | Clause::Lock(..)
+                    | Clause::Forbidden(..)
+                    | Clause::Excluded(..)
+            ) {
+                num_constraint_clauses += 1;
+            }
+        }
 
-        // The first clause will always be the install root clause. Here we verify that
-        // this is indeed the case.
-        let root_clause = {
-            let (state, kind) = WatchedLiterals::root();
-            self.clauses.alloc(state, kind)
+        let num_variables = self.variable_map.count();
+        let num_clauses = self.clauses.kinds.len() - 1;
+        let score = DifficultyScore {
+            num_variables,
+            num_clauses,
+            average_clause_length: num_literals as f64 / num_clauses.max(1) as f64,
+            constraint_density: num_constraint_clauses as f64 / num_variables as f64,
         };
-        assert_eq!(root_clause, ClauseId::install_root());
 
-        assert!(
-            self.run_sat(SolvableOrRootId::root())?,
-            "bug: Since root is the first requested solvable, \
-                  should have returned Err instead of Ok(false) if root is unsolvable"
-        );
+        self.reset();
+        Ok(score)
+    }
 
-        for additional in problem.soft_requirements {
-            let additional_var = self
-                .variable_map
-                .intern_solvable_or_string(additional.into());
+    /// Generates the clauses for the given requirements and constraints and
+    /// returns them in conjunctive normal form, see [`Cnf`].
+    ///
+    /// This generates the clauses for every solvable that could be part of a
+    /// solution, but does not search for a solution. This allows verifying
+    /// the encoding of a problem with, or benchmarking it against, other SAT
+    /// solvers. The candidates and dependencies retrieved from the
+    /// [`DependencyProvider`] are cached for subsequent solves. Returns an
+    /// `Err` if the provider cancelled (see
+    /// [`DependencyProvider::should_cancel_with_value`]).
+    pub fn export_cnf(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+    ) -> Result<Cnf, Box<dyn Any>> {
+        self.add_all_clauses(requirements, constraints)?;
+        let cnf = self.cnf();
+        self.reset();
+        Ok(cnf)
+    }
 
-            if self
-                .decision_tracker
-                .assigned_value(additional_var)
-                .is_none()
-            {
-                self.run_sat(additional.into())?;
+    /// Returns the clauses that were generated during the last solve in
+    /// conjunctive normal form, see [`Cnf`]. Learnt clauses are not included.
+    ///
+    /// Unlike [`Solver::export_cnf`], these are only the clauses the solver
+    /// needed to find a solution or to prove that there is none. This is the
+    /// formula that a proof from [`Conflict::drat_proof`] refers to.
+    pub fn cnf(&self) -> Cnf {
+        let mut cnf = Cnf::default();
+        for idx in 0..self.variable_map.count() {
+            let solvable = VariableId::from_usize(idx).as_solvable(&self.variable_map);
+            if let Some(solvable) = solvable {
+                cnf.solvable_variables.insert(solvable, idx as u32 + 1);
             }
+            cnf.variables.push(solvable);
         }
 
-        Ok(self.chosen_solvables().collect())
+        if self.clauses.kinds.is_empty() {
+            return cnf;
+        }
+        cnf.clauses
+            .push(vec![VariableId::root().positive().to_dimacs()]);
+        for clause in &self.clauses.kinds[1..] {
+            if let Clause::Learnt(_) = clause {
+                continue;
+            }
+            let mut literals = Vec::new();
+            clause.visit_literals(
+                &self.learnt_clauses,
+                &self.requirement_to_sorted_candidates,
+                |literal| literals.push(literal.to_dimacs()),
+            );
+            cnf.clauses.push(literals);
+        }
+        cnf
+    }
+
+    /// Resets the solver and adds the clauses for the given requirements and
+    /// constraints, and for every solvable that the clauses refer to.
+    fn add_all_clauses(
+        &mut self,
+        requirements: Vec<ConditionalRequirement>,
+        constraints: Vec<VersionSetId>,
+    ) -> Result<(), Box<dyn Any>> {
+        self.reset();
+        self.root_requirements = requirements;
+        self.root_constraints = constraints;
+        self.root_pins.clear();
+        self.root_base.clear();
+        self.root_keep.clear();
+        self.root_forbidden.clear();
+        self.root_prohibited_names.clear();
+        self.root_policy_pins.clear();
+        self.root_depth_constraints.clear();
+        self.root_raw_clauses.clear();
+        self.candidate_window = None;
+
+        let (state, kind) = WatchedLiterals::root();
+        self.clauses.alloc(state, kind);
+
+        // Keep adding clauses for all the solvables that clauses refer to, until the
+        // clauses of every solvable that could be installed have been added.
+        let mut pending = vec![SolvableOrRootId::root()];
+        while !pending.is_empty() {
+            self.add_clauses(pending, false)
+                .now_or_never()
+                .expect("bug: adding clauses should not yield when not cooperative")?;
+            pending = self
+                .variable_map
+                .solvables()
+                .map(|(_, solvable)| SolvableOrRootId::from(solvable))
+                .filter(|solvable| !self.clauses_added_for_solvable.contains(solvable))
+                .collect();
+        }
+        Ok(())
+    }
+
+    /// Returns statistics about the work performed during the last solve.
+    pub fn stats(&self) -> SolverStats {
+        let watch = WatchStats {
+            num_watches: 2 * self.clauses.watched_literals.iter().flatten().count(),
+            num_watch_traversals: self.stats.watch.num_watch_traversals,
+            most_propagating_clauses: self.most_propagating_clauses(),
+        };
+
+        SolverStats {
+            num_variables: self.variable_map.count(),
+            num_clauses: self.clauses.kinds.len() - self.learnt_clause_ids.len(),
+            num_learnt_clauses: self.learnt_clause_ids.len(),
+            num_learnt_literals: self
+                .learnt_clause_ids
+                .iter()
+                .map(
+                    |&clause_id| match self.clauses.kinds[clause_id.to_usize()] {
+                        Clause::Learnt(learnt_id) => self.learnt_clauses[learnt_id].len(),
+                        _ => 0,
+                    },
+                )
+                .sum(),
+            watch,
+            ..self.stats.clone()
+        }
+    }
+
+    /// Returns the clauses that propagated the most literals during the last
+    /// solve, see [`WatchStats::most_propagating_clauses`].
+    #[cfg(feature = "watch-stats")]
+    fn most_propagating_clauses(&self) -> Vec<(String, usize)> {
+        let mut propagations: Vec<_> = self
+            .clause_propagations
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .collect();
+        propagations.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(&b.0)));
+        propagations
+            .into_iter()
+            .take(WatchStats::MAX_PROPAGATING_CLAUSES)
+            .map(|(index, &count)| {
+                let description = self.clauses.kinds[index]
+                    .display(&self.variable_map, self.provider())
+                    .to_string();
+                (description, count as usize)
+            })
+            .collect()
+    }
+
+    #[cfg(not(feature = "watch-stats"))]
+    fn most_propagating_clauses(&self) -> Vec<(String, usize)> {
+        Vec::new()
     }
 
     /// Returns the solvables that the solver has chosen to include in the
@@ -407,6 +3489,15 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
         })
     }
 
+    /// Returns the level of the last assignment on the trail, or `0` if
+    /// nothing is assigned.
+    fn decision_level(&self) -> u32 {
+        self.decision_tracker
+            .stack()
+            .last()
+            .map_or(0, |decision| self.decision_tracker.level(decision.variable))
+    }
+
     /// Run the CDCL algorithm to solve the SAT problem
     ///
     /// The CDCL algorithm's job is to find a valid assignment to the variables
@@ -440,7 +3531,15 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
     /// If the solution process is cancelled (see
     /// [`DependencyProvider::should_cancel_with_value`]),
     /// returns [`UnsolvableOrCancelled::Cancelled`] as an `Err`.
-    fn run_sat(&mut self, root_solvable: SolvableOrRootId) -> Result<bool, UnsolvableOrCancelled> {
+    ///
+    /// If `cooperative` is `true` the futures of the dependency provider are
+    /// awaited directly and the solver periodically yields to the async
+    /// runtime. Otherwise the returned future never yields.
+    async fn run_sat(
+        &mut self,
+        root_solvable: SolvableOrRootId,
+        cooperative: bool,
+    ) -> Result<bool, UnsolvableOrCancelled> {
         let starting_level = self
             .decision_tracker
             .stack()
@@ -484,19 +3583,7 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                     .expect("already decided");
 
                 // Add the clauses for the root solvable.
-                let output = self.async_runtime.block_on(add_clauses_for_solvables(
-                    [root_solvable],
-                    &self.cache,
-                    &mut self.clauses,
-                    &self.decision_tracker,
-                    &mut self.variable_map,
-                    &mut self.clauses_added_for_solvable,
-                    &mut self.clauses_added_for_package,
-                    &mut self.forbidden_clauses_added,
-                    &mut self.requirement_to_sorted_candidates,
-                    &self.root_requirements,
-                    &self.root_constraints,
-                ))?;
+                let output = self.add_clauses([root_solvable], cooperative).await?;
                 if let Err(clause_id) = self.process_add_clause_output(output) {
                     return self.run_sat_process_unsolvable(
                         root_solvable,
@@ -542,7 +3629,7 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
             // Enter the solver loop, return immediately if no new assignments have been
             // made.
             tracing::trace!("Level {}: Resolving dependencies", level);
-            level = self.resolve_dependencies(level)?;
+            level = self.resolve_dependencies(level, cooperative).await?;
             tracing::trace!("Level {}: Done resolving dependencies", level);
 
             // We have a partial solution. E.g. there is a solution that satisfies all the
@@ -592,27 +3679,16 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
             tracing::debug!("====");
 
             // Concurrently get the solvable's clauses
-            let output = self.async_runtime.block_on(add_clauses_for_solvables(
-                new_solvables
-                    .iter()
-                    .filter_map(|(variable, _)| {
-                        self.variable_map
-                            .origin(*variable)
-                            .as_solvable()
-                            .map(Into::into)
-                    })
-                    .collect::<Vec<_>>(),
-                &self.cache,
-                &mut self.clauses,
-                &self.decision_tracker,
-                &mut self.variable_map,
-                &mut self.clauses_added_for_solvable,
-                &mut self.clauses_added_for_package,
-                &mut self.forbidden_clauses_added,
-                &mut self.requirement_to_sorted_candidates,
-                &self.root_requirements,
-                &self.root_constraints,
-            ))?;
+            let new_solvables = new_solvables
+                .iter()
+                .filter_map(|(variable, _)| {
+                    self.variable_map
+                        .origin(*variable)
+                        .as_solvable()
+                        .map(Into::into)
+                })
+                .collect::<Vec<_>>();
+            let output = self.add_clauses(new_solvables, cooperative).await?;
 
             // Serially process the outputs, to reduce the need for synchronization
             for &clause_id in &output.conflicting_clauses {
@@ -627,6 +3703,86 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
         }
     }
 
+    /// Requests more candidates for the packages of the last attempt for which
+    /// the provider has more, see [`DependencyProvider::get_more_candidates`].
+    /// Returns whether any candidates were added.
+    async fn request_more_candidates(&mut self, cooperative: bool) -> Result<bool, Box<dyn Any>> {
+        let names = self
+            .clauses_added_for_package
+            .iter()
+            .copied()
+            .filter(|&name| self.cache.more_candidates_available(name))
+            .sorted()
+            .collect_vec();
+
+        let mut added = false;
+        for name in names {
+            if let Some(value) = self.provider().should_cancel_with_value() {
+                return Err(value);
+            }
+
+            let query = self.cache.provider().get_more_candidates(name);
+            let more = if cooperative {
+                query.await
+            } else {
+                self.async_runtime.block_on(query)
+            }
+            .unwrap_or_default();
+
+            tracing::debug!(
+                "requested {} more candidates for {}",
+                more.candidates.len(),
+                self.provider().display_name(name)
+            );
+            added |= !more.candidates.is_empty();
+            self.cache.add_candidates(name, more);
+        }
+        Ok(added)
+    }
+
+    /// Adds the clauses for the given solvables, see
+    /// [`add_clauses_for_solvables`].
+    ///
+    /// If `cooperative` is `false` the clauses are added by blocking on the
+    /// async runtime, and the returned future never yields.
+    async fn add_clauses(
+        &mut self,
+        solvable_ids: impl IntoIterator<Item = SolvableOrRootId>,
+        cooperative: bool,
+    ) -> Result<AddClauseOutput, Box<dyn Any>> {
+        let add_clauses = add_clauses_for_solvables(
+            solvable_ids,
+            &self.cache,
+            &mut self.clauses,
+            &self.decision_tracker,
+            &mut self.variable_map,
+            &mut self.clauses_added_for_solvable,
+            &mut self.clauses_added_for_package,
+            &mut self.forbidden_clauses_added,
+            &mut self.requirement_to_sorted_candidates,
+            &mut self.package_depths,
+            &self.root_requirements,
+            &self.root_constraints,
+            &self.root_depth_constraints,
+            &self.root_pins,
+            &self.root_keep,
+            &self.root_forbidden,
+            &self.root_groups,
+            &self.group_variables,
+            &self.root_raw_clauses,
+            self.candidate_window,
+            self.platform,
+            self.strict_unknown_dependencies,
+        );
+        let output = if cooperative {
+            add_clauses.await
+        } else {
+            self.async_runtime.block_on(add_clauses)
+        }?;
+        self.candidates_truncated |= output.candidates_truncated;
+        Ok(output)
+    }
+
     /// Decides how to terminate the solver algorithm when the given `solvable`
     /// was deemed unsolvable by [`Solver::run_sat`].
     ///
@@ -718,8 +3874,30 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
     /// for which no concrete package has been picked yet. Then we pick the
     /// highest possible version for that package, or the favored version if
     /// it was provided by the user, and set its value to true.
-    fn resolve_dependencies(&mut self, mut level: u32) -> Result<u32, UnsolvableOrCancelled> {
+    ///
+    /// If `cooperative` is `true`, the solver yields to the async runtime
+    /// whenever the number of conflicts since the last yield reaches the
+    /// configured yield interval.
+    async fn resolve_dependencies(
+        &mut self,
+        mut level: u32,
+        cooperative: bool,
+    ) -> Result<u32, UnsolvableOrCancelled> {
         loop {
+            if self
+                .conflict_budget
+                .is_some_and(|budget| self.stats.num_conflicts >= budget)
+            {
+                return Err(UnsolvableOrCancelled::Cancelled(Box::new(
+                    ConflictBudgetExhausted,
+                )));
+            }
+
+            if cooperative && self.conflicts_since_yield >= self.yield_interval {
+                self.conflicts_since_yield = 0;
+                self.async_runtime.yield_now().await;
+            }
+
             // Make a decision. If no decision could be made it means the problem is
             // satisfiable.
             let Some((candidate, required_by, clause_id)) = self.decide() else {
@@ -792,6 +3970,17 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
             decision: (VariableId, VariableId, ClauseId),
         }
 
+        // Activate the requirement groups before anything else, in order, so that groups
+        // that come first are activated preferably. A group that cannot be activated is
+        // set to false by propagation once the solver has learnt why.
+        if let Some(&group_variable) = self
+            .group_variables
+            .iter()
+            .find(|&&variable| self.decision_tracker.assigned_value(variable).is_none())
+        {
+            return Some((group_variable, VariableId::root(), ClauseId::install_root()));
+        }
+
         let mut best_decision: Option<PossibleDecision> = None;
 
         // Chain together the requires_clauses and conditional_clauses iterations
@@ -820,8 +4009,11 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                     )
                 });
 
-        for (solvable_id, condition, requirements) in requires_iter.chain(conditional_iter) {
-            let is_explicit_requirement = solvable_id == VariableId::root();
+        'requirements: for (solvable_id, condition, requirements) in
+            requires_iter.chain(conditional_iter)
+        {
+            let is_explicit_requirement =
+                solvable_id == VariableId::root() || self.group_variables.contains(&solvable_id);
 
             if let Some(best_decision) = &best_decision {
                 // If we already have an explicit requirement, there is no need to evaluate
@@ -839,12 +4031,9 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
             // For conditional clauses, check that at least one conditional variable is true
             if let Some(condition_variable) = condition {
                 // Check if any candidate that matches the condition's version set is installed
-                let condition_met =
-                    condition_variable
-                        .iter()
-                        .all(|(condition_variable, _)| {
-                            self.decision_tracker.assigned_value(*condition_variable) == Some(true)
-                        });
+                let condition_met = condition_variable.iter().all(|(condition_variable, _)| {
+                    self.decision_tracker.assigned_value(*condition_variable) == Some(true)
+                });
 
                 // If the condition is not met, skip this requirement entirely
                 if !condition_met {
@@ -855,6 +4044,11 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
             for (requirement, clause_id) in requirements {
                 let mut candidate = ControlFlow::Break(());
 
+                // The number of candidates that have to be installed before the requirement
+                // is satisfied, and the distinct candidates that already are.
+                let min_count = requirement.min_count();
+                let mut installed = Vec::new();
+
                 // Get the candidates for the individual version sets.
                 let version_set_candidates = &self.requirement_to_sorted_candidates[&requirement];
 
@@ -880,8 +4074,17 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                             ControlFlow::Continue(match assigned_value {
                                 Some(true) => {
                                     // This candidate has already been assigned so the clause is
-                                    // already true. Skip it.
-                                    return ControlFlow::Break(());
+                                    // already true once enough candidates are. Skip it.
+                                    if min_count <= 1 {
+                                        return ControlFlow::Break(());
+                                    }
+                                    if !installed.contains(&candidate) {
+                                        installed.push(candidate);
+                                    }
+                                    if installed.len() >= min_count {
+                                        return ControlFlow::Break(());
+                                    }
+                                    first_candidate
                                 }
                                 Some(false) => {
                                     // This candidate has already been assigned false, continue the
@@ -914,7 +4117,9 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                                         // yet.
                                         let package_activity = self.name_activity[self
                                             .provider()
-                                            .version_set_name(version_set)
+                                            .canonical_name(
+                                                self.provider().version_set_name(version_set),
+                                            )
                                             .to_usize()];
                                         Some((candidate, version_set, 1, package_activity))
                                     }
@@ -945,6 +4150,18 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                         package_activity,
                     ))) => {
                         let decision = (candidate, solvable_id, clause_id);
+
+                        // Without heuristics, the first possible decision is as good as any.
+                        if self.first_fit {
+                            best_decision = Some(PossibleDecision {
+                                is_explicit_requirement,
+                                package_activity,
+                                candidate_count,
+                                decision,
+                            });
+                            break 'requirements;
+                        }
+
                         best_decision = Some(match &best_decision {
                             None => PossibleDecision {
                                 is_explicit_requirement,
@@ -1033,6 +4250,7 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
     ) -> Result<u32, UnsolvableOrCancelled> {
         level += 1;
 
+        self.stats.num_decisions += 1;
         self.decision_tracker
             .try_add_decision(Decision::new(solvable, true, clause_id), level)
             .expect("bug: solvable was already decided!");
@@ -1054,6 +4272,8 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                     attempted_value,
                     conflicting_clause,
                 )) => {
+                    self.conflicts_since_yield += 1;
+                    self.stats.num_conflicts += 1;
                     level = self.learn_from_conflict(
                         level,
                         conflicting_solvable,
@@ -1134,6 +4354,13 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                 level,
             )
             .expect("bug: solvable was already decided!");
+        report_excluded(
+            &mut self.on_candidate_excluded,
+            &self.variable_map,
+            literal.variable(),
+            decision,
+            &self.clauses.kinds[learned_clause_id.to_usize()],
+        );
         tracing::debug!(
             "│├ Propagate after learn: {} = {decision}",
             literal
@@ -1194,6 +4421,7 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                 .watches
                 .cursor(&mut self.clauses.watched_literals, watched_literal);
             while let Some(cursor) = next_cursor.take() {
+                self.stats.watch.num_watch_traversals += 1;
                 let clause_id = cursor.clause_id();
                 let clause = &clause_kinds[clause_id.to_usize()];
                 let watch_index = cursor.watch_index();
@@ -1237,6 +4465,34 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                         })?;
 
                     if decided {
+                        report_excluded(
+                            &mut self.on_candidate_excluded,
+                            &self.variable_map,
+                            other_watched_literal.variable(),
+                            other_watched_literal.satisfying_value(),
+                            clause,
+                        );
+
+                        // Same as `record_warm_start_use`, which cannot be called while the
+                        // clauses are borrowed.
+                        if let Clause::Learnt(learnt_id) = *clause {
+                            if let Some(used @ false) =
+                                self.warm_start_clauses_used.get_mut(learnt_id.to_usize())
+                            {
+                                *used = true;
+                                self.stats.warm_start_clauses_used += 1;
+                            }
+                        }
+
+                        #[cfg(feature = "watch-stats")]
+                        {
+                            let index = clause_id.to_usize();
+                            if self.clause_propagations.len() <= index {
+                                self.clause_propagations.resize(index + 1, 0);
+                            }
+                            self.clause_propagations[index] += 1;
+                        }
+
                         match clause {
                             // Skip logging for ForbidMultipleInstances, which is so noisy
                             Clause::ForbidMultipleInstances(..) => {}
@@ -1274,6 +4530,13 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                 .map_err(|_| PropagationError::Conflict(solvable_id, value, clause_id))?;
 
             if decided {
+                report_excluded(
+                    &mut self.on_candidate_excluded,
+                    &self.variable_map,
+                    solvable_id,
+                    value,
+                    &self.clauses.kinds[clause_id.to_usize()],
+                );
                 tracing::trace!(
                     "Negative assertions derived from other rules: Propagate assertion {} = {}",
                     solvable_id.display(&self.variable_map, self.provider()),
@@ -1313,6 +4576,15 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                 .map_err(|_| PropagationError::Conflict(literal.variable(), decision, clause_id))?;
 
             if decided {
+                report_excluded(
+                    &mut self.on_candidate_excluded,
+                    &self.variable_map,
+                    literal.variable(),
+                    decision,
+                    clause,
+                );
+                let learnt_id = *learnt_index;
+                self.record_warm_start_use(learnt_id);
                 tracing::trace!(
                     "├─ Propagate assertion {} = {}",
                     literal
@@ -1325,6 +4597,15 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
         Ok(())
     }
 
+    /// Counts the learnt clause in [`SolverStats::warm_start_clauses_used`] if
+    /// it was kept from the previous solve and was not used before.
+    fn record_warm_start_use(&mut self, learnt_id: LearntClauseId) {
+        if let Some(used @ false) = self.warm_start_clauses_used.get_mut(learnt_id.to_usize()) {
+            *used = true;
+            self.stats.warm_start_clauses_used += 1;
+        }
+    }
+
     /// Adds the clause with `clause_id` to the current [`Conflict`]
     ///
     /// Because learnt clauses are not relevant for the user, they are not added
@@ -1357,6 +4638,10 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
     /// Create a [`Conflict`] based on the id of the clause that triggered an
     /// unrecoverable conflict
     fn analyze_unsolvable(&mut self, clause_id: ClauseId) -> Conflict {
+        if self.satisfiability_only {
+            return Conflict::default();
+        }
+
         let last_decision = self.decision_tracker.stack().last().unwrap();
         let highest_level = self.decision_tracker.level(last_decision.variable);
         debug_assert_eq!(highest_level, 1);
@@ -1417,6 +4702,12 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
             );
         }
 
+        if self.proof_logging {
+            // The conflict at the root level is what derives the empty clause.
+            self.proof.push(ProofStep::Add(Vec::new()));
+            conflict.proof = std::mem::take(&mut self.proof);
+        }
+
         conflict
     }
 
@@ -1442,11 +4733,20 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
         let mut seen = HashSet::default();
         let mut causes_at_current_level = 0u32;
         let mut learnt = Vec::new();
+        let mut learnt_levels = Vec::new();
         let mut back_track_to = 0;
 
         let mut s_value;
         let mut learnt_why = Vec::new();
         let mut first_iteration = true;
+
+        // Remember the assignments if they have to be reported after backjumping.
+        let from_level = current_level;
+        let trail = self
+            .on_backtrack
+            .is_some()
+            .then(|| self.decision_tracker.stack().collect::<Vec<_>>());
+
         let clause_kinds = &self.clauses.kinds;
         loop {
             learnt_why.push(clause_id);
@@ -1477,6 +4777,7 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
                                 .unwrap(),
                         );
                         learnt.push(learnt_literal);
+                        learnt_levels.push(decision_level);
                         back_track_to = back_track_to.max(decision_level);
                     } else {
                         unreachable!();
@@ -1509,11 +4810,40 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
             }
         }
 
+        if self.clause_minimization {
+            self.minimize_learnt_clause(
+                conflicting_solvable,
+                &mut learnt,
+                &mut learnt_levels,
+                &mut learnt_why,
+            );
+            back_track_to = learnt_levels.iter().copied().max().unwrap_or(0);
+        }
+
+        for &clause_id in &learnt_why {
+            *self.clause_activity.entry(clause_id).or_default() += 1;
+            if let Clause::Learnt(learnt_id) = self.clauses.kinds[clause_id.to_usize()] {
+                self.record_warm_start_use(learnt_id);
+            }
+        }
+
         let last_literal = Literal::new(conflicting_solvable, s_value);
         learnt.push(last_literal);
+        learnt_levels.push(current_level);
+
+        // The literal block distance (LBD) of the learnt clause is the number of
+        // distinct decision levels of its literals.
+        learnt_levels.sort_unstable();
+        learnt_levels.dedup();
+        let lbd = learnt_levels.len();
+        let lbd_histogram = &mut self.stats.lbd_histogram;
+        if lbd_histogram.len() <= lbd {
+            lbd_histogram.resize(lbd + 1, 0);
+        }
+        lbd_histogram[lbd] += 1;
 
         // Increase the activity of the packages in the learned clause
-        for literal in &learnt {
+        for literal in learnt.iter().filter(|_| !self.first_fit) {
             let name_id = literal
                 .variable()
                 .as_solvable(&self.variable_map)
@@ -1524,6 +4854,11 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
         }
 
         // Add the clause
+        if self.proof_logging {
+            self.proof.push(ProofStep::Add(
+                learnt.iter().map(|literal| literal.to_dimacs()).collect(),
+            ));
+        }
         let learnt_id = self.learnt_clauses.alloc(learnt.clone());
         self.learnt_why.insert(learnt_id, learnt_why);
 
@@ -1548,11 +4883,134 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
         let target_level = back_track_to.max(1);
         self.decision_tracker.undo_until(target_level);
 
-        self.decay_activity_scores();
+        if let (Some(on_backtrack), Some(trail)) = (self.on_backtrack.as_mut(), trail) {
+            let remaining = self.decision_tracker.stack().count();
+            on_backtrack(BacktrackEvent {
+                from_level,
+                to_level: target_level,
+                undone: trail[remaining..]
+                    .iter()
+                    .filter_map(|decision| decision.variable.as_solvable(&self.variable_map))
+                    .collect(),
+            });
+        }
+
+        if !self.first_fit {
+            self.decay_activity_scores();
+        }
 
         (target_level, clause_id, last_literal)
     }
 
+    /// Removes the literals from a learnt clause whose assignment is implied by
+    /// the other literals of the clause (recursive clause minimization).
+    ///
+    /// `learnt` holds the literals below the current decision level, the
+    /// literal of `asserting` is added to it afterwards. The clauses that
+    /// implied the removed literals are added to `learnt_why`, because the
+    /// minimized clause is derived from them too.
+    fn minimize_learnt_clause(
+        &self,
+        asserting: VariableId,
+        learnt: &mut Vec<Literal>,
+        learnt_levels: &mut Vec<u32>,
+        learnt_why: &mut Vec<ClauseId>,
+    ) {
+        if learnt.len() < 2 {
+            return;
+        }
+
+        let in_clause: HashSet<_> = learnt
+            .iter()
+            .map(|literal| literal.variable())
+            .chain([asserting])
+            .collect();
+        let mut implied = HashMap::default();
+
+        // Literals stay in `in_clause` once removed, which is fine because they are
+        // implied by the literals that remain.
+        let mut removed = Vec::new();
+        let mut index = 0;
+        while index < learnt.len() {
+            let variable = learnt[index].variable();
+            if self.is_implied(variable, &in_clause, &mut implied, 0) {
+                removed.push(variable);
+                learnt.swap_remove(index);
+                learnt_levels.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+
+        // Collect the clauses through which the removed literals were implied.
+        let mut visited = HashSet::default();
+        while let Some(variable) = removed.pop() {
+            if !visited.insert(variable) {
+                continue;
+            }
+            let clause_id = self
+                .decision_tracker
+                .implied_by(variable)
+                .expect("removed literals are implied");
+            learnt_why.push(clause_id);
+            self.clauses.kinds[clause_id.to_usize()].visit_literals(
+                &self.learnt_clauses,
+                &self.requirement_to_sorted_candidates,
+                |literal| {
+                    if implied.get(&literal.variable()) == Some(&true) {
+                        removed.push(literal.variable());
+                    }
+                },
+            );
+        }
+    }
+
+    /// Returns true if the assignment of `variable` was implied by assignments
+    /// to variables that are `in_clause`, directly or through other implied
+    /// assignments, see [`Solver::minimize_learnt_clause`]. The results for
+    /// variables that are not `in_clause` are memoized in `implied`.
+    fn is_implied(
+        &self,
+        variable: VariableId,
+        in_clause: &HashSet<VariableId>,
+        implied: &mut HashMap<VariableId, bool>,
+        depth: usize,
+    ) -> bool {
+        /// Bounds the recursion, a literal that is only implied through a
+        /// longer chain of assignments is kept.
+        const MAX_DEPTH: usize = 100;
+
+        let Some(clause_id) = self.decision_tracker.implied_by(variable) else {
+            return false;
+        };
+
+        let mut others = Vec::new();
+        self.clauses.kinds[clause_id.to_usize()].visit_literals(
+            &self.learnt_clauses,
+            &self.requirement_to_sorted_candidates,
+            |literal| {
+                if literal.variable() != variable {
+                    others.push(literal.variable());
+                }
+            },
+        );
+
+        others.into_iter().all(|other| {
+            if in_clause.contains(&other) {
+                return true;
+            }
+            if self.decision_tracker.assigned_value(other).is_none() || depth >= MAX_DEPTH {
+                return false;
+            }
+            if let Some(&other_implied) = implied.get(&other) {
+                return other_implied;
+            }
+            let other_implied = self.is_implied(other, in_clause, implied, depth + 1);
+            implied.insert(other, other_implied);
+            other_implied
+        })
+    }
+
     /// Decays the activity scores of all packages in the solver. This function
     /// is caleld after each conflict.
     fn decay_activity_scores(&mut self) {
@@ -1562,6 +5020,180 @@ impl<D: DependencyProvider, RT: AsyncRuntime> Solver<D, RT> {
     }
 }
 
+/// Invokes the [`SolverBuilder::on_candidate_excluded`] callback, if any, when
+/// `clause` propagated `false` to a candidate.
+fn report_excluded(
+    on_candidate_excluded: &mut Option<Box<dyn FnMut(SolvableId, ExclusionReason)>>,
+    variable_map: &VariableMap,
+    variable: VariableId,
+    value: bool,
+    clause: &Clause,
+) {
+    let Some(on_candidate_excluded) = on_candidate_excluded else {
+        return;
+    };
+    if value {
+        return;
+    }
+    let Some(solvable) = variable.as_solvable(variable_map) else {
+        return;
+    };
+
+    on_candidate_excluded(solvable, exclusion_reason(clause));
+}
+
+/// Returns why a candidate was eliminated when `clause` assigned `false` to
+/// it.
+fn exclusion_reason(clause: &Clause) -> ExclusionReason {
+    match clause {
+        Clause::Constrains(..) => ExclusionReason::Constrained,
+        Clause::Forbidden(_) | Clause::Lock(..) => ExclusionReason::ForbiddenByCaller,
+        Clause::Excluded(..) => ExclusionReason::Excluded,
+        _ => ExclusionReason::DependencyConflict,
+    }
+}
+
+/// Adds the clauses that require at least `requirement.min_count()` of the
+/// `candidates` of a [`Requirement::AtLeast`] to be installed, if `parent` is
+/// installed and the conditions of the requirement are met.
+///
+/// The candidates are counted with a sequential counter, which takes a number
+/// of counter variables and clauses that grows with the product of the number
+/// of candidates and `n`, see [`Clause::AtLeastCounter`].
+#[allow(clippy::too_many_arguments)]
+fn add_at_least_clauses(
+    parent: VariableId,
+    requirement: Requirement,
+    condition_literals: &[Literal],
+    candidates: &[VariableId],
+    clauses: &mut Clauses,
+    decision_tracker: &DecisionTracker,
+    variable_map: &mut VariableMap,
+    output: &mut AddClauseOutput,
+) {
+    let n = requirement.min_count();
+    let k = candidates.len();
+    let mut add_clause =
+        |(watched_literals, conflict, kind): (Option<WatchedLiterals>, bool, Clause), variable| {
+            let has_watches = watched_literals.is_some();
+            let clause_id = clauses.alloc(watched_literals, kind);
+            if has_watches {
+                output.clauses_to_watch.push(clause_id);
+            }
+            if conflict {
+                output.conflicting_clauses.push(clause_id);
+            } else if !has_watches {
+                output.negative_assertions.push((variable, clause_id));
+            }
+        };
+
+    // Without enough candidates the parent cannot be installed while the
+    // conditions are met.
+    if n > k {
+        let clause = WatchedLiterals::at_least(
+            parent,
+            requirement,
+            condition_literals.to_vec(),
+            decision_tracker,
+        );
+        add_clause(clause, parent);
+        return;
+    }
+
+    // `counters[j]` holds the counter variable S(i, j) for the candidates up to
+    // the current one. It is `None` if the counter is trivially false, or
+    // trivially true for `j = 0`. Counters that can no longer reach `n` with the
+    // remaining candidates are not needed.
+    let mut counters: Vec<Option<VariableId>> = vec![None; n + 1];
+    for (i, &candidate) in candidates.iter().enumerate() {
+        let count = i + 1;
+        let lowest = (n + count).saturating_sub(k).max(1);
+        let mut next = vec![None; n + 1];
+        for (j, next) in next
+            .iter_mut()
+            .enumerate()
+            .take(count.min(n) + 1)
+            .skip(lowest)
+        {
+            let counter = variable_map.alloc_at_least_counter_variable(requirement, j);
+            let previous = counters[j];
+
+            // The candidate has to be installed, or the previous candidates
+            // already reach the count.
+            let literals = previous
+                .map(VariableId::positive)
+                .into_iter()
+                .chain([candidate.positive()])
+                .collect();
+            add_clause(
+                WatchedLiterals::at_least_counter(counter, literals, decision_tracker),
+                counter,
+            );
+
+            // The previous candidates have to reach one less than the count,
+            // unless that is trivially true.
+            if let Some(previous_count) = counters[j - 1] {
+                let literals = previous
+                    .map(VariableId::positive)
+                    .into_iter()
+                    .chain([previous_count.positive()])
+                    .collect();
+                add_clause(
+                    WatchedLiterals::at_least_counter(counter, literals, decision_tracker),
+                    counter,
+                );
+            }
+
+            *next = Some(counter);
+        }
+        counters = next;
+    }
+
+    let literals = condition_literals
+        .iter()
+        .copied()
+        .chain(counters[n].map(VariableId::positive))
+        .collect();
+    add_clause(
+        WatchedLiterals::at_least(parent, requirement, literals, decision_tracker),
+        parent,
+    );
+}
+
+/// Returns true if all the [`Condition::Marker`]s of the requirement hold.
+fn markers_hold(requirement: &ConditionalRequirement, provider: &impl DependencyProvider) -> bool {
+    requirement
+        .conditions
+        .iter()
+        .all(|condition| match condition {
+            Condition::Marker(marker) => provider.evaluate_marker(*marker),
+            _ => true,
+        })
+}
+
+/// Awaits `future` while polling the `pending` tasks, the results of the tasks
+/// that finish in the meantime are added to `ready`.
+///
+/// The future may be waiting for a request that was started by one of the
+/// pending tasks, so awaiting it without polling them might never finish.
+async fn await_with_pending<F: Future, T>(
+    future: F,
+    pending: &mut FuturesUnordered<impl Future<Output = T> + Unpin>,
+    ready: &mut VecDeque<T>,
+) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    futures::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(output);
+        }
+        while let Poll::Ready(Some(result)) = pending.poll_next_unpin(cx) {
+            ready.push_back(result);
+        }
+        Poll::Pending
+    })
+    .await
+}
+
 /// Adds clauses for a solvable. These clauses include requirements and
 /// constrains on other solvables.
 ///
@@ -1586,10 +5218,21 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
     requirement_to_sorted_candidates: &mut FrozenMap<
         Requirement,
         RequirementCandidateVariables,
-        ahash::RandomState,
+        DefaultHashBuilder,
     >,
+    package_depths: &mut HashMap<NameId, u32>,
     root_requirements: &[ConditionalRequirement],
     root_constraints: &[VersionSetId],
+    root_depth_constraints: &[(VersionSetId, u32)],
+    root_pins: &[SolvableId],
+    root_keep: &[SolvableId],
+    root_forbidden: &[SolvableId],
+    root_groups: &[RequirementGroup],
+    group_variables: &[VariableId],
+    root_raw_clauses: &[Vec<(SolvableId, bool)>],
+    candidate_window: Option<usize>,
+    platform: Option<StringId>,
+    strict_unknown_dependencies: bool,
 ) -> Result<AddClauseOutput, Box<dyn Any>> {
     let mut output = AddClauseOutput::default();
 
@@ -1601,9 +5244,9 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
             dependencies: Dependencies,
         },
         SortedCandidates {
-            solvable_id: SolvableOrRootId,
+            parent: VariableId,
             requirement: Requirement,
-            version_set_conditions: Vec<(SolvableId, VersionSetId)>,
+            version_set_conditions: Vec<(SolvableId, Condition)>,
             string_conditions: Vec<StringId>,
             candidates: Vec<&'i [SolvableId]>,
         },
@@ -1616,6 +5259,9 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
             name_id: NameId,
             package_candidates: &'i Candidates,
         },
+        DependenciesBatch {
+            solvable_ids: Vec<SolvableId>,
+        },
     }
 
     // Mark the initial seen solvables as seen
@@ -1630,6 +5276,7 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
 
     let mut seen = pending_solvables.iter().copied().collect::<HashSet<_>>();
     let mut pending_futures = FuturesUnordered::new();
+    let mut ready_results = VecDeque::new();
     loop {
         // Iterate over all pending solvables and request their dependencies.
         for solvable_or_root in pending_solvables.drain(..) {
@@ -1656,6 +5303,7 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     dependencies: Dependencies::Known(KnownDependencies {
                         requirements: root_requirements.to_vec(),
                         constrains: root_constraints.to_vec(),
+                        conditional_constrains: Vec::new(),
                     }),
                 }))
                 .right_future()
@@ -1664,9 +5312,15 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
             pending_futures.push(get_dependencies_fut.boxed_local());
         }
 
-        let Some(result) = pending_futures.next().await else {
-            // No more pending results
-            break;
+        let result = match ready_results.pop_front() {
+            Some(result) => result,
+            None => {
+                let Some(result) = pending_futures.next().await else {
+                    // No more pending results
+                    break;
+                };
+                result
+            }
         };
 
         match result? {
@@ -1686,9 +5340,94 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     None => variable_map.root(),
                 };
 
-                let (conditional_requirements, constrains) = match dependencies {
-                    Dependencies::Known(deps) => (deps.requirements, deps.constrains),
+                // Pinned and kept solvables must be installed whenever the root is. They
+                // are installed before any requirement on their package is processed, so
+                // immediately forbid installing other solvables of the same package
+                // alongside them.
+                if solvable_id.is_root() {
+                    for &pinned in root_pins.iter().chain(root_keep) {
+                        let pinned_var = variable_map.intern_solvable_or_string(pinned.into());
+                        let (watched_literals, kind) = WatchedLiterals::pin(pinned_var);
+                        let clause_id = clauses.alloc(watched_literals, kind);
+                        output.clauses_to_watch.push(clause_id);
+
+                        let name_id = cache
+                            .provider()
+                            .canonical_name(cache.provider().solvable_name(pinned));
+                        forbidden_clauses_added.entry(name_id).or_default().add(
+                            pinned_var,
+                            |a, b, positive| {
+                                let (watched_literals, kind) = WatchedLiterals::forbid_multiple(
+                                    a,
+                                    if positive { b.positive() } else { b.negative() },
+                                    name_id,
+                                );
+                                let clause_id = clauses.alloc(watched_literals, kind);
+                                output.clauses_to_watch.push(clause_id);
+                            },
+                            || variable_map.alloc_forbid_multiple_variable(name_id),
+                        );
+                    }
+
+                    for &forbidden in root_forbidden {
+                        let forbidden_var =
+                            variable_map.intern_solvable_or_string(forbidden.into());
+                        let (watched_literals, kind) = WatchedLiterals::forbidden(forbidden_var);
+                        let clause_id = clauses.alloc(watched_literals, kind);
+                        output.clauses_to_watch.push(clause_id);
+                    }
+
+                    for raw_clause in root_raw_clauses {
+                        let literals = raw_clause
+                            .iter()
+                            .map(|&(solvable, value)| {
+                                let variable =
+                                    variable_map.intern_solvable_or_string(solvable.into());
+                                if value {
+                                    variable.positive()
+                                } else {
+                                    variable.negative()
+                                }
+                            })
+                            .collect();
+                        let (watched_literals, kind) = WatchedLiterals::raw(literals);
+                        let clause_id = clauses.alloc(watched_literals, kind);
+                        match clauses.watched_literals[clause_id.to_usize()] {
+                            Some(_) => output.clauses_to_watch.push(clause_id),
+                            // An empty clause can only be satisfied without the root.
+                            None => output
+                                .negative_assertions
+                                .push((variable_map.root(), clause_id)),
+                        }
+                    }
+                }
+
+                let (conditional_requirements, mut constrains) = match dependencies {
+                    Dependencies::Known(deps) => {
+                        // Identical requirements and constraints result in identical clauses,
+                        // so deduplicate them before generating any clauses.
+                        let constrains = deps
+                            .active_constrains(cache.provider())
+                            .unique()
+                            .collect::<Vec<_>>();
+                        let requirements = deps
+                            .requirements
+                            .into_iter()
+                            .map(ConditionalRequirement::canonicalize)
+                            .unique()
+                            .collect::<Vec<_>>();
+                        (requirements, constrains)
+                    }
                     Dependencies::Unknown(reason) => {
+                        if strict_unknown_dependencies {
+                            return Err(Box::new(UnknownDependencies {
+                                solvable: solvable_id
+                                    .solvable()
+                                    .expect("the root always has known dependencies"),
+                                reason,
+                            }));
+                        }
+
                         // There is no information about the solvable's dependencies, so we add
                         // an exclusion clause for it
 
@@ -1708,14 +5447,78 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     }
                 };
 
+                // The requirements of the root and of solvables are active when their
+                // variable is true. The requirements of a requirement group are active when
+                // the variable of the group is true instead.
+                let mut conditional_requirements = conditional_requirements
+                    .into_iter()
+                    .map(|requirement| (variable, requirement))
+                    .collect::<Vec<_>>();
+                if solvable_id.is_root() {
+                    for (group, &group_variable) in root_groups.iter().zip(group_variables) {
+                        conditional_requirements.extend(
+                            group
+                                .requirements
+                                .iter()
+                                .cloned()
+                                .map(ConditionalRequirement::canonicalize)
+                                .unique()
+                                .map(|requirement| (group_variable, requirement)),
+                        );
+                    }
+                }
+
+                // Constrain the required packages that are deeper than the depth of a
+                // depth constraint, unless they are required closer to the roots.
+                let depth = match solvable_id.solvable() {
+                    Some(solvable) => package_depths
+                        .get(
+                            &cache
+                                .provider()
+                                .canonical_name(cache.provider().solvable_name(solvable)),
+                        )
+                        .copied(),
+                    None => Some(0),
+                };
+                if let Some(depth) = depth {
+                    for version_set_id in
+                        conditional_requirements
+                            .iter()
+                            .flat_map(|(_, requirement)| {
+                                requirement.requirement_version_sets(cache.provider())
+                            })
+                    {
+                        let dependency_name = cache
+                            .provider()
+                            .canonical_name(cache.provider().version_set_name(version_set_id));
+                        let dependency_depth = *package_depths
+                            .entry(dependency_name)
+                            .and_modify(|known| *known = (*known).min(depth + 1))
+                            .or_insert(depth + 1);
+                        for &(constraint, max_depth) in root_depth_constraints {
+                            if dependency_depth > max_depth
+                                && cache
+                                    .provider()
+                                    .canonical_name(cache.provider().version_set_name(constraint))
+                                    == dependency_name
+                                && !constrains.contains(&constraint)
+                            {
+                                constrains.push(constraint);
+                            }
+                        }
+                    }
+                }
+
                 for (version_set_id, conditions) in conditional_requirements
                     .iter()
-                    .flat_map(|conditional_requirement| {
+                    .flat_map(|(_, conditional_requirement)| {
                         conditional_requirement.version_sets_with_condition(cache.provider())
                     })
                     .chain(constrains.iter().map(|&vs| (vs, Vec::new())))
                 {
-                    let dependency_name = cache.provider().version_set_name(version_set_id);
+                    let dependency_name = cache
+                        .provider()
+                        .canonical_name(cache.provider().version_set_name(version_set_id));
                     if clauses_added_for_package.insert(dependency_name) {
                         if !conditions.is_empty() {
                             tracing::trace!(
@@ -1742,12 +5545,13 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                             .boxed_local(),
                         );
 
-                        for condition in conditions {
-                            if let Condition::Extra(_) = condition {
-                                continue;
-                            }
-                            let condition_name =
-                                cache.provider().version_set_name(condition.into());
+                        for condition_version_set in conditions
+                            .iter()
+                            .flat_map(|condition| condition.version_sets(cache.provider()))
+                        {
+                            let condition_name = cache.provider().canonical_name(
+                                cache.provider().version_set_name(condition_version_set),
+                            );
                             if clauses_added_for_package.insert(condition_name) {
                                 pending_futures.push(
                                     async move {
@@ -1765,19 +5569,31 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     }
                 }
 
-                for conditional_requirement in conditional_requirements {
+                for (parent, conditional_requirement) in conditional_requirements {
+                    // Requirements with a marker that does not hold are dropped.
+                    if !markers_hold(&conditional_requirement, cache.provider()) {
+                        continue;
+                    }
+
+                    // Requiring zero solvables is always satisfied.
+                    if conditional_requirement.requirement.min_count() == 0 {
+                        continue;
+                    }
+
                     // Find all the solvable that match for the given version set
                     let version_sets =
                         conditional_requirement.requirement_version_sets(cache.provider());
-                    let candidates =
+                    let candidates = await_with_pending(
                         futures::future::try_join_all(version_sets.map(|version_set| {
                             cache.get_or_cache_sorted_candidates_for_version_set(version_set)
-                        }))
-                        .await?;
+                        })),
+                        &mut pending_futures,
+                        &mut ready_results,
+                    )
+                    .await?;
 
                     // Collect all non-Extra conditions and their candidates
-                    let conditions: Vec<_> =
-                        conditional_requirement.conditions.to_vec();
+                    let conditions: Vec<_> = conditional_requirement.conditions.to_vec();
                     let mut string_conditions = Vec::new();
                     let mut version_set_conditions = Vec::new();
                     let mut condition_candidates_futures = Vec::new();
@@ -1788,17 +5604,41 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                             Condition::Extra(extra_id) => {
                                 string_conditions.push(extra_id);
                             }
-                            Condition::VersionSetId(version_set_id) => {
-                                version_set_conditions.push(version_set_id);
-                                condition_candidates_futures
-                                    .push(cache.get_or_cache_matching_candidates(version_set_id));
+                            // The markers are known to hold at this point.
+                            Condition::Marker(_) => {}
+                            Condition::VersionSetId(_) | Condition::Requirement(_) => {
+                                // The condition is met by a solvable matching any of its
+                                // version sets.
+                                version_set_conditions.push(condition);
+                                let version_sets =
+                                    condition.version_sets(cache.provider()).collect_vec();
+                                condition_candidates_futures.push(async move {
+                                    let matching_candidates = futures::future::try_join_all(
+                                        version_sets.into_iter().map(|version_set| {
+                                            cache.get_or_cache_matching_candidates(version_set)
+                                        }),
+                                    )
+                                    .await?;
+                                    Ok::<_, Box<dyn Any>>(
+                                        matching_candidates
+                                            .into_iter()
+                                            .flatten()
+                                            .copied()
+                                            .unique()
+                                            .collect_vec(),
+                                    )
+                                });
                             }
                         }
                     }
 
                     // Get all condition candidates in parallel
-                    let condition_candidates =
-                        futures::future::try_join_all(condition_candidates_futures).await?;
+                    let condition_candidates = await_with_pending(
+                        futures::future::try_join_all(condition_candidates_futures),
+                        &mut pending_futures,
+                        &mut ready_results,
+                    )
+                    .await?;
 
                     // Create cartesian product of all condition candidates
                     let condition_combinations = condition_candidates
@@ -1817,7 +5657,7 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                             pending_futures.push(
                                 async move {
                                     Ok(TaskResult::SortedCandidates {
-                                        solvable_id,
+                                        parent,
                                         requirement,
                                         version_set_conditions: condition_combination,
                                         string_conditions,
@@ -1832,7 +5672,7 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                         pending_futures.push(
                             async move {
                                 Ok(TaskResult::SortedCandidates {
-                                    solvable_id,
+                                    parent,
                                     requirement: conditional_requirement.requirement,
                                     version_set_conditions: Vec::new(),
                                     string_conditions: Vec::new(),
@@ -1861,6 +5701,10 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     )
                 }
             }
+            TaskResult::DependenciesBatch { solvable_ids } => {
+                // The dependencies are cached now, so process them like any other solvable.
+                pending_solvables.extend(solvable_ids.into_iter().map(SolvableOrRootId::from));
+            }
             TaskResult::Candidates {
                 name_id,
                 package_candidates,
@@ -1875,8 +5719,19 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
 
                 let candidates = &package_candidates.candidates;
 
-                // If there is a locked solvable, forbid other solvables.
-                if let Some(locked_solvable_id) = package_candidates.locked {
+                // If there is a pinned or locked solvable, forbid other solvables. A pin
+                // takes precedence over the lock provided by the dependency provider.
+                let locked = root_pins
+                    .iter()
+                    .copied()
+                    .find(|&pinned| {
+                        cache
+                            .provider()
+                            .canonical_name(cache.provider().solvable_name(pinned))
+                            == name_id
+                    })
+                    .or(package_candidates.locked);
+                if let Some(locked_solvable_id) = locked {
                     let locked_solvable_var =
                         variable_map.intern_solvable_or_string(locked_solvable_id.into());
                     for &other_candidate in candidates {
@@ -1905,9 +5760,26 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     // Conflicts should be impossible here
                     debug_assert!(decision_tracker.assigned_value(solvable_var) != Some(true));
                 }
+
+                // Likewise for solvables that are not available for the platform.
+                let platform_excluded = platform.into_iter().flat_map(|platform| {
+                    candidates.iter().filter_map(move |&solvable| {
+                        cache
+                            .provider()
+                            .excluded_on_platform(solvable, platform)
+                            .map(|reason| (solvable, reason))
+                    })
+                });
+                for (solvable, reason) in platform_excluded {
+                    let solvable_var = variable_map.intern_solvable_or_string(solvable.into());
+                    let (watched_literals, kind) = WatchedLiterals::exclude(solvable_var, reason);
+                    let clause_id = clauses.alloc(watched_literals, kind);
+                    output.negative_assertions.push((solvable_var, clause_id));
+                    debug_assert!(decision_tracker.assigned_value(solvable_var) != Some(true));
+                }
             }
             TaskResult::SortedCandidates {
-                solvable_id,
+                parent: variable,
                 requirement,
                 version_set_conditions,
                 string_conditions,
@@ -1918,10 +5790,19 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     requirement.display(cache.provider()),
                 );
 
-                // Allocate a variable for the solvable
-                let variable = match solvable_id.solvable() {
-                    Some(solvable_id) => variable_map.intern_solvable_or_string(solvable_id.into()),
-                    None => variable_map.root(),
+                // Only consider the best candidates of every version set if the number of
+                // candidates is limited.
+                let candidates = match candidate_window {
+                    Some(window) => candidates
+                        .into_iter()
+                        .map(|candidates| {
+                            if candidates.len() > window {
+                                output.candidates_truncated = true;
+                            }
+                            &candidates[..candidates.len().min(window)]
+                        })
+                        .collect(),
+                    None => candidates,
                 };
 
                 // Intern all the solvables of the candidates.
@@ -1940,6 +5821,7 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
 
                 // Queue requesting the dependencies of the candidates as well if they are
                 // cheaply available from the dependency provider.
+                let mut available_candidates = Vec::new();
                 for (candidate, candidate_var) in candidates
                     .iter()
                     .zip(version_set_variables)
@@ -1956,13 +5838,15 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     if cache.are_dependencies_available_for(candidate)
                         && clauses_added_for_solvable.insert(candidate.into())
                     {
-                        pending_solvables.push(candidate.into());
+                        available_candidates.push(candidate);
                     }
 
                     // Add forbid constraints for this solvable on all other
                     // solvables that have been visited already for the same
                     // version set name.
-                    let name_id = cache.provider().solvable_name(candidate);
+                    let name_id = cache
+                        .provider()
+                        .canonical_name(cache.provider().solvable_name(candidate));
                     let other_solvables = forbidden_clauses_added.entry(name_id).or_default();
                     other_solvables.add(
                         candidate_var,
@@ -1980,26 +5864,62 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                     );
                 }
 
+                // Request the dependencies of all the available candidates at once.
+                if !available_candidates.is_empty() {
+                    pending_futures.push(
+                        async move {
+                            cache
+                                .get_or_cache_dependencies_batch(&available_candidates)
+                                .await?;
+                            Ok(TaskResult::DependenciesBatch {
+                                solvable_ids: available_candidates,
+                            })
+                        }
+                        .boxed_local(),
+                    );
+                }
+
+                let mut condition_literals = Vec::new();
                 if !version_set_conditions.is_empty() {
                     let mut condition_variables = Vec::new();
-                    for (condition, condition_version_set_id) in version_set_conditions {
-                        let condition_variable = variable_map
-                            .intern_solvable_or_string(SolvableOrStringId::Solvable(condition));
-                        condition_variables.push((
-                            condition_variable,
-                            Condition::VersionSetId(condition_version_set_id),
-                        ));
+                    for (condition_candidate, condition) in version_set_conditions {
+                        let condition_variable = variable_map.intern_solvable_or_string(
+                            SolvableOrStringId::Solvable(condition_candidate),
+                        );
+                        condition_variables.push((condition_variable, condition));
                     }
 
                     for string_condition in string_conditions {
-                        let condition_variable = variable_map.intern_solvable_or_string(
-                            SolvableOrStringId::String(string_condition),
-                        );
+                        // The extras of a package can only be active if the package is
+                        // installed, extras of the root are free to be chosen.
+                        let condition_variable = match variable.as_solvable(variable_map) {
+                            Some(solvable_id) => {
+                                let variable_count = variable_map.count();
+                                let condition_variable = variable_map.intern_solvable_or_string(
+                                    SolvableOrStringId::Extra(solvable_id, string_condition),
+                                );
+                                if variable_map.count() > variable_count {
+                                    let (watched_literals, kind) =
+                                        WatchedLiterals::extra(variable, condition_variable);
+                                    let clause_id = clauses.alloc(watched_literals, kind);
+                                    output.clauses_to_watch.push(clause_id);
+                                }
+                                condition_variable
+                            }
+                            None => variable_map.intern_solvable_or_string(
+                                SolvableOrStringId::String(string_condition),
+                            ),
+                        };
                         condition_variables
                             .push((condition_variable, Condition::Extra(string_condition)));
                     }
 
                     if !condition_variables.is_empty() {
+                        condition_literals = condition_variables
+                            .iter()
+                            .map(|&(condition_variable, _)| condition_variable.negative())
+                            .collect();
+
                         // Add a condition clause
                         let (watched_literals, conflict, kind) = WatchedLiterals::conditional(
                             variable,
@@ -2063,6 +5983,72 @@ async fn add_clauses_for_solvables<D: DependencyProvider>(
                         output.negative_assertions.push((variable, clause_id));
                     }
                 }
+
+                if requirement.min_count() > 1 {
+                    let candidate_variables = version_set_variables
+                        .iter()
+                        .flatten()
+                        .copied()
+                        .unique()
+                        .collect_vec();
+                    add_at_least_clauses(
+                        variable,
+                        requirement,
+                        &condition_literals,
+                        &candidate_variables,
+                        clauses,
+                        decision_tracker,
+                        variable_map,
+                        &mut output,
+                    );
+                }
+
+                // Forbid selecting two of the candidates of an "exactly one of"
+                // requirement. Candidates of the same package already exclude each other.
+                if let Requirement::ExactlyOne(_) = requirement {
+                    let candidate_variables = candidates
+                        .iter()
+                        .zip(version_set_variables)
+                        .flat_map(|(&candidates, variables)| {
+                            candidates.iter().copied().zip(variables.iter().copied())
+                        })
+                        .unique()
+                        .map(|(candidate, candidate_var)| {
+                            let name = cache
+                                .provider()
+                                .canonical_name(cache.provider().solvable_name(candidate));
+                            (name, candidate_var)
+                        })
+                        .collect_vec();
+                    for ((first_name, first), (second_name, second)) in
+                        candidate_variables.into_iter().tuple_combinations()
+                    {
+                        if first_name == second_name {
+                            continue;
+                        }
+                        let literals = condition_literals
+                            .iter()
+                            .copied()
+                            .chain([first.negative(), second.negative()])
+                            .collect();
+                        let (watched_literals, conflict, kind) = WatchedLiterals::at_most_one(
+                            variable,
+                            requirement,
+                            literals,
+                            decision_tracker,
+                        );
+                        let has_watches = watched_literals.is_some();
+                        let clause_id = clauses.alloc(watched_literals, kind);
+
+                        if has_watches {
+                            output.clauses_to_watch.push(clause_id);
+                        }
+
+                        if conflict {
+                            output.conflicting_clauses.push(clause_id);
+                        }
+                    }
+                }
             }
             TaskResult::NonMatchingCandidates {
                 solvable_id,
@@ -0,0 +1,125 @@
+/// Statistics about the work performed by a [`crate::Solver`] during the last
+/// call to [`crate::Solver::solve`].
+///
+/// Statistics are purely informational and are useful to tune the solver or
+/// to diagnose slow solves.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// The number of variables allocated by the solver.
+    pub num_variables: usize,
+
+    /// The number of clauses generated from the problem, excluding learnt
+    /// clauses.
+    pub num_clauses: usize,
+
+    /// The number of clauses learnt during conflict analysis.
+    pub num_learnt_clauses: usize,
+
+    /// The total number of literals of the learnt clauses, see
+    /// [`SolverStats::average_learnt_clause_length`].
+    pub num_learnt_literals: usize,
+
+    /// The number of clauses that were kept from the previous solve by
+    /// [`crate::Solver::solve_current`] or imported with
+    /// [`crate::Solver::import_learnt_clauses`], and that took part in
+    /// propagation or conflict analysis. This tells whether keeping or
+    /// importing them paid off. It is zero for all other solves.
+    pub warm_start_clauses_used: usize,
+
+    /// The number of conflicts encountered during the search.
+    pub num_conflicts: usize,
+
+    /// The number of decisions made by the solver.
+    pub num_decisions: usize,
+
+    /// The distribution of the literal block distance (LBD) of the learnt
+    /// clauses: the value at index `i` is the number of learnt clauses with an
+    /// LBD of `i`. The LBD of a clause is the number of distinct decision
+    /// levels of its literals, clauses with a low LBD are generally more
+    /// useful to keep around.
+    pub lbd_histogram: Vec<u32>,
+
+    /// Statistics about the watched literals of the clauses.
+    pub watch: WatchStats,
+}
+
+impl SolverStats {
+    /// The average number of watches per clause, including the clauses that
+    /// are not watched because they consist of a single literal.
+    pub fn average_watches_per_clause(&self) -> f64 {
+        let num_clauses = self.num_clauses + self.num_learnt_clauses;
+        if num_clauses == 0 {
+            0.0
+        } else {
+            self.watch.num_watches as f64 / num_clauses as f64
+        }
+    }
+
+    /// The average number of literals per learnt clause.
+    pub fn average_learnt_clause_length(&self) -> f64 {
+        if self.num_learnt_clauses == 0 {
+            0.0
+        } else {
+            self.num_learnt_literals as f64 / self.num_learnt_clauses as f64
+        }
+    }
+}
+
+/// Statistics about the two-watched-literal scheme that the solver uses to
+/// propagate decisions, see [`SolverStats::watch`].
+///
+/// Every clause with more than one literal watches two of its literals. Each
+/// time a literal becomes false, the solver traverses the clauses that are
+/// watching it, so a high number of traversals relative to the number of
+/// decisions points at clauses that are expensive to keep watched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchStats {
+    /// The number of watches of the clauses at the end of the solve.
+    pub num_watches: usize,
+
+    /// The number of times a clause was visited in the watch list of a literal
+    /// that became false.
+    pub num_watch_traversals: usize,
+
+    /// The clauses that propagated the most literals, together with the number
+    /// of literals they propagated, sorted from most to least. This is only
+    /// collected when the `watch-stats` feature is enabled and is empty
+    /// otherwise.
+    pub most_propagating_clauses: Vec<(String, usize)>,
+}
+
+impl WatchStats {
+    /// The maximum number of clauses in
+    /// [`WatchStats::most_propagating_clauses`].
+    pub const MAX_PROPAGATING_CLAUSES: usize = 10;
+}
+
+/// A cheap estimate of how hard a problem is to solve, see
+/// [`crate::Solver::difficulty_estimate`].
+///
+/// The estimate is derived from the clauses of the problem without searching
+/// for a solution. It is a hint for scheduling solves, not a guarantee.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DifficultyScore {
+    /// The number of variables of the problem.
+    pub num_variables: usize,
+
+    /// The number of clauses of the problem.
+    pub num_clauses: usize,
+
+    /// The average number of literals per clause.
+    pub average_clause_length: f64,
+
+    /// The number of clauses that forbid solvables from being installed
+    /// together (constraints, multiple versions of the same package, locks
+    /// and exclusions) per variable.
+    pub constraint_density: f64,
+}
+
+impl DifficultyScore {
+    /// Combines the estimate into a single number, a higher score indicates
+    /// a harder problem.
+    pub fn score(&self) -> f64 {
+        self.num_variables as f64 * self.average_clause_length * (1.0 + self.constraint_density)
+    }
+}
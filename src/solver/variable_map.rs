@@ -1,19 +1,19 @@
 use std::{collections::hash_map::Entry, fmt::Display};
 
-use ahash::HashMap;
-
 use crate::{
     internal::{
         arena::ArenaId,
+        hash::HashMap,
         id::{SolvableOrRootId, VariableId},
     },
-    Interner, NameId, SolvableId, StringId,
+    Interner, NameId, Requirement, SolvableId, StringId,
 };
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum SolvableOrStringId {
     Solvable(SolvableId),
     String(StringId),
+    Extra(SolvableId, StringId),
 }
 
 impl From<SolvableId> for SolvableOrStringId {
@@ -59,6 +59,19 @@ pub enum VariableOrigin {
 
     /// The variable represents a specific string.
     String(StringId),
+
+    /// The variable activates the requirements of the requirement group with
+    /// the given index, see [`crate::Problem::requirement_groups`].
+    RequirementGroup(usize),
+
+    /// A variable that counts the installed candidates of an at least
+    /// requirement. It can only be true if at least the given number of
+    /// candidates is installed.
+    AtLeastCounter(Requirement, usize),
+
+    /// The variable represents an extra of a specific solvable, which can
+    /// only be active if the solvable is installed.
+    Extra(SolvableId, StringId),
 }
 
 impl Default for VariableMap {
@@ -99,6 +112,10 @@ impl VariableMap {
                         self.origins
                             .insert(variable_id, VariableOrigin::String(string_id));
                     }
+                    SolvableOrStringId::Extra(solvable_id, string_id) => {
+                        self.origins
+                            .insert(variable_id, VariableOrigin::Extra(solvable_id, string_id));
+                    }
                 }
                 variable_id
             }
@@ -113,6 +130,17 @@ impl VariableMap {
         }
     }
 
+    /// Returns all the variables that represent a solvable, together with the
+    /// solvable they represent.
+    pub fn solvables(&self) -> impl Iterator<Item = (VariableId, SolvableId)> + '_ {
+        self.solvable_or_string_id_to_variable
+            .iter()
+            .filter_map(|(&id, &variable_id)| match id {
+                SolvableOrStringId::Solvable(solvable_id) => Some((variable_id, solvable_id)),
+                SolvableOrStringId::String(_) | SolvableOrStringId::Extra(..) => None,
+            })
+    }
+
     /// Allocate a variable that helps encode an at most one constraint.
     pub fn alloc_forbid_multiple_variable(&mut self, name: NameId) -> VariableId {
         let id = self.next_id;
@@ -123,12 +151,46 @@ impl VariableMap {
         variable_id
     }
 
+    /// Allocate a variable that activates the requirements of a requirement
+    /// group.
+    pub fn alloc_requirement_group_variable(&mut self, group: usize) -> VariableId {
+        let id = self.next_id;
+        self.next_id += 1;
+        let variable_id = VariableId::from_usize(id);
+        self.origins
+            .insert(variable_id, VariableOrigin::RequirementGroup(group));
+        variable_id
+    }
+
+    /// Allocate a variable that counts the installed candidates of an at
+    /// least requirement.
+    pub fn alloc_at_least_counter_variable(
+        &mut self,
+        requirement: Requirement,
+        count: usize,
+    ) -> VariableId {
+        let id = self.next_id;
+        self.next_id += 1;
+        let variable_id = VariableId::from_usize(id);
+        self.origins.insert(
+            variable_id,
+            VariableOrigin::AtLeastCounter(requirement, count),
+        );
+        variable_id
+    }
+
     /// Returns the origin of a variable. The origin describes the semantics of
     /// a variable.
     pub fn origin(&self, variable_id: VariableId) -> VariableOrigin {
         self.origins[&variable_id].clone()
     }
 
+    /// Returns the number of variables that have been allocated, including
+    /// the root variable.
+    pub fn count(&self) -> usize {
+        self.next_id
+    }
+
     /// Returns the root variable
     pub fn root(&self) -> VariableId {
         VariableId::root()
@@ -179,6 +241,22 @@ impl<'i, I: Interner> Display for VariableDisplay<'i, I> {
             VariableOrigin::String(string_id) => {
                 write!(f, "{}", self.interner.display_string(string_id))
             }
+            VariableOrigin::RequirementGroup(group) => write!(f, "requirement-group({group})"),
+            VariableOrigin::AtLeastCounter(requirement, count) => {
+                write!(
+                    f,
+                    "at-least-counter({}, {count})",
+                    requirement.display(self.interner)
+                )
+            }
+            VariableOrigin::Extra(solvable_id, string_id) => {
+                write!(
+                    f,
+                    "{}[{}]",
+                    self.interner.display_solvable(solvable_id),
+                    self.interner.display_string(string_id)
+                )
+            }
         }
     }
 }
@@ -194,7 +272,11 @@ impl VariableOrigin {
     pub fn as_solvable_or_root(&self) -> Option<SolvableOrRootId> {
         match self {
             VariableOrigin::Solvable(solvable_id) => Some((*solvable_id).into()),
-            VariableOrigin::Root => Some(SolvableOrRootId::root()),
+            // The requirements of a requirement group are requirements of the root that
+            // are only active when the group is.
+            VariableOrigin::Root | VariableOrigin::RequirementGroup(_) => {
+                Some(SolvableOrRootId::root())
+            }
             _ => None,
         }
     }
@@ -0,0 +1,25 @@
+/// A clause that was learnt while solving, in a form that does not depend on
+/// the ids of the solvables, see [`crate::Solver::export_learnt_clauses`].
+///
+/// The clause is satisfied if at least one of its literals is true.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortableClause {
+    /// The literals of the clause.
+    pub literals: Vec<PortableLiteral>,
+}
+
+/// A literal of a [`PortableClause`], which identifies its solvable by hashes
+/// of how the solvable and its package are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortableLiteral {
+    /// The hash of the name of the package, as displayed by
+    /// [`crate::Interner::display_name`].
+    pub name: u64,
+    /// The hash of the name of the package together with the solvable, as
+    /// displayed by [`crate::Interner::display_solvable`].
+    pub solvable: u64,
+    /// Whether the literal is true if the solvable is not selected.
+    pub negate: bool,
+}
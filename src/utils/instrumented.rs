@@ -0,0 +1,334 @@
+use std::{any::Any, cell::RefCell, cmp::Ordering, collections::HashMap, fmt::Display, hash::Hash};
+
+use crate::{
+    Candidates, Condition, Dependencies, DependencyProvider, Interner, NameId, PreviousSort,
+    Requirement, SolvableId, SolverCache, StringId, VersionBound, VersionSetBounds, VersionSetId,
+    VersionSetUnionId,
+};
+
+/// The number of times the ids of each kind were passed to the [`Interner`]
+/// methods of an [`InstrumentedInterner`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AccessCounts {
+    /// The number of accesses per package name.
+    pub names: HashMap<NameId, usize>,
+    /// The number of accesses per solvable.
+    pub solvables: HashMap<SolvableId, usize>,
+    /// The number of accesses per version set.
+    pub version_sets: HashMap<VersionSetId, usize>,
+    /// The number of accesses per version set union.
+    pub version_set_unions: HashMap<VersionSetUnionId, usize>,
+    /// The number of accesses per string.
+    pub strings: HashMap<StringId, usize>,
+}
+
+impl AccessCounts {
+    /// Returns the package names ordered by descending number of accesses.
+    /// Names with the same number of accesses are ordered by id.
+    pub fn names_by_frequency(&self) -> Vec<(NameId, usize)> {
+        by_frequency(&self.names)
+    }
+
+    /// Returns the version sets ordered by descending number of accesses.
+    /// Version sets with the same number of accesses are ordered by id.
+    pub fn version_sets_by_frequency(&self) -> Vec<(VersionSetId, usize)> {
+        by_frequency(&self.version_sets)
+    }
+
+    fn name(&mut self, name: NameId) {
+        *self.names.entry(name).or_default() += 1;
+    }
+
+    fn solvable(&mut self, solvable: SolvableId) {
+        *self.solvables.entry(solvable).or_default() += 1;
+    }
+
+    fn version_set(&mut self, version_set: VersionSetId) {
+        *self.version_sets.entry(version_set).or_default() += 1;
+    }
+
+    fn version_set_union(&mut self, version_set_union: VersionSetUnionId) {
+        *self
+            .version_set_unions
+            .entry(version_set_union)
+            .or_default() += 1;
+    }
+
+    fn string(&mut self, string: StringId) {
+        *self.strings.entry(string).or_default() += 1;
+    }
+
+    fn requirement(&mut self, requirement: Requirement) {
+        match requirement {
+            Requirement::Single(version_set) => self.version_set(version_set),
+            Requirement::Union(version_set_union)
+            | Requirement::ExactlyOne(version_set_union)
+            | Requirement::AtLeast {
+                version_sets: version_set_union,
+                ..
+            } => self.version_set_union(version_set_union),
+        }
+    }
+
+    fn condition(&mut self, condition: Condition) {
+        match condition {
+            Condition::VersionSetId(version_set) => self.version_set(version_set),
+            Condition::Extra(string) | Condition::Marker(string) => self.string(string),
+            Condition::Requirement(requirement) => self.requirement(requirement),
+        }
+    }
+}
+
+fn by_frequency<T: Copy + Ord + Hash>(counts: &HashMap<T, usize>) -> Vec<(T, usize)> {
+    let mut counts = counts
+        .iter()
+        .map(|(&id, &count)| (id, count))
+        .collect::<Vec<_>>();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    counts
+}
+
+/// A [`DependencyProvider`] that wraps another one and counts how often the
+/// solver passes every id to its [`Interner`] methods, for instance to learn
+/// which package names and version sets are accessed most and should be
+/// cheap to look up.
+///
+/// All methods are delegated to the wrapped provider. Its
+/// [`DependencyProvider::sort_candidates`] is passed a [`SolverCache`] of its
+/// own, so candidates and dependencies that it looks up through the cache are
+/// requested from it again.
+pub struct InstrumentedInterner<I: DependencyProvider> {
+    cache: SolverCache<I>,
+    counts: RefCell<AccessCounts>,
+}
+
+impl<I: DependencyProvider> InstrumentedInterner<I> {
+    /// Wraps the given provider.
+    pub fn new(inner: I) -> Self {
+        Self {
+            cache: SolverCache::new(inner),
+            counts: RefCell::default(),
+        }
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &I {
+        self.cache.provider()
+    }
+
+    /// Returns the number of accesses per id so far.
+    pub fn access_counts(&self) -> AccessCounts {
+        self.counts.borrow().clone()
+    }
+
+    /// Resets the number of accesses per id to zero.
+    pub fn reset_access_counts(&self) {
+        *self.counts.borrow_mut() = AccessCounts::default();
+    }
+
+    fn count(&self, count: impl FnOnce(&mut AccessCounts)) {
+        count(&mut self.counts.borrow_mut());
+    }
+}
+
+impl<I: DependencyProvider> Interner for InstrumentedInterner<I> {
+    fn display_solvable(&self, solvable: SolvableId) -> impl Display + '_ {
+        self.count(|counts| counts.solvable(solvable));
+        self.inner().display_solvable(solvable)
+    }
+
+    fn display_solvable_name(&self, solvable: SolvableId) -> impl Display + '_ {
+        self.count(|counts| counts.solvable(solvable));
+        self.inner().display_solvable_name(solvable)
+    }
+
+    fn display_merged_solvables(&self, solvables: &[SolvableId]) -> impl Display + '_ {
+        self.count(|counts| solvables.iter().for_each(|&s| counts.solvable(s)));
+        self.inner().display_merged_solvables(solvables)
+    }
+
+    fn display_name(&self, name: NameId) -> impl Display + '_ {
+        self.count(|counts| counts.name(name));
+        self.inner().display_name(name)
+    }
+
+    fn display_name_short(&self, name: NameId) -> impl Display + '_ {
+        self.count(|counts| counts.name(name));
+        self.inner().display_name_short(name)
+    }
+
+    fn display_condition(&self, condition: Condition) -> impl Display + '_ {
+        self.count(|counts| counts.condition(condition));
+        self.inner().display_condition(condition)
+    }
+
+    fn display_version_set(&self, version_set: VersionSetId) -> impl Display + '_ {
+        self.count(|counts| counts.version_set(version_set));
+        self.inner().display_version_set(version_set)
+    }
+
+    fn display_string(&self, string_id: StringId) -> impl Display + '_ {
+        self.count(|counts| counts.string(string_id));
+        self.inner().display_string(string_id)
+    }
+
+    fn version_set_name(&self, version_set: VersionSetId) -> NameId {
+        self.count(|counts| counts.version_set(version_set));
+        self.inner().version_set_name(version_set)
+    }
+
+    fn solvable_name(&self, solvable: SolvableId) -> NameId {
+        self.count(|counts| counts.solvable(solvable));
+        self.inner().solvable_name(solvable)
+    }
+
+    fn canonical_name(&self, name: NameId) -> NameId {
+        self.count(|counts| counts.name(name));
+        self.inner().canonical_name(name)
+    }
+
+    fn version_sets_in_union(
+        &self,
+        version_set_union: VersionSetUnionId,
+    ) -> impl Iterator<Item = VersionSetId> {
+        self.count(|counts| counts.version_set_union(version_set_union));
+        self.inner().version_sets_in_union(version_set_union)
+    }
+
+    fn solvable_priority(&self, solvable: SolvableId) -> i64 {
+        self.count(|counts| counts.solvable(solvable));
+        self.inner().solvable_priority(solvable)
+    }
+
+    fn solvable_channel_priority(&self, solvable: SolvableId) -> u32 {
+        self.count(|counts| counts.solvable(solvable));
+        self.inner().solvable_channel_priority(solvable)
+    }
+
+    fn contains_string(&self, string_id: StringId) -> bool {
+        self.count(|counts| counts.string(string_id));
+        self.inner().contains_string(string_id)
+    }
+
+    fn contains_version_set(&self, version_set: VersionSetId) -> bool {
+        self.count(|counts| counts.version_set(version_set));
+        self.inner().contains_version_set(version_set)
+    }
+
+    fn contains_version_set_union(&self, version_set_union: VersionSetUnionId) -> bool {
+        self.count(|counts| counts.version_set_union(version_set_union));
+        self.inner().contains_version_set_union(version_set_union)
+    }
+
+    fn version_set_subset(&self, subset: VersionSetId, superset: VersionSetId) -> Option<bool> {
+        self.count(|counts| {
+            counts.version_set(subset);
+            counts.version_set(superset);
+        });
+        self.inner().version_set_subset(subset, superset)
+    }
+
+    fn matches_with_metadata(
+        &self,
+        solvable: SolvableId,
+        version_set: VersionSetId,
+    ) -> Option<bool> {
+        self.count(|counts| {
+            counts.solvable(solvable);
+            counts.version_set(version_set);
+        });
+        self.inner().matches_with_metadata(solvable, version_set)
+    }
+
+    fn provided_version_sets(&self, solvable: SolvableId) -> impl Iterator<Item = VersionSetId> {
+        self.count(|counts| counts.solvable(solvable));
+        self.inner().provided_version_sets(solvable)
+    }
+
+    fn version_sets_for_name(&self, name: NameId) -> impl Iterator<Item = VersionSetId> {
+        self.count(|counts| counts.name(name));
+        self.inner().version_sets_for_name(name)
+    }
+
+    fn version_set_bounds(&self, version_set: VersionSetId) -> Option<VersionSetBounds> {
+        self.count(|counts| counts.version_set(version_set));
+        self.inner().version_set_bounds(version_set)
+    }
+
+    fn compare_solvables(&self, a: SolvableId, b: SolvableId) -> Option<Ordering> {
+        self.count(|counts| {
+            counts.solvable(a);
+            counts.solvable(b);
+        });
+        self.inner().compare_solvables(a, b)
+    }
+
+    fn compare_to_bound(&self, solvable: SolvableId, bound: VersionBound) -> Option<Ordering> {
+        self.count(|counts| counts.solvable(solvable));
+        self.inner().compare_to_bound(solvable, bound)
+    }
+}
+
+impl<I: DependencyProvider> DependencyProvider for InstrumentedInterner<I> {
+    async fn filter_candidates(
+        &self,
+        candidates: &[SolvableId],
+        version_set: VersionSetId,
+        inverse: bool,
+    ) -> Vec<SolvableId> {
+        self.inner()
+            .filter_candidates(candidates, version_set, inverse)
+            .await
+    }
+
+    async fn get_candidates(&self, name: NameId) -> Option<Candidates> {
+        self.inner().get_candidates(name).await
+    }
+
+    async fn sort_candidates(&self, _solver: &SolverCache<Self>, solvables: &mut [SolvableId]) {
+        self.inner().sort_candidates(&self.cache, solvables).await
+    }
+
+    async fn sort_candidates_with_previous(
+        &self,
+        _solver: &SolverCache<Self>,
+        solvables: &mut [SolvableId],
+        previous: Option<&PreviousSort>,
+    ) {
+        self.inner()
+            .sort_candidates_with_previous(&self.cache, solvables, previous)
+            .await
+    }
+
+    fn score_candidate(&self, solvable: SolvableId) -> f64 {
+        self.inner().score_candidate(solvable)
+    }
+
+    async fn get_dependencies(&self, solvable: SolvableId) -> Dependencies {
+        self.inner().get_dependencies(solvable).await
+    }
+
+    async fn get_dependencies_batch(&self, solvables: &[SolvableId]) -> Vec<Dependencies> {
+        self.inner().get_dependencies_batch(solvables).await
+    }
+
+    fn should_cancel_with_value(&self) -> Option<Box<dyn Any>> {
+        self.inner().should_cancel_with_value()
+    }
+
+    fn known_names(&self) -> impl Iterator<Item = NameId> + '_ {
+        self.inner().known_names()
+    }
+
+    async fn get_more_candidates(&self, name: NameId) -> Option<Candidates> {
+        self.inner().get_more_candidates(name).await
+    }
+
+    fn excluded_on_platform(&self, solvable: SolvableId, platform: StringId) -> Option<StringId> {
+        self.inner().excluded_on_platform(solvable, platform)
+    }
+
+    fn evaluate_marker(&self, marker: StringId) -> bool {
+        self.inner().evaluate_marker(marker)
+    }
+}
@@ -1,6 +1,179 @@
 //! Defines several helper functions and structs that make it easier to
 //! implement a custom dependency provider.
 
+mod instrumented;
 mod pool;
 
-pub use pool::{PackageName, Pool, VersionSet};
+use std::collections::HashMap;
+
+pub use instrumented::{AccessCounts, InstrumentedInterner};
+pub use pool::{ExactVersionSet, FullVersionSet, PackageName, Pool, VersionSet};
+
+use crate::{
+    runtime::{AsyncRuntime, NowOrNeverRuntime},
+    ConditionalRequirement, DependencyProvider, Interner, NameId, SolvableId, SolverCache,
+    VersionSetId,
+};
+
+/// Groups the version sets by the package they apply to, which is useful to
+/// present all the constraints on a package together.
+///
+/// Aliases are resolved with [`Interner::canonical_name`], so version sets of
+/// the same package are grouped together regardless of which of its names
+/// they use. Within a group the version sets retain their order.
+pub fn group_version_sets_by_name(
+    version_sets: impl IntoIterator<Item = VersionSetId>,
+    interner: &impl Interner,
+) -> HashMap<NameId, Vec<VersionSetId>> {
+    let mut groups: HashMap<NameId, Vec<VersionSetId>> = HashMap::new();
+    for version_set in version_sets {
+        let name = interner.canonical_name(interner.version_set_name(version_set));
+        groups.entry(name).or_default().push(version_set);
+    }
+    groups
+}
+
+/// Turns a solution into requirements that each match exactly one of its
+/// solvables, see [`Pool::exact_version_set`].
+///
+/// Solving these requirements again yields the same solution, which is
+/// useful to capture a solved environment and recreate it later.
+pub fn pin_solution<VS: ExactVersionSet, N: PackageName>(
+    solution: &[SolvableId],
+    pool: &Pool<VS, N>,
+) -> Vec<ConditionalRequirement> {
+    solution
+        .iter()
+        .map(|&solvable| pool.exact_version_set(solvable).into())
+        .collect()
+}
+
+/// Computes a hash of a solution that only depends on how its solvables are
+/// displayed with [`Interner::display_solvable`], not on their ids or order.
+///
+/// Solutions with the same packages have the same hash, even if they were
+/// solved with separately built pools in which the solvables have different
+/// ids. The hash is stable across runs and platforms, so it can be stored,
+/// e.g. to detect whether a solution changed.
+pub fn solution_hash(solution: &[SolvableId], interner: &impl Interner) -> u64 {
+    let mut solvables = solution
+        .iter()
+        .map(|&solvable| interner.display_solvable(solvable).to_string())
+        .collect::<Vec<_>>();
+    solvables.sort_unstable();
+    stable_hash(&solvables)
+}
+
+/// Hashes a sequence of strings in a way that is stable across runs and
+/// platforms.
+pub(crate) fn stable_hash(strings: impl IntoIterator<Item = impl AsRef<str>>) -> u64 {
+    // 64-bit FNV-1a, which unlike the hashers of the standard library is
+    // guaranteed to stay the same.
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let mut hash = OFFSET_BASIS;
+    for string in strings {
+        // Terminate every string with a byte that never occurs in UTF-8, so
+        // that concatenations of different strings do not collide.
+        for &byte in string.as_ref().as_bytes().iter().chain(&[0xff]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// Finds the requirements that are implied by another requirement, for
+/// instance `foo >=1` is implied by `foo >=2`.
+///
+/// Returns pairs of `(redundant_index, subsuming_index)` into `requirements`,
+/// ordered by the redundant index, reporting the first subsuming requirement
+/// of every redundant requirement. A requirement is subsumed by another if
+/// both have the same conditions, and every version set of the other is a
+/// subset of a version set of the same package in the requirement, according
+/// to [`Interner::version_set_subset`]. Of two equivalent requirements, only
+/// the later one is reported.
+pub fn find_redundant_requirements(
+    requirements: &[ConditionalRequirement],
+    interner: &impl Interner,
+) -> Vec<(usize, usize)> {
+    let implies = |subsuming: &ConditionalRequirement, redundant: &ConditionalRequirement| {
+        subsuming.conditions == redundant.conditions
+            && subsuming.requirement.version_sets(interner).all(|subset| {
+                let name = interner.canonical_name(interner.version_set_name(subset));
+                redundant
+                    .requirement
+                    .version_sets(interner)
+                    .any(|superset| {
+                        interner.canonical_name(interner.version_set_name(superset)) == name
+                            && interner.version_set_subset(subset, superset) == Some(true)
+                    })
+            })
+    };
+
+    let mut redundant = Vec::new();
+    for (index, requirement) in requirements.iter().enumerate() {
+        let subsuming = requirements.iter().enumerate().find(|&(other, subsuming)| {
+            other != index
+                && implies(subsuming, requirement)
+                // Equivalent requirements imply each other, keep the first one.
+                && (other < index || !implies(requirement, subsuming))
+        });
+        if let Some((other, _)) = subsuming {
+            redundant.push((index, other));
+        }
+    }
+    redundant
+}
+
+/// Returns the candidate of a package that the solver would try first, after
+/// applying the `constraints` on the package, without solving.
+///
+/// The candidates are ordered like the solver orders them, see
+/// [`DependencyProvider::sort_candidates`], and excluded candidates are
+/// skipped. Constraints on other packages are ignored. This is a best-effort
+/// preview: the dependencies of the candidate are not taken into account, so
+/// a solve might end up selecting another candidate, or none at all. Returns
+/// `None` if no candidate matches the constraints or if the provider cancelled.
+///
+/// The futures of the provider are evaluated with the [`NowOrNeverRuntime`],
+/// so they must not yield.
+pub fn preview_candidate<D: DependencyProvider>(
+    name: NameId,
+    constraints: &[VersionSetId],
+    cache: &SolverCache<D>,
+) -> Option<SolvableId> {
+    NowOrNeverRuntime.block_on(async {
+        let provider = cache.provider();
+        let name = provider.canonical_name(name);
+        let candidates = cache.get_or_cache_candidates(name).await.ok()?;
+        let mut viable = candidates
+            .candidates
+            .iter()
+            .copied()
+            .filter(|&candidate| {
+                !candidates
+                    .excluded
+                    .iter()
+                    .any(|&(excluded, _)| excluded == candidate)
+            })
+            .collect::<Vec<_>>();
+
+        for &constraint in constraints {
+            if provider.canonical_name(provider.version_set_name(constraint)) != name {
+                continue;
+            }
+            let matching = cache
+                .get_or_cache_matching_candidates(constraint)
+                .await
+                .ok()?;
+            viable.retain(|candidate| matching.contains(candidate));
+        }
+
+        cache
+            .sort_in_solver_order(name, &mut viable, candidates.favored)
+            .await;
+        viable.first().copied()
+    })
+}
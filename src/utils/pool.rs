@@ -1,13 +1,20 @@
 use std::{
+    cell::{Cell, OnceCell},
     fmt::{Display, Formatter},
     hash::Hash,
 };
 
-use crate::internal::{
-    arena::Arena,
-    frozen_copy_map::FrozenCopyMap,
-    id::{NameId, SolvableId, StringId, VersionSetId, VersionSetUnionId},
-    small_vec::SmallVec,
+use elsa::FrozenMap;
+
+use crate::{
+    internal::{
+        arena::{Arena, ArenaId},
+        frozen_copy_map::FrozenCopyMap,
+        hash::DefaultHashBuilder,
+        id::{NameId, SolvableId, StringId, VersionSetId, VersionSetUnionId},
+        small_vec::SmallVec,
+    },
+    Requirement,
 };
 
 /// A solvable represents a single candidate of a package.
@@ -35,19 +42,23 @@ pub struct Pool<VS: VersionSet, N: PackageName = String> {
     package_names: Arena<NameId, N>,
 
     /// Map from package names to the id of their interned counterpart
-    pub(crate) names_to_ids: FrozenCopyMap<N, NameId, ahash::RandomState>,
+    pub(crate) names_to_ids: FrozenCopyMap<N, NameId, DefaultHashBuilder>,
 
     /// Interned strings
     strings: Arena<StringId, String>,
 
     /// Map from package names to the id of their interned counterpart
-    pub(crate) string_to_ids: FrozenCopyMap<String, StringId, ahash::RandomState>,
-    pub(crate) version_sets: Arena<VersionSetId, (NameId, VS)>,
+    pub(crate) string_to_ids: FrozenCopyMap<String, StringId, DefaultHashBuilder>,
+    version_sets: Arena<VersionSetId, (NameId, LazyVersionSet<VS>)>,
 
     /// Map from version set to the id of their interned counterpart
-    version_set_to_id: FrozenCopyMap<(NameId, VS), VersionSetId, ahash::RandomState>,
+    version_set_to_id: FrozenCopyMap<(NameId, VS), VersionSetId, DefaultHashBuilder>,
 
     version_set_unions: Arena<VersionSetUnionId, SmallVec<VersionSetId>>,
+
+    /// The solvables of the version sets interned with
+    /// [`Self::intern_solvable_set`].
+    solvable_sets: FrozenMap<VersionSetId, Vec<SolvableId>, DefaultHashBuilder>,
 }
 
 impl<VS: VersionSet, N: PackageName> Default for Pool<VS, N> {
@@ -63,6 +74,7 @@ impl<VS: VersionSet, N: PackageName> Default for Pool<VS, N> {
             version_set_to_id: Default::default(),
             version_sets: Arena::new(),
             version_set_unions: Arena::new(),
+            solvable_sets: Default::default(),
         }
     }
 }
@@ -73,6 +85,55 @@ impl<VS: VersionSet, N: PackageName> Pool<VS, N> {
         Self::default()
     }
 
+    /// Creates a new [`Pool`] with room for the given number of package
+    /// names, version sets, solvables and strings.
+    ///
+    /// Interning more items than the pool has room for is fine, but callers
+    /// that know the size of their package index up front avoid growing the
+    /// pool while interning. The capacity does not influence the ids that
+    /// are assigned.
+    pub fn with_capacity(
+        names: usize,
+        version_sets: usize,
+        solvables: usize,
+        strings: usize,
+    ) -> Self {
+        Self {
+            solvables: Arena::with_capacity(solvables),
+            names_to_ids: FrozenCopyMap::with_capacity(names),
+            package_names: Arena::with_capacity(names),
+            strings: Arena::with_capacity(strings),
+            string_to_ids: FrozenCopyMap::with_capacity(strings),
+            version_set_to_id: FrozenCopyMap::with_capacity(version_sets),
+            version_sets: Arena::with_capacity(version_sets),
+            version_set_unions: Arena::new(),
+            solvable_sets: Default::default(),
+        }
+    }
+
+    /// Reserves room for at least `additional` more package names.
+    pub fn reserve_package_names(&mut self, additional: usize) {
+        self.package_names.reserve(additional);
+        self.names_to_ids.reserve(additional);
+    }
+
+    /// Reserves room for at least `additional` more version sets.
+    pub fn reserve_version_sets(&mut self, additional: usize) {
+        self.version_sets.reserve(additional);
+        self.version_set_to_id.reserve(additional);
+    }
+
+    /// Reserves room for at least `additional` more solvables.
+    pub fn reserve_solvables(&mut self, additional: usize) {
+        self.solvables.reserve(additional);
+    }
+
+    /// Reserves room for at least `additional` more strings.
+    pub fn reserve_strings(&mut self, additional: usize) {
+        self.strings.reserve(additional);
+        self.string_to_ids.reserve(additional);
+    }
+
     /// Interns a generic string into the `Pool` and returns its `StringId`.
     /// Strings are deduplicated.
     pub fn intern_string(&self, name: impl Into<String> + AsRef<str>) -> StringId {
@@ -93,6 +154,11 @@ impl<VS: VersionSet, N: PackageName> Pool<VS, N> {
         &self.strings[string_id]
     }
 
+    /// Returns whether the given [`StringId`] was interned in this pool.
+    pub fn contains_string(&self, string_id: StringId) -> bool {
+        string_id.to_usize() < self.strings.len()
+    }
+
     /// Interns a package name into the `Pool`, returning its `NameId`. Names
     /// are deduplicated. If the same name is inserted twice the same
     /// `NameId` will be returned.
@@ -171,18 +237,101 @@ impl<VS: VersionSet, N: PackageName> Pool<VS, N> {
         {
             entry
         } else {
-            let id = self.version_sets.alloc((package_name, version_set.clone()));
+            let id = self
+                .version_sets
+                .alloc((package_name, LazyVersionSet::new(version_set.clone())));
             self.version_set_to_id
                 .insert_copy((package_name, version_set), id);
             id
         }
     }
 
+    /// Interns the version set that matches every version of the given
+    /// package, which can be used to express a `name *` requirement.
+    ///
+    /// The version set is only interned once, every call for the same package
+    /// returns the same [`VersionSetId`].
+    pub fn any_version_set(&self, package_name: NameId) -> VersionSetId
+    where
+        VS: FullVersionSet,
+    {
+        self.intern_version_set(package_name, VS::full())
+    }
+
+    /// Interns the version set that only matches the version of the given
+    /// solvable, which can be used to require exactly that solvable.
+    ///
+    /// Like [`Self::any_version_set`], every call for the same solvable
+    /// returns the same [`VersionSetId`].
+    pub fn exact_version_set(&self, solvable: SolvableId) -> VersionSetId
+    where
+        VS: ExactVersionSet,
+    {
+        let solvable = self.resolve_solvable(solvable);
+        self.intern_version_set(solvable.name, VS::exact(&solvable.record))
+    }
+
+    /// Interns a version set that is only constructed once it is resolved
+    /// through [`Self::resolve_version_set`], which avoids constructing
+    /// version sets that are never used by the solver. The result of
+    /// `version_set` is cached.
+    ///
+    /// Unlike [`Self::intern_version_set`], lazily interned version sets are
+    /// *not* deduplicated, and a unique id is returned on every invocation.
+    pub fn intern_version_set_lazy(
+        &self,
+        package_name: NameId,
+        version_set: impl FnOnce() -> VS + Send + 'static,
+    ) -> VersionSetId {
+        self.version_sets
+            .alloc((package_name, LazyVersionSet::lazy(version_set)))
+    }
+
+    /// Interns a version set of the given package that matches exactly the
+    /// given solvables, for providers that enumerate the allowed solvables
+    /// instead of describing them with a range of versions.
+    ///
+    /// Such a version set cannot be described by a `VS`, so
+    /// [`Self::resolve_version_set`] panics for it. A provider that interns
+    /// solvable sets has to check for them with [`Self::resolve_solvable_set`]
+    /// or [`Self::matches_solvable_set`] before resolving a version set, e.g.
+    /// in [`crate::DependencyProvider::filter_candidates`] and
+    /// [`crate::Interner::display_version_set`].
+    ///
+    /// Like [`Self::intern_version_set_lazy`], solvable sets are *not*
+    /// deduplicated, and a unique id is returned on every invocation.
+    pub fn intern_solvable_set(
+        &self,
+        package_name: NameId,
+        solvables: &[SolvableId],
+    ) -> VersionSetId {
+        let id = self
+            .version_sets
+            .alloc((package_name, LazyVersionSet::unresolvable()));
+        self.solvable_sets.insert(id, solvables.to_vec());
+        id
+    }
+
+    /// Returns the solvables of a version set that was interned with
+    /// [`Self::intern_solvable_set`], or `None` for any other version set.
+    pub fn resolve_solvable_set(&self, id: VersionSetId) -> Option<&[SolvableId]> {
+        self.solvable_sets.get(&id)
+    }
+
+    /// Returns whether `solvable` is one of the solvables of a version set
+    /// that was interned with [`Self::intern_solvable_set`], or `None` for any
+    /// other version set.
+    pub fn matches_solvable_set(&self, solvable: SolvableId, id: VersionSetId) -> Option<bool> {
+        self.resolve_solvable_set(id)
+            .map(|solvables| solvables.contains(&solvable))
+    }
+
     /// Returns the version set associated with the provided id
     ///
-    /// Panics if the version set is not found in the pool
+    /// Panics if the version set is not found in the pool, or if it was
+    /// interned with [`Self::intern_solvable_set`].
     pub fn resolve_version_set(&self, id: VersionSetId) -> &VS {
-        &self.version_sets[id].1
+        self.version_sets[id].1.get()
     }
 
     /// Returns the package name associated with the provide id.
@@ -192,24 +341,77 @@ impl<VS: VersionSet, N: PackageName> Pool<VS, N> {
         self.version_sets[id].0
     }
 
+    /// Returns the ids of all the version sets interned for the given package
+    /// name, in the order they were interned.
+    pub fn version_sets_for_name(
+        &self,
+        package_name: NameId,
+    ) -> impl Iterator<Item = VersionSetId> + '_ {
+        self.version_sets
+            .iter()
+            .filter(move |(_, (name, _))| *name == package_name)
+            .map(|(id, _)| id)
+    }
+
+    /// Returns whether the given [`VersionSetId`] was interned in this pool.
+    pub fn contains_version_set(&self, id: VersionSetId) -> bool {
+        id.to_usize() < self.version_sets.len()
+    }
+
     /// Interns a union of two or more version sets and returns its
     /// [`VersionSetUnionId`].
     ///
     /// Version set unions are *not* deduplicated, and a unique id is returned
     /// on every invocation.
+    ///
+    /// The version sets are stored ordered by their [`VersionSetId`], so the
+    /// order in which they are passed does not influence the order in which
+    /// [`Self::resolve_version_set_union`] returns them.
     pub fn intern_version_set_union(
         &self,
         first: VersionSetId,
         others: impl Iterator<Item = VersionSetId>,
     ) -> VersionSetUnionId {
-        self.version_set_unions
-            .alloc(others.fold(SmallVec::one(first), |mut vec, version_set| {
+        let mut version_sets = std::iter::once(first).chain(others).collect::<Vec<_>>();
+        version_sets.sort_unstable();
+        self.version_set_unions.alloc(version_sets.into_iter().fold(
+            SmallVec::empty(),
+            |mut vec, version_set| {
                 vec.push(version_set);
                 vec
-            }))
+            },
+        ))
+    }
+
+    /// Interns a [`Requirement`] from a string in which the alternatives are
+    /// separated by `|`, for instance `foo >=2 | bar`. Every alternative is
+    /// trimmed and turned into a version set by `parse_version_set`, which
+    /// implements the version syntax of the provider.
+    ///
+    /// A single alternative results in a [`Requirement::Single`], several
+    /// alternatives in a [`Requirement::Union`] that is interned with
+    /// [`Self::intern_version_set_union`].
+    pub fn parse_requirement(
+        &self,
+        input: &str,
+        mut parse_version_set: impl FnMut(&str) -> VersionSetId,
+    ) -> Requirement {
+        let mut version_sets = input
+            .split('|')
+            .map(|alternative| parse_version_set(alternative.trim()));
+        let first = version_sets
+            .next()
+            .expect("split always returns at least one item");
+        let mut others = version_sets.peekable();
+        if others.peek().is_none() {
+            Requirement::Single(first)
+        } else {
+            Requirement::Union(self.intern_version_set_union(first, others))
+        }
     }
 
-    /// Returns the version sets in the version set union with the given id.
+    /// Returns the version sets in the version set union with the given id,
+    /// ordered by their [`VersionSetId`].
     ///
     /// Panics if there is no union with the given id.
     pub fn resolve_version_set_union(
@@ -218,6 +420,53 @@ impl<VS: VersionSet, N: PackageName> Pool<VS, N> {
     ) -> impl Iterator<Item = VersionSetId> + '_ {
         self.version_set_unions[id].iter().copied()
     }
+
+    /// Returns whether the given [`VersionSetUnionId`] was interned in this
+    /// pool.
+    pub fn contains_version_set_union(&self, id: VersionSetUnionId) -> bool {
+        id.to_usize() < self.version_set_unions.len()
+    }
+}
+
+/// A version set that is possibly only constructed when it is first used.
+struct LazyVersionSet<VS> {
+    version_set: OnceCell<VS>,
+    init: Cell<Option<Box<dyn FnOnce() -> VS + Send>>>,
+}
+
+impl<VS> LazyVersionSet<VS> {
+    fn new(version_set: VS) -> Self {
+        Self {
+            version_set: OnceCell::from(version_set),
+            init: Cell::new(None),
+        }
+    }
+
+    fn lazy(init: impl FnOnce() -> VS + Send + 'static) -> Self {
+        Self {
+            version_set: OnceCell::new(),
+            init: Cell::new(Some(Box::new(init))),
+        }
+    }
+
+    /// Returns a version set that cannot be resolved, for the version sets of
+    /// [`Pool::intern_solvable_set`].
+    fn unresolvable() -> Self {
+        Self {
+            version_set: OnceCell::new(),
+            init: Cell::new(None),
+        }
+    }
+
+    fn get(&self) -> &VS {
+        self.version_set.get_or_init(|| {
+            let init = self.init.take().expect(
+                "a version set interned with `Pool::intern_solvable_set` must be resolved with \
+                 `Pool::resolve_solvable_set`",
+            );
+            init()
+        })
+    }
 }
 
 /// A helper struct to visualize a name.
@@ -272,3 +521,30 @@ pub trait VersionSet: Clone + Eq + Hash {
 impl<R: Clone + Eq + Hash + Display> VersionSet for version_ranges::Ranges<R> {
     type V = R;
 }
+
+/// A [`VersionSet`] that can represent the set of all versions.
+pub trait FullVersionSet: VersionSet {
+    /// Returns the version set that contains every version. Every candidate of
+    /// a package must match this version set.
+    fn full() -> Self;
+}
+
+#[cfg(feature = "version-ranges")]
+impl<R: Clone + Eq + Hash + Display> FullVersionSet for version_ranges::Ranges<R> {
+    fn full() -> Self {
+        version_ranges::Ranges::full()
+    }
+}
+
+/// A [`VersionSet`] that can represent a single version.
+pub trait ExactVersionSet: VersionSet {
+    /// Returns the version set that only contains the given version.
+    fn exact(version: &Self::V) -> Self;
+}
+
+#[cfg(feature = "version-ranges")]
+impl<R: Clone + Eq + Hash + Display> ExactVersionSet for version_ranges::Ranges<R> {
+    fn exact(version: &R) -> Self {
+        version_ranges::Ranges::singleton(version.clone())
+    }
+}
@@ -0,0 +1,305 @@
+//! Verifies that a solution satisfies a problem, independently of the solver.
+
+use std::fmt::Display;
+
+use crate::{
+    internal::hash::{HashMap, HashSet},
+    runtime::{AsyncRuntime, NowOrNeverRuntime},
+    solver::filter_candidates,
+    Condition, ConditionalRequirement, Dependencies, DependencyProvider, Interner, NameId,
+    Requirement, SolvableId, StringId, VersionSetId,
+};
+
+/// The reason why a solution does not satisfy a problem, see
+/// [`verify_solution`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValidationError {
+    /// Not enough of the selected solvables satisfy a requirement of the
+    /// problem.
+    MissingRequirement(Requirement),
+    /// Not enough of the selected solvables satisfy a dependency of a
+    /// selected solvable.
+    MissingDependency {
+        /// The solvable that has the dependency.
+        solvable: SolvableId,
+        /// The dependency that is not satisfied.
+        requirement: Requirement,
+    },
+    /// A selected solvable does not match a constraint. The constraint either
+    /// belongs to the problem, in which case `constrained_by` is `None`, or to
+    /// the dependencies of another selected solvable.
+    ConstraintViolated {
+        /// The solvable that does not match the constraint.
+        solvable: SolvableId,
+        /// The constraint that is violated.
+        constraint: VersionSetId,
+        /// The selected solvable that imposes the constraint, if any.
+        constrained_by: Option<SolvableId>,
+    },
+    /// More than one solvable of the same package is selected.
+    MultipleSolvables {
+        /// The name of the package.
+        name: NameId,
+        /// Two of the selected solvables of the package.
+        solvables: [SolvableId; 2],
+    },
+    /// The dependencies of a selected solvable are unknown.
+    UnknownDependencies {
+        /// The solvable with unknown dependencies.
+        solvable: SolvableId,
+        /// The reason why the dependencies are unknown.
+        reason: StringId,
+    },
+}
+
+impl ValidationError {
+    /// Returns an object that can be used to display the error in a
+    /// user-friendly way.
+    pub fn display<'i>(&'i self, interner: &'i impl Interner) -> impl Display + 'i {
+        DisplayValidationError {
+            error: self,
+            interner,
+        }
+    }
+}
+
+struct DisplayValidationError<'i, I: Interner> {
+    error: &'i ValidationError,
+    interner: &'i I,
+}
+
+impl<I: Interner> Display for DisplayValidationError<'_, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let interner = self.interner;
+        let missing = |requirement: Requirement| {
+            if requirement.min_count() > 1 {
+                "not enough selected solvables satisfy"
+            } else {
+                "no selected solvable satisfies"
+            }
+        };
+        match *self.error {
+            ValidationError::MissingRequirement(requirement) => write!(
+                f,
+                "{} the requirement {}",
+                missing(requirement),
+                requirement.display(interner)
+            ),
+            ValidationError::MissingDependency {
+                solvable,
+                requirement,
+            } => write!(
+                f,
+                "{} the dependency {} of {}",
+                missing(requirement),
+                requirement.display(interner),
+                interner.display_solvable(solvable)
+            ),
+            ValidationError::ConstraintViolated {
+                solvable,
+                constraint,
+                constrained_by,
+            } => {
+                write!(
+                    f,
+                    "{} does not match the constraint {} {}",
+                    interner.display_solvable(solvable),
+                    interner.display_name(interner.version_set_name(constraint)),
+                    interner.display_version_set(constraint)
+                )?;
+                if let Some(constrained_by) = constrained_by {
+                    write!(f, " of {}", interner.display_solvable(constrained_by))?;
+                }
+                Ok(())
+            }
+            ValidationError::MultipleSolvables {
+                name,
+                solvables: [first, second],
+            } => write!(
+                f,
+                "both {} and {} are selected for {}",
+                interner.display_solvable(first),
+                interner.display_solvable(second),
+                interner.display_name(name)
+            ),
+            ValidationError::UnknownDependencies { solvable, reason } => write!(
+                f,
+                "the dependencies of {} are unknown: {}",
+                interner.display_solvable(solvable),
+                interner.display_string(reason)
+            ),
+        }
+    }
+}
+
+/// Verifies that `solution` satisfies the given requirements and constraints,
+/// without relying on the solver.
+///
+/// A solution is valid if
+/// * at most one solvable is selected per package,
+/// * the dependencies of every selected solvable are known,
+/// * every requirement, and every dependency of a selected solvable, is
+///   satisfied by a selected solvable,
+/// * every selected solvable matches the constraints of the problem and the
+///   constraints of the other selected solvables.
+///
+/// Requirements that only apply when an extra is enabled are not checked,
+/// because a solution does not record which extras are enabled.
+///
+/// The futures of the `provider` are evaluated with the [`NowOrNeverRuntime`],
+/// so they must not yield.
+pub fn verify_solution<D: DependencyProvider>(
+    solution: &[SolvableId],
+    requirements: &[ConditionalRequirement],
+    constraints: &[VersionSetId],
+    provider: &D,
+) -> Result<(), ValidationError> {
+    NowOrNeverRuntime.block_on(Verifier::new(solution, provider).verify(requirements, constraints))
+}
+
+struct Verifier<'a, D: DependencyProvider> {
+    provider: &'a D,
+    selected: HashMap<NameId, SolvableId>,
+    solution: &'a [SolvableId],
+}
+
+impl<'a, D: DependencyProvider> Verifier<'a, D> {
+    fn new(solution: &'a [SolvableId], provider: &'a D) -> Self {
+        Self {
+            provider,
+            selected: HashMap::default(),
+            solution,
+        }
+    }
+
+    async fn verify(
+        mut self,
+        requirements: &[ConditionalRequirement],
+        constraints: &[VersionSetId],
+    ) -> Result<(), ValidationError> {
+        for &solvable in self.solution {
+            let name = self
+                .provider
+                .canonical_name(self.provider.solvable_name(solvable));
+            if let Some(&other) = self.selected.get(&name) {
+                if other != solvable {
+                    return Err(ValidationError::MultipleSolvables {
+                        name,
+                        solvables: [other, solvable],
+                    });
+                }
+            }
+            self.selected.insert(name, solvable);
+        }
+
+        for requirement in requirements {
+            if self.is_active(requirement).await
+                && !self.is_satisfied(requirement.requirement).await
+            {
+                return Err(ValidationError::MissingRequirement(requirement.requirement));
+            }
+        }
+
+        for &constraint in constraints {
+            if let Some(solvable) = self.violates(constraint).await {
+                return Err(ValidationError::ConstraintViolated {
+                    solvable,
+                    constraint,
+                    constrained_by: None,
+                });
+            }
+        }
+
+        for &solvable in self.solution {
+            let dependencies = match self.provider.get_dependencies(solvable).await {
+                Dependencies::Known(dependencies) => dependencies,
+                Dependencies::Unknown(reason) => {
+                    return Err(ValidationError::UnknownDependencies { solvable, reason });
+                }
+            };
+
+            for requirement in &dependencies.requirements {
+                if self.is_active(requirement).await
+                    && !self.is_satisfied(requirement.requirement).await
+                {
+                    return Err(ValidationError::MissingDependency {
+                        solvable,
+                        requirement: requirement.requirement,
+                    });
+                }
+            }
+
+            for constraint in dependencies.active_constrains(self.provider) {
+                if let Some(violating) = self.violates(constraint).await {
+                    return Err(ValidationError::ConstraintViolated {
+                        solvable: violating,
+                        constraint,
+                        constrained_by: Some(solvable),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the selected solvable of the package of the version set, if it
+    /// matches the version set (or does not match it if `inverse` is true).
+    async fn selected_matching(
+        &self,
+        version_set: VersionSetId,
+        inverse: bool,
+    ) -> Option<SolvableId> {
+        let name = self
+            .provider
+            .canonical_name(self.provider.version_set_name(version_set));
+        let &selected = self.selected.get(&name)?;
+        filter_candidates(self.provider, &[selected], version_set, inverse)
+            .await
+            .first()
+            .copied()
+    }
+
+    /// Returns whether enough selected solvables match the requirement, which
+    /// is at least `n` distinct solvables for a [`Requirement::AtLeast`].
+    async fn is_satisfied(&self, requirement: Requirement) -> bool {
+        self.has_matching(requirement, requirement.min_count())
+            .await
+    }
+
+    /// Returns whether at least `count` distinct selected solvables match
+    /// one of the version sets of the requirement.
+    async fn has_matching(&self, requirement: Requirement, count: usize) -> bool {
+        let mut matching = HashSet::default();
+        for version_set in requirement.version_sets(self.provider) {
+            if matching.len() >= count {
+                break;
+            }
+            if let Some(solvable) = self.selected_matching(version_set, false).await {
+                matching.insert(solvable);
+            }
+        }
+        matching.len() >= count
+    }
+
+    async fn violates(&self, constraint: VersionSetId) -> Option<SolvableId> {
+        self.selected_matching(constraint, true).await
+    }
+
+    async fn is_active(&self, requirement: &ConditionalRequirement) -> bool {
+        for &condition in &requirement.conditions {
+            let met = match condition {
+                Condition::Extra(_) => false,
+                Condition::Marker(marker) => self.provider.evaluate_marker(marker),
+                Condition::VersionSetId(version_set) => {
+                    self.selected_matching(version_set, false).await.is_some()
+                }
+                Condition::Requirement(requirement) => self.has_matching(requirement, 1).await,
+            };
+            if !met {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -2,10 +2,12 @@ use std::{
     any::Any,
     borrow::Borrow,
     cell::{Cell, RefCell},
+    cmp::Ordering as CmpOrdering,
     collections::HashSet,
     fmt::{Debug, Display, Formatter},
     io::{stderr, Write},
     num::ParseIntError,
+    ops::{Bound, ControlFlow},
     rc::Rc,
     str::FromStr,
     sync::{
@@ -18,13 +20,25 @@ use std::{
 use ahash::HashMap;
 use indexmap::IndexMap;
 use insta::assert_snapshot;
-use itertools::{Itertools};
+use itertools::Itertools;
 use resolvo::{
-    snapshot::{DependencySnapshot, SnapshotProvider},
-    utils::Pool,
-    Candidates, ConditionalRequirement, Dependencies, DependencyProvider, Interner,
-    KnownDependencies, NameId, Problem, Requirement, SolvableId, Solver, SolverCache, StringId,
-    UnsolvableOrCancelled, VersionSetId, VersionSetUnionId,
+    closure_size,
+    conflict::{ConflictMessages, ConflictReason, Suggestion},
+    gen::{generate_problem, GenParams, GeneratedPool},
+    runtime::{AsyncRuntime, NowOrNeverRuntime},
+    snapshot::{
+        reduce_problem, BinarySnapshotError, DependencySnapshot, ProblemSnapshot, SnapshotProvider,
+    },
+    utils::{
+        find_redundant_requirements, group_version_sets_by_name, pin_solution, preview_candidate,
+        solution_hash, InstrumentedInterner, Pool,
+    },
+    verify_solution, Candidates, Condition, ConditionError, ConditionExpr, ConditionExprError,
+    ConditionalConstraint, ConditionalRequirement, Dependencies, DependencyClosure,
+    DependencyProvider, ExclusionReason, FrozenSolveError, Interner, KnownDependencies, NameId,
+    Objective, PartialResult, PreviousSort, Problem, ProofStep, Requirement, RequirementGroup,
+    SolvableId, Solver, SolverBuilder, SolverCache, StringId, UnknownDependencies,
+    UnsolvableOrCancelled, VersionBound, VersionSetBounds, VersionSetId, VersionSetUnionId,
 };
 use tracing_test::traced_test;
 use version_ranges::Ranges;
@@ -53,6 +67,7 @@ struct Pack {
     version: u32,
     unknown_deps: bool,
     cancel_during_get_dependencies: bool,
+    priority: i64,
 }
 
 impl Pack {
@@ -61,9 +76,15 @@ impl Pack {
             version,
             unknown_deps: false,
             cancel_during_get_dependencies: false,
+            priority: 0,
         }
     }
 
+    fn with_priority(mut self, priority: i64) -> Pack {
+        self.priority = priority;
+        self
+    }
+
     fn with_unknown_deps(mut self) -> Pack {
         self.unknown_deps = true;
         self
@@ -74,14 +95,6 @@ impl Pack {
         self
     }
 
-    fn with_extra(mut self, extra: impl Into<String>, value: impl Into<String>) -> Pack {
-        self.extra
-            .entry(extra.into())
-            .or_default()
-            .push(value.into());
-        self
-    }
-
     fn offset(&self, version_offset: i32) -> Pack {
         let mut pack = *self;
         pack.version = pack.version.wrapping_add_signed(version_offset);
@@ -122,7 +135,7 @@ struct Spec {
     name: String,
     versions: Ranges<Pack>,
     /// c 1; if a 1 and b 1 (conditions are a, b)
-    conditions: Vec<Box<Spec>>,
+    conditions: Vec<Spec>,
     /// a[b,c] 1; if d 1 and c 1 (extras are b, c)
     extras: Vec<String>,
 }
@@ -131,7 +144,7 @@ impl Spec {
     pub fn new(
         name: String,
         versions: Ranges<Pack>,
-        conditions: Vec<Box<Spec>>,
+        conditions: Vec<Spec>,
         extras: Vec<String>,
     ) -> Self {
         Self {
@@ -173,7 +186,7 @@ impl FromStr for Spec {
             };
 
             let versions = version_range(split.get(1));
-            return Ok(Spec::new(name, versions, None, extras));
+            return Ok(Spec::new(name, versions, vec![], extras));
         }
 
         let (spec, condition) = split.unwrap();
@@ -202,7 +215,7 @@ impl FromStr for Spec {
         Ok(Spec::new(
             spec.name,
             spec.versions,
-            Some(Box::new(condition)),
+            vec![condition],
             spec.extras,
         ))
     }
@@ -215,25 +228,68 @@ struct BundleBoxProvider {
     packages: IndexMap<String, IndexMap<Pack, BundleBoxPackageDependencies>>,
     favored: HashMap<String, Pack>,
     locked: HashMap<String, Pack>,
+    aliases: HashMap<NameId, NameId>,
     excluded: HashMap<String, HashMap<Pack, String>>,
     cancel_solving: Cell<bool>,
     // TODO: simplify?
     concurrent_requests: Arc<AtomicUsize>,
     concurrent_requests_max: Rc<Cell<usize>>,
     sleep_before_return: bool,
+    // Whether to hint to the solver that the dependencies of all candidates are available.
+    hint_dependencies_available: bool,
 
     // A mapping of packages that we have requested candidates for. This way we can keep track of
     // duplicate requests.
     requested_candidates: RefCell<HashSet<NameId>>,
     requested_dependencies: RefCell<HashSet<SolvableId>>,
+    requested_dependency_batches: RefCell<Vec<Vec<SolvableId>>>,
     interned_solvables: RefCell<HashMap<(NameId, Pack), SolvableId>>,
+
+    // The extras that have been requested for a package through a root requirement like
+    // `a[feat1]`.
+    requested_extras: RefCell<HashMap<String, HashSet<StringId>>>,
+
+    // The markers that hold for the environment that is solved for.
+    markers: HashSet<StringId>,
+
+    // If set, only this many of the highest candidates of a package are returned at first, the
+    // others are returned when more candidates are requested.
+    candidate_batch: Option<usize>,
+    requested_more_candidates: RefCell<Vec<NameId>>,
+
+    // Whether to describe the bounds of version sets, so that unsat messages suggest the closest
+    // available candidate.
+    describe_bounds: bool,
+
+    // The scores of candidates, candidates without a score have the default score.
+    scores: HashMap<SolvableId, f64>,
+
+    // The platforms for which a solvable is not available.
+    unavailable_on: HashMap<SolvableId, Vec<StringId>>,
+
+    // The local versions of solvables and the local versions that version sets require, which
+    // are taken into account in addition to the version when matching.
+    local_versions: HashMap<SolvableId, StringId>,
+    required_local_versions: HashMap<VersionSetId, StringId>,
+
+    // The version sets of virtual packages that solvables provide.
+    provides: HashMap<SolvableId, Vec<VersionSetId>>,
+
+    // The channel priorities of solvables, solvables without a channel priority are in the
+    // channel with the highest priority.
+    channel_priorities: HashMap<SolvableId, u32>,
+
+    // Whether the dependencies of all candidates are returned together with the candidates, in
+    // which case requesting dependencies separately panics.
+    prefetch_dependencies: bool,
 }
 
 #[derive(Debug, Clone)]
 struct BundleBoxPackageDependencies {
     dependencies: Vec<Vec<Spec>>,
     constrains: Vec<Spec>,
-    extras: HashMap<StringId, Vec<Spec>>,
+    marker_constrains: Vec<(StringId, Spec)>,
+    extras: HashMap<StringId, Vec<Vec<Spec>>>,
 }
 
 impl BundleBoxProvider {
@@ -247,27 +303,66 @@ impl BundleBoxProvider {
             .expect("package missing")
     }
 
-    pub fn requirements<R: From<(VersionSetId, Option<VersionSetId>, Vec<String>)>>(
-        &self,
-        requirements: &[&str],
-    ) -> Vec<R> {
+    pub fn requirements(&self, requirements: &[&str]) -> Vec<ConditionalRequirement> {
         requirements
             .iter()
             .map(|dep| Spec::from_str(dep).unwrap())
             .map(|spec| {
-                (
-                    self.intern_version_set(&spec),
-                    spec.conditions
-                        .iter()
-                        .as_ref()
-                        .map(|c| self.intern_version_set(c)),
-                    spec.extras.iter().map(|e| e.to_string()),
-                )
+                if !spec.extras.is_empty() {
+                    let mut requested_extras = self.requested_extras.borrow_mut();
+                    let extras = requested_extras.entry(spec.name.clone()).or_default();
+                    for extra in &spec.extras {
+                        extras.insert(self.pool.intern_string(extra));
+                    }
+                }
+                self.conditional_requirement(std::slice::from_ref(&spec), &[])
             })
-            .map(From::from)
             .collect()
     }
 
+    pub fn version_sets(&self, specs: &[&str]) -> Vec<VersionSetId> {
+        specs
+            .iter()
+            .map(|spec| self.intern_version_set(&Spec::from_str(spec).unwrap()))
+            .collect()
+    }
+
+    /// Converts a union of specs into a requirement that is only active when
+    /// the conditions of the specs and the given extras are met.
+    pub fn conditional_requirement(
+        &self,
+        specs: &[Spec],
+        extras: &[StringId],
+    ) -> ConditionalRequirement {
+        let first = specs
+            .first()
+            .expect("Dependency spec must have at least one constraint");
+        assert!(
+            specs.iter().all(|spec| spec.conditions == first.conditions),
+            "All specs in a union must have the same condition"
+        );
+
+        let requirement = if specs.len() == 1 {
+            self.intern_version_set(first).into()
+        } else {
+            self.intern_version_set_union(specs).into()
+        };
+
+        let conditions = extras
+            .iter()
+            .copied()
+            .map(Condition::Extra)
+            .chain(
+                first
+                    .conditions
+                    .iter()
+                    .map(|condition| Condition::VersionSetId(self.intern_version_set(condition))),
+            )
+            .collect();
+
+        ConditionalRequirement::new(conditions, requirement)
+    }
+
     pub fn parse_requirements(&self, requirements: &[&str]) -> Vec<ConditionalRequirement> {
         requirements
             .iter()
@@ -295,10 +390,14 @@ impl BundleBoxProvider {
             .intern_version_set_union(specs.next().unwrap(), specs)
     }
 
-    pub fn from_packages(packages: &[(&str, u32, Vec<&str>, &[(&str, &[&str])])]) -> Self {
+    pub fn version_set_union(&self, specs: &[&str]) -> VersionSetUnionId {
+        self.intern_version_set_union(specs.iter().map(|spec| Spec::from_str(spec).unwrap()))
+    }
+
+    pub fn from_packages(packages: &[(&str, u32, Vec<&str>)]) -> Self {
         let mut result = Self::new();
-        for (name, version, deps, extras) in packages {
-            result.add_package(name, Pack::new(*version), deps, &[], extras);
+        for (name, version, deps) in packages {
+            result.add_package(name, Pack::new(*version), deps, &[], &[]);
         }
         result
     }
@@ -316,11 +415,56 @@ impl BundleBoxProvider {
             .insert(Pack::new(version), reason.into());
     }
 
+    /// Adds a constraint to a package that only applies if the marker holds.
+    pub fn add_marker_constraint(
+        &mut self,
+        package_name: &str,
+        version: u32,
+        marker: &str,
+        constraint: &str,
+    ) {
+        let marker = self.pool.intern_string(marker);
+        self.packages
+            .get_mut(package_name)
+            .and_then(|packs| packs.get_mut(&Pack::new(version)))
+            .expect("the package should have been added")
+            .marker_constrains
+            .push((marker, Spec::from_str(constraint).unwrap()));
+    }
+
+    /// Makes a package provide a version set of a virtual package.
+    pub fn add_provides(&mut self, package_name: &str, version: u32, provided: &str) {
+        let solvable = self.solvable_id(package_name, version);
+        let provided = self.intern_version_set(&Spec::from_str(provided).unwrap());
+        self.provides.entry(solvable).or_default().push(provided);
+    }
+
+    /// Returns the solvables that provide the virtual package with the given
+    /// name, ordered by their ids.
+    fn providers_of(&self, name: NameId) -> Vec<SolvableId> {
+        self.provides
+            .iter()
+            .filter(|(_, provided)| {
+                provided
+                    .iter()
+                    .any(|&vs| self.pool.resolve_version_set_package_name(vs) == name)
+            })
+            .map(|(&solvable, _)| solvable)
+            .sorted()
+            .collect()
+    }
+
     pub fn set_locked(&mut self, package_name: &str, version: u32) {
         self.locked
             .insert(package_name.to_owned(), Pack::new(version));
     }
 
+    pub fn set_alias(&mut self, alias: &str, canonical: &str) {
+        let alias = self.pool.intern_package_name(alias);
+        let canonical = self.pool.intern_package_name(canonical);
+        self.aliases.insert(alias, canonical);
+    }
+
     pub fn add_package(
         &mut self,
         package_name: &str,
@@ -329,21 +473,21 @@ impl BundleBoxProvider {
         constrains: &[&str],
         extras: &[(&str, &[&str])],
     ) {
-        let package_id = self.pool.intern_package_name(package_name);
+        self.pool.intern_package_name(package_name);
 
         let dependencies = dependencies
             .iter()
-            .map(|dep| Spec::parse_union(dep).collect())
+            .map(|dep| Spec::parse_union(dep).collect::<Result<Vec<_>, _>>())
             .collect::<Result<Vec<_>, _>>()
             .unwrap();
 
         let extras = extras
             .iter()
             .map(|(key, values)| {
-                (self.pool.intern_string(key), {
+                (self.pool.intern_string(*key), {
                     values
                         .iter()
-                        .map(|dep| Spec::parse_union(dep).collect())
+                        .map(|dep| Spec::parse_union(dep).collect::<Result<Vec<_>, _>>())
                         .collect::<Result<Vec<_>, _>>()
                         .unwrap()
                 })
@@ -364,6 +508,7 @@ impl BundleBoxProvider {
                 BundleBoxPackageDependencies {
                     dependencies,
                     constrains,
+                    marker_constrains: Vec::new(),
                     extras,
                 },
             );
@@ -372,6 +517,73 @@ impl BundleBoxProvider {
     // Sends a value from the dependency provider to the solver, introducing a
     // minimal delay to force concurrency to be used (unless there is no async
     // runtime available)
+    /// Returns the dependencies of a solvable, which are returned by `get_dependencies` or
+    /// prefetched with the candidates.
+    fn dependencies(&self, solvable: SolvableId) -> Dependencies {
+        let candidate = self.pool.resolve_solvable(solvable);
+        let package_name = self.pool.resolve_package_name(candidate.name);
+        let pack = candidate.record;
+
+        if pack.cancel_during_get_dependencies {
+            self.cancel_solving.set(true);
+            let reason = self.pool.intern_string("cancelled");
+            return Dependencies::Unknown(reason);
+        }
+
+        if pack.unknown_deps {
+            let reason = self.pool.intern_string("could not retrieve deps");
+            return Dependencies::Unknown(reason);
+        }
+
+        let Some(deps) = self.packages.get(package_name).and_then(|v| v.get(&pack)) else {
+            return Dependencies::Known(Default::default());
+        };
+
+        let mut result = KnownDependencies {
+            requirements: Vec::with_capacity(deps.dependencies.len()),
+            constrains: Vec::with_capacity(deps.constrains.len()),
+            conditional_constrains: Vec::with_capacity(deps.marker_constrains.len()),
+        };
+        for req in &deps.dependencies {
+            result
+                .requirements
+                .push(self.conditional_requirement(req, &[]));
+        }
+
+        if let Some(requested_extras) = self.requested_extras.borrow().get(package_name) {
+            for (&extra, extra_deps) in deps
+                .extras
+                .iter()
+                .filter(|(extra, _)| requested_extras.contains(extra))
+            {
+                for req in extra_deps {
+                    result
+                        .requirements
+                        .push(self.conditional_requirement(req, &[extra]));
+                }
+            }
+        }
+
+        for req in &deps.constrains {
+            let dep_name = self.pool.intern_package_name(&req.name);
+            let dep_spec = self.pool.intern_version_set(dep_name, req.versions.clone());
+            result.constrains.push(dep_spec);
+        }
+
+        for (marker, req) in &deps.marker_constrains {
+            let dep_name = self.pool.intern_package_name(&req.name);
+            let dep_spec = self.pool.intern_version_set(dep_name, req.versions.clone());
+            result
+                .conditional_constrains
+                .push(ConditionalConstraint::new(
+                    vec![Condition::Marker(*marker)],
+                    dep_spec,
+                ));
+        }
+
+        Dependencies::Known(result)
+    }
+
     async fn maybe_delay<T: Send + 'static>(&self, value: T) -> T {
         if self.sleep_before_return {
             tokio::time::sleep(Duration::from_millis(10)).await;
@@ -399,6 +611,17 @@ impl BundleBoxProvider {
             .or_insert_with_key(|&(name_id, pack)| self.pool.intern_solvable(name_id, pack))
     }
 
+    /// Returns the candidates ordered from the highest to the lowest version.
+    fn highest_candidates(&self, candidates: &[SolvableId]) -> Vec<SolvableId> {
+        candidates
+            .iter()
+            .copied()
+            .sorted_by_key(|&solvable| {
+                std::cmp::Reverse(self.pool.resolve_solvable(solvable).record)
+            })
+            .collect()
+    }
+
     pub fn solvable_id(&self, name: impl Into<String>, version: impl Into<Pack>) -> SolvableId {
         self.intern_solvable(self.pool.intern_package_name(name.into()), version.into())
     }
@@ -427,25 +650,82 @@ impl Interner for BundleBoxProvider {
         self.pool.resolve_package_name(name).clone()
     }
 
+    fn display_name_short(&self, name: NameId) -> impl Display + '_ {
+        let name = self.pool.resolve_package_name(name);
+        name.rsplit('/').next().unwrap().to_owned()
+    }
+
     fn display_condition(&self, condition: Condition) -> impl Display + '_ {
         match condition {
-            Condition::Extra(extra) => self.display_string(extra),
-            Condition::VersionSet(version_set) => format!(
+            Condition::Extra(extra) => self.display_string(extra).to_string(),
+            Condition::VersionSetId(version_set) => format!(
                 "{} {}",
                 self.display_name(self.version_set_name(version_set)),
                 self.display_version_set(version_set)
             ),
+            Condition::Requirement(requirement) => requirement.display(self).to_string(),
+            Condition::Marker(marker) => format!("marker '{}'", self.display_string(marker)),
         }
     }
 
     fn display_version_set(&self, version_set: VersionSetId) -> impl Display + '_ {
-        self.pool.resolve_version_set(version_set).clone()
+        match self.pool.resolve_solvable_set(version_set) {
+            Some(solvables) => solvables
+                .iter()
+                .map(|&solvable| self.pool.resolve_solvable(solvable).record)
+                .join(" | "),
+            None => self.pool.resolve_version_set(version_set).to_string(),
+        }
     }
 
     fn display_string(&self, string_id: StringId) -> impl Display + '_ {
         self.pool.resolve_string(string_id).to_owned()
     }
 
+    fn version_set_bounds(&self, version_set: VersionSetId) -> Option<VersionSetBounds> {
+        if !self.describe_bounds {
+            return None;
+        }
+        let (lower, upper) = self
+            .pool
+            .resolve_version_set(version_set)
+            .bounding_range()?;
+        let bound = |bound: Bound<&Pack>| match bound {
+            Bound::Included(pack) | Bound::Excluded(pack) => Some(VersionBound::Version(
+                self.pool.intern_string(pack.version.to_string()),
+            )),
+            Bound::Unbounded => None,
+        };
+        Some(VersionSetBounds {
+            lower: bound(lower),
+            upper: bound(upper),
+        })
+    }
+
+    fn matches_with_metadata(
+        &self,
+        solvable: SolvableId,
+        version_set: VersionSetId,
+    ) -> Option<bool> {
+        let required = self.required_local_versions.get(&version_set)?;
+        Some(self.local_versions.get(&solvable) == Some(required))
+    }
+
+    fn compare_to_bound(&self, solvable: SolvableId, bound: VersionBound) -> Option<CmpOrdering> {
+        match bound {
+            VersionBound::Solvable(other) => self.compare_solvables(solvable, other),
+            VersionBound::Version(string) => {
+                let version = self.pool.resolve_solvable(solvable).record.version;
+                let bound: u32 = self.pool.resolve_string(string).parse().ok()?;
+                Some(version.cmp(&bound))
+            }
+        }
+    }
+
+    fn version_sets_for_name(&self, name: NameId) -> impl Iterator<Item = VersionSetId> {
+        self.pool.version_sets_for_name(name)
+    }
+
     fn version_set_name(&self, version_set: VersionSetId) -> NameId {
         self.pool.resolve_version_set_package_name(version_set)
     }
@@ -453,12 +733,59 @@ impl Interner for BundleBoxProvider {
     fn solvable_name(&self, solvable: SolvableId) -> NameId {
         self.pool.resolve_solvable(solvable).name
     }
+
+    fn canonical_name(&self, name: NameId) -> NameId {
+        self.aliases.get(&name).copied().unwrap_or(name)
+    }
+
+    fn contains_string(&self, string_id: StringId) -> bool {
+        self.pool.contains_string(string_id)
+    }
+
+    fn contains_version_set(&self, version_set: VersionSetId) -> bool {
+        self.pool.contains_version_set(version_set)
+    }
+
+    fn contains_version_set_union(&self, version_set_union: VersionSetUnionId) -> bool {
+        self.pool.contains_version_set_union(version_set_union)
+    }
+
+    fn version_set_subset(&self, subset: VersionSetId, superset: VersionSetId) -> Option<bool> {
+        let subset = self.pool.resolve_version_set(subset);
+        let superset = self.pool.resolve_version_set(superset);
+        Some(subset.subset_of(superset))
+    }
     fn version_sets_in_union(
         &self,
         version_set_union: VersionSetUnionId,
     ) -> impl Iterator<Item = VersionSetId> {
         self.pool.resolve_version_set_union(version_set_union)
     }
+
+    fn solvable_priority(&self, solvable: SolvableId) -> i64 {
+        self.pool.resolve_solvable(solvable).record.priority
+    }
+
+    fn compare_solvables(&self, a: SolvableId, b: SolvableId) -> Option<CmpOrdering> {
+        let a = self.pool.resolve_solvable(a).record.version;
+        let b = self.pool.resolve_solvable(b).record.version;
+        Some(a.cmp(&b))
+    }
+
+    fn solvable_channel_priority(&self, solvable: SolvableId) -> u32 {
+        self.channel_priorities
+            .get(&solvable)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn provided_version_sets(&self, solvable: SolvableId) -> impl Iterator<Item = VersionSetId> {
+        self.provides
+            .get(&solvable)
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+    }
 }
 
 impl DependencyProvider for BundleBoxProvider {
@@ -466,22 +793,20 @@ impl DependencyProvider for BundleBoxProvider {
         &self,
         candidates: &[SolvableId],
         version_set: VersionSetId,
-        extra: Option<StringId>,
         inverse: bool,
     ) -> Vec<SolvableId> {
+        if let Some(solvables) = self.pool.resolve_solvable_set(version_set) {
+            return candidates
+                .iter()
+                .copied()
+                .filter(|s| solvables.contains(s) != inverse)
+                .collect();
+        }
         let range = self.pool.resolve_version_set(version_set);
         candidates
             .iter()
             .copied()
-            .map(|s| self.pool.resolve_solvable(s))
-            .filter(|s| range.contains(&s.record) != inverse)
-            .filter(|s| {
-                if let Some(extra) = extra {
-                    s.record.extra.contains(&self.pool.resolve_string(extra))
-                } else {
-                    true
-                }
-            })
+            .filter(|s| range.contains(&self.pool.resolve_solvable(*s).record) != inverse)
             .collect()
     }
 
@@ -494,6 +819,35 @@ impl DependencyProvider for BundleBoxProvider {
         });
     }
 
+    async fn sort_candidates_with_previous(
+        &self,
+        solver: &SolverCache<Self>,
+        solvables: &mut [SolvableId],
+        previous: Option<&PreviousSort>,
+    ) {
+        // Candidates with the same version keep the order of the previous solve.
+        if let Some(previous) = previous {
+            solvables.sort_by_key(|&s| previous.position(s).unwrap_or(usize::MAX));
+        }
+        self.sort_candidates(solver, solvables).await;
+    }
+
+    fn excluded_on_platform(&self, solvable: SolvableId, platform: StringId) -> Option<StringId> {
+        self.unavailable_on
+            .get(&solvable)
+            .is_some_and(|platforms| platforms.contains(&platform))
+            .then(|| {
+                self.pool.intern_string(format!(
+                    "not available for {}",
+                    self.pool.resolve_string(platform)
+                ))
+            })
+    }
+
+    fn score_candidate(&self, solvable: SolvableId) -> f64 {
+        self.scores.get(&solvable).copied().unwrap_or_default()
+    }
+
     async fn get_candidates(&self, name: NameId) -> Option<Candidates> {
         let concurrent_requests = self.concurrent_requests.fetch_add(1, Ordering::SeqCst);
         self.concurrent_requests_max.set(
@@ -509,13 +863,13 @@ impl DependencyProvider for BundleBoxProvider {
 
         let package_name = self.pool.resolve_package_name(name);
         let Some(package) = self.packages.get(package_name) else {
-            return self.maybe_delay(None).await;
+            // A name without packages can be a virtual package provided by others.
+            let providers = self.providers_of(name);
+            let candidates = (!providers.is_empty()).then(|| Candidates::new(providers));
+            return self.maybe_delay(candidates).await;
         };
 
-        let mut candidates = Candidates {
-            candidates: Vec::with_capacity(package.len()),
-            ..Candidates::default()
-        };
+        let mut candidates = Candidates::new(Vec::with_capacity(package.len()));
         let favor = self.favored.get(package_name);
         let locked = self.locked.get(package_name);
         let excluded = self.excluded.get(package_name);
@@ -535,14 +889,54 @@ impl DependencyProvider for BundleBoxProvider {
             }
         }
 
+        if self.hint_dependencies_available {
+            candidates.hint_dependencies_available = candidates.candidates.clone();
+        }
+
+        if self.prefetch_dependencies {
+            let prefetched = candidates
+                .candidates
+                .iter()
+                .map(|&solvable| (solvable, self.dependencies(solvable)))
+                .collect();
+            candidates = candidates.with_prefetched(prefetched);
+        }
+
+        if let Some(batch) = self.candidate_batch {
+            let candidates = self.highest_candidates(&candidates.candidates);
+            return self
+                .maybe_delay(Some(
+                    Candidates::new(candidates.iter().copied().take(batch).collect())
+                        .with_more_available(candidates.len() > batch),
+                ))
+                .await;
+        }
+
         self.maybe_delay(Some(candidates)).await
     }
 
-    async fn get_dependencies(
-        &self,
-        solvable: SolvableId,
-        extra: Option<StringId>,
-    ) -> Dependencies {
+    async fn get_more_candidates(&self, name: NameId) -> Option<Candidates> {
+        self.requested_more_candidates.borrow_mut().push(name);
+        let batch = self.candidate_batch?;
+        let package = self.packages.get(self.pool.resolve_package_name(name))?;
+        let candidates = package
+            .keys()
+            .map(|&pack| self.intern_solvable(name, pack))
+            .collect::<Vec<_>>();
+        Some(Candidates::new(
+            self.highest_candidates(&candidates)
+                .into_iter()
+                .skip(batch)
+                .collect(),
+        ))
+    }
+
+    async fn get_dependencies(&self, solvable: SolvableId) -> Dependencies {
+        assert!(
+            !self.prefetch_dependencies,
+            "the dependencies should have been prefetched"
+        );
+
         tracing::info!(
             "get dependencies for {}",
             self.pool
@@ -563,123 +957,15 @@ impl DependencyProvider for BundleBoxProvider {
             "duplicate get_dependencies request"
         );
 
-        let candidate = self.pool.resolve_solvable(solvable);
-        let package_name = self.pool.resolve_package_name(candidate.name);
-        let pack = candidate.record;
-
-        if pack.cancel_during_get_dependencies {
-            self.cancel_solving.set(true);
-            let reason = self.pool.intern_string("cancelled");
-            return self.maybe_delay(Dependencies::Unknown(reason)).await;
-        }
-
-        if pack.unknown_deps {
-            let reason = self.pool.intern_string("could not retrieve deps");
-            return self.maybe_delay(Dependencies::Unknown(reason)).await;
-        }
-
-        let Some(deps) = self.packages.get(package_name).and_then(|v| v.get(&pack)) else {
-            return self
-                .maybe_delay(Dependencies::Known(Default::default()))
-                .await;
-        };
-
-        let extra_deps = if let Some(extra) = extra {
-            deps.extras.get(&extra)
-        } else {
-            None
-        };
-
-        let mut result = KnownDependencies {
-            requirements: Vec::with_capacity(deps.dependencies.len()),
-            constrains: Vec::with_capacity(deps.constrains.len()),
-        };
-        for req in &deps.dependencies {
-            let mut remaining_req_specs = req.iter();
-
-            let first = remaining_req_specs
-                .next()
-                .expect("Dependency spec must have at least one constraint");
-
-            let first_name = self.pool.intern_package_name(&first.name);
-            let first_version_set = self
-                .pool
-                .intern_version_set(first_name, first.versions.clone());
-
-            let requirement = if remaining_req_specs.len() == 0 {
-                let mut conditions = vec![];
-                if let Some(condition) = &first.condition {
-                    conditions.push(self.intern_version_set(condition));
-                }
-                ConditionalRequirement::new(conditions, first_version_set.into())
-            } else {
-                // Check if all specs have the same condition
-                let common_condition = first.condition.as_ref().map(|c| self.intern_version_set(c));
-
-                // Collect version sets for union
-                let mut version_sets = vec![first_version_set];
-                for spec in remaining_req_specs {
-                    // Verify condition matches
-                    if spec.condition.as_ref().map(|c| self.intern_version_set(c))
-                        != common_condition
-                    {
-                        panic!("All specs in a union must have the same condition");
-                    }
-
-                    version_sets.push(self.pool.intern_version_set(
-                        self.pool.intern_package_name(&spec.name),
-                        spec.versions.clone(),
-                    ));
-                }
-
-                // Create union and wrap in conditional if needed
-                let union = self
-                    .pool
-                    .intern_version_set_union(version_sets[0], version_sets.into_iter().skip(1));
-
-                let mut conditions = vec![];
-                if let Some(condition) = common_condition {
-                    conditions.push(condition);
-                }
-                ConditionalRequirement::new(conditions, union.into())
-            };
-
-            result.requirements.push(requirement);
-        }
-
-        for req in extra_deps {
-            let mut remaining_req_specs = req.iter();
-
-            let first = remaining_req_specs
-                .next()
-                .expect("Dependency spec must have at least one constraint");
-
-            let first_name = self.pool.intern_package_name(&first.name);
-            let first_version_set = self
-                .pool
-                .intern_version_set(first_name, first.versions.clone());
-
-            let requirement = if remaining_req_specs.len() == 0 {
-                let mut conditions = vec![Condition::Extra(extra)];
-                if let Some(condition) = &first.condition {
-                    conditions.push(self.intern_version_set(condition));
-                }
-                ConditionalRequirement::new(conditions, first_version_set.into())
-            } else {
-                // TODO: Implement extra deps for union
-                todo!("extra deps for union not implemented")
-            };
-
-            result.requirements.push(requirement);
-        }
-
-        for req in &deps.constrains {
-            let dep_name = self.pool.intern_package_name(&req.name);
-            let dep_spec = self.pool.intern_version_set(dep_name, req.versions.clone());
-            result.constrains.push(dep_spec);
-        }
+        let dependencies = self.dependencies(solvable);
+        self.maybe_delay(dependencies).await
+    }
 
-        self.maybe_delay(Dependencies::Known(result)).await
+    async fn get_dependencies_batch(&self, solvables: &[SolvableId]) -> Vec<Dependencies> {
+        self.requested_dependency_batches
+            .borrow_mut()
+            .push(solvables.to_vec());
+        futures::future::join_all(solvables.iter().map(|&s| self.get_dependencies(s))).await
     }
 
     fn should_cancel_with_value(&self) -> Option<Box<dyn Any>> {
@@ -689,6 +975,17 @@ impl DependencyProvider for BundleBoxProvider {
             None
         }
     }
+
+    fn known_names(&self) -> impl Iterator<Item = NameId> + '_ {
+        self.packages
+            .iter()
+            .filter(|(_, packs)| !packs.is_empty())
+            .filter_map(|(name, _)| self.pool.lookup_package_name(name))
+    }
+
+    fn evaluate_marker(&self, marker: StringId) -> bool {
+        self.markers.contains(&marker)
+    }
 }
 
 /// Create a string from a [`Transaction`]
@@ -743,8 +1040,8 @@ fn solve_snapshot(mut provider: BundleBoxProvider, specs: &[&str]) -> String {
     provider.sleep_before_return = true;
 
     let requirements = provider.parse_requirements(specs);
-    let mut solver = Solver::new(provider).with_runtime(runtime);
-    let problem = Problem::new().requirements(requirements.into_iter().map(|r| r.into()).collect());
+    let mut solver = SolverBuilder::new().with_runtime(runtime).build(provider);
+    let problem = Problem::new().requirements(requirements);
     match solver.solve(problem) {
         Ok(solvables) => transaction_to_string(solver.provider(), &solvables),
         Err(UnsolvableOrCancelled::Unsolvable(conflict)) => {
@@ -853,1120 +1150,3654 @@ fn test_resolve_with_concurrent_metadata_fetching() {
     assert_eq!(2, max_concurrent_requests.get());
 }
 
-/// In case of a conflict the version should not be selected with the conflict
+/// Solving asynchronously should periodically yield to the executor so other
+/// tasks are not starved while the solver is busy.
 #[test]
-fn test_resolve_with_conflict() {
+fn test_solve_async_yields_to_other_tasks() {
     let provider = BundleBoxProvider::from_packages(&[
-        ("asdf", 4, vec!["conflicting 1"], &[]),
-        ("asdf", 3, vec!["conflicting 0"], &[]),
-        ("efgh", 7, vec!["conflicting 0"], &[]),
-        ("efgh", 6, vec!["conflicting 0"], &[]),
-        ("conflicting", 1, vec![], &[]),
-        ("conflicting", 0, vec![], &[]),
+        ("asdf", 4, vec!["conflicting 1"]),
+        ("asdf", 3, vec!["conflicting 0"]),
+        ("efgh", 7, vec!["conflicting 0"]),
+        ("efgh", 6, vec!["conflicting 0"]),
+        ("conflicting", 1, vec![]),
+        ("conflicting", 0, vec![]),
     ]);
-    let result = solve_snapshot(provider, &["asdf", "efgh"]);
-    insta::assert_snapshot!(result);
+    let requirements = provider.requirements(&["asdf", "efgh"]);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let local_set = tokio::task::LocalSet::new();
+    let other_task_ran = Rc::new(Cell::new(false));
+
+    let result = local_set.block_on(&runtime, async {
+        let other_task_ran_clone = other_task_ran.clone();
+        tokio::task::spawn_local(async move { other_task_ran_clone.set(true) });
+
+        let mut solver = SolverBuilder::new().with_yield_interval(1).build(provider);
+        let problem = Problem::new().requirements(requirements);
+        let solved = solver.solve_async(problem).await.unwrap();
+
+        // The dependency provider never yields, so the only way for the other task
+        // to have run is if the solver yielded.
+        assert!(other_task_ran.get());
+
+        transaction_to_string(solver.provider(), &solved)
+    });
+
+    assert_snapshot!(result, @r###"
+    asdf=3
+    conflicting=0
+    efgh=7
+    "###);
 }
 
-/// The non-existing package should not be selected
+/// Duplicate requirements should not result in additional clauses.
 #[test]
-#[traced_test]
-fn test_resolve_with_nonexisting() {
-    let provider = BundleBoxProvider::from_packages(&[
-        ("asdf", 4, vec!["b"], &[]),
-        ("asdf", 3, vec![], &[]),
-        ("b", 1, vec!["idontexist"], &[]),
-    ]);
-    let requirements = provider.requirements(&["asdf"]);
-    let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let pool = &solver.provider().pool;
-
-    assert_eq!(solved.len(), 1);
+fn test_duplicate_requirements_are_deduplicated() {
+    let solve = |packages: &[(&str, u32, Vec<&str>)], specs: &[&str]| {
+        let provider = BundleBoxProvider::from_packages(packages);
+        let requirements = provider.requirements(specs);
+        let mut solver = Solver::new(provider);
+        let problem = Problem::new().requirements(requirements);
+        let solved = solver.solve(problem).unwrap();
+        (
+            transaction_to_string(solver.provider(), &solved),
+            solver.stats(),
+        )
+    };
 
-    let solvable = pool.resolve_solvable(solved[0]);
+    let (once, once_stats) = solve(&[("a", 1, vec!["b"]), ("b", 1, vec![])], &["a"]);
+    let (twice, twice_stats) = solve(&[("a", 1, vec!["b", "b"]), ("b", 1, vec![])], &["a", "a"]);
 
-    assert_eq!(pool.resolve_package_name(solvable.name), "asdf");
-    assert_eq!(solvable.record.version, 3);
+    assert_eq!(once, twice);
+    assert_eq!(once_stats.num_clauses, twice_stats.num_clauses);
 }
 
 #[test]
-#[traced_test]
-fn test_resolve_with_nested_deps() {
+fn test_on_backtrack_observes_backjumps() {
     let provider = BundleBoxProvider::from_packages(&[
-        (
-            "apache-airflow",
-            3,
-            vec!["opentelemetry-api 2..4", "opentelemetry-exporter-otlp"],
-            &[],
-        ),
-        (
-            "apache-airflow",
-            2,
-            vec!["opentelemetry-api 2..4", "opentelemetry-exporter-otlp"],
-            &[],
-        ),
-        ("apache-airflow", 1, vec![], &[]),
-        ("opentelemetry-api", 3, vec!["opentelemetry-sdk"], &[]),
-        ("opentelemetry-api", 2, vec![], &[]),
-        ("opentelemetry-api", 1, vec![], &[]),
-        (
-            "opentelemetry-exporter-otlp",
-            1,
-            vec!["opentelemetry-grpc"],
-            &[],
-        ),
-        ("opentelemetry-grpc", 1, vec!["opentelemetry-api 1"], &[]),
+        ("asdf", 4, vec!["conflicting 1"]),
+        ("asdf", 3, vec!["conflicting 0"]),
+        ("efgh", 7, vec!["conflicting 0"]),
+        ("efgh", 6, vec!["conflicting 0"]),
+        ("conflicting", 1, vec![]),
+        ("conflicting", 0, vec![]),
     ]);
-    let requirements = provider.requirements(&["apache-airflow"]);
-    let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let pool = &solver.provider().pool;
+    let requirements = provider.requirements(&["asdf", "efgh"]);
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut solver = SolverBuilder::new()
+        .on_backtrack({
+            let events = events.clone();
+            move |event| events.borrow_mut().push(event)
+        })
+        .build(provider);
+    solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
 
-    assert_eq!(solved.len(), 1);
+    let events = events.take();
+    assert!(!events.is_empty());
+    for event in events {
+        assert!(event.to_level < event.from_level);
+        assert!(!event.undone.is_empty());
+    }
+}
 
-    let solvable = pool.resolve_solvable(solved[0]);
+#[test]
+fn test_on_candidate_excluded() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 2, vec![]), ("a", 1, vec![])]);
+    let a2 = provider.solvable_id("a", 2);
+    let requirements = provider.requirements(&["a"]);
+    let constraints = provider.version_sets(&["a 1"]);
+    let excluded = Rc::new(RefCell::new(Vec::new()));
+    let mut solver = SolverBuilder::new()
+        .on_candidate_excluded({
+            let excluded = excluded.clone();
+            move |solvable, reason| excluded.borrow_mut().push((solvable, reason))
+        })
+        .build(provider);
+    solver
+        .solve(
+            Problem::new()
+                .requirements(requirements)
+                .constraints(constraints),
+        )
+        .unwrap();
 
-    assert_eq!(pool.resolve_package_name(solvable.name), "apache-airflow");
-    assert_eq!(solvable.record.version, 1);
+    assert_eq!(excluded.take(), vec![(a2, ExclusionReason::Constrained)]);
 }
 
 #[test]
-#[traced_test]
-fn test_resolve_with_unknown_deps() {
-    let mut provider = BundleBoxProvider::new();
-    provider.add_package(
-        "opentelemetry-api",
-        Pack::new(3).with_unknown_deps(),
-        &[],
-        &[],
-        &[],
+fn test_eliminated_candidates() {
+    let mut provider =
+        BundleBoxProvider::from_packages(&[("a", 3, vec![]), ("a", 2, vec![]), ("a", 1, vec![])]);
+    provider.exclude("a", 2, "broken build");
+    let a = provider.package_name("a");
+    let a3 = provider.solvable_id("a", 3);
+    let a2 = provider.solvable_id("a", 2);
+    let requirements = provider.requirements(&["a"]);
+    let constraints = provider.version_sets(&["a 1..3"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(
+            Problem::new()
+                .requirements(requirements)
+                .constraints(constraints),
+        )
+        .unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @"a=1");
+
+    let mut eliminated = solver.eliminated_candidates(a);
+    eliminated.sort_by_key(|&(solvable, _)| solvable != a3);
+    assert_eq!(
+        eliminated,
+        vec![
+            (a3, ExclusionReason::Constrained),
+            (a2, ExclusionReason::Excluded)
+        ]
     );
-    provider.add_package("opentelemetry-api", Pack::new(2), &[], &[], &[]);
-    let requirements = provider.requirements(&["opentelemetry-api"]);
+}
+
+#[test]
+fn test_difficulty_estimate() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![])]);
+    let requirements = provider.requirements(&["a"]);
     let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let pool = &solver.provider().pool;
+    let trivial = solver
+        .difficulty_estimate(requirements, Vec::new())
+        .unwrap();
 
-    assert_eq!(solved.len(), 1);
+    let provider = BundleBoxProvider::from_packages(&[
+        ("asdf", 4, vec!["conflicting 1"]),
+        ("asdf", 3, vec!["conflicting 0"]),
+        ("efgh", 7, vec!["conflicting 0"]),
+        ("efgh", 6, vec!["conflicting 0"]),
+        ("conflicting", 1, vec![]),
+        ("conflicting", 0, vec![]),
+    ]);
+    let requirements = provider.requirements(&["asdf", "efgh"]);
+    let constraints = provider.version_sets(&["conflicting 0"]);
+    let mut solver = Solver::new(provider);
+    let dense = solver
+        .difficulty_estimate(requirements, constraints)
+        .unwrap();
 
-    let solvable = pool.resolve_solvable(solved[0]);
+    assert!(trivial.num_variables < dense.num_variables);
+    assert!(trivial.constraint_density < dense.constraint_density);
+    assert!(trivial.score() < dense.score());
+}
+
+#[test]
+fn test_lbd_histogram_counts_learnt_clauses() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("asdf", 4, vec!["conflicting 1"]),
+        ("asdf", 3, vec!["conflicting 0"]),
+        ("efgh", 7, vec!["conflicting 0"]),
+        ("efgh", 6, vec!["conflicting 0"]),
+        ("conflicting", 1, vec![]),
+        ("conflicting", 0, vec![]),
+    ]);
+    let requirements = provider.requirements(&["asdf", "efgh"]);
+    let mut solver = Solver::new(provider);
+    solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
 
+    let stats = solver.stats();
+    assert!(stats.num_learnt_clauses > 0);
     assert_eq!(
-        pool.resolve_package_name(solvable.name),
-        "opentelemetry-api"
+        stats.lbd_histogram.iter().sum::<u32>() as usize,
+        stats.num_learnt_clauses
     );
-    assert_eq!(solvable.record.version, 2);
 }
 
 #[test]
-#[traced_test]
-fn test_resolve_and_cancel() {
-    let mut provider = BundleBoxProvider::new();
-    provider.add_package(
-        "opentelemetry-api",
-        Pack::new(3).with_unknown_deps(),
-        &[],
-        &[],
-        &[],
+fn test_watch_traversals_grow_with_problem_size() {
+    let solve_chain = |length: usize| {
+        let names: Vec<String> = (0..length).map(|i| format!("p{i}")).collect();
+        let mut packages = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            for version in 1..=2 {
+                let dependencies = names.get(i + 1).map(String::as_str).into_iter().collect();
+                packages.push((name.as_str(), version, dependencies));
+            }
+        }
+
+        let provider = BundleBoxProvider::from_packages(&packages);
+        let requirements = provider.requirements(&["p0"]);
+        let mut solver = Solver::new(provider);
+        solver
+            .solve(Problem::new().requirements(requirements))
+            .unwrap();
+        solver.stats()
+    };
+
+    let small = solve_chain(5);
+    let large = solve_chain(50);
+    assert!(small.watch.num_watch_traversals > 0);
+    assert!(small.watch.num_watch_traversals < large.watch.num_watch_traversals);
+    assert!(large.average_watches_per_clause() > 0.0);
+
+    #[cfg(feature = "watch-stats")]
+    assert!(!large.watch.most_propagating_clauses.is_empty());
+}
+
+#[test]
+fn test_group_version_sets_by_name() {
+    let provider = BundleBoxProvider::new();
+    let version_sets = provider.version_sets(&["a 1", "b 1", "a 2..4", "b 3"]);
+    let groups = group_version_sets_by_name(version_sets.iter().copied(), &provider);
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(
+        groups[&provider.package_name("a")],
+        vec![version_sets[0], version_sets[2]]
     );
-    provider.add_package(
-        "opentelemetry-api",
-        Pack::new(2).cancel_during_get_dependencies(),
-        &[],
-        &[],
-        &[],
+    assert_eq!(
+        groups[&provider.package_name("b")],
+        vec![version_sets[1], version_sets[3]]
     );
-    let error = solve_unsat(provider, &["opentelemetry-api"]);
-    insta::assert_snapshot!(error);
 }
 
-/// Locking a specific package version in this case a lower version namely `3`
-/// should result in the higher package not being considered
 #[test]
-fn test_resolve_locked_top_level() {
-    let mut provider =
-        BundleBoxProvider::from_packages(&[("asdf", 4, vec![], &[]), ("asdf", 3, vec![], &[])]);
-    provider.set_locked("asdf", 3);
+fn test_solution_hash() {
+    let solve = |packages: &[(&str, u32, Vec<&str>)], interned_first: Option<(&str, u32)>| {
+        let provider = BundleBoxProvider::from_packages(packages);
+        if let Some((name, version)) = interned_first {
+            provider.solvable_id(name, version);
+        }
+        let requirements = provider.requirements(&["a"]);
+        let mut solver = Solver::new(provider);
+        let solved = solver
+            .solve(Problem::new().requirements(requirements))
+            .unwrap();
+        (solution_hash(&solved, solver.provider()), solved)
+    };
 
-    let requirements = provider.requirements(&["asdf"]);
+    let packages = [("a", 1, vec!["b"]), ("b", 1, vec![]), ("c", 1, vec![])];
+    let (hash, solved) = solve(&packages, None);
 
-    let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let pool = &solver.provider().pool;
+    // Interning another solvable first shifts the ids of the solution.
+    let (other_hash, other_solved) = solve(&packages, Some(("c", 1)));
+    assert_ne!(
+        solved.iter().sorted().collect_vec(),
+        other_solved.iter().sorted().collect_vec()
+    );
+    assert_eq!(hash, other_hash);
 
-    assert_eq!(solved.len(), 1);
-    let solvable_id = solved[0];
-    assert_eq!(pool.resolve_solvable(solvable_id).record.version, 3);
+    let (different_hash, _) = solve(&[("a", 1, vec!["b"]), ("b", 2, vec![])], None);
+    assert_ne!(hash, different_hash);
 }
 
-/// Should ignore lock when it is not a top level package and a newer version
-/// exists without it
 #[test]
-fn test_resolve_ignored_locked_top_level() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("asdf", 4, vec![], &[]),
-        ("asdf", 3, vec!["fgh"], &[]),
-        ("fgh", 1, vec![], &[]),
-    ]);
+fn test_find_redundant_requirements() {
+    let provider = BundleBoxProvider::new();
+    let requirements = provider.requirements(&["a 1..10", "b", "a 2..5", "c 1", "c 1"]);
 
-    provider.set_locked("fgh", 1);
+    assert_eq!(
+        find_redundant_requirements(&requirements, &provider),
+        vec![(0, 2), (4, 3)]
+    );
+}
 
-    let requirements = provider.requirements(&["asdf"]);
-    let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let pool = &solver.provider().pool;
+#[test]
+fn test_pool_capacity_does_not_change_ids() {
+    let intern_all = |pool: &Pool<Ranges<u32>>| {
+        let mut ids = Vec::new();
+        for i in 0..300u32 {
+            let name = pool.intern_package_name(format!("package-{}", i % 150));
+            let version_set = pool.intern_version_set(name, Ranges::singleton(i));
+            let solvable = pool.intern_solvable(name, i);
+            let string = pool.intern_string(format!("extra-{}", i % 50));
+            ids.push((name, version_set, solvable, string));
+        }
+        ids
+    };
 
-    assert_eq!(solved.len(), 1);
-    let solvable = pool.resolve_solvable(solved[0]);
+    let mut reserved = Pool::with_capacity(10, 10, 10, 10);
+    reserved.reserve_package_names(1000);
+    reserved.reserve_version_sets(1000);
+    reserved.reserve_solvables(1000);
+    reserved.reserve_strings(1000);
 
-    assert_eq!(pool.resolve_package_name(solvable.name), "asdf");
-    assert_eq!(solvable.record.version, 4);
+    assert_eq!(intern_all(&Pool::new()), intern_all(&reserved));
 }
 
-/// Test checks if favoring without a conflict results in a package upgrade
 #[test]
-fn test_resolve_favor_without_conflict() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("a", 1, vec![], &[]),
-        ("a", 2, vec![], &[]),
-        ("b", 1, vec![], &[]),
-        ("b", 2, vec![], &[]),
-    ]);
-    provider.set_favored("a", 1);
-    provider.set_favored("b", 1);
-
-    // Already installed: A=1; B=1
-    let result = solve_snapshot(provider, &["a", "b 2"]);
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=2
-        "###);
+fn test_version_sets_for_name() {
+    let provider = BundleBoxProvider::from_packages(&[("foo", 1, vec![]), ("bar", 1, vec![])]);
+    let foo_version_sets = provider.version_sets(&["foo 1..3", "foo 2", "foo"]);
+    let bar = provider.version_sets(&["bar 1..3"]);
+    // Interning a version set again does not return it twice.
+    provider.version_sets(&["foo 2"]);
+
+    let name = provider.package_name("foo");
+    let version_sets = provider.version_sets_for_name(name).collect::<Vec<_>>();
+    assert_eq!(version_sets, foo_version_sets);
+    assert!(!version_sets.contains(&bar[0]));
+    let displayed = version_sets
+        .iter()
+        .map(|&version_set| provider.display_version_set(version_set))
+        .join(" | ");
+    assert_snapshot!(displayed, @">=1, <3 | >=2, <3 | *");
 }
 
 #[test]
-fn test_resolve_favor_with_conflict() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("a", 1, vec!["c 1"], &[]),
-        ("a", 2, vec![], &[]),
-        ("b", 1, vec!["c 1"], &[]),
-        ("b", 2, vec!["c 2"], &[]),
-        ("c", 1, vec![], &[]),
-        ("c", 2, vec![], &[]),
+fn test_any_version_set_matches_all_candidates() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("a", 3, vec![]),
+        ("b", 1, vec![]),
     ]);
-    provider.set_favored("a", 1);
-    provider.set_favored("b", 1);
-    provider.set_favored("c", 1);
-
-    let result = solve_snapshot(provider, &["a", "b 2"]);
-    insta::assert_snapshot!(result, @r###"
-        a=2
-        b=2
-        c=2
-        "###);
+    let name = provider.pool.intern_package_name("a");
+    let any = provider.pool.any_version_set(name);
+    assert_eq!(any, provider.pool.any_version_set(name));
+
+    let (candidates, matching) = NowOrNeverRuntime.block_on(async {
+        let candidates = provider.get_candidates(name).await.unwrap().candidates;
+        let matching = provider.filter_candidates(&candidates, any, false).await;
+        (candidates, matching)
+    });
+    assert_eq!(candidates.len(), 3);
+    assert_eq!(candidates, matching);
 }
 
 #[test]
-fn test_resolve_cyclic() {
-    let provider = BundleBoxProvider::from_packages(&[
-        ("a", 2, vec!["b 0..10"], &[]),
-        ("b", 5, vec!["a 2..4"], &[]),
-    ]);
-    let requirements = provider.requirements(&["a 0..100"]);
+fn test_lazy_version_sets_are_only_constructed_when_used() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("b", 1, vec![])]);
+    let a = provider.pool.intern_package_name("a");
+    let b = provider.pool.intern_package_name("b");
+
+    let constructed = Arc::new(AtomicUsize::new(0));
+    let used = provider.pool.intern_version_set_lazy(a, {
+        let constructed = constructed.clone();
+        move || {
+            constructed.fetch_add(1, Ordering::SeqCst);
+            Ranges::full()
+        }
+    });
+    provider
+        .pool
+        .intern_version_set_lazy(b, || panic!("the version set should not be constructed"));
+
     let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
+    let problem = Problem::new().requirements(vec![used.into()]);
     let solved = solver.solve(problem).unwrap();
-
     let result = transaction_to_string(solver.provider(), &solved);
-    insta::assert_snapshot!(result, @r###"
-        a=2
-        b=5
-        "###);
+    assert_snapshot!(result, @"a=1");
+    assert_eq!(constructed.load(Ordering::SeqCst), 1);
 }
 
 #[test]
-fn test_resolve_union_requirements() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("a", 1, vec![], &[]),
-        ("b", 1, vec![], &[]),
-        ("c", 1, vec!["a"], &[]),
-        ("d", 1, vec!["b"], &[]),
-        ("e", 1, vec!["a | b"], &[]),
-    ]);
-
-    // Make d conflict with a=1
-    provider.add_package("f", 1.into(), &["b"], &["a 2"], &["b"]);
-
-    let result = solve_snapshot(provider, &["c | d", "e", "f"]);
-    assert_snapshot!(result, @r###"
-        b=1
-        d=1
-        e=1
-        f=1
-        "###);
+fn test_solve_empty_problem() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![])]);
+    let mut solver = Solver::new(provider);
+    let solved = solver.solve(Problem::new()).unwrap();
+    assert!(solved.is_empty());
+    assert_eq!(solver.stats().num_clauses, 0);
+    assert!(solver.provider().requested_candidates.borrow().is_empty());
 }
 
 #[test]
-fn test_unsat_locked_and_excluded() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("asdf", 1, vec!["c 2"]),
-        ("c", 2, vec![]),
-        ("c", 1, vec![]),
-    ]);
-    provider.set_locked("c", 1);
-    insta::assert_snapshot!(solve_snapshot(provider, &["asdf"]));
+fn test_solve_constraints_without_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![])]);
+    let constraints = provider.version_sets(&["a 1", "b 3"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().constraints(constraints))
+        .unwrap();
+    assert!(solved.is_empty());
+    assert_eq!(solver.stats().num_clauses, 0);
 }
 
 #[test]
-#[tracing_test::traced_test]
-fn test_unsat_no_candidates_for_child_1() {
-    let provider = BundleBoxProvider::from_packages(&[("asdf", 1, vec!["c 2"]), ("c", 1, vec![])]);
-    let error = solve_unsat(provider, &["asdf"]);
-    insta::assert_snapshot!(error);
+fn test_pinned_solvable_is_used() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("a", 3, vec![]),
+        ("b", 1, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a"]);
+    let pins = vec![provider.solvable_id("a", 2), provider.solvable_id("b", 1)];
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements).pins(pins);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r###"
+    a=2
+    b=1
+    "###);
 }
 
-//
 #[test]
-fn test_unsat_no_candidates_for_child_2() {
-    let provider = BundleBoxProvider::from_packages(&[("a", 41, vec!["B 0..20"])]);
-    let error = solve_unsat(provider, &["a 0..1000"]);
-    insta::assert_snapshot!(error);
+fn test_solvable_priority_breaks_ties() {
+    let mut provider = BundleBoxProvider::new();
+    provider.add_package("a", Pack::new(1), &[], &[], &[]);
+    provider.add_package("a", Pack::new(1).with_priority(5), &[], &[], &[]);
+    provider.add_package("a", Pack::new(1).with_priority(2), &[], &[], &[]);
+    let requirements = provider.requirements(&["a"]);
+    let preferred = provider.solvable_id("a", Pack::new(1).with_priority(5));
+
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_eq!(solved, vec![preferred]);
 }
 
+/// The priority only breaks ties, a newer version is still preferred
 #[test]
-fn test_unsat_missing_top_level_dep_1() {
-    let provider = BundleBoxProvider::from_packages(&[("asdf", 1, vec![])]);
-    let error = solve_unsat(provider, &["fghj"]);
-    insta::assert_snapshot!(error);
+fn test_solvable_priority_keeps_provider_order() {
+    let mut provider = BundleBoxProvider::new();
+    provider.add_package("a", Pack::new(1).with_priority(5), &[], &[], &[]);
+    provider.add_package("a", Pack::new(2), &[], &[], &[]);
+    let requirements = provider.requirements(&["a"]);
+    let newest = provider.solvable_id("a", Pack::new(2));
+
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_eq!(solved, vec![newest]);
 }
 
 #[test]
-fn test_unsat_missing_top_level_dep_2() {
-    let provider = BundleBoxProvider::from_packages(&[("a", 41, vec!["b 15"]), ("b", 15, vec![])]);
-    let error = solve_unsat(provider, &["a 41", "b 14"]);
-    insta::assert_snapshot!(error);
-}
-
-#[test]
-#[tracing_test::traced_test]
-fn test_unsat_after_backtracking() {
-    let provider = BundleBoxProvider::from_packages(&[
-        ("b", 7, vec!["d 1"]),
-        ("b", 6, vec!["d 1"]),
-        ("c", 1, vec!["d 2"]),
-        ("c", 2, vec!["d 2"]),
-        ("d", 2, vec![]),
-        ("d", 1, vec![]),
-        ("e", 1, vec![]),
-        ("e", 2, vec![]),
-    ]);
-
-    let error = solve_unsat(provider, &["b", "c", "e"]);
-    insta::assert_snapshot!(error);
-}
-
-#[test]
-fn test_unsat_incompatible_root_requirements() {
-    let provider = BundleBoxProvider::from_packages(&[("a", 2, vec![]), ("a", 5, vec![])]);
-    let error = solve_unsat(provider, &["a 0..4", "a 5..10"]);
-    insta::assert_snapshot!(error);
-}
-
-#[test]
-fn test_unsat_bluesky_conflict() {
-    let provider = BundleBoxProvider::from_packages(&[
-        ("suitcase-utils", 54, vec![]),
-        ("suitcase-utils", 53, vec![]),
-        (
-            "bluesky-widgets",
-            42,
-            vec![
-                "bluesky-live 0..10",
-                "numpy 0..10",
-                "python 0..10",
-                "suitcase-utils 0..54",
-            ],
-        ),
-        ("bluesky-live", 1, vec![]),
-        ("numpy", 1, vec![]),
-        ("python", 1, vec![]),
-    ]);
-    let error = solve_unsat(
-        provider,
-        &["bluesky-widgets 0..100", "suitcase-utils 54..100"],
-    );
-    insta::assert_snapshot!(error);
-}
-
-#[test]
-fn test_unsat_pubgrub_article() {
-    // Taken from the pubgrub article: https://nex3.medium.com/pubgrub-2fb6470504f
-    let provider = BundleBoxProvider::from_packages(&[
-        ("menu", 15, vec!["dropdown 2..3"]),
-        ("menu", 10, vec!["dropdown 1..2"]),
-        ("dropdown", 2, vec!["icons 2"]),
-        ("dropdown", 1, vec!["intl 3"]),
-        ("icons", 2, vec![]),
-        ("icons", 1, vec![]),
-        ("intl", 5, vec![]),
-        ("intl", 3, vec![]),
-    ]);
-    let error = solve_unsat(provider, &["menu", "icons 1", "intl 5"]);
-    insta::assert_snapshot!(error);
-}
-
-#[test]
-fn test_unsat_applies_graph_compression() {
-    let provider = BundleBoxProvider::from_packages(&[
-        ("a", 10, vec!["b"]),
-        ("a", 9, vec!["b"]),
-        ("b", 100, vec!["c 0..100"]),
-        ("b", 42, vec!["c 0..100"]),
-        ("c", 103, vec![]),
-        ("c", 101, vec![]),
-        ("c", 100, vec![]),
-        ("c", 99, vec![]),
-    ]);
-    let error = solve_unsat(provider, &["a", "c 101..104"]);
-    insta::assert_snapshot!(error);
-}
+fn test_previous_sorts() {
+    // Two builds of `a 1`, whose priorities are swapped between the solves.
+    let create_provider = |priorities: [i64; 2]| {
+        let mut provider = BundleBoxProvider::new();
+        for priority in priorities {
+            provider.add_package("a", Pack::new(1).with_priority(priority), &[], &[], &[]);
+        }
+        let builds = priorities
+            .map(|priority| provider.solvable_id("a", Pack::new(1).with_priority(priority)));
+        let requirements = provider.requirements(&["a"]);
+        (provider, builds, requirements)
+    };
+
+    let (provider, builds, requirements) = create_provider([0, 1]);
+    let a = provider.package_name("a");
+    let mut solver = Solver::new(provider);
+    let first = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_eq!(first, vec![builds[1]]);
+    let sorts = solver.candidate_sorts();
+    assert_eq!(sorts[&a].order, vec![builds[1], builds[0]]);
 
-#[test]
-fn test_unsat_constrains() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("a", 10, vec!["b 50..100"]),
-        ("a", 9, vec!["b 50..100"]),
-        ("b", 50, vec![]),
-        ("b", 42, vec![]),
-    ]);
+    // Without the previous order the other build is selected now.
+    let (provider, builds, requirements) = create_provider([1, 0]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_eq!(solved, vec![builds[0]]);
 
-    provider.add_package("c", 10.into(), &[], &["b 0..50"], &[]);
-    provider.add_package("c", 8.into(), &[], &["b 0..50"], &[]);
-    let error = solve_unsat(provider, &["a", "c"]);
-    insta::assert_snapshot!(error);
+    // With the previous order the same build is selected again.
+    let (provider, _, requirements) = create_provider([1, 0]);
+    let mut solver = SolverBuilder::new()
+        .with_previous_sorts(sorts.clone())
+        .build(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_eq!(solved, first);
+
+    // The previous order also reaches a provider that is wrapped for
+    // instrumentation.
+    let (provider, _, requirements) = create_provider([1, 0]);
+    let mut solver = SolverBuilder::new()
+        .with_previous_sorts(sorts)
+        .build(InstrumentedInterner::new(provider));
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_eq!(solved, first);
 }
 
 #[test]
-#[tracing_test::traced_test]
-fn test_unsat_constrains_2() {
+fn test_solvable_channel_priority() {
     let mut provider = BundleBoxProvider::from_packages(&[
         ("a", 1, vec!["b"]),
         ("a", 2, vec!["b"]),
-        ("b", 1, vec!["c 1"]),
-        ("b", 2, vec!["c 2"]),
+        ("a", 3, vec!["b"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
     ]);
-
-    provider.add_package("c", 1.into(), &[], &["a 3"], &[]);
-    provider.add_package("c", 2.into(), &[], &["a 3"], &[]);
-    let error = solve_unsat(provider, &["a"]);
-    insta::assert_snapshot!(error);
+    // The newest version of `a` is only available from a channel with a lower priority.
+    provider
+        .channel_priorities
+        .insert(provider.solvable_id("a", 3), 1);
+    let requirements = provider.requirements(&["a"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    a=2
+    b=2
+    ");
 }
 
 #[test]
-fn test_missing_dep() {
-    let provider = BundleBoxProvider::from_packages(&[("a", 2, vec!["missing"]), ("a", 1, vec![])]);
-    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
-}
+fn test_matches_with_metadata() {
+    let create_provider = || {
+        let mut provider = BundleBoxProvider::new();
+        provider.add_package("a", Pack::new(1), &[], &[], &[]);
+        provider.add_package("a", Pack::new(1).with_priority(5), &[], &[], &[]);
+        let cpu = provider.solvable_id("a", Pack::new(1));
+        let cuda = provider.solvable_id("a", Pack::new(1).with_priority(5));
+        let cpu_local = provider.pool.intern_string("cpu");
+        let cuda_local = provider.pool.intern_string("cuda");
+        provider.local_versions.insert(cpu, cpu_local);
+        provider.local_versions.insert(cuda, cuda_local);
+        // `a 1+cpu`: both solvables have version 1, but only one has the cpu local version.
+        let version_set = provider.version_sets(&["a 1"])[0];
+        provider
+            .required_local_versions
+            .insert(version_set, cpu_local);
+        (provider, cpu, cuda, version_set)
+    };
+
+    // The solvable with the cpu local version is selected, even though the other one has a
+    // higher priority.
+    let (provider, cpu, cuda, version_set) = create_provider();
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(vec![version_set.into()]))
+        .unwrap();
+    assert_eq!(solved, vec![cpu]);
+    // The local version is also taken into account when verifying a solution.
+    assert!(verify_solution(&[cuda], &[version_set.into()], &[], solver.provider()).is_err());
 
-#[test]
-#[tracing_test::traced_test]
-fn test_no_backtracking() {
-    let provider = BundleBoxProvider::from_packages(&[
-        ("quetz-server", 2, vec!["pydantic 10..20"]),
-        ("quetz-server", 1, vec!["pydantic 0..10"]),
-        ("pydantic", 1, vec![]),
-        ("pydantic", 2, vec![]),
-        ("pydantic", 3, vec![]),
-        ("pydantic", 4, vec![]),
-        ("pydantic", 5, vec![]),
-        ("pydantic", 6, vec![]),
-        ("pydantic", 7, vec![]),
-        ("pydantic", 8, vec![]),
-        ("pydantic", 9, vec![]),
-        ("pydantic", 10, vec![]),
-        ("pydantic", 11, vec![]),
-        ("pydantic", 12, vec![]),
-        ("pydantic", 13, vec![]),
-        ("pydantic", 14, vec![]),
-    ]);
-    insta::assert_snapshot!(solve_snapshot(
-        provider,
-        &["quetz-server", "pydantic 0..10"]
-    ));
+    // Constraints forbid the solvables that do not match their local version.
+    let (provider, cpu, _, version_set) = create_provider();
+    let requirements = provider.requirements(&["a"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(
+            Problem::new()
+                .requirements(requirements)
+                .constraints(vec![version_set]),
+        )
+        .unwrap();
+    assert_eq!(solved, vec![cpu]);
 }
 
 #[test]
-#[tracing_test::traced_test]
-fn test_incremental_crash() {
+fn test_recommends() {
     let provider = BundleBoxProvider::from_packages(&[
-        ("a", 3, vec!["missing"]),
-        ("a", 2, vec!["missing"]),
-        ("a", 1, vec!["b"]),
-        ("b", 2, vec!["a 2..4"]),
+        ("a", 1, vec!["b 1"]),
         ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("c", 1, vec![]),
     ]);
-    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
+    let requirements = provider.requirements(&["a"]);
+    let recommends = provider.requirements(&["b 2", "c"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .recommends(recommends);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r###"
+    a=1
+    b=1
+    c=1
+    "###);
 }
 
 #[test]
-#[traced_test]
-fn test_excluded() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("a", 2, vec!["b"]),
-        ("a", 1, vec!["c"]),
+fn test_candidate_limit_widens_window() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b"]),
         ("b", 1, vec![]),
-        ("c", 1, vec![]),
+        ("b", 2, vec![]),
+        ("b", 3, vec![]),
+        ("c", 1, vec!["b 1"]),
     ]);
-    provider.exclude("b", 1, "it is externally excluded");
-    provider.exclude("c", 1, "it is externally excluded");
-    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
-}
-
-#[test]
-fn test_merge_excluded() {
-    let mut provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![])]);
-    provider.exclude("a", 1, "it is externally excluded");
-    provider.exclude("a", 2, "it is externally excluded");
-    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
+    let requirements = provider.requirements(&["a", "c"]);
+    let mut solver = SolverBuilder::new().with_candidate_limit(1).build(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r###"
+    a=1
+    b=1
+    c=1
+    "###);
 }
 
 #[test]
-#[traced_test]
-fn test_merge_installable() {
-    let provider = BundleBoxProvider::from_packages(&[
+fn test_candidate_limit_requests_more_candidates() {
+    let packages = [
         ("a", 1, vec![]),
         ("a", 2, vec![]),
         ("a", 3, vec![]),
         ("a", 4, vec![]),
-    ]);
-    insta::assert_snapshot!(solve_snapshot(provider, &["a 0..3", "a 3..5"]));
-}
-
-#[test]
-fn test_root_excluded() {
-    let mut provider = BundleBoxProvider::from_packages(&[("a", 1, vec![])]);
-    provider.exclude("a", 1, "it is externally excluded");
-    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
+        ("a", 5, vec![]),
+        ("b", 1, vec!["a 1..3"]),
+    ];
+    let solve = |provider: BundleBoxProvider, limit: Option<usize>| {
+        let requirements = provider.requirements(&["a", "b"]);
+        let mut builder = SolverBuilder::new();
+        if let Some(limit) = limit {
+            builder = builder.with_candidate_limit(limit);
+        }
+        let mut solver = builder.build(provider);
+        let solved = solver
+            .solve(Problem::new().requirements(requirements))
+            .unwrap();
+        let result = transaction_to_string(solver.provider(), &solved);
+        (result, solver.provider().requested_more_candidates.take())
+    };
+
+    // Only `a=5` and `a=4` are returned at first, `b` needs one of the others.
+    let mut provider = BundleBoxProvider::from_packages(&packages);
+    provider.candidate_batch = Some(2);
+    let a = provider.package_name("a");
+    let (result, requested) = solve(provider, Some(2));
+    assert_eq!(requested, vec![a]);
+
+    let (unbounded, _) = solve(BundleBoxProvider::from_packages(&packages), None);
+    assert_eq!(result, unbounded);
+    assert_snapshot!(result, @r"
+    a=2
+    b=1
+    ");
 }
 
 #[test]
-fn test_constraints() {
+fn test_keep_installed_solvables() {
     let provider = BundleBoxProvider::from_packages(&[
-        ("a", 1, vec!["b 0..10"]),
+        ("a", 1, vec!["b 1"]),
+        ("a", 2, vec!["b 2"]),
         ("b", 1, vec![]),
         ("b", 2, vec![]),
         ("c", 1, vec![]),
     ]);
-    let requirements = provider.requirements(&["a 0..10"]);
-    let constraints = provider.requirements(&["b 1..2", "c"]);
+    let requirements = provider.requirements(&["b 2"]);
+    let installed = vec![
+        provider.solvable_id("a", 1),
+        provider.solvable_id("b", 1),
+        provider.solvable_id("c", 1),
+    ];
     let mut solver = Solver::new(provider);
     let problem = Problem::new()
         .requirements(requirements)
-        .constraints(constraints);
+        .keep(installed.clone());
     let solved = solver.solve(problem).unwrap();
-
     let result = transaction_to_string(solver.provider(), &solved);
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=1
-        "###);
+    assert_snapshot!(result, @r###"
+    b=2
+    c=1
+    "###);
+
+    // Both `a=1` and `b=1` conflict with the requirement on `b 2`.
+    assert_eq!(solver.removed_solvables(), &[installed[1], installed[0]]);
 }
 
 #[test]
-fn test_solve_with_additional() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("a", 1, vec!["b 0..10"]),
-        ("b", 1, vec![]),
-        ("b", 2, vec![]),
-        ("c", 1, vec![]),
-        ("d", 1, vec![]),
-        ("e", 1, vec!["d"]),
-        ("locked", 1, vec![]),
-        ("locked", 2, vec![]),
-    ]);
+fn test_conflict_involved_version_sets() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![]), ("c", 1, vec![])]);
+    let a1 = provider.intern_version_set(&Spec::from_str("a 1").unwrap());
+    let a2 = provider.intern_version_set(&Spec::from_str("a 2").unwrap());
+    let c = provider.intern_version_set(&Spec::from_str("c").unwrap());
+    let requirements = provider.requirements(&["c", "a 1", "a 2"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the requirements on `a` to conflict");
+    };
+
+    let involved = conflict.involved_version_sets(&solver);
+    assert!(involved.contains(&a1));
+    assert!(involved.contains(&a2));
+    assert!(!involved.contains(&c));
+}
 
-    provider.set_locked("locked", 2);
-
-    let requirements = provider.requirements(&["a 0..10"]);
-    let constraints = provider.requirements(&["b 1..2", "c"]);
-
-    let extra_solvables = [
-        provider.solvable_id("b", 2),
-        provider.solvable_id("c", 1),
-        provider.solvable_id("e", 1),
-        // Does not obey the locked clause since it has not been requested
-        // in a version set by another solvable
-        provider.solvable_id("locked", 1),
-        provider.solvable_id("unknown-deps", Pack::new(1).with_unknown_deps()),
-    ];
+#[test]
+fn test_unsat_uses_short_names_for_top_level_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("channel/a", 1, vec!["channel/b 2"]),
+        ("channel/b", 1, vec![]),
+    ]);
+    let error = solve_unsat(provider, &["channel/a"]);
+    assert_snapshot!(error, @r"
+    a * cannot be installed because there are no viable options:
+    └─ channel/a 1 would require
+       └─ channel/b >=2, <3, for which no candidates were found.
+          └─ available, but not matching: channel/b=1
+    ");
+}
 
+#[test]
+fn test_forbidden_solvables() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![])]);
+    let requirements = provider.requirements(&["a"]);
+    let forbidden = vec![provider.solvable_id("a", 2)];
     let mut solver = Solver::new(provider);
-
     let problem = Problem::new()
         .requirements(requirements)
-        .constraints(constraints)
-        .soft_requirements(extra_solvables);
+        .forbidden_solvables(forbidden);
     let solved = solver.solve(problem).unwrap();
-
     let result = transaction_to_string(solver.provider(), &solved);
-    assert_snapshot!(result, @r###"
-        a=1
-        b=1
-        c=1
-        d=1
-        e=1
-        locked=1
-        "###);
+    assert_snapshot!(result, @"a=1");
 }
 
 #[test]
-fn test_solve_with_additional_with_constrains() {
-    let mut provider = BundleBoxProvider::from_packages(&[
-        ("a", 1, vec!["b 0..10"]),
-        ("b", 1, vec![]),
-        ("b", 2, vec![]),
-        ("b", 3, vec![]),
-        ("c", 1, vec![]),
-        ("d", 1, vec!["f"]),
-        ("e", 1, vec!["c"]),
-    ]);
+fn test_raw_clauses() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![]), ("b", 1, vec![])]);
+    let requirements = provider.requirements(&["a"]);
+    let a1 = provider.solvable_id("a", 1);
+    let a2 = provider.solvable_id("a", 2);
+    let b1 = provider.solvable_id("b", 1);
+    let mut solver = Solver::new(provider);
 
-    provider.add_package("f", 1.into(), &[], &["c 2..3"], &[]);
-    provider.add_package("g", 1.into(), &[], &["b 2..3"], &[]);
-    provider.add_package("h", 1.into(), &[], &["b 1..2"], &[]);
-    provider.add_package("i", 1.into(), &[], &[], &[]);
-    provider.add_package("j", 1.into(), &["i"], &[], &[]);
-    provider.add_package("k", 1.into(), &["i"], &[], &[]);
-    provider.add_package("l", 1.into(), &["j", "k"], &[], &[]);
+    // Forbid a=2 and make a=1 imply b=1.
+    let problem = Problem::new()
+        .requirements(requirements.clone())
+        .raw_clauses(vec![vec![(a2, false)], vec![(a1, false), (b1, true)]]);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    a=1
+    b=1
+    ");
 
-    let requirements = provider.requirements(&["a 0..10", "e"]);
-    let constraints = provider.requirements(&["b 1..2", "c", "k 2..3"]);
+    // An empty clause cannot be satisfied.
+    let problem = Problem::new()
+        .requirements(requirements)
+        .raw_clauses(vec![Vec::new()]);
+    assert!(matches!(
+        solver.solve(problem),
+        Err(UnsolvableOrCancelled::Unsolvable(_))
+    ));
 
-    let extra_solvables = [
-        provider.solvable_id("d", 1),
-        provider.solvable_id("g", 1),
-        provider.solvable_id("h", 1),
-        provider.solvable_id("j", 1),
-        provider.solvable_id("l", 1),
-        provider.solvable_id("k", 1),
-    ];
+    // A conflict caused by a raw clause does not explain it.
+    let requirements = solver.provider().requirements(&["a 2"]);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .raw_clauses(vec![vec![(a2, false)]]);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the raw clause to make the problem unsolvable");
+    };
+    let error = conflict.display_user_friendly(&solver).to_string();
+    assert_snapshot!(error, @r"
+    The following packages are incompatible
+    └─ a >=2, <3 can be installed with any of the following options:
+       └─ a 2
+    ");
+}
 
+#[test]
+fn test_unsat_forbidden_solvable() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![])]);
+    let requirements = provider.requirements(&["a 2"]);
+    let forbidden = vec![provider.solvable_id("a", 2)];
     let mut solver = Solver::new(provider);
-
     let problem = Problem::new()
         .requirements(requirements)
-        .constraints(constraints)
-        .soft_requirements(extra_solvables);
-
-    let solved = solver.solve(problem).unwrap();
+        .forbidden_solvables(forbidden);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the forbidden solvable to make the problem unsolvable");
+    };
+    let error = conflict.display_user_friendly(&solver).to_string();
+    assert_snapshot!(error, @r"
+    The following packages are incompatible
+    └─ a >=2, <3 cannot be installed because there are no viable options:
+       └─ a 2 is forbidden
+    ");
+}
 
-    let result = transaction_to_string(solver.provider(), &solved);
-    assert_snapshot!(result, @r###"
-        a=1
-        b=1
-        c=1
-        e=1
-        h=1
-        i=1
-        j=1
-        "###);
+#[test]
+fn test_prohibited_name_without_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![])]);
+    let prohibited = vec![provider.pool.intern_package_name("a")];
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().prohibited_names(prohibited))
+        .unwrap();
+    assert!(solved.is_empty());
+    assert!(solver.provider().requested_candidates.borrow().is_empty());
 }
 
 #[test]
-fn test_snapshot() {
+fn test_unsat_prohibited_name() {
     let provider = BundleBoxProvider::from_packages(&[
-        ("menu", 15, vec!["dropdown 2..3"]),
-        ("menu", 10, vec!["dropdown 1..2"]),
-        ("dropdown", 2, vec!["icons 2"]),
-        ("dropdown", 1, vec!["intl 3"]),
-        ("icons", 2, vec![]),
-        ("icons", 1, vec![]),
-        ("intl", 5, vec![]),
-        ("intl", 3, vec![]),
+        ("a", 1, vec!["b"]),
+        ("b", 1, vec!["c"]),
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
     ]);
+    let requirements = provider.requirements(&["a"]);
+    let prohibited = vec![provider.pool.intern_package_name("c")];
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .prohibited_names(prohibited);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the prohibited package to make the problem unsolvable");
+    };
+    let reasons = conflict
+        .reasons(&solver)
+        .map(|reason| reason.display(solver.provider()).to_string())
+        .join("\n");
+    assert_snapshot!(reasons, @r"
+    c=1 is forbidden because c is prohibited
+    c=2 is forbidden because c is prohibited
+    the problem requires a *
+    b=1 requires c *
+    a=1 requires b *
+    ");
+    let error = conflict.display_user_friendly(&solver).to_string();
+    assert_snapshot!(error, @r"
+    The following packages are incompatible
+    └─ a * cannot be installed because there are no viable options:
+       └─ a 1 would require
+          └─ b *, which cannot be installed because there are no viable options:
+             └─ b 1 would require
+                └─ c *, which cannot be installed because there are no viable options:
+                   └─ c 1 | 2 is forbidden because c is prohibited
+    ");
+}
 
-    let menu_name_id = provider.package_name("menu");
-
-    let snapshot = provider.into_snapshot();
-
-    #[cfg(feature = "serde")]
-    serialize_snapshot(&snapshot, "snapshot_pubgrub_menu.json");
-
-    let mut snapshot_provider = snapshot.provider();
+#[test]
+fn test_unsat_policy_pin() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![]), ("a", 3, vec![])]);
+    let requirements = provider.requirements(&["a 3"]);
+    let policy_pins = vec![(
+        provider.package_name("a"),
+        provider.version_sets(&["a 1..3"])[0],
+        provider.pool.intern_string("approved by the security team"),
+    )];
+    let mut solver = Solver::new(provider);
 
-    let menu_req = snapshot_provider.add_package_requirement(menu_name_id, "*");
+    // Without a conflicting requirement the policy restricts the candidates.
+    let requirements_within_policy = solver.provider().requirements(&["a"]);
+    let solved = solver
+        .solve(
+            Problem::new()
+                .requirements(requirements_within_policy)
+                .policy_pins(policy_pins.clone()),
+        )
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @"a=2");
 
-    assert_snapshot!(solve_for_snapshot(snapshot_provider, &[menu_req], &[]));
+    let problem = Problem::new()
+        .requirements(requirements)
+        .policy_pins(policy_pins);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the policy pin to make the problem unsolvable");
+    };
+    let error = conflict.display_user_friendly(&solver).to_string();
+    assert!(
+        error.contains("a is pinned to >=1, <3 by policy (approved by the security team)"),
+        "{error}"
+    );
 }
 
 #[test]
-fn test_snapshot_union_requirements() {
+fn test_depth_constraints() {
+    // b=2 is a prerelease, which is only allowed for direct dependencies.
     let provider = BundleBoxProvider::from_packages(&[
-        ("icons", 2, vec![]),
-        ("icons", 1, vec![]),
-        ("intl", 5, vec![]),
-        ("intl", 3, vec![]),
-        ("union", 1, vec!["icons 2 | intl"]),
+        ("a", 1, vec!["b"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
     ]);
+    let depth_constraints = vec![(provider.version_sets(&["b 1"])[0], 1)];
+    let transitive = provider.requirements(&["a"]);
+    let direct = provider.requirements(&["a", "b"]);
+    let mut solver = Solver::new(provider);
 
-    let intl_name_id = provider.package_name("intl");
-    let union_name_id = provider.package_name("union");
-
-    let snapshot = provider.into_snapshot();
+    let solved = solver
+        .solve(
+            Problem::new()
+                .requirements(transitive)
+                .depth_constraints(depth_constraints.clone()),
+        )
+        .unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=1
+    b=1
+    ");
 
-    let mut snapshot_provider = snapshot.provider();
+    // Required directly, b is at the shallowest depth of 1.
+    let solved = solver
+        .solve(
+            Problem::new()
+                .requirements(direct)
+                .depth_constraints(depth_constraints),
+        )
+        .unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=1
+    b=2
+    ");
+}
 
-    let intl_req = snapshot_provider.add_package_requirement(intl_name_id, "*");
-    let union_req = snapshot_provider.add_package_requirement(union_name_id, "*");
+#[test]
+fn test_virtual_provides() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("app", 1, vec!["abi 3..5"]),
+        ("python", 1, vec![]),
+        ("python", 2, vec![]),
+        ("pypy", 1, vec![]),
+    ]);
+    provider.add_provides("python", 1, "abi 2");
+    provider.add_provides("python", 2, "abi 3");
+    provider.add_provides("pypy", 1, "abi 5");
+    let requirements = provider.requirements(&["app"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    app=1
+    python=2
+    ");
 
-    assert_snapshot!(solve_for_snapshot(
-        snapshot_provider,
-        &[intl_req, union_req],
-        &[]
-    ));
+    // A constraint on the virtual package applies to its providers.
+    let requirements = solver.provider().requirements(&["python"]);
+    let constraints = solver.provider().version_sets(&["abi 1..3"]);
+    let solved = solver
+        .solve(
+            Problem::new()
+                .requirements(requirements)
+                .constraints(constraints),
+        )
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @"python=1");
 }
 
 #[test]
-fn test_union_empty_requirements() {
-    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec!["b 1 | c"]), ("b", 1, vec![])]);
-
-    let result = solve_snapshot(provider, &["a"]);
-    assert_snapshot!(result, @r"
-    a=1
-    b=1
+fn test_unsat_merge_by_display() {
+    let mut provider = BundleBoxProvider::new();
+    for (build, requirement) in ["c 2", "c 3", "c 4"].into_iter().enumerate() {
+        let pack = Pack::new(1).with_priority(build as i64);
+        provider.add_package("a", pack, &[requirement], &[], &[]);
+    }
+    for version in 1..=4 {
+        provider.add_package("c", Pack::new(version), &[], &[], &[]);
+    }
+    let requirements = provider.requirements(&["a", "c 1"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected every build of a to conflict with c 1");
+    };
+    let error = conflict.display_user_friendly(&solver).to_string();
+    assert_snapshot!(error, @r"
+    The following packages are incompatible
+    ├─ a * cannot be installed because there are no viable options:
+    │  ├─ a 1 would require
+    │  │  └─ c >=4, <5, which cannot be installed because there are no viable options:
+    │  │     └─ c 4, which conflicts with the versions reported above.
+    │  ├─ a 1 would require
+    │  │  └─ c >=3, <4, which cannot be installed because there are no viable options:
+    │  │     └─ c 3, which conflicts with the versions reported above.
+    │  └─ a 1 would require
+    │     └─ c >=2, <3, which cannot be installed because there are no viable options:
+    │        └─ c 2, which conflicts with the versions reported above.
+    └─ c >=1, <2 cannot be installed because there are no viable options:
+       └─ c 1, which conflicts with the versions reported above.
+    ");
+    let error = conflict
+        .display_user_friendly(&solver)
+        .merge_by_display(true)
+        .to_string();
+    assert_snapshot!(error, @r"
+    The following packages are incompatible
+    ├─ a * cannot be installed because there are no viable options:
+    │  └─ a 1 would require
+    │     └─ c >=2, <3, which cannot be installed because there are no viable options:
+    │        └─ c 2, which conflicts with the versions reported above.
+    └─ c >=1, <2 cannot be installed because there are no viable options:
+       └─ c 1, which conflicts with the versions reported above.
     ");
 }
 
 #[test]
-fn test_root_constraints() {
-    let provider =
-        BundleBoxProvider::from_packages(&[("icons", 1, vec![]), ("union", 1, vec!["icons"])]);
+fn test_unsat_with_translated_messages() {
+    struct GermanMessages;
 
-    let union_name_id = provider.package_name("union");
+    impl ConflictMessages for GermanMessages {
+        fn incompatible_packages(&self) -> String {
+            "Die folgenden Pakete sind nicht kompatibel".to_string()
+        }
 
-    let snapshot = provider.into_snapshot();
+        fn not_installable(&self, requirement: &dyn Display) -> String {
+            format!("{requirement} kann nicht installiert werden, weil es keine Optionen gibt:")
+        }
 
-    let mut snapshot_provider = snapshot.provider();
+        fn forbidden(&self, candidate: &dyn Display) -> String {
+            format!("{candidate} ist verboten")
+        }
+    }
 
-    let union_req = snapshot_provider.add_package_requirement(union_name_id, "*");
-    let union_constraint = snapshot_provider.add_package_requirement(union_name_id, "5");
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![])]);
+    let requirements = provider.requirements(&["a 2"]);
+    let forbidden = vec![provider.solvable_id("a", 2)];
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .forbidden_solvables(forbidden);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the forbidden solvable to make the problem unsolvable");
+    };
+    let error = conflict
+        .display_with_messages(&solver, &GermanMessages)
+        .to_string();
+    assert_snapshot!(error, @r"
+    Die folgenden Pakete sind nicht kompatibel
+    └─ a >=2, <3 kann nicht installiert werden, weil es keine Optionen gibt:
+       └─ a 2 ist verboten
+    ");
+}
 
-    assert_snapshot!(solve_for_snapshot(
-        snapshot_provider,
-        &[union_req],
-        &[union_constraint]
-    ));
+#[test]
+fn test_unsat_pinned_solvable_does_not_match() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 2, vec![]), ("a", 3, vec![])]);
+    let requirements = provider.requirements(&["a 3"]);
+    let pins = vec![provider.solvable_id("a", 2)];
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements).pins(pins);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the pin to make the problem unsolvable");
+    };
+    let error = conflict.display_user_friendly(&solver).to_string();
+    assert_snapshot!(error, @r"
+    The following packages are incompatible
+    └─ a >=3, <4 can be installed with any of the following options:
+       └─ a 3
+    └─ a 2 is pinned, but another version is required as reported above
+    ");
 }
 
 #[test]
-fn test_explicit_root_requirements() {
+fn test_solve_over_base() {
     let provider = BundleBoxProvider::from_packages(&[
-        // `a` depends transitively on `b`
-        ("a", 1, vec!["b"]),
-        // `b` depends on `c`, but the highest version of `b` constrains `c` to `<2`.
-        ("b", 1, vec!["c"]),
-        ("b", 2, vec!["c 1..2"]),
-        // `c` has more versions than `b`, so the heuristic will most likely pick `b` first.
-        ("c", 1, vec![]),
-        ("c", 2, vec![]),
-        ("c", 3, vec![]),
-        ("c", 4, vec![]),
-        ("c", 5, vec![]),
+        ("app", 1, vec!["lib"]),
+        ("tool", 1, vec!["lib 2"]),
+        ("lib", 1, vec![]),
+        ("lib", 2, vec![]),
+        ("extra", 1, vec![]),
     ]);
-
-    // We require both `a` and `c` explicitly. The expected outcome will be that we
-    // get the highest versions of `a` and `c` and a lower version of `b`.
-    let requirements = provider.requirements(&["a", "c"]);
-
+    let base = vec![
+        provider.solvable_id("app", 1),
+        provider.solvable_id("lib", 1),
+    ];
+    let compatible = provider.requirements(&["extra"]);
+    let conflicting = provider.requirements(&["tool"]);
     let mut solver = Solver::new(provider);
-    let problem = Problem::default().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
 
+    // The dependencies of the base environment are satisfied from it.
+    let solved = solver
+        .solve_over_base(&base, compatible, Vec::new())
+        .unwrap();
     let result = transaction_to_string(solver.provider(), &solved);
-    assert_snapshot!(result, @r###"
-    a=1
-    b=1
-    c=5
-    "###);
-}
+    assert_snapshot!(result, @r"
+    app=1
+    extra=1
+    lib=1
+    ");
 
-#[test]
-#[traced_test]
-fn test_conditional_requirements() {
-    let mut provider = BundleBoxProvider::new();
-
-    // Add packages
-    provider.add_package("a", 1.into(), &["b"], &[], &[]); // a depends on b
-    provider.add_package("b", 1.into(), &[], &[], &[]); // Simple package b
-    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
-
-    // Create problem with both regular and conditional requirements
-    let requirements = provider.requirements(&["a", "c 1; if b 1..2"]);
-
-    let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let result = transaction_to_string(solver.provider(), &solved);
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=1
-        c=1
-        "###);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) =
+        solver.solve_over_base(&base, conflicting, Vec::new())
+    else {
+        panic!("expected the base environment to make the problem unsolvable");
+    };
+    let error = conflict.display_user_friendly(&solver).to_string();
+    assert_snapshot!(error, @r"
+    The following packages are incompatible
+    └─ tool * can be installed with any of the following options:
+       └─ tool 1 would require
+          └─ lib >=2, <3, which can be installed with any of the following options:
+             └─ lib 2
+    └─ another version than lib 1 is required as reported above, which is incompatible with base environment
+    ");
+    let reasons = conflict
+        .reasons(&solver)
+        .map(|reason| reason.display(solver.provider()).to_string())
+        .join("\n");
+    assert_snapshot!(reasons, @r"
+    lib=1 is part of the base environment
+    the problem requires tool *
+    tool=1 requires lib >=2, <3
+    ");
 }
 
+/// If the dependencies of candidates are available, they should be requested
+/// for all candidates of a package at once.
 #[test]
-#[traced_test]
-fn test_conditional_requirements_not_met() {
-    let mut provider = BundleBoxProvider::new();
-    provider.add_package("b", 1.into(), &[], &[], &[]); // Add b=1 as a candidate
-    provider.add_package("b", 2.into(), &[], &[], &[]); // Different version of b
-    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
-    provider.add_package("a", 1.into(), &["b 2"], &[], &[]); // a depends on b=2 specifically
-
-    // Create conditional requirement: if b=1 is installed, require c
-    let requirements = provider.requirements(&[
-        "a",           // Require package a
-        "c 1; if b 1", // If b=1 is installed, require c (note the exact version)
+fn test_solve_many() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
     ]);
-
+    let sat = provider.requirements(&["a"]);
+    let unsat = provider.requirements(&["a", "b 3"]);
     let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let result = transaction_to_string(solver.provider(), &solved);
-    // Since b=2 is installed (not b=1), c should not be installed
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=2
-        "###);
+
+    // The provider panics if candidates or dependencies are requested twice.
+    let results = solver.solve_many([(sat, Vec::new()), (unsat, Vec::new())]);
+    assert_eq!(results.len(), 2);
+    let solved = results[0].as_ref().unwrap();
+    let result = transaction_to_string(solver.provider(), solved);
+    assert_snapshot!(result, @r###"
+    a=1
+    b=2
+    "###);
+    assert!(matches!(
+        results[1],
+        Err(UnsolvableOrCancelled::Unsolvable(_))
+    ));
+    assert_eq!(solver.provider().requested_candidates.borrow().len(), 2);
 }
 
 #[test]
-fn test_nested_conditional_dependencies() {
-    let mut provider = BundleBoxProvider::new();
-
-    // Setup packages
-    provider.add_package("a", 1.into(), &[], &[], &[]); // Base package
-    provider.add_package("b", 1.into(), &[], &[], &[]); // First level conditional
-    provider.add_package("c", 1.into(), &[], &[], &[]); // Second level conditional
-    provider.add_package("d", 1.into(), &[], &[], &[]); // Third level conditional
-
-    // Create nested conditional requirements using the parser
-    let requirements = provider.requirements(&[
-        "a",           // Require package a
-        "b 1; if a 1", // If a is installed, require b
-        "c 1; if b 1", // If b is installed, require c
-        "d 1; if c 1", // If c is installed, require d
+fn test_is_satisfiable() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
     ]);
-
+    let cases = [
+        (provider.requirements(&["a"]), Vec::new()),
+        (provider.requirements(&["a", "b 3"]), Vec::new()),
+        (
+            provider.requirements(&["a"]),
+            provider.version_sets(&["b 3"]),
+        ),
+    ];
     let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let result = transaction_to_string(solver.provider(), &solved);
-    // All packages should be installed due to chain reaction
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=1
-        c=1
-        d=1
-        "###);
+
+    let satisfiable = cases
+        .iter()
+        .map(|(requirements, constraints)| {
+            let satisfiable = solver
+                .is_satisfiable(requirements.clone(), constraints.clone())
+                .unwrap();
+            let problem = Problem::new()
+                .requirements(requirements.clone())
+                .constraints(constraints.clone());
+            assert_eq!(satisfiable, solver.solve(problem).is_ok());
+            satisfiable
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(satisfiable, [true, false, false]);
 }
 
 #[test]
-fn test_multiple_conditions_same_package() {
-    let mut provider = BundleBoxProvider::new();
+fn test_are_compatible() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![]), ("a", 3, vec![])]);
+    let requirement = |spec: &str| provider.requirements(&[spec])[0].requirement;
+    let (low, high, overlapping) = (
+        requirement("a 1..2"),
+        requirement("a 3"),
+        requirement("a 2..4"),
+    );
+    let mut solver = Solver::new(provider);
 
-    // Setup packages
-    provider.add_package("a", 1.into(), &[], &[], &[]);
-    provider.add_package("b", 1.into(), &[], &[], &[]);
-    provider.add_package("c", 1.into(), &[], &[], &[]);
-    provider.add_package("target", 1.into(), &[], &[], &[]);
+    assert!(solver.are_compatible(high, overlapping).unwrap());
 
-    // Create multiple conditions that all require the same package using the parser
-    let requirements = provider.requirements(&[
-        "b",                // Only require package b
-        "target 1; if a 1", // If a is installed, require target
-        "target 1; if b 1", // If b is installed, require target
-        "target 1; if c 1", // If c is installed, require target
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.are_compatible(low, high) else {
+        panic!("expected the requirements to be incompatible");
+    };
+    let error = conflict.display_user_friendly(&solver).to_string();
+    insta::assert_snapshot!(error, @r"
+    The following packages are incompatible
+    ├─ a >=3, <4 can be installed with any of the following options:
+    │  └─ a 3
+    └─ a >=1, <2 cannot be installed because there are no viable options:
+       └─ a 1, which conflicts with the versions reported above.
+    ");
+}
+
+#[test]
+fn test_score_candidate() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b"]),
+        ("a", 2, vec!["b"]),
+        ("a", 3, vec!["b"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("b", 3, vec![]),
     ]);
+    // A higher score overrides the sorted order.
+    provider.scores.insert(provider.solvable_id("a", 1), 1.0);
+    // Candidates with equal scores keep their sorted order.
+    provider.scores.insert(provider.solvable_id("b", 1), 1.0);
+    provider.scores.insert(provider.solvable_id("b", 2), 1.0);
 
+    let requirements = provider.requirements(&["a"]);
     let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
     let result = transaction_to_string(solver.provider(), &solved);
-    // b and target should be installed
-    insta::assert_snapshot!(result, @r###"
-        b=1
-        target=1
-        "###);
+    assert_snapshot!(result, @r"
+    a=1
+    b=2
+    ");
 }
 
 #[test]
-fn test_circular_conditional_dependencies() {
-    let mut provider = BundleBoxProvider::new();
+fn test_preview_candidate() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b 5"]),
+        ("a", 2, vec![]),
+        ("a", 3, vec![]),
+        ("b", 1, vec![]),
+    ]);
+    let a = provider.package_name("a");
+    let constraints = provider.version_sets(&["a 1..3", "b 1"]);
+    let cache = SolverCache::new(provider);
+
+    let preview = |constraints: &[VersionSetId]| {
+        preview_candidate(a, constraints, &cache)
+            .map(|solvable| cache.provider().display_solvable(solvable).to_string())
+    };
+    assert_eq!(preview(&[]).as_deref(), Some("a=3"));
+    // The constraint on `a` rules out the newest candidate, the one on `b` is ignored.
+    assert_eq!(preview(&constraints).as_deref(), Some("a=2"));
+}
 
-    // Setup packages
-    provider.add_package("a", 1.into(), &[], &[], &[]);
-    provider.add_package("b", 1.into(), &[], &[], &[]);
+#[test]
+fn test_display_conditional_requirement() {
+    let provider = BundleBoxProvider::from_packages(&[("foo", 1, vec![]), ("bar", 1, vec![])]);
+    let gui = provider.pool.intern_string("gui");
+    let unconditional = provider.requirements(&["foo"]).remove(0);
+    let conditional = provider.conditional_requirement(&[Spec::from_str("bar 1").unwrap()], &[gui]);
 
-    // Create circular conditional dependencies using the parser
-    let requirements = provider.requirements(&[
-        "a",           // Require package a
-        "b 1; if a 1", // If a is installed, require b
-        "a 1; if b 1", // If b is installed, require a
-    ]);
+    let rendered = [unconditional, conditional]
+        .iter()
+        .flat_map(|requirement| {
+            [
+                requirement.display(&provider).to_string(),
+                requirement
+                    .display(&provider)
+                    .show_conditions(true)
+                    .to_string(),
+            ]
+        })
+        .join("\n");
+    assert_snapshot!(rendered, @r"
+    foo *
+    foo *
+    bar >=1, <2
+    bar >=1, <2 (if gui)
+    ");
+}
 
+#[test]
+fn test_constraints_on() {
+    let mut provider =
+        BundleBoxProvider::from_packages(&[("c", 1, vec![]), ("c", 2, vec![]), ("c", 3, vec![])]);
+    provider.add_package("a", Pack::new(1), &[], &["c 1..3"], &[]);
+    provider.add_package("b", Pack::new(1), &[], &["c 2..4"], &[]);
+    let requirements = provider.requirements(&["a", "b", "c"]);
     let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let result = transaction_to_string(solver.provider(), &solved);
-    // Both packages should be installed due to circular dependency
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=1
-        "###);
+    solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+
+    let c = solver.provider().package_name("c");
+    let constraints = solver
+        .cache()
+        .constraints_on(c)
+        .into_iter()
+        .map(|(source, version_set)| {
+            format!(
+                "{} constrains {}",
+                solver.provider().display_solvable(source),
+                solver.provider().display_version_set(version_set)
+            )
+        })
+        .sorted()
+        .join("\n");
+    assert_snapshot!(constraints, @r"
+    a=1 constrains >=1, <3
+    b=1 constrains >=2, <4
+    ");
+    assert!(solver
+        .cache()
+        .constraints_on(solver.provider().package_name("a"))
+        .is_empty());
 }
 
 #[test]
-fn test_conditional_requirements_multiple_versions() {
-    let mut provider = BundleBoxProvider::new();
-
-    // Add multiple versions of package b
-    provider.add_package("b", 1.into(), &[], &[], &[]);
-    provider.add_package("b", 2.into(), &[], &[], &[]);
-    provider.add_package("b", 3.into(), &[], &[], &[]);
-    provider.add_package("b", 4.into(), &[], &[], &[]);
-    provider.add_package("b", 5.into(), &[], &[], &[]);
+fn test_solve_multi_platform() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("app", 1, vec!["lib", "tool"]),
+        ("lib", 1, vec![]),
+        ("lib", 2, vec![]),
+        ("tool", 1, vec![]),
+    ]);
+    let linux = provider.pool.intern_string("linux");
+    let windows = provider.pool.intern_string("windows");
+    provider
+        .unavailable_on
+        .insert(provider.solvable_id("lib", 2), vec![windows]);
+    let requirements = provider.requirements(&["app"]);
+    let mut solver = Solver::new(provider);
 
-    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
-    provider.add_package("a", 1.into(), &["b 4..6"], &[], &[]); // a depends on b versions 4-5
+    // The provider panics if candidates or dependencies are requested twice.
+    let result = solver.solve_multi_platform(requirements, Vec::new(), &[linux, windows]);
+    let solutions = result
+        .solutions
+        .iter()
+        .map(|(platform, solution)| {
+            format!(
+                "{}:\n{}",
+                solver.provider().display_string(*platform),
+                transaction_to_string(solver.provider(), solution.as_ref().unwrap())
+            )
+        })
+        .join("");
+    assert_snapshot!(solutions, @r"
+    linux:
+    app=1
+    lib=2
+    tool=1
+    windows:
+    app=1
+    lib=1
+    tool=1
+    ");
+    let shared = transaction_to_string(solver.provider(), &result.shared);
+    assert_snapshot!(shared, @r"
+    app=1
+    tool=1
+    ");
+}
 
-    // Create conditional requirement: if b=1..3 is installed, require c
-    let requirements = provider.requirements(&[
-        "a",              // Require package a
-        "c 1; if b 1..3", // If b is version 1-2, require c
-    ]);
+#[test]
+fn test_solve_current_adds_requirements_incrementally() {
+    let packages: &[(&str, u32, Vec<&str>)] = &[
+        ("a", 1, vec!["c 1"]),
+        ("a", 2, vec!["c 2"]),
+        ("b", 1, vec!["c 1"]),
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
+        ("d", 1, vec![]),
+    ];
+    let fresh_solve = |specs: &[&str]| {
+        let provider = BundleBoxProvider::from_packages(packages);
+        let requirements = provider.requirements(specs);
+        let mut solver = Solver::new(provider);
+        let solved = solver
+            .solve(Problem::new().requirements(requirements))
+            .unwrap();
+        transaction_to_string(solver.provider(), &solved)
+    };
 
+    let provider = BundleBoxProvider::from_packages(packages);
+    let requirements = provider.requirements(&["a", "b", "d"]);
     let mut solver = Solver::new(provider);
-    let problem = Problem::new().requirements(requirements);
-    let solved = solver.solve(problem).unwrap();
-    let result = transaction_to_string(solver.provider(), &solved);
-    // Since b=4 is installed (not b 1..3), c should not be installed
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=5
-        "###);
+    let mut specs = Vec::new();
+    for (spec, requirement) in ["a", "b", "d"].into_iter().zip(requirements) {
+        specs.push(spec);
+        solver.add_requirement(requirement);
+        let solved = solver.solve_current().unwrap();
+        let result = transaction_to_string(solver.provider(), &solved);
+        assert_eq!(result, fresh_solve(&specs));
+    }
 }
 
 #[test]
-fn test_conditional_requirements_multiple_versions_met() {
-    let mut provider = BundleBoxProvider::new();
+fn test_compact() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("a", 3, vec![]),
+        ("b", 1, vec!["a"]),
+        ("c", 1, vec![]),
+    ]);
+    let requirements = provider.requirements(&["b", "c"]);
+    let constraints = provider.version_sets(&["a 1..3", "a 1"]);
+    let mut solver = Solver::new(provider);
+    solver
+        .solve(Problem::new().requirements(vec![requirements[0].clone()]))
+        .unwrap();
+    for constraint in constraints {
+        solver.add_constraint(constraint);
+        solver.solve_current().unwrap();
+    }
 
-    // Add multiple versions of package b
-    provider.add_package("b", 1.into(), &[], &[], &[]);
-    provider.add_package("b", 2.into(), &[], &[], &[]);
-    provider.add_package("b", 3.into(), &[], &[], &[]);
-    provider.add_package("b", 4.into(), &[], &[], &[]);
-    provider.add_package("b", 5.into(), &[], &[], &[]);
+    // Both constraints forbid a=3, only one of the clauses is still needed.
+    let before = solver.stats().num_clauses;
+    solver.compact();
+    assert!(solver.stats().num_clauses < before);
 
-    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
-    provider.add_package("c", 2.into(), &[], &[], &[]); // Version 2 of c
-    provider.add_package("c", 3.into(), &[], &[], &[]); // Version 3 of c
-    provider.add_package("a", 1.into(), &["b 1..3", "c 1..3; if b 1..3"], &[], &[]); // a depends on b 1-3 and conditionally on c 1-3
+    solver.add_requirement(requirements[1].clone());
+    let solved = solver.solve_current().unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=1
+    b=1
+    c=1
+    ");
+}
 
-    let requirements = provider.requirements(&[
-        "a", // Require package a
+#[test]
+fn test_dependencies_are_requested_in_batches() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("b", 3, vec![]),
     ]);
+    provider.hint_dependencies_available = true;
 
+    let requirements = provider.requirements(&["a"]);
     let mut solver = Solver::new(provider);
     let problem = Problem::new().requirements(requirements);
     let solved = solver.solve(problem).unwrap();
-    let result = transaction_to_string(solver.provider(), &solved);
-    // Since b=2 is installed (within b 1..2), c should be installed
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=2
-        c=2
-        "###);
+
+    let provider = solver.provider();
+    let b_candidates: HashSet<_> = (1..=3).map(|v| provider.solvable_id("b", v)).collect();
+    let batches = provider.requested_dependency_batches.borrow();
+    assert!(batches
+        .iter()
+        .any(|batch| batch.iter().copied().collect::<HashSet<_>>() == b_candidates));
+
+    assert_snapshot!(transaction_to_string(provider, &solved), @r###"
+    a=1
+    b=3
+    "###);
 }
 
 #[test]
-fn test_conditional_requirements_conflict() {
-    let mut provider = BundleBoxProvider::new();
-
-    // Add multiple versions of package b
-    provider.add_package("b", 1.into(), &[], &[], &[]);
-    provider.add_package("b", 2.into(), &[], &[], &[]);
-    provider.add_package("b", 3.into(), &[], &[], &[]);
+fn test_prefetched_dependencies() {
+    // The provider panics if the solver requests dependencies separately.
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b 1..3"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec!["c"]),
+        ("b", 3, vec![]),
+        ("c", 1, vec![]),
+    ]);
+    provider.prefetch_dependencies = true;
 
-    // Package c has two versions with different dependencies
-    provider.add_package("c", 1.into(), &["d 1"], &[], &[]); // c v1 requires d v1
-    provider.add_package("c", 2.into(), &["d 2"], &[], &[]); // c v2 requires d v2
+    let requirements = provider.requirements(&["a"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=1
+    b=2
+    c=1
+    ");
+}
 
-    // Package d has incompatible versions
-    provider.add_package("d", 1.into(), &[], &[], &[]);
-    provider.add_package("d", 2.into(), &[], &[], &[]);
+/// In case of a conflict the version should not be selected with the conflict
+#[test]
+fn test_resolve_with_conflict() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("asdf", 4, vec!["conflicting 1"]),
+        ("asdf", 3, vec!["conflicting 0"]),
+        ("efgh", 7, vec!["conflicting 0"]),
+        ("efgh", 6, vec!["conflicting 0"]),
+        ("conflicting", 1, vec![]),
+        ("conflicting", 0, vec![]),
+    ]);
+    let result = solve_snapshot(provider, &["asdf", "efgh"]);
+    insta::assert_snapshot!(result);
+}
 
-    provider.add_package(
-        "a",
-        1.into(),
-        &["b 1", "c 1; if b 1", "d 2", "c 2; if b 2"],
-        &[],
-        &[],
-    );
+/// Names without any candidates should not be reported as known names
+#[test]
+fn test_known_names_only_includes_names_with_candidates() {
+    let mut provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("b", 2, vec![])]);
+    provider
+        .packages
+        .insert("c".to_string(), IndexMap::default());
+
+    let names = ["a", "b", "c"].map(|name| provider.pool.intern_package_name(name));
+    let known_names = provider.known_names().collect::<Vec<_>>();
+    assert_eq!(known_names, vec![names[0], names[1]]);
+}
 
-    let requirements = provider.requirements(&[
-        "a", // Require package a
+/// The non-existing package should not be selected
+#[test]
+#[traced_test]
+fn test_resolve_with_nonexisting() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("asdf", 4, vec!["b"]),
+        ("asdf", 3, vec![]),
+        ("b", 1, vec!["idontexist"]),
     ]);
-
+    let requirements = provider.requirements(&["asdf"]);
     let mut solver = Solver::new(provider);
     let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let pool = &solver.provider().pool;
 
-    // This should fail to solve because:
-    // 1. When b=1 is chosen, it triggers the conditional requirement for c 1
-    // 2. c 1 requires d 1, but a requires d 2
-    // 3. d 1 and d 2 cannot be installed together
+    assert_eq!(solved.len(), 1);
 
-    let solved = solver
-        .solve(problem)
-        .map_err(|e| match e {
-            UnsolvableOrCancelled::Unsolvable(conflict) => {
-                conflict.display_user_friendly(&solver).to_string()
-            }
-            UnsolvableOrCancelled::Cancelled(_) => "kir".to_string(),
-        })
-        .unwrap_err();
+    let solvable = pool.resolve_solvable(solved[0]);
 
-    assert_snapshot!(solved, @r"
-    The following packages are incompatible
-    └─ a * cannot be installed because there are no viable options:
-       └─ a 1 would require
-          ├─ b >=1, <2, which can be installed with any of the following options:
-          │  └─ b 1
-          ├─ d >=2, <3, which can be installed with any of the following options:
-          │  └─ d 2
-          └─ c >=1, <2, which cannot be installed because there are no viable options:
-             └─ c 1 would require
-                └─ d >=1, <2, which cannot be installed because there are no viable options:
-                   └─ d 1, which conflicts with the versions reported above.
-    ");
+    assert_eq!(pool.resolve_package_name(solvable.name), "asdf");
+    assert_eq!(solvable.record.version, 3);
 }
 
-/// In this test, the resolver installs the highest available version of b which is b 2
-/// However, the conditional requirement is that if b 1..2 is installed, require c
-/// Since b 2 is installed, c should not be installed
 #[test]
-fn test_conditional_requirements_multiple_versions_not_met() {
-    let mut provider = BundleBoxProvider::new();
-
-    // Add multiple versions of package b
-    provider.add_package("b", 1.into(), &[], &[], &[]);
-    provider.add_package("b", 2.into(), &[], &[], &[]);
-    provider.add_package("b", 3.into(), &[], &[], &[]);
-    provider.add_package("b", 4.into(), &[], &[], &[]);
-    provider.add_package("b", 5.into(), &[], &[], &[]);
-
-    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
-    provider.add_package("c", 2.into(), &[], &[], &[]); // Version 2 of c
-    provider.add_package("c", 3.into(), &[], &[], &[]); // Version 3 of c
-    provider.add_package("a", 1.into(), &["b 1..3", "c 1..3; if b 1..2"], &[], &[]); // a depends on b 1-3 and conditionally on c 1-3
-
-    let requirements = provider.requirements(&[
-        "a", // Require package a
+#[traced_test]
+fn test_resolve_with_nested_deps() {
+    let provider = BundleBoxProvider::from_packages(&[
+        (
+            "apache-airflow",
+            3,
+            vec!["opentelemetry-api 2..4", "opentelemetry-exporter-otlp"],
+        ),
+        (
+            "apache-airflow",
+            2,
+            vec!["opentelemetry-api 2..4", "opentelemetry-exporter-otlp"],
+        ),
+        ("apache-airflow", 1, vec![]),
+        ("opentelemetry-api", 3, vec!["opentelemetry-sdk"]),
+        ("opentelemetry-api", 2, vec![]),
+        ("opentelemetry-api", 1, vec![]),
+        ("opentelemetry-exporter-otlp", 1, vec!["opentelemetry-grpc"]),
+        ("opentelemetry-grpc", 1, vec!["opentelemetry-api 1"]),
     ]);
-
+    let requirements = provider.requirements(&["apache-airflow"]);
     let mut solver = Solver::new(provider);
     let problem = Problem::new().requirements(requirements);
     let solved = solver.solve(problem).unwrap();
-    let result = transaction_to_string(solver.provider(), &solved);
-    // Since b=2 is installed (within b 1..2), c should be installed
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=2
-        "###);
+    let pool = &solver.provider().pool;
+
+    assert_eq!(solved.len(), 1);
+
+    let solvable = pool.resolve_solvable(solved[0]);
+
+    assert_eq!(pool.resolve_package_name(solvable.name), "apache-airflow");
+    assert_eq!(solvable.record.version, 1);
 }
 
 #[test]
-fn test_optional_dependencies() {
+#[traced_test]
+fn test_resolve_with_unknown_deps() {
     let mut provider = BundleBoxProvider::new();
-
-    // Add package a with base dependency on b and optional dependencies via features
     provider.add_package(
-        "a",
-        1.into(),
-        &["b 1"],
+        "opentelemetry-api",
+        Pack::new(3).with_unknown_deps(),
+        &[],
+        &[],
         &[],
-        &[("feat1", &["c"]), ("feat2", &["d"])],
     );
-    provider.add_package("b", 1.into(), &[], &[], &[]);
-    provider.add_package("c", 1.into(), &[], &[], &[]);
-    provider.add_package("d", 1.into(), &[], &[], &[]);
-
-    // Request package a with both optional features enabled
-    let requirements = provider.requirements(&["a[feat2]"]);
+    provider.add_package("opentelemetry-api", Pack::new(2), &[], &[], &[]);
+    let requirements = provider.requirements(&["opentelemetry-api"]);
     let mut solver = Solver::new(provider);
     let problem = Problem::new().requirements(requirements);
     let solved = solver.solve(problem).unwrap();
+    let pool = &solver.provider().pool;
+
+    assert_eq!(solved.len(), 1);
+
+    let solvable = pool.resolve_solvable(solved[0]);
+
+    assert_eq!(
+        pool.resolve_package_name(solvable.name),
+        "opentelemetry-api"
+    );
+    assert_eq!(solvable.record.version, 2);
+}
+
+#[test]
+fn test_strict_unknown_dependencies() {
+    let create_provider = || {
+        let mut provider = BundleBoxProvider::from_packages(&[("a", 1, vec!["b"])]);
+        provider.add_package("b", Pack::new(2).with_unknown_deps(), &[], &[], &[]);
+        provider.add_package("b", Pack::new(1), &[], &[], &[]);
+        let requirements = provider.requirements(&["a"]);
+        (provider, requirements)
+    };
+
+    // By default the candidate with unknown dependencies is excluded.
+    let (provider, requirements) = create_provider();
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
     let result = transaction_to_string(solver.provider(), &solved);
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=1
-        d=1
-        "###);
+    insta::assert_snapshot!(result, @r"
+    a=1
+    b=1
+    ");
+
+    // In strict mode solving stops at the candidate with unknown dependencies.
+    let (provider, requirements) = create_provider();
+    let b2 = provider.solvable_id("b", Pack::new(2).with_unknown_deps());
+    let mut solver = SolverBuilder::new()
+        .with_strict_unknown_dependencies(true)
+        .build(provider);
+    let Err(UnsolvableOrCancelled::Cancelled(reason)) =
+        solver.solve(Problem::new().requirements(requirements))
+    else {
+        panic!("expected the solve to be cancelled");
+    };
+    let unknown = *reason.downcast::<UnknownDependencies>().unwrap();
+    assert_eq!(unknown.solvable, b2);
+    assert_eq!(
+        solver.provider().display_string(unknown.reason).to_string(),
+        "could not retrieve deps"
+    );
 }
 
 #[test]
-fn test_conditonal_requirements_with_extras() {
+fn test_solver_builder() {
+    let create_provider = || {
+        let mut provider = BundleBoxProvider::from_packages(&[
+            ("a", 3, vec![]),
+            ("a", 2, vec![]),
+            ("a", 1, vec!["b"]),
+        ]);
+        provider.add_package("b", Pack::new(1).with_unknown_deps(), &[], &[], &[]);
+        let requirements = provider.requirements(&["a"]);
+        (provider, requirements)
+    };
+
+    // A default builder is equivalent to `Solver::new`.
+    let (provider, requirements) = create_provider();
+    let mut solver = SolverBuilder::new().build(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    insta::assert_snapshot!(result, @"a=3");
+
+    // Preferring the oldest version of `a` pulls in `b`, whose dependencies
+    // are unknown, which is a hard error in strict mode.
+    let (provider, requirements) = create_provider();
+    let b1 = provider.solvable_id("b", Pack::new(1).with_unknown_deps());
+    let mut solver = SolverBuilder::new()
+        .with_objective(Objective::MinimizeVersions)
+        .with_candidate_limit(1)
+        .with_strict_unknown_dependencies(true)
+        .with_proof_logging()
+        .build(provider);
+    let Err(UnsolvableOrCancelled::Cancelled(reason)) =
+        solver.solve(Problem::new().requirements(requirements))
+    else {
+        panic!("expected the solve to be cancelled");
+    };
+    let unknown = *reason.downcast::<UnknownDependencies>().unwrap();
+    assert_eq!(unknown.solvable, b1);
+}
+
+#[test]
+#[traced_test]
+fn test_resolve_and_cancel() {
     let mut provider = BundleBoxProvider::new();
+    provider.add_package(
+        "opentelemetry-api",
+        Pack::new(3).with_unknown_deps(),
+        &[],
+        &[],
+        &[],
+    );
+    provider.add_package(
+        "opentelemetry-api",
+        Pack::new(2).cancel_during_get_dependencies(),
+        &[],
+        &[],
+        &[],
+    );
+    let error = solve_unsat(provider, &["opentelemetry-api"]);
+    insta::assert_snapshot!(error);
+}
 
-    // Package a has both optional dependencies (via features) and conditional dependencies
+#[test]
+fn test_undecided_names_after_cancel() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("b", 1, vec!["c"]),
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
+    ]);
     provider.add_package(
-        "a",
-        1.into(),
-        &["b 1"],
+        "x",
+        Pack::new(1).cancel_during_get_dependencies(),
+        &[],
+        &[],
         &[],
-        &[("feat1", &["c"]), ("feat2", &["d"])],
     );
-    provider.add_package("b", 1.into(), &[], &[], &[]);
-    provider.add_package("b", 2.into(), &[], &[], &[]);
-    provider.add_package("c", 1.into(), &[], &[], &[]);
-    provider.add_package("d", 1.into(), &[], &[], &[]);
-    provider.add_package("e", 1.into(), &[], &[], &[]);
+    let requirements = provider.requirements(&["x", "b"]);
+    let mut solver = Solver::new(provider);
+    let result = solver.solve(Problem::new().requirements(requirements));
+    assert!(matches!(result, Err(UnsolvableOrCancelled::Cancelled(_))));
+
+    let undecided = solver
+        .undecided_names()
+        .into_iter()
+        .map(|name| solver.provider().display_name(name).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(undecided, vec!["b", "c", "x"]);
+}
 
-    // Request package a with feat1 enabled, which will pull in c
-    // This should trigger the conditional requirement on e
-    let requirements = provider.requirements(&["a[feat1]", "e 1; if c 1"]);
+/// Locking a specific package version in this case a lower version namely `3`
+/// should result in the higher package not being considered
+#[test]
+fn test_resolve_locked_top_level() {
+    let mut provider =
+        BundleBoxProvider::from_packages(&[("asdf", 4, vec![]), ("asdf", 3, vec![])]);
+    provider.set_locked("asdf", 3);
+
+    let requirements = provider.requirements(&["asdf"]);
 
     let mut solver = Solver::new(provider);
     let problem = Problem::new().requirements(requirements);
     let solved = solver.solve(problem).unwrap();
-    let result = transaction_to_string(solver.provider(), &solved);
-    insta::assert_snapshot!(result, @r###"
-        a=1
-        b=1
-        c=1
-        e=1
-        "###);
-}
+    let pool = &solver.provider().pool;
 
-#[cfg(feature = "serde")]
-fn serialize_snapshot(snapshot: &DependencySnapshot, destination: impl AsRef<std::path::Path>) {
-    let file = std::io::BufWriter::new(std::fs::File::create(destination.as_ref()).unwrap());
-    serde_json::to_writer_pretty(file, snapshot).unwrap()
+    assert_eq!(solved.len(), 1);
+    let solvable_id = solved[0];
+    assert_eq!(pool.resolve_solvable(solvable_id).record.version, 3);
 }
 
-fn solve_for_snapshot(
-    provider: SnapshotProvider,
-    root_reqs: &[VersionSetId],
-    root_constraints: &[VersionSetId],
-) -> String {
-    let mut solver = Solver::new(provider);
-    let problem = Problem::new()
-        .requirements(root_reqs.iter().copied().map(Into::into).collect())
+/// Should ignore lock when it is not a top level package and a newer version
+/// exists without it
+#[test]
+fn test_resolve_ignored_locked_top_level() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("asdf", 4, vec![]),
+        ("asdf", 3, vec!["fgh"]),
+        ("fgh", 1, vec![]),
+    ]);
+
+    provider.set_locked("fgh", 1);
+
+    let requirements = provider.requirements(&["asdf"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let pool = &solver.provider().pool;
+
+    assert_eq!(solved.len(), 1);
+    let solvable = pool.resolve_solvable(solved[0]);
+
+    assert_eq!(pool.resolve_package_name(solvable.name), "asdf");
+    assert_eq!(solvable.record.version, 4);
+}
+
+/// Test checks if favoring without a conflict results in a package upgrade
+#[test]
+fn test_resolve_favor_without_conflict() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+    ]);
+    provider.set_favored("a", 1);
+    provider.set_favored("b", 1);
+
+    // Already installed: A=1; B=1
+    let result = solve_snapshot(provider, &["a", "b 2"]);
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=2
+        "###);
+}
+
+#[test]
+fn test_first_fit_ignores_favored() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+    ]);
+    provider.set_favored("a", 1);
+    let requirements = provider.requirements(&["a", "b"]);
+
+    let mut solver = SolverBuilder::new().with_first_fit(true).build(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    a=2
+    b=2
+    ");
+}
+
+#[test]
+fn test_objective_minimize_versions() {
+    let solve = |objective| {
+        let provider = BundleBoxProvider::from_packages(&[
+            ("a", 1, vec!["b 2..4"]),
+            ("a", 2, vec!["b 1..4"]),
+            ("b", 1, vec![]),
+            ("b", 2, vec![]),
+            ("b", 3, vec![]),
+        ]);
+        let requirements = provider.requirements(&["a"]);
+        let mut solver = SolverBuilder::new()
+            .with_objective(objective)
+            .build(provider);
+        let solved = solver
+            .solve(Problem::new().requirements(requirements))
+            .unwrap();
+        transaction_to_string(solver.provider(), &solved)
+    };
+
+    assert_snapshot!(solve(Objective::MinimizeVersions), @r"
+    a=1
+    b=2
+    ");
+    assert_snapshot!(solve(Objective::MaximizeVersions), @r"
+    a=2
+    b=3
+    ");
+}
+
+#[test]
+fn test_objective_minimize_versions_tightens_favored() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("a", 3, vec![]),
+        ("b", 1, vec!["a 2..4"]),
+        ("b", 2, vec![]),
+    ]);
+    provider.set_favored("a", 3);
+    let requirements = provider.requirements(&["a", "b"]);
+    let mut solver = SolverBuilder::new()
+        .with_objective(Objective::MinimizeVersions)
+        .build(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    a=2
+    b=1
+    ");
+}
+
+#[test]
+fn test_clause_minimization() {
+    // Five pigeons that each need one of four holes: pigeon `p` in hole `h` requires
+    // version `p` of the hole, and a hole can only have one version installed.
+    let solve = |clause_minimization| {
+        let mut provider = BundleBoxProvider::new();
+        for pigeon in 0..5 {
+            for hole in 1..5 {
+                let requirement = format!("hole{hole} {pigeon}");
+                provider.add_package(
+                    &format!("pigeon{pigeon}"),
+                    Pack::new(hole),
+                    &[&requirement],
+                    &[],
+                    &[],
+                );
+                provider.add_package(&format!("hole{hole}"), Pack::new(pigeon), &[], &[], &[]);
+            }
+        }
+        let requirements =
+            provider.requirements(&["pigeon0", "pigeon1", "pigeon2", "pigeon3", "pigeon4"]);
+        let mut solver = SolverBuilder::new()
+            .with_clause_minimization(clause_minimization)
+            .build(provider);
+        let result = solver.solve(Problem::new().requirements(requirements));
+        assert!(matches!(result, Err(UnsolvableOrCancelled::Unsolvable(_))));
+        solver.stats()
+    };
+
+    let minimized = solve(true);
+    let not_minimized = solve(false);
+    assert!(minimized.num_learnt_clauses > 0);
+    assert!(
+        minimized.average_learnt_clause_length() < not_minimized.average_learnt_clause_length()
+    );
+}
+
+#[test]
+fn test_import_learnt_clauses() {
+    // Pigeon `p` in hole `h` requires version `p` of the hole, and a hole can only
+    // have one version installed. With as many holes as pigeons every pigeon ends
+    // up in a hole of its own, but finding out which takes some conflicts.
+    let create_provider = |rebuild: bool, without: Option<(&str, u32)>| {
+        let mut provider = BundleBoxProvider::new();
+        let mut packages = Vec::new();
+        for pigeon in 0..6 {
+            for hole in 1..7 {
+                let requirement = format!("hole{hole} {pigeon}");
+                packages.push((format!("pigeon{pigeon}"), hole, Some(requirement)));
+                packages.push((format!("hole{hole}"), pigeon, None));
+            }
+        }
+        packages.retain(|(name, version, _)| without != Some((name.as_str(), *version)));
+
+        // A rebuilt pool interns the packages and solvables in a different order,
+        // so their ids differ.
+        if rebuild {
+            packages.reverse();
+        }
+        for (name, version, requirement) in &packages {
+            let requirements = requirement.as_deref().into_iter().collect::<Vec<_>>();
+            provider.add_package(name, Pack::new(*version), &requirements, &[], &[]);
+        }
+        if rebuild {
+            for (name, version, _) in &packages {
+                provider.solvable_id(name, Pack::new(*version));
+            }
+        }
+
+        let requirements = provider.requirements(&[
+            "pigeon0", "pigeon1", "pigeon2", "pigeon3", "pigeon4", "pigeon5",
+        ]);
+        (provider, requirements)
+    };
+
+    let (provider, requirements) = create_provider(false, None);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let cold_result = transaction_to_string(solver.provider(), &solved);
+    let cold = solver.stats();
+    let clauses = solver.export_learnt_clauses();
+    assert!(cold.num_conflicts > 0);
+    assert_eq!(clauses.len(), cold.num_learnt_clauses);
+
+    // The clauses apply to the rebuilt pool and save most of the conflicts.
+    let (provider, requirements) = create_provider(true, None);
+    let mut solver = Solver::new(provider);
+    assert_eq!(
+        solver.import_learnt_clauses(&clauses).unwrap(),
+        clauses.len()
+    );
+    let solved = solver
+        .solve(Problem::new().requirements(requirements.clone()))
+        .unwrap();
+    // The provider does not answer the same request twice, so verify with a fresh one.
+    let (fresh, _) = create_provider(true, None);
+    verify_solution(&solved, &requirements, &[], &fresh).unwrap();
+    assert_eq!(
+        transaction_to_string(solver.provider(), &solved),
+        cold_result
+    );
+    let warm = solver.stats();
+    assert!(warm.warm_start_clauses_used > 0);
+    assert!(warm.num_conflicts < cold.num_conflicts);
+
+    // Clauses that refer to a solvable that no longer exists are discarded.
+    let (provider, requirements) = create_provider(true, Some(("hole6", 0)));
+    let mut solver = Solver::new(provider);
+    let imported = solver.import_learnt_clauses(&clauses).unwrap();
+    assert!(imported < clauses.len());
+    let solved = solver
+        .solve(Problem::new().requirements(requirements.clone()))
+        .unwrap();
+    let (fresh, _) = create_provider(true, Some(("hole6", 0)));
+    verify_solution(&solved, &requirements, &[], &fresh).unwrap();
+}
+
+#[test]
+fn test_must_upgrade_forbids_older_candidates() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("a", 3, vec![]),
+        ("a", 4, vec![]),
+    ]);
+    provider.set_favored("a", 2);
+    let installed = provider.solvable_id("a", 2);
+    let requirements = provider.requirements(&["a 1..4"]);
+
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements.clone()))
+        .unwrap();
+    assert_eq!(solved, vec![installed]);
+
+    let problem = Problem::new()
+        .requirements(requirements)
+        .must_upgrade(vec![installed]);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @"a=3");
+}
+
+#[test]
+fn test_resolve_favor_with_conflict() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["c 1"]),
+        ("a", 2, vec![]),
+        ("b", 1, vec!["c 1"]),
+        ("b", 2, vec!["c 2"]),
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
+    ]);
+    provider.set_favored("a", 1);
+    provider.set_favored("b", 1);
+    provider.set_favored("c", 1);
+
+    let result = solve_snapshot(provider, &["a", "b 2"]);
+    insta::assert_snapshot!(result, @r###"
+        a=2
+        b=2
+        c=2
+        "###);
+}
+
+#[test]
+fn test_resolve_cyclic() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 2, vec!["b 0..10"]), ("b", 5, vec!["a 2..4"])]);
+    let requirements = provider.requirements(&["a 0..100"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+
+    let result = transaction_to_string(solver.provider(), &solved);
+    insta::assert_snapshot!(result, @r###"
+        a=2
+        b=5
+        "###);
+}
+
+#[test]
+fn test_resolve_union_requirements() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("b", 1, vec![]),
+        ("c", 1, vec!["a"]),
+        ("d", 1, vec!["b"]),
+        ("e", 1, vec!["a | b"]),
+    ]);
+
+    // Make d conflict with a=1
+    provider.add_package("f", 1.into(), &["b"], &["a 2"], &[]);
+
+    let result = solve_snapshot(provider, &["c | d", "e", "f"]);
+    assert_snapshot!(result, @r###"
+        b=1
+        d=1
+        e=1
+        f=1
+        "###);
+}
+
+#[test]
+fn test_unsat_locked_and_excluded() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("asdf", 1, vec!["c 2"]),
+        ("c", 2, vec![]),
+        ("c", 1, vec![]),
+    ]);
+    provider.set_locked("c", 1);
+    insta::assert_snapshot!(solve_snapshot(provider, &["asdf"]));
+}
+
+#[test]
+#[tracing_test::traced_test]
+fn test_unsat_no_candidates_for_child_1() {
+    let provider = BundleBoxProvider::from_packages(&[("asdf", 1, vec!["c 2"]), ("c", 1, vec![])]);
+    let error = solve_unsat(provider, &["asdf"]);
+    insta::assert_snapshot!(error);
+}
+
+//
+#[test]
+fn test_unsat_no_candidates_for_child_2() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 41, vec!["B 0..20"])]);
+    let error = solve_unsat(provider, &["a 0..1000"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+fn test_unsat_lists_rejected_candidate_versions() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("app", 1, vec!["libc 35..40"]),
+        ("libc", 30, vec![]),
+        ("libc", 31, vec![]),
+    ]);
+    let error = solve_unsat(provider, &["app"]);
+    insta::assert_snapshot!(error, @r"
+    app * cannot be installed because there are no viable options:
+    └─ app 1 would require
+       └─ libc >=35, <40, for which no candidates were found.
+          └─ available, but not matching: libc=30, libc=31
+    ");
+}
+
+#[test]
+fn test_unsat_suggests_closest_candidate() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("app", 1, vec!["libc 35..40"]),
+        ("libc", 30, vec![]),
+        ("libc", 31, vec![]),
+        ("libc", 42, vec![]),
+    ]);
+    provider.describe_bounds = true;
+    let error = solve_unsat(provider, &["app"]);
+    insta::assert_snapshot!(error, @r"
+    app * cannot be installed because there are no viable options:
+    └─ app 1 would require
+       └─ libc >=35, <40, for which no candidates were found.
+          ├─ available, but not matching: libc=30, libc=31, libc=42
+          └─ closest available: libc=31
+    ");
+}
+
+#[test]
+fn test_unsat_suggests_closest_version_below_requirement() {
+    // `foo >=3.0` where only `foo 2.9` exists, with versions multiplied by ten.
+    let mut provider = BundleBoxProvider::from_packages(&[("foo", 29, vec![])]);
+    provider.describe_bounds = true;
+    let error = solve_unsat(provider, &["foo 30..1000"]);
+    insta::assert_snapshot!(error, @r"
+    No candidates were found for foo >=30, <1000.
+    ├─ available, but not matching: foo=29
+    └─ closest available: foo=29
+    ");
+}
+
+#[test]
+fn test_conflict_suggestions() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("b", 1, vec!["a 2"]),
+    ]);
+    provider.describe_bounds = true;
+    let requirements = provider.requirements(&["a 1", "b"]);
+    let relaxed = requirements[0].requirement;
+    let a2 = provider.solvable_id("a", 2);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected `b` to conflict with `a 1`");
+    };
+
+    // Allowing `a 2` resolves the conflict.
+    let suggestions = conflict.suggestions(&solver);
+    assert_eq!(
+        suggestions,
+        vec![Suggestion::RelaxRequirement {
+            requirement: relaxed,
+            candidate: a2,
+        }]
+    );
+    assert_snapshot!(
+        suggestions[0].display(solver.provider()).to_string(),
+        @"relax the requirement a >=1, <2 to include a=2"
+    );
+}
+
+#[test]
+fn test_conflict_suggestions_skip_policy_pins() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![]), ("a", 3, vec![])]);
+    let requirements = provider.requirements(&["a 3"]);
+    let restriction = provider.version_sets(&["a 1..3"])[0];
+    let policy_pins = vec![(
+        provider.package_name("a"),
+        restriction,
+        provider.pool.intern_string("approved by the security team"),
+    )];
+    let mut solver = Solver::new(provider);
+
+    // A constraint of the problem can be removed.
+    let problem = Problem::new()
+        .requirements(requirements.clone())
+        .constraints(vec![restriction]);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the constraint to conflict with `a 3`");
+    };
+    assert_eq!(
+        conflict.suggestions(&solver),
+        vec![Suggestion::RemoveConstraint(restriction)]
+    );
+
+    // The same restriction as a policy pin is not suggested to be removed.
+    let problem = Problem::new()
+        .requirements(requirements)
+        .policy_pins(policy_pins);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected the policy pin to conflict with `a 3`");
+    };
+    assert_eq!(conflict.suggestions(&solver), Vec::new());
+}
+
+#[test]
+fn test_version_set_bounds_closest_candidate() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 3, vec![]),
+        ("a", 7, vec![]),
+        ("a", 9, vec![]),
+    ]);
+    provider.describe_bounds = true;
+    let candidates = [1, 3, 7, 9].map(|version| provider.solvable_id("a", version));
+
+    let closest = |spec: &str| {
+        let version_set = provider.intern_version_set(&Spec::from_str(spec).unwrap());
+        let bounds = provider.version_set_bounds(version_set).unwrap();
+        bounds
+            .closest_candidate(&candidates, &provider)
+            .map(|solvable| provider.display_solvable(solvable).to_string())
+    };
+    assert_eq!(closest("a 4..6").as_deref(), Some("a=3"));
+    assert_eq!(closest("a 10..11").as_deref(), Some("a=9"));
+    assert_eq!(closest("a 0..1").as_deref(), Some("a=1"));
+}
+
+#[test]
+fn test_unsat_missing_top_level_dep_1() {
+    let provider = BundleBoxProvider::from_packages(&[("asdf", 1, vec![])]);
+    let error = solve_unsat(provider, &["fghj"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+fn test_unsat_missing_top_level_dep_2() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 41, vec!["b 15"]), ("b", 15, vec![])]);
+    let error = solve_unsat(provider, &["a 41", "b 14"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+#[tracing_test::traced_test]
+fn test_unsat_after_backtracking() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("b", 7, vec!["d 1"]),
+        ("b", 6, vec!["d 1"]),
+        ("c", 1, vec!["d 2"]),
+        ("c", 2, vec!["d 2"]),
+        ("d", 2, vec![]),
+        ("d", 1, vec![]),
+        ("e", 1, vec![]),
+        ("e", 2, vec![]),
+    ]);
+
+    let error = solve_unsat(provider, &["b", "c", "e"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+fn test_unsat_incompatible_root_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 2, vec![]), ("a", 5, vec![])]);
+    let error = solve_unsat(provider, &["a 0..4", "a 5..10"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+fn test_unsat_bluesky_conflict() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("suitcase-utils", 54, vec![]),
+        ("suitcase-utils", 53, vec![]),
+        (
+            "bluesky-widgets",
+            42,
+            vec![
+                "bluesky-live 0..10",
+                "numpy 0..10",
+                "python 0..10",
+                "suitcase-utils 0..54",
+            ],
+        ),
+        ("bluesky-live", 1, vec![]),
+        ("numpy", 1, vec![]),
+        ("python", 1, vec![]),
+    ]);
+    let error = solve_unsat(
+        provider,
+        &["bluesky-widgets 0..100", "suitcase-utils 54..100"],
+    );
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+fn test_unsat_pubgrub_article() {
+    // Taken from the pubgrub article: https://nex3.medium.com/pubgrub-2fb6470504f
+    let provider = BundleBoxProvider::from_packages(&[
+        ("menu", 15, vec!["dropdown 2..3"]),
+        ("menu", 10, vec!["dropdown 1..2"]),
+        ("dropdown", 2, vec!["icons 2"]),
+        ("dropdown", 1, vec!["intl 3"]),
+        ("icons", 2, vec![]),
+        ("icons", 1, vec![]),
+        ("intl", 5, vec![]),
+        ("intl", 3, vec![]),
+    ]);
+    let error = solve_unsat(provider, &["menu", "icons 1", "intl 5"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+fn test_unsat_applies_graph_compression() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 10, vec!["b"]),
+        ("a", 9, vec!["b"]),
+        ("b", 100, vec!["c 0..100"]),
+        ("b", 42, vec!["c 0..100"]),
+        ("c", 103, vec![]),
+        ("c", 101, vec![]),
+        ("c", 100, vec![]),
+        ("c", 99, vec![]),
+    ]);
+    let error = solve_unsat(provider, &["a", "c 101..104"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+fn test_unsat_constrains() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 10, vec!["b 50..100"]),
+        ("a", 9, vec!["b 50..100"]),
+        ("b", 50, vec![]),
+        ("b", 42, vec![]),
+    ]);
+
+    provider.add_package("c", 10.into(), &[], &["b 0..50"], &[]);
+    provider.add_package("c", 8.into(), &[], &["b 0..50"], &[]);
+    let error = solve_unsat(provider, &["a", "c"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+#[tracing_test::traced_test]
+fn test_unsat_constrains_2() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b"]),
+        ("a", 2, vec!["b"]),
+        ("b", 1, vec!["c 1"]),
+        ("b", 2, vec!["c 2"]),
+    ]);
+
+    provider.add_package("c", 1.into(), &[], &["a 3"], &[]);
+    provider.add_package("c", 2.into(), &[], &["a 3"], &[]);
+    let error = solve_unsat(provider, &["a"]);
+    insta::assert_snapshot!(error);
+}
+
+#[test]
+fn test_missing_dep() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 2, vec!["missing"]), ("a", 1, vec![])]);
+    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
+}
+
+#[test]
+#[tracing_test::traced_test]
+fn test_no_backtracking() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("quetz-server", 2, vec!["pydantic 10..20"]),
+        ("quetz-server", 1, vec!["pydantic 0..10"]),
+        ("pydantic", 1, vec![]),
+        ("pydantic", 2, vec![]),
+        ("pydantic", 3, vec![]),
+        ("pydantic", 4, vec![]),
+        ("pydantic", 5, vec![]),
+        ("pydantic", 6, vec![]),
+        ("pydantic", 7, vec![]),
+        ("pydantic", 8, vec![]),
+        ("pydantic", 9, vec![]),
+        ("pydantic", 10, vec![]),
+        ("pydantic", 11, vec![]),
+        ("pydantic", 12, vec![]),
+        ("pydantic", 13, vec![]),
+        ("pydantic", 14, vec![]),
+    ]);
+    insta::assert_snapshot!(solve_snapshot(
+        provider,
+        &["quetz-server", "pydantic 0..10"]
+    ));
+}
+
+#[test]
+#[tracing_test::traced_test]
+fn test_incremental_crash() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 3, vec!["missing"]),
+        ("a", 2, vec!["missing"]),
+        ("a", 1, vec!["b"]),
+        ("b", 2, vec!["a 2..4"]),
+        ("b", 1, vec![]),
+    ]);
+    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
+}
+
+#[test]
+#[traced_test]
+fn test_excluded() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 2, vec!["b"]),
+        ("a", 1, vec!["c"]),
+        ("b", 1, vec![]),
+        ("c", 1, vec![]),
+    ]);
+    provider.exclude("b", 1, "it is externally excluded");
+    provider.exclude("c", 1, "it is externally excluded");
+    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
+}
+
+#[test]
+fn test_merge_excluded() {
+    let mut provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![])]);
+    provider.exclude("a", 1, "it is externally excluded");
+    provider.exclude("a", 2, "it is externally excluded");
+    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
+}
+
+#[test]
+#[traced_test]
+fn test_merge_installable() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("a", 3, vec![]),
+        ("a", 4, vec![]),
+    ]);
+    insta::assert_snapshot!(solve_snapshot(provider, &["a 0..3", "a 3..5"]));
+}
+
+#[test]
+fn test_root_excluded() {
+    let mut provider = BundleBoxProvider::from_packages(&[("a", 1, vec![])]);
+    provider.exclude("a", 1, "it is externally excluded");
+    insta::assert_snapshot!(solve_snapshot(provider, &["a"]));
+}
+
+#[test]
+fn test_constraints() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b 0..10"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("c", 1, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a 0..10"]);
+    let constraints = provider.version_sets(&["b 1..2", "c"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .constraints(constraints);
+    let solved = solver.solve(problem).unwrap();
+
+    let result = transaction_to_string(solver.provider(), &solved);
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=1
+        "###);
+}
+
+#[test]
+fn test_solve_with_additional() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b 0..10"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("c", 1, vec![]),
+        ("d", 1, vec![]),
+        ("e", 1, vec!["d"]),
+        ("locked", 1, vec![]),
+        ("locked", 2, vec![]),
+    ]);
+
+    provider.set_locked("locked", 2);
+
+    let requirements = provider.requirements(&["a 0..10"]);
+    let constraints = provider.version_sets(&["b 1..2", "c"]);
+
+    let extra_solvables = [
+        provider.solvable_id("b", 2),
+        provider.solvable_id("c", 1),
+        provider.solvable_id("e", 1),
+        // Does not obey the locked clause since it has not been requested
+        // in a version set by another solvable
+        provider.solvable_id("locked", 1),
+        provider.solvable_id("unknown-deps", Pack::new(1).with_unknown_deps()),
+    ];
+
+    let mut solver = Solver::new(provider);
+
+    let problem = Problem::new()
+        .requirements(requirements)
+        .constraints(constraints)
+        .soft_requirements(extra_solvables);
+    let solved = solver.solve(problem).unwrap();
+
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r###"
+        a=1
+        b=1
+        c=1
+        d=1
+        e=1
+        locked=1
+        "###);
+}
+
+#[test]
+fn test_solve_with_additional_with_constrains() {
+    let mut provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b 0..10"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("b", 3, vec![]),
+        ("c", 1, vec![]),
+        ("d", 1, vec!["f"]),
+        ("e", 1, vec!["c"]),
+    ]);
+
+    provider.add_package("f", 1.into(), &[], &["c 2..3"], &[]);
+    provider.add_package("g", 1.into(), &[], &["b 2..3"], &[]);
+    provider.add_package("h", 1.into(), &[], &["b 1..2"], &[]);
+    provider.add_package("i", 1.into(), &[], &[], &[]);
+    provider.add_package("j", 1.into(), &["i"], &[], &[]);
+    provider.add_package("k", 1.into(), &["i"], &[], &[]);
+    provider.add_package("l", 1.into(), &["j", "k"], &[], &[]);
+
+    let requirements = provider.requirements(&["a 0..10", "e"]);
+    let constraints = provider.version_sets(&["b 1..2", "c", "k 2..3"]);
+
+    let extra_solvables = [
+        provider.solvable_id("d", 1),
+        provider.solvable_id("g", 1),
+        provider.solvable_id("h", 1),
+        provider.solvable_id("j", 1),
+        provider.solvable_id("l", 1),
+        provider.solvable_id("k", 1),
+    ];
+
+    let mut solver = Solver::new(provider);
+
+    let problem = Problem::new()
+        .requirements(requirements)
+        .constraints(constraints)
+        .soft_requirements(extra_solvables);
+
+    let solved = solver.solve(problem).unwrap();
+
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r###"
+        a=1
+        b=1
+        c=1
+        e=1
+        h=1
+        i=1
+        j=1
+        "###);
+}
+
+#[test]
+fn test_snapshot() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("menu", 15, vec!["dropdown 2..3"]),
+        ("menu", 10, vec!["dropdown 1..2"]),
+        ("dropdown", 2, vec!["icons 2"]),
+        ("dropdown", 1, vec!["intl 3"]),
+        ("icons", 2, vec![]),
+        ("icons", 1, vec![]),
+        ("intl", 5, vec![]),
+        ("intl", 3, vec![]),
+    ]);
+
+    let menu_name_id = provider.package_name("menu");
+
+    let snapshot = provider.into_snapshot();
+
+    #[cfg(feature = "serde")]
+    serialize_snapshot(&snapshot, "snapshot_pubgrub_menu.json");
+
+    let mut snapshot_provider = snapshot.provider();
+
+    let menu_req = snapshot_provider.add_package_requirement(menu_name_id, "*");
+
+    assert_snapshot!(solve_for_snapshot(snapshot_provider, &[menu_req], &[]));
+}
+
+#[test]
+fn test_snapshot_union_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("icons", 2, vec![]),
+        ("icons", 1, vec![]),
+        ("intl", 5, vec![]),
+        ("intl", 3, vec![]),
+        ("union", 1, vec!["icons 2 | intl"]),
+    ]);
+
+    let intl_name_id = provider.package_name("intl");
+    let union_name_id = provider.package_name("union");
+
+    let snapshot = provider.into_snapshot();
+
+    let mut snapshot_provider = snapshot.provider();
+
+    let intl_req = snapshot_provider.add_package_requirement(intl_name_id, "*");
+    let union_req = snapshot_provider.add_package_requirement(union_name_id, "*");
+
+    assert_snapshot!(solve_for_snapshot(
+        snapshot_provider,
+        &[intl_req, union_req],
+        &[]
+    ));
+}
+
+#[test]
+fn test_snapshot_binary_round_trip() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("menu", 15, vec!["dropdown 2..3"]),
+        ("menu", 10, vec!["dropdown 1..2"]),
+        ("dropdown", 2, vec!["icons 2 | intl 5"]),
+        ("dropdown", 1, vec!["intl 3"]),
+        ("icons", 2, vec![]),
+        ("icons", 1, vec![]),
+        ("intl", 5, vec![]),
+        ("intl", 3, vec![]),
+    ]);
+
+    let menu_name_id = provider.package_name("menu");
+    let intl_name_id = provider.package_name("intl");
+
+    let snapshot = provider.into_snapshot();
+    let binary = snapshot.to_binary();
+    let decoded = DependencySnapshot::from_binary(&binary).unwrap();
+    assert_eq!(decoded.to_binary(), binary);
+
+    #[cfg(feature = "serde")]
+    assert!(binary.len() < serde_json::to_vec(&snapshot).unwrap().len());
+
+    let solve = |snapshot: &DependencySnapshot| {
+        let mut snapshot_provider = snapshot.provider();
+        let menu_req = snapshot_provider.add_package_requirement(menu_name_id, "*");
+        let intl_constraint = snapshot_provider.add_package_requirement(intl_name_id, "3");
+        solve_for_snapshot(snapshot_provider, &[menu_req], &[intl_constraint])
+    };
+    let result = solve(&decoded);
+    assert_eq!(result, solve(&snapshot));
+    assert_snapshot!(result, @r"
+    dropdown=2
+    icons=2
+    menu=15
+    ");
+
+    let mut other_version = binary;
+    other_version[4] += 1;
+    assert_eq!(
+        DependencySnapshot::from_binary(&other_version).err(),
+        Some(BinarySnapshotError::UnsupportedVersion(2))
+    );
+}
+
+#[test]
+fn test_union_empty_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec!["b 1 | c"]), ("b", 1, vec![])]);
+
+    let result = solve_snapshot(provider, &["a"]);
+    assert_snapshot!(result, @r"
+    a=1
+    b=1
+    ");
+}
+
+#[test]
+fn test_union_order_is_deterministic() {
+    let provider = BundleBoxProvider::from_packages(&[("b", 1, vec![]), ("c", 1, vec![])]);
+    let b = Spec::from_str("b 1").unwrap();
+    let c = Spec::from_str("c").unwrap();
+
+    let first = provider.intern_version_set_union([&c, &b]);
+    let second = provider.intern_version_set_union([&b, &c]);
+    assert_eq!(
+        provider.version_sets_in_union(first).collect_vec(),
+        provider.version_sets_in_union(second).collect_vec()
+    );
+
+    let first = Requirement::Union(first).display(&provider).to_string();
+    let second = Requirement::Union(second).display(&provider).to_string();
+    assert_eq!(first, second);
+    assert_eq!(first, "c * | b >=1, <2");
+}
+
+#[test]
+fn test_parse_requirement() {
+    let provider = BundleBoxProvider::from_packages(&[("foo", 1, vec![]), ("bar", 1, vec![])]);
+    let mut parsed = Vec::new();
+    let mut parse_version_set = |input: &str| {
+        parsed.push(input.to_string());
+        provider.intern_version_set(&Spec::from_str(input).unwrap())
+    };
+
+    let requirement = provider
+        .pool
+        .parse_requirement("foo | bar", &mut parse_version_set);
+    let Requirement::Union(union) = requirement else {
+        panic!("expected a union, got {requirement:?}");
+    };
+    assert_eq!(provider.version_sets_in_union(union).count(), 2);
+
+    let requirement = provider
+        .pool
+        .parse_requirement("foo", &mut parse_version_set);
+    assert!(matches!(requirement, Requirement::Single(_)));
+
+    assert_eq!(parsed, ["foo", "bar", "foo"]);
+}
+
+fn solve_requirement(
+    provider: BundleBoxProvider,
+    requirement: Requirement,
+    others: &[&str],
+) -> String {
+    let mut requirements = provider.requirements(others);
+    requirements.insert(0, requirement.into());
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    match solver.solve(problem) {
+        Ok(solvables) => transaction_to_string(solver.provider(), &solvables),
+        Err(UnsolvableOrCancelled::Unsolvable(conflict)) => {
+            conflict.display_user_friendly(&solver).to_string()
+        }
+        Err(UnsolvableOrCancelled::Cancelled(_)) => unreachable!(),
+    }
+}
+
+#[test]
+fn test_at_least_requirement() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("b", 1, vec![]), ("c", 1, vec![])]);
+    let version_sets = provider.version_set_union(&["a", "b", "c"]);
+    let requirement = Requirement::AtLeast { n: 2, version_sets };
+    assert_eq!(
+        requirement.display(&provider).to_string(),
+        "at least 2 of (a * | b * | c *)"
+    );
+
+    let result = solve_requirement(provider, requirement, &[]);
+    assert_eq!(result.lines().count(), 2, "{result}");
+}
+
+#[test]
+fn test_at_least_requirement_zero() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("b", 1, vec![])]);
+    let version_sets = provider.version_set_union(&["a", "b"]);
+    let requirement = Requirement::AtLeast { n: 0, version_sets };
+    assert_snapshot!(solve_requirement(provider, requirement, &[]), @"");
+}
+
+#[test]
+fn test_at_least_requirement_skips_uninstallable() {
+    let solve = |n| {
+        let provider = BundleBoxProvider::from_packages(&[
+            ("a", 1, vec!["missing"]),
+            ("b", 1, vec![]),
+            ("c", 1, vec!["missing"]),
+            ("d", 1, vec![]),
+            ("e", 1, vec![]),
+        ]);
+        let version_sets = provider.version_set_union(&["a", "b", "c", "d", "e"]);
+        solve_requirement(provider, Requirement::AtLeast { n, version_sets }, &[])
+    };
+
+    // Only b, d and e can be installed, so exactly those have to be selected.
+    assert_snapshot!(solve(3), @r"
+    b=1
+    d=1
+    e=1
+    ");
+
+    let result = solve(4);
+    assert!(result.contains("missing"), "{result}");
+}
+
+#[test]
+fn test_at_least_requirement_verify() {
+    // The provider asserts that dependencies are only requested once, so every
+    // verification uses a fresh provider.
+    let verify = |solution: &[&str]| {
+        let provider = BundleBoxProvider::from_packages(&[
+            ("a", 1, vec![]),
+            ("b", 1, vec![]),
+            ("c", 1, vec![]),
+        ]);
+        let version_sets = provider.version_set_union(&["a", "b", "c"]);
+        let requirement = Requirement::AtLeast { n: 2, version_sets };
+        let solution = solution
+            .iter()
+            .map(|&name| provider.solvable_id(name, 1))
+            .collect_vec();
+        verify_solution(&solution, &[requirement.into()], &[], &provider)
+            .map_err(|error| error.display(&provider).to_string())
+    };
+
+    assert_eq!(
+        verify(&["a"]),
+        Err(
+            "not enough selected solvables satisfy the requirement at least 2 of (a * | b * | c *)"
+                .to_string()
+        )
+    );
+    assert_eq!(verify(&["a", "c"]), Ok(()));
+}
+
+#[test]
+fn test_at_least_requirement_too_few_candidates() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("b", 1, vec![])]);
+    let version_sets = provider.version_set_union(&["a", "b"]);
+    assert_snapshot!(
+        solve_requirement(provider, Requirement::AtLeast { n: 3, version_sets }, &[]),
+        @"Not enough candidates were found for at least 3 of (a * | b *)."
+    );
+}
+
+#[test]
+fn test_exactly_one_requirement() {
+    // `d` needs `b`, so neither `a` nor `c` may be selected alongside it.
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("b", 1, vec![]),
+        ("c", 1, vec![]),
+        ("d", 1, vec!["b"]),
+    ]);
+    let requirement = Requirement::ExactlyOne(provider.version_set_union(&["a", "b", "c"]));
+    assert_eq!(
+        requirement.display(&provider).to_string(),
+        "exactly one of (a * | b * | c *)"
+    );
+    assert_snapshot!(solve_requirement(provider, requirement, &["d"]), @r"
+    b=1
+    d=1
+    ");
+}
+
+#[test]
+fn test_exactly_one_requirement_without_candidates() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![])]);
+    let requirement = Requirement::ExactlyOne(provider.version_set_union(&["x", "y"]));
+    assert_snapshot!(
+        solve_requirement(provider, requirement, &[]),
+        @"No candidates were found for exactly one of (x * | y *)."
+    );
+}
+
+#[test]
+fn test_instrumented_interner() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 3, vec!["b"]),
+        ("a", 2, vec!["b"]),
+        ("a", 1, vec![]),
+        ("b", 1, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a"]);
+    let a = provider.pool.intern_package_name("a");
+
+    let provider = InstrumentedInterner::new(provider);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+
+    let counts = solver.provider().access_counts();
+    assert_eq!(counts.names_by_frequency()[0].0, a, "{counts:?}");
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=3
+    b=1
+    ");
+}
+
+#[test]
+fn test_solvable_set_version_set() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![]), ("a", 3, vec![])]);
+    let a = provider.pool.intern_package_name("a");
+    let allowed = [provider.solvable_id("a", 1), provider.solvable_id("a", 2)];
+    let version_set = provider.pool.intern_solvable_set(a, &allowed);
+    assert_eq!(
+        provider.pool.resolve_solvable_set(version_set),
+        Some(&allowed[..])
+    );
+
+    let mut solver = Solver::new(provider);
+    let matching = NowOrNeverRuntime
+        .block_on(solver.cache().get_or_cache_matching_candidates(version_set))
+        .unwrap()
+        .to_vec();
+    assert_eq!(matching, allowed);
+
+    let problem = Problem::new().requirements(vec![version_set.into()]);
+    let solved = solver.solve(problem).unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @"a=2");
+}
+
+#[test]
+#[should_panic(expected = "Pool::resolve_solvable_set")]
+fn test_solvable_set_cannot_be_resolved() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![])]);
+    let a = provider.pool.intern_package_name("a");
+    let version_set = provider
+        .pool
+        .intern_solvable_set(a, &[provider.solvable_id("a", 1)]);
+    provider.pool.resolve_version_set(version_set);
+}
+
+#[test]
+fn test_condition_combinators_simplify() {
+    let x = Condition::VersionSetId(VersionSetId(0));
+    assert_eq!(Condition::all([x]), ConditionExpr::Condition(x));
+    assert_eq!(Condition::any([x]), ConditionExpr::Condition(x));
+    assert_eq!(
+        Condition::not(Condition::not(x)),
+        ConditionExpr::Condition(x)
+    );
+
+    let requirement = Requirement::from(VersionSetId(1));
+    assert_eq!(
+        requirement.when(Condition::all([x])),
+        Ok(vec![ConditionalRequirement::new(vec![x], requirement)])
+    );
+}
+
+#[test]
+fn test_condition_combinators_unsupported() {
+    let x = Condition::VersionSetId(VersionSetId(0));
+    let y = Condition::VersionSetId(VersionSetId(1));
+    let requirement = Requirement::from(VersionSetId(2));
+
+    assert_eq!(
+        requirement.when(Condition::not(Condition::all([x, y]))),
+        Err(ConditionExprError::Negated(x))
+    );
+    assert_eq!(
+        requirement.when(Condition::any(Vec::<Condition>::new())),
+        Err(ConditionExprError::NeverMet)
+    );
+
+    // An empty conjunction is always met, so its negation is never met.
+    assert_eq!(
+        requirement.when(Condition::not(Condition::all(Vec::<Condition>::new()))),
+        Err(ConditionExprError::NeverMet)
+    );
+}
+
+#[test]
+fn test_root_constraints() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("icons", 1, vec![]), ("union", 1, vec!["icons"])]);
+
+    let union_name_id = provider.package_name("union");
+
+    let snapshot = provider.into_snapshot();
+
+    let mut snapshot_provider = snapshot.provider();
+
+    let union_req = snapshot_provider.add_package_requirement(union_name_id, "*");
+    let union_constraint = snapshot_provider.add_package_requirement(union_name_id, "5");
+
+    assert_snapshot!(solve_for_snapshot(
+        snapshot_provider,
+        &[union_req],
+        &[union_constraint]
+    ));
+}
+
+#[test]
+fn test_explicit_root_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[
+        // `a` depends transitively on `b`
+        ("a", 1, vec!["b"]),
+        // `b` depends on `c`, but the highest version of `b` constrains `c` to `<2`.
+        ("b", 1, vec!["c"]),
+        ("b", 2, vec!["c 1..2"]),
+        // `c` has more versions than `b`, so the heuristic will most likely pick `b` first.
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
+        ("c", 3, vec![]),
+        ("c", 4, vec![]),
+        ("c", 5, vec![]),
+    ]);
+
+    // We require both `a` and `c` explicitly. The expected outcome will be that we
+    // get the highest versions of `a` and `c` and a lower version of `b`.
+    let requirements = provider.requirements(&["a", "c"]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::default().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r###"
+    a=1
+    b=1
+    c=5
+    "###);
+}
+
+#[test]
+#[traced_test]
+fn test_conditional_requirements() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Add packages
+    provider.add_package("a", 1.into(), &["b"], &[], &[]); // a depends on b
+    provider.add_package("b", 1.into(), &[], &[], &[]); // Simple package b
+    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
+
+    // Create problem with both regular and conditional requirements
+    let requirements = provider.requirements(&["a", "c 1; if b 1..2"]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=1
+        c=1
+        "###);
+}
+
+#[test]
+fn test_satisfied_dependencies() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b", "c | d"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("d", 1, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a"]);
+    let mut solver = Solver::new(provider);
+    solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+
+    let a = solver.provider().solvable_id("a", 1);
+    let dependencies = solver
+        .satisfied_dependencies(a)
+        .into_iter()
+        .map(|(requirement, solvable)| {
+            format!(
+                "{} -> {}",
+                requirement.requirement.display(solver.provider()),
+                solver.provider().display_solvable(solvable)
+            )
+        })
+        .join("\n");
+    assert_snapshot!(dependencies, @r"
+    b * -> b=2
+    c * | d * -> d=1
+    ");
+
+    // Solvables that were not selected have no satisfied dependencies.
+    let b1 = solver.provider().solvable_id("b", 1);
+    assert!(solver.satisfied_dependencies(b1).is_empty());
+}
+
+#[test]
+fn test_solve_canonical() {
+    let solve = |favored: Option<(&str, u32)>, canonical: bool| {
+        let mut provider = BundleBoxProvider::from_packages(&[
+            ("a", 1, vec!["b 2"]),
+            ("a", 2, vec!["b 1"]),
+            ("b", 1, vec![]),
+            ("b", 2, vec![]),
+        ]);
+        if let Some((name, version)) = favored {
+            provider.set_favored(name, version);
+        }
+        let requirements = provider.requirements(&["a", "b"]);
+        let mut solver = Solver::new(provider);
+        let solved = if canonical {
+            solver.solve_canonical(requirements, Vec::new())
+        } else {
+            solver.solve(Problem::new().requirements(requirements))
+        };
+        transaction_to_string(solver.provider(), &solved.unwrap())
+    };
+
+    // Favoring `a 1` steers the solver to another solution.
+    assert_snapshot!(solve(None, false), @r"
+    a=2
+    b=1
+    ");
+    assert_snapshot!(solve(Some(("a", 1)), false), @r"
+    a=1
+    b=2
+    ");
+
+    // The canonical solution is the same either way.
+    assert_snapshot!(solve(None, true), @r"
+    a=2
+    b=1
+    ");
+    assert_eq!(solve(Some(("a", 1)), true), solve(None, true));
+}
+
+#[test]
+fn test_solve_canonical_prefers_leaving_packages_out() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("b", 1, vec![]),
+        ("b", 2, vec!["a"]),
+    ]);
+    let requirements = provider.requirements(&["b"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements.clone()))
+        .unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=1
+    b=2
+    ");
+
+    // `a` comes before `b`, so a solution without `a` comes first.
+    let solved = solver.solve_canonical(requirements, Vec::new()).unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @"b=1");
+}
+
+#[test]
+fn test_solve_detailed() {
+    let mut provider = BundleBoxProvider::new();
+    provider.add_package("a", 1.into(), &["b"], &[], &[]);
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+    provider.add_package("c", 1.into(), &[], &[], &[]);
+    provider.add_package("d", 1.into(), &[], &[], &[]);
+    provider.add_package("e", 1.into(), &[], &[], &[]);
+
+    let requirements = provider.requirements(&["a", "c 1; if b 1..2", "d 1; if e"]);
+    let names = ["a", "b", "c", "d", "e"].map(|name| provider.pool.intern_package_name(name));
+
+    let mut solver = Solver::new(provider);
+    let outcome = solver.solve_detailed(requirements, Vec::new()).unwrap();
+
+    assert_eq!(outcome.active_conditions, solver.active_conditions());
+    assert_eq!(outcome.stats, solver.stats());
+    for name in names {
+        assert_eq!(
+            outcome.selected.get(&name).copied(),
+            solver.selected_for(name)
+        );
+    }
+
+    let conditions = outcome
+        .active_conditions
+        .iter()
+        .map(|&(solvable, condition)| {
+            format!(
+                "{:?}: {}",
+                solvable.map(|solvable| solver.provider().display_solvable(solvable).to_string()),
+                solver.provider().display_condition(condition)
+            )
+        })
+        .join("\n");
+    assert_snapshot!(conditions, @"None: b >=1, <2");
+    assert_snapshot!(transaction_to_string(solver.provider(), &outcome.solution), @r"
+    a=1
+    b=1
+    c=1
+    ");
+}
+
+#[test]
+#[traced_test]
+fn test_conditional_requirements_not_met() {
+    let mut provider = BundleBoxProvider::new();
+    provider.add_package("b", 1.into(), &[], &[], &[]); // Add b=1 as a candidate
+    provider.add_package("b", 2.into(), &[], &[], &[]); // Different version of b
+    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
+    provider.add_package("a", 1.into(), &["b 2"], &[], &[]); // a depends on b=2 specifically
+
+    // Create conditional requirement: if b=1 is installed, require c
+    let requirements = provider.requirements(&[
+        "a",           // Require package a
+        "c 1; if b 1", // If b=1 is installed, require c (note the exact version)
+    ]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    // Since b=2 is installed (not b=1), c should not be installed
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=2
+        "###);
+}
+
+#[test]
+fn test_nested_conditional_dependencies() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Setup packages
+    provider.add_package("a", 1.into(), &[], &[], &[]); // Base package
+    provider.add_package("b", 1.into(), &[], &[], &[]); // First level conditional
+    provider.add_package("c", 1.into(), &[], &[], &[]); // Second level conditional
+    provider.add_package("d", 1.into(), &[], &[], &[]); // Third level conditional
+
+    // Create nested conditional requirements using the parser
+    let requirements = provider.requirements(&[
+        "a",           // Require package a
+        "b 1; if a 1", // If a is installed, require b
+        "c 1; if b 1", // If b is installed, require c
+        "d 1; if c 1", // If c is installed, require d
+    ]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    // All packages should be installed due to chain reaction
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=1
+        c=1
+        d=1
+        "###);
+}
+
+#[test]
+fn test_multiple_conditions_same_package() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Setup packages
+    provider.add_package("a", 1.into(), &[], &[], &[]);
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+    provider.add_package("c", 1.into(), &[], &[], &[]);
+    provider.add_package("target", 1.into(), &[], &[], &[]);
+
+    // Create multiple conditions that all require the same package using the parser
+    let requirements = provider.requirements(&[
+        "b",                // Only require package b
+        "target 1; if a 1", // If a is installed, require target
+        "target 1; if b 1", // If b is installed, require target
+        "target 1; if c 1", // If c is installed, require target
+    ]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    // b and target should be installed
+    insta::assert_snapshot!(result, @r###"
+        b=1
+        target=1
+        "###);
+}
+
+#[test]
+fn test_circular_conditional_dependencies() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Setup packages
+    provider.add_package("a", 1.into(), &[], &[], &[]);
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+
+    // Create circular conditional dependencies using the parser
+    let requirements = provider.requirements(&[
+        "a",           // Require package a
+        "b 1; if a 1", // If a is installed, require b
+        "a 1; if b 1", // If b is installed, require a
+    ]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    // Both packages should be installed due to circular dependency
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=1
+        "###);
+}
+
+#[test]
+fn test_conditional_requirements_multiple_versions() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Add multiple versions of package b
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+    provider.add_package("b", 2.into(), &[], &[], &[]);
+    provider.add_package("b", 3.into(), &[], &[], &[]);
+    provider.add_package("b", 4.into(), &[], &[], &[]);
+    provider.add_package("b", 5.into(), &[], &[], &[]);
+
+    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
+    provider.add_package("a", 1.into(), &["b 4..6"], &[], &[]); // a depends on b versions 4-5
+
+    // Create conditional requirement: if b=1..3 is installed, require c
+    let requirements = provider.requirements(&[
+        "a",              // Require package a
+        "c 1; if b 1..3", // If b is version 1-2, require c
+    ]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    // Since b=4 is installed (not b 1..3), c should not be installed
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=5
+        "###);
+}
+
+#[test]
+fn test_conditional_requirements_multiple_versions_met() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Add multiple versions of package b
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+    provider.add_package("b", 2.into(), &[], &[], &[]);
+    provider.add_package("b", 3.into(), &[], &[], &[]);
+    provider.add_package("b", 4.into(), &[], &[], &[]);
+    provider.add_package("b", 5.into(), &[], &[], &[]);
+
+    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
+    provider.add_package("c", 2.into(), &[], &[], &[]); // Version 2 of c
+    provider.add_package("c", 3.into(), &[], &[], &[]); // Version 3 of c
+    provider.add_package("a", 1.into(), &["b 1..3", "c 1..3; if b 1..3"], &[], &[]); // a depends on b 1-3 and conditionally on c 1-3
+
+    let requirements = provider.requirements(&[
+        "a", // Require package a
+    ]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    // Since b=2 is installed (within b 1..2), c should be installed
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=2
+        c=2
+        "###);
+}
+
+#[test]
+fn test_conditional_requirements_conflict() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Add multiple versions of package b
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+    provider.add_package("b", 2.into(), &[], &[], &[]);
+    provider.add_package("b", 3.into(), &[], &[], &[]);
+
+    // Package c has two versions with different dependencies
+    provider.add_package("c", 1.into(), &["d 1"], &[], &[]); // c v1 requires d v1
+    provider.add_package("c", 2.into(), &["d 2"], &[], &[]); // c v2 requires d v2
+
+    // Package d has incompatible versions
+    provider.add_package("d", 1.into(), &[], &[], &[]);
+    provider.add_package("d", 2.into(), &[], &[], &[]);
+
+    provider.add_package(
+        "a",
+        1.into(),
+        &["b 1", "c 1; if b 1", "d 2", "c 2; if b 2"],
+        &[],
+        &[],
+    );
+
+    let requirements = provider.requirements(&[
+        "a", // Require package a
+    ]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+
+    // This should fail to solve because:
+    // 1. When b=1 is chosen, it triggers the conditional requirement for c 1
+    // 2. c 1 requires d 1, but a requires d 2
+    // 3. d 1 and d 2 cannot be installed together
+
+    let solved = solver
+        .solve(problem)
+        .map_err(|e| match e {
+            UnsolvableOrCancelled::Unsolvable(conflict) => {
+                conflict.display_user_friendly(&solver).to_string()
+            }
+            UnsolvableOrCancelled::Cancelled(_) => "kir".to_string(),
+        })
+        .unwrap_err();
+
+    assert_snapshot!(solved, @r"
+    The following packages are incompatible
+    └─ a * cannot be installed because there are no viable options:
+       └─ a 1 would require
+          ├─ b >=1, <2, which can be installed with any of the following options:
+          │  └─ b 1
+          ├─ d >=2, <3, which can be installed with any of the following options:
+          │  └─ d 2
+          └─ c >=1, <2, which cannot be installed because there are no viable options:
+             └─ c 1 would require
+                └─ d >=1, <2, which cannot be installed because there are no viable options:
+                   └─ d 1, which conflicts with the versions reported above.
+    ");
+}
+
+/// In this test, the resolver installs the highest available version of b which is b 2
+/// However, the conditional requirement is that if b 1..2 is installed, require c
+/// Since b 2 is installed, c should not be installed
+#[test]
+fn test_conditional_requirements_multiple_versions_not_met() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Add multiple versions of package b
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+    provider.add_package("b", 2.into(), &[], &[], &[]);
+    provider.add_package("b", 3.into(), &[], &[], &[]);
+    provider.add_package("b", 4.into(), &[], &[], &[]);
+    provider.add_package("b", 5.into(), &[], &[], &[]);
+
+    provider.add_package("c", 1.into(), &[], &[], &[]); // Simple package c
+    provider.add_package("c", 2.into(), &[], &[], &[]); // Version 2 of c
+    provider.add_package("c", 3.into(), &[], &[], &[]); // Version 3 of c
+    provider.add_package("a", 1.into(), &["b 1..3", "c 1..3; if b 1..2"], &[], &[]); // a depends on b 1-3 and conditionally on c 1-3
+
+    let requirements = provider.requirements(&[
+        "a", // Require package a
+    ]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    // Since b=2 is installed (within b 1..2), c should be installed
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=2
+        "###);
+}
+
+#[test]
+fn test_optional_dependencies() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Add package a with base dependency on b and optional dependencies via features
+    provider.add_package(
+        "a",
+        1.into(),
+        &["b 1"],
+        &[],
+        &[("feat1", &["c"]), ("feat2", &["d"])],
+    );
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+    provider.add_package("c", 1.into(), &[], &[], &[]);
+    provider.add_package("d", 1.into(), &[], &[], &[]);
+
+    // Request package a with both optional features enabled
+    let requirements = provider.requirements(&["a[feat2]"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=1
+        d=1
+        "###);
+}
+
+#[test]
+fn test_conditonal_requirements_with_extras() {
+    let mut provider = BundleBoxProvider::new();
+
+    // Package a has both optional dependencies (via features) and conditional dependencies
+    provider.add_package(
+        "a",
+        1.into(),
+        &["b 1"],
+        &[],
+        &[("feat1", &["c"]), ("feat2", &["d"])],
+    );
+    provider.add_package("b", 1.into(), &[], &[], &[]);
+    provider.add_package("b", 2.into(), &[], &[], &[]);
+    provider.add_package("c", 1.into(), &[], &[], &[]);
+    provider.add_package("d", 1.into(), &[], &[], &[]);
+    provider.add_package("e", 1.into(), &[], &[], &[]);
+
+    // Request package a with feat1 enabled, which will pull in c
+    // This should trigger the conditional requirement on e
+    let requirements = provider.requirements(&["a[feat1]", "e 1; if c 1"]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    insta::assert_snapshot!(result, @r###"
+        a=1
+        b=1
+        c=1
+        e=1
+        "###);
+}
+
+#[test]
+fn test_condition_max_depth() {
+    let x = Condition::VersionSetId(VersionSetId(0));
+    let requirement = Requirement::from(VersionSetId(1));
+    let nested = |depth: usize| {
+        (0..depth).fold(ConditionExpr::from(x), |condition, i| {
+            if i % 2 == 0 {
+                ConditionExpr::All(vec![condition])
+            } else {
+                ConditionExpr::Any(vec![condition])
+            }
+        })
+    };
+
+    let solver = Solver::new(BundleBoxProvider::new());
+    assert_eq!(
+        solver.conditional_requirements(requirement, &nested(64)),
+        Ok(vec![ConditionalRequirement::new(vec![x], requirement)])
+    );
+
+    // Neither checking nor dropping a condition this deep may overflow the
+    // stack.
+    assert_eq!(
+        solver.conditional_requirements(requirement, &nested(100_000)),
+        Err(ConditionExprError::TooDeep { max_depth: 64 })
+    );
+
+    let solver = SolverBuilder::new()
+        .with_max_condition_depth(2)
+        .build(BundleBoxProvider::new());
+    assert_eq!(
+        solver.conditional_requirements(requirement, &nested(3)),
+        Err(ConditionExprError::TooDeep { max_depth: 2 })
+    );
+}
+
+#[test]
+fn test_condition_max_terms() {
+    let requirement = Requirement::from(VersionSetId(0));
+
+    // Every combination of one alternative per `Any` is a separate term.
+    let condition = |alternatives: u32| {
+        ConditionExpr::All(
+            (0..alternatives)
+                .map(|i| {
+                    ConditionExpr::Any(vec![
+                        Condition::VersionSetId(VersionSetId(2 * i + 1)).into(),
+                        Condition::VersionSetId(VersionSetId(2 * i + 2)).into(),
+                    ])
+                })
+                .collect(),
+        )
+    };
+
+    let solver = Solver::new(BundleBoxProvider::new());
+    assert_eq!(
+        solver
+            .conditional_requirements(requirement, &condition(10))
+            .map(|requirements| requirements.len()),
+        Ok(1024)
+    );
+    assert_eq!(
+        solver.conditional_requirements(requirement, &condition(64)),
+        Err(ConditionExprError::TooManyTerms { max_terms: 1024 })
+    );
+
+    let solver = SolverBuilder::new()
+        .with_max_condition_terms(4)
+        .build(BundleBoxProvider::new());
+    assert_eq!(
+        solver.conditional_requirements(requirement, &condition(3)),
+        Err(ConditionExprError::TooManyTerms { max_terms: 4 })
+    );
+    assert_eq!(
+        solver.conditional_requirements(requirement, &ConditionExpr::Any(Vec::new())),
+        Err(ConditionExprError::NeverMet)
+    );
+}
+
+#[test]
+fn test_extra_dependencies_not_pulled_without_base_package() {
+    let mut provider = BundleBoxProvider::new();
+    provider.add_package("a", 1.into(), &[], &[], &[("feat", &["c"])]);
+    provider.add_package("d", 1.into(), &[], &[], &[("feat", &["e"])]);
+    provider.add_package("c", 1.into(), &[], &[], &[]);
+    provider.add_package("e", 1.into(), &[], &[], &[]);
+    provider.add_package("x", 1.into(), &[], &[], &[]);
+
+    // The `feat` extra is requested for both a and d, but d is only required if
+    // x is installed, which it is not. The extra of d can therefore not be
+    // active, even though the same extra of a is.
+    let requirements = provider.requirements(&["a[feat]", "d[feat] 1; if x 1"]);
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    insta::assert_snapshot!(result, @r"
+    a=1
+    c=1
+    ");
+}
+
+#[cfg(feature = "serde")]
+fn serialize_snapshot(snapshot: &DependencySnapshot, destination: impl AsRef<std::path::Path>) {
+    let file = std::io::BufWriter::new(std::fs::File::create(destination.as_ref()).unwrap());
+    serde_json::to_writer_pretty(file, snapshot).unwrap()
+}
+
+fn solve_for_snapshot(
+    provider: SnapshotProvider,
+    root_reqs: &[VersionSetId],
+    root_constraints: &[VersionSetId],
+) -> String {
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(root_reqs.iter().copied().map(Into::into).collect())
         .constraints(root_constraints.iter().copied().map(Into::into).collect());
     match solver.solve(problem) {
         Ok(solvables) => transaction_to_string(solver.provider(), &solvables),
@@ -1980,9 +4811,935 @@ fn solve_for_snapshot(
                 .unwrap();
             writeln!(output, "\n").unwrap();
 
-            // Format a user friendly error message
-            conflict.display_user_friendly(&solver).to_string()
+            // Format a user friendly error message
+            conflict.display_user_friendly(&solver).to_string()
+        }
+        Err(UnsolvableOrCancelled::Cancelled(reason)) => *reason.downcast().unwrap(),
+    }
+}
+
+#[test]
+fn test_alias_resolves_to_canonical_name() {
+    let mut provider = BundleBoxProvider::from_packages(&[("new", 1, vec![]), ("new", 2, vec![])]);
+    provider.set_alias("old", "new");
+    let requirements = provider.requirements(&["old"]);
+    let constraints = provider.version_sets(&["new 1"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .constraints(constraints);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @"new=1");
+}
+
+#[test]
+fn test_downgrades_report_newest_candidate_and_reason() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("a", 2, vec![]), ("b", 1, vec![])]);
+    let requirements = provider.requirements(&["a", "b"]);
+    let constraints = provider.version_sets(&["a 1"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .constraints(constraints);
+    let solved = solver.solve(problem).unwrap();
+
+    let downgrades = solver.downgrades(&solved).unwrap();
+    assert_eq!(downgrades.len(), 1);
+    let downgrade = &downgrades[0];
+    let provider = solver.provider();
+    assert_eq!(downgrade.from, provider.solvable_id("a", 2));
+    assert_eq!(downgrade.to, provider.solvable_id("a", 1));
+    let reason = downgrade.reason.display_user_friendly(provider).to_string();
+    assert_snapshot!(reason, @r"
+    The following packages are incompatible
+    ├─ the constraint a >=1, <2 cannot be fulfilled
+    └─ a 2 is pinned, but cannot be installed
+    ");
+}
+
+#[test]
+fn test_warm_start_clauses_used() {
+    // The `p`s and `q` must agree on the version of `z`, but `q` only works with
+    // `z 1`, which the solver learns through conflicts.
+    let mut packages = Vec::new();
+    let z_versions = (1..=4).map(|v| format!("z {v}")).collect_vec();
+    for v in 1..=4 {
+        let z = vec![z_versions[v as usize - 1].as_str()];
+        packages.push(("p1", v, z.clone()));
+        packages.push(("p2", v, z));
+        packages.push(("z", v, vec![]));
+        packages.push(("q", v, vec!["z 1"]));
+    }
+    packages.push(("w", 1, vec![]));
+    let provider = BundleBoxProvider::from_packages(&packages);
+    let requirements = provider.requirements(&["p1", "p2", "q"]);
+    let w = provider.requirements(&["w"]);
+    let mut solver = Solver::new(provider);
+
+    solver
+        .solve(Problem::new().requirements(requirements.clone()))
+        .unwrap();
+    let stats = solver.stats();
+    assert!(stats.num_learnt_clauses > 0);
+    assert_eq!(stats.warm_start_clauses_used, 0);
+
+    // The clauses learnt about `z` apply again when extending the problem.
+    solver.add_requirement(w[0].clone());
+    solver.solve_current().unwrap();
+    let used = solver.stats().warm_start_clauses_used;
+    assert!(used > 0 && used <= stats.num_learnt_clauses);
+
+    // A new solve starts without the clauses learnt before.
+    solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_eq!(solver.stats().warm_start_clauses_used, 0);
+}
+
+#[test]
+fn test_pin_solution_reproduces_solution() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("menu", 15, vec!["dropdown 2..3"]),
+        ("menu", 10, vec!["dropdown 1..2"]),
+        ("dropdown", 2, vec!["icons 2"]),
+        ("dropdown", 1, vec!["intl 3"]),
+        ("icons", 2, vec![]),
+        ("icons", 1, vec![]),
+        ("intl", 5, vec![]),
+        ("intl", 3, vec![]),
+    ]);
+    let requirements = provider.requirements(&["menu", "icons 1"]);
+    let mut solver = Solver::new(provider);
+    let mut solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+
+    let pinned = pin_solution(&solved, &solver.provider().pool);
+    assert_eq!(pinned.len(), solved.len());
+    let mut resolved = solver.solve(Problem::new().requirements(pinned)).unwrap();
+
+    solved.sort();
+    resolved.sort();
+    assert_eq!(resolved, solved);
+    assert_snapshot!(transaction_to_string(solver.provider(), &resolved), @r"
+    dropdown=1
+    icons=1
+    intl=3
+    menu=10
+    ");
+}
+
+#[test]
+fn test_conflict_reasons_start_at_the_problem() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["c 1"]),
+        ("b", 1, vec!["c 2"]),
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a", "b"]);
+    let mut solver = Solver::new(provider);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) =
+        solver.solve(Problem::new().requirements(requirements))
+    else {
+        panic!("expected the problem to be unsolvable");
+    };
+
+    // The requirement of `a=1` was involved in a conflict before the problem
+    // turned out to be unsolvable, the other reasons are equally active.
+    let reasons = conflict.reasons(&solver).collect_vec();
+    assert_snapshot!(
+        reasons
+            .iter()
+            .map(|reason| reason.display(solver.provider()).to_string())
+            .join("\n"),
+        @r"
+    a=1 requires c >=1, <2
+    the problem requires a *
+    the problem requires b *
+    b=1 requires c >=2, <3
+    c=2 cannot be installed alongside another version of c
+    c=1 cannot be installed alongside another version of c
+    "
+    );
+
+    let reason = &reasons[1];
+    let ConflictReason::Requires {
+        solvable,
+        candidates,
+        ..
+    } = reason
+    else {
+        panic!("expected a requirement, found {reason:?}");
+    };
+    assert_eq!(*solvable, None);
+    assert_eq!(candidates.len(), 1);
+}
+
+#[test]
+fn test_conflict_reasons_ordered_by_activity() {
+    let mut provider = BundleBoxProvider::new();
+    for version in 1..=4 {
+        provider.add_package("a", version.into(), &["c"], &[], &[]);
+        provider.add_package("c", version.into(), &[], &[], &[]);
+    }
+    // `b` only allows a version of `c` that does not exist, so every version of
+    // `a` conflicts with it.
+    provider.add_package("b", 1.into(), &[], &["c 5"], &[]);
+    let requirements = provider.requirements(&["b", "a"]);
+    let mut solver = Solver::new(provider);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) =
+        solver.solve(Problem::new().requirements(requirements))
+    else {
+        panic!("expected the problem to be unsolvable");
+    };
+
+    let reasons = conflict.reasons(&solver).collect_vec();
+    assert!(
+        matches!(reasons[0], ConflictReason::Constrains { .. }),
+        "{reasons:?}"
+    );
+    assert_snapshot!(
+        reasons
+            .iter()
+            .map(|reason| reason.display(solver.provider()).to_string())
+            .join("\n"),
+        @r"
+    b=1 constrains c >=5, <6, which excludes c=4
+    b=1 constrains c >=5, <6, which excludes c=3
+    b=1 constrains c >=5, <6, which excludes c=2
+    b=1 constrains c >=5, <6, which excludes c=1
+    the problem requires a *
+    the problem requires b *
+    a=3 requires c *
+    a=2 requires c *
+    a=1 requires c *
+    a=4 requires c *
+    "
+    );
+}
+
+#[test]
+fn test_requirement_groups_are_all_or_nothing() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("b", 1, vec![]),
+        ("c", 1, vec![]),
+        ("d", 1, vec![]),
+        ("e", 1, vec!["c 1"]),
+    ]);
+    let requirements = provider.requirements(&["a"]);
+    // `c 2` does not exist, so `b` must not be selected either.
+    let partial = RequirementGroup::new(provider.requirements(&["b", "c 2"]));
+    let complete = RequirementGroup::new(provider.requirements(&["d", "e"]));
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .requirement_groups(vec![partial, complete]);
+    let solved = solver.solve(problem).unwrap();
+
+    assert_eq!(solver.excluded_requirement_groups(), &[0]);
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=1
+    c=1
+    d=1
+    e=1
+    ");
+}
+
+#[test]
+fn test_requirement_groups_prefer_earlier_groups() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["c 1"]),
+        ("b", 1, vec!["c 2"]),
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
+    ]);
+    // Both groups can be activated on their own, but not together.
+    let first = RequirementGroup::new(provider.requirements(&["a"]));
+    let second = RequirementGroup::new(provider.requirements(&["b"]));
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirement_groups(vec![first.clone(), second.clone()]))
+        .unwrap();
+    assert_eq!(solver.excluded_requirement_groups(), &[1]);
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=1
+    c=1
+    ");
+
+    let solved = solver
+        .solve(Problem::new().requirement_groups(vec![second, first]))
+        .unwrap();
+    assert_eq!(solver.excluded_requirement_groups(), &[1]);
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    b=1
+    c=2
+    ");
+}
+
+#[test]
+fn test_redundant_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("b", 1, vec!["a 1"]),
+    ]);
+    let requirements = provider.requirements(&["a", "b"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    a=1
+    b=1
+    ");
+
+    // `b` requires `a 1` anyway, while without `b` the newer `a 2` is selected.
+    assert_eq!(solver.redundant_requirements().unwrap(), vec![0]);
+}
+
+#[test]
+fn test_redundant_requirements_keep_requirement_groups() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("b", 1, vec!["a 1"]),
+        ("c", 1, vec![]),
+    ]);
+    let requirements = provider.requirements(&["c", "a"]);
+    let group = RequirementGroup::new(provider.requirements(&["b"]));
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .requirement_groups(vec![group]);
+    solver.solve(problem).unwrap();
+
+    // The indices refer to the requirements only, and `a` is still pulled in by
+    // the group.
+    assert_eq!(solver.redundant_requirements().unwrap(), vec![1]);
+}
+
+#[test]
+fn test_removal_impact() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["c"]),
+        ("b", 1, vec![]),
+        ("c", 1, vec![]),
+    ]);
+    let b = provider.solvable_id("b", 1);
+    let requirements = provider.requirements(&["a", "b"]);
+    let mut solver = Solver::new(provider);
+
+    let impact = solver.removal_impact(requirements, Vec::new(), 1).unwrap();
+    assert_eq!(impact.removed, vec![b]);
+    assert!(impact.changed.is_empty());
+}
+
+#[test]
+fn test_solve_frozen() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec![]),
+        ("a", 2, vec![]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("c", 1, vec!["b 2"]),
+    ]);
+    let previous = [provider.solvable_id("a", 1), provider.solvable_id("b", 1)];
+    let a = provider.package_name("a");
+    let b = provider.package_name("b");
+    let requirements = provider.requirements(&["a", "b"]);
+    let with_c = provider.requirements(&["a", "b", "c"]);
+    let mut solver = Solver::new(provider);
+
+    // Only `a` is updated, `b` stays at the previous version.
+    let solved = solver
+        .solve_frozen(requirements, Vec::new(), &previous, &[a])
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r###"
+    a=2
+    b=1
+    "###);
+
+    // `c` requires a newer `b`, which is frozen.
+    match solver.solve_frozen(with_c, Vec::new(), &previous, &[a]) {
+        Err(FrozenSolveError::Frozen { names, .. }) => assert_eq!(names, vec![b]),
+        _ => panic!("expected the frozen `b` to conflict"),
+    }
+}
+
+#[test]
+fn test_solution_iter() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b", "c"]),
+        ("b", 1, vec!["c"]),
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a"]);
+    let mut solver = Solver::new(provider);
+
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let streamed = solver.solution_iter().collect::<HashSet<_>>();
+    assert_eq!(streamed, solved.iter().copied().collect::<HashSet<_>>());
+    assert_eq!(solver.solution_iter().count(), solved.len());
+}
+
+#[test]
+fn test_invariant_packages() {
+    // `a` is always selected, but which version depends on whether `b` or
+    // `c` is selected along with it.
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["c"]),
+        ("a", 2, vec!["b"]),
+        ("b", 1, vec![]),
+        ("c", 1, vec![]),
+    ]);
+    let a = provider.package_name("a");
+    let requirements = provider.requirements(&["a"]);
+    let mut solver = Solver::new(provider);
+
+    let invariant = solver
+        .invariant_packages(requirements.clone(), Vec::new(), 10)
+        .unwrap();
+    assert_eq!(invariant, [a].into_iter().collect());
+
+    // A single sample only sees the first solution.
+    let sampled = solver
+        .invariant_packages(requirements, Vec::new(), 1)
+        .unwrap();
+    assert_eq!(sampled.len(), 2);
+}
+
+#[test]
+fn test_pivotal_decisions_only_report_real_choices() {
+    // `b` can be either version, every other package is forced.
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b", "c"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("c", 1, vec!["d 1"]),
+        ("d", 1, vec![]),
+        ("d", 2, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a"]);
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    assert_snapshot!(transaction_to_string(solver.provider(), &solved), @r"
+    a=1
+    b=2
+    c=1
+    d=1
+    ");
+
+    let pivotal = solver.pivotal_decisions().unwrap();
+    assert_eq!(pivotal, vec![solver.provider().solvable_id("b", 2)]);
+}
+
+#[cfg(feature = "deterministic")]
+#[test]
+fn test_deterministic_feature_gives_identical_output() {
+    let solve = || {
+        let provider = BundleBoxProvider::from_packages(&[
+            ("menu", 15, vec!["dropdown 2..3"]),
+            ("menu", 10, vec!["dropdown 1..2"]),
+            ("dropdown", 2, vec!["icons 2"]),
+            ("dropdown", 1, vec!["intl 3"]),
+            ("icons", 2, vec![]),
+            ("icons", 1, vec![]),
+            ("intl", 5, vec![]),
+            ("intl", 3, vec![]),
+        ]);
+        let requirements = provider.requirements(&["menu", "icons 1", "intl 5"]);
+        let mut solver = Solver::new(provider);
+        let problem = Problem::new().requirements(requirements);
+        let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+            panic!("expected unsat");
+        };
+        let mut graphviz = Vec::new();
+        conflict
+            .graph(&solver)
+            .graphviz(&mut graphviz, solver.provider(), true)
+            .unwrap();
+        (
+            String::from_utf8(graphviz).unwrap(),
+            conflict.display_user_friendly(&solver).to_string(),
+        )
+    };
+    assert_eq!(solve(), solve());
+}
+
+#[test]
+fn test_export_cnf_is_satisfied_by_solution() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["b 1..3"]),
+        ("a", 2, vec!["b 3"]),
+        ("b", 1, vec![]),
+        ("b", 2, vec![]),
+        ("b", 3, vec!["c 2..3"]),
+        ("c", 1, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a"]);
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements.clone());
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    a=1
+    b=2
+    ");
+
+    let cnf = solver.export_cnf(requirements, Vec::new()).unwrap();
+    assert!(cnf.to_string().starts_with(&format!(
+        "p cnf {} {}\n",
+        cnf.num_variables(),
+        cnf.clauses().len()
+    )));
+    for &solvable in &solved {
+        let variable = cnf.variable(solvable).unwrap();
+        assert_eq!(cnf.solvable(variable), Some(solvable));
+    }
+
+    // Variables that do not represent a solvable only help the encoding, so it
+    // suffices that some assignment of them satisfies every clause.
+    let helpers = (2..=cnf.num_variables() as u32)
+        .filter(|&variable| cnf.solvable(variable).is_none())
+        .collect::<Vec<_>>();
+    assert!(helpers.len() < 16);
+    let satisfied = (0..1u32 << helpers.len()).any(|mask| {
+        let value = |variable: u32| match cnf.solvable(variable) {
+            Some(solvable) => solved.contains(&solvable),
+            None if variable == 1 => true,
+            None => {
+                let idx = helpers.iter().position(|&h| h == variable).unwrap();
+                mask & (1 << idx) != 0
+            }
+        };
+        cnf.clauses().iter().all(|clause| {
+            clause
+                .iter()
+                .any(|&literal| value(literal.unsigned_abs()) == (literal > 0))
+        })
+    });
+    assert!(satisfied);
+}
+
+/// Returns whether `clause` follows from `clauses` by unit propagation, i.e.
+/// whether assigning the negation of every literal of `clause` leads to a
+/// conflict.
+fn is_reverse_unit_propagation(clauses: &[Vec<i32>], clause: &[i32]) -> bool {
+    let mut assignment = HashMap::default();
+    for &literal in clause {
+        if assignment.insert(literal.unsigned_abs(), literal < 0) == Some(literal > 0) {
+            return true;
         }
-        Err(UnsolvableOrCancelled::Cancelled(reason)) => *reason.downcast().unwrap(),
+    }
+    loop {
+        let mut propagated = false;
+        for clause in clauses {
+            let mut unassigned = Vec::new();
+            let mut satisfied = false;
+            for &literal in clause {
+                match assignment.get(&literal.unsigned_abs()) {
+                    Some(&value) if value == (literal > 0) => satisfied = true,
+                    Some(_) => {}
+                    None => unassigned.push(literal),
+                }
+            }
+            match unassigned.as_slice() {
+                _ if satisfied => {}
+                [] => return true,
+                &[literal] => {
+                    assignment.insert(literal.unsigned_abs(), literal > 0);
+                    propagated = true;
+                }
+                _ => {}
+            }
+        }
+        if !propagated {
+            return false;
+        }
+    }
+}
+
+#[test]
+fn test_drat_proof_of_unsat_problem() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("menu", 15, vec!["dropdown 2..3"]),
+        ("menu", 10, vec!["dropdown 1..2"]),
+        ("dropdown", 2, vec!["icons 2"]),
+        ("dropdown", 1, vec!["intl 3"]),
+        ("icons", 2, vec![]),
+        ("icons", 1, vec![]),
+        ("intl", 5, vec![]),
+        ("intl", 3, vec![]),
+    ]);
+    let requirements = provider.requirements(&["menu", "icons 1", "intl 5"]);
+    let mut solver = SolverBuilder::new().with_proof_logging().build(provider);
+    let problem = Problem::new().requirements(requirements);
+    let Err(UnsolvableOrCancelled::Unsolvable(conflict)) = solver.solve(problem) else {
+        panic!("expected unsat");
+    };
+
+    // Besides the final empty clause, the proof contains the learnt clauses.
+    let proof = conflict.drat_proof();
+    assert!(proof.len() > 1);
+    assert_eq!(proof.last(), Some(&ProofStep::Add(Vec::new())));
+
+    // Every step of the proof must follow from the clauses of the problem and
+    // the steps before it.
+    let mut clauses = solver.cnf().clauses().to_vec();
+    let num_variables = solver.cnf().num_variables() as u32;
+    for ProofStep::Add(clause) in proof {
+        assert!(clause
+            .iter()
+            .all(|literal| literal.unsigned_abs() <= num_variables));
+        assert!(is_reverse_unit_propagation(&clauses, clause));
+        clauses.push(clause.clone());
+    }
+}
+
+#[test]
+fn test_condition_on_union_requirement() {
+    let provider =
+        BundleBoxProvider::from_packages(&[("x", 1, vec![]), ("y", 1, vec![]), ("c", 1, vec![])]);
+    let x = Spec::from_str("x").unwrap();
+    let y = Spec::from_str("y").unwrap();
+    let x_or_y = Condition::Requirement(provider.intern_version_set_union([&x, &y]).into());
+    assert_eq!(provider.display_condition(x_or_y).to_string(), "x * | y *");
+
+    let c = provider.requirements(&["c"]).pop().unwrap().requirement;
+    let c_if_x_or_y = ConditionalRequirement::new(vec![x_or_y], c);
+    let mut requirements = provider.requirements(&["y"]);
+    requirements.push(c_if_x_or_y.clone());
+
+    let mut solver = Solver::new(provider);
+    let solved = solver
+        .solve(Problem::new().requirements(requirements))
+        .unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    c=1
+    y=1
+    ");
+
+    // Without any member of the union the requirement is not active.
+    let solved = solver
+        .solve(Problem::new().requirements(vec![c_if_x_or_y]))
+        .unwrap();
+    assert!(solved.is_empty());
+}
+
+#[test]
+fn test_validate_reports_unknown_extra() {
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec![])]);
+    let requirement = provider.requirements(&["a"]).pop().unwrap();
+    assert_eq!(requirement.validate(&provider), Ok(()));
+
+    let stale_extra = ConditionalRequirement::new(
+        vec![Condition::Extra(StringId(999))],
+        requirement.requirement,
+    );
+    let error = stale_extra.validate(&provider).unwrap_err();
+    assert_eq!(error, ConditionError::UnknownExtra(StringId(999)));
+    assert_eq!(error.to_string(), "the extra with id 999 does not exist");
+}
+
+#[test]
+fn test_closure_size() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 2, vec!["b", "c"]),
+        ("a", 1, vec!["b"]),
+        ("b", 1, vec!["d"]),
+        ("c", 1, vec!["d", "e"]),
+        // d and b depend on each other, so their closures share a cycle.
+        ("d", 1, vec!["b"]),
+        ("e", 1, vec![]),
+    ]);
+    let [a2, a1, b, c, e] = [("a", 2), ("a", 1), ("b", 1), ("c", 1), ("e", 1)]
+        .map(|(name, version)| provider.solvable_id(name, version));
+
+    let mut closure = DependencyClosure::new(&provider);
+    // a 2 reaches b, c, d and e, a 1 only reaches b and d.
+    assert_eq!(closure.size(a2), 4);
+    assert_eq!(closure.size(a1), 2);
+    // b reaches d, which reaches b again, but b is not counted itself.
+    assert_eq!(closure.size(b), 1);
+    assert_eq!(closure.size(c), 3);
+    assert_eq!(closure.size(e), 0);
+
+    let provider = BundleBoxProvider::from_packages(&[("a", 1, vec!["b"]), ("b", 1, vec![])]);
+    assert_eq!(closure_size(provider.solvable_id("a", 1), &provider), 1);
+}
+
+#[test]
+fn test_verify_solution() {
+    // The provider asserts that dependencies are only requested once, so every
+    // verification uses a fresh provider. The ids are the same in each of them
+    // because they are interned in the same order.
+    let create_provider = || {
+        let mut provider = BundleBoxProvider::from_packages(&[
+            ("a", 1, vec!["b"]),
+            ("b", 1, vec![]),
+            ("b", 2, vec![]),
+            ("c", 1, vec![]),
+        ]);
+        provider.add_package("c", 2.into(), &[], &["b 2"], &[]);
+        let solvables = [("a", 1), ("b", 1), ("b", 2), ("c", 2)]
+            .map(|(name, version)| provider.solvable_id(name, version));
+        let requirements = provider.requirements(&["a"]);
+        let constraints = provider.version_sets(&["b 1"]);
+        (provider, solvables, requirements, constraints)
+    };
+    let verify = |solution: &[SolvableId]| {
+        let (provider, _, requirements, constraints) = create_provider();
+        verify_solution(solution, &requirements, &constraints, &provider)
+            .map_err(|error| error.display(&provider).to_string())
+    };
+
+    let (provider, [a, b1, b2, c2], requirements, constraints) = create_provider();
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new()
+        .requirements(requirements)
+        .constraints(constraints);
+    let solved = solver.solve(problem).unwrap();
+    assert_eq!(verify(&solved), Ok(()));
+
+    assert_eq!(
+        verify(&[]),
+        Err("no selected solvable satisfies the requirement a *".to_string())
+    );
+    assert_eq!(
+        verify(&[a]),
+        Err("no selected solvable satisfies the dependency b * of a=1".to_string())
+    );
+    assert_eq!(
+        verify(&[a, b2]),
+        Err("b=2 does not match the constraint b >=1, <2".to_string())
+    );
+    assert_eq!(
+        verify(&[a, b1, c2]),
+        Err("b=1 does not match the constraint b >=2, <3 of c=2".to_string())
+    );
+    assert_eq!(
+        verify(&[a, b1, b2]),
+        Err("both b=1 and b=2 are selected for b".to_string())
+    );
+}
+
+#[test]
+fn test_solve_anytime_grows_conflict_budget() {
+    // The `p`s and `q` must agree on the version of `z`, but `q` only works with
+    // `z 1`. The solver prefers the newest `p`s and discovers this one conflict
+    // at a time.
+    let mut packages = Vec::new();
+    let z_versions = (1..=8).map(|v| format!("z {v}")).collect_vec();
+    for v in 1..=8 {
+        let z = vec![z_versions[v as usize - 1].as_str()];
+        packages.push(("p1", v, z.clone()));
+        packages.push(("p2", v, z));
+        packages.push(("z", v, vec![]));
+        packages.push(("q", v, vec!["z 1"]));
+    }
+    let provider = BundleBoxProvider::from_packages(&packages);
+    let requirements = provider.requirements(&["p1", "p2", "q"]);
+    let mut solver = Solver::new(provider);
+
+    let mut budgets = Vec::new();
+    let mut decision_levels = Vec::new();
+    let solved = solver
+        .solve_anytime(requirements.clone(), Vec::new(), |partial| {
+            budgets.push(partial.conflict_budget);
+            decision_levels.push(partial.decision_level);
+            assert!(partial.stats.num_conflicts >= partial.conflict_budget);
+            assert!(partial.trail_length >= partial.solvables.len());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(budgets, vec![1, 2, 4]);
+    // The first attempt is stopped while it is still deciding beyond the root
+    // level, the later ones right after backjumping to it.
+    assert_eq!(decision_levels, vec![3, 1, 1]);
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    p1=1
+    p2=1
+    q=8
+    z=1
+    ");
+
+    // Stopping early returns the state of the last attempt.
+    let Err(UnsolvableOrCancelled::Cancelled(reason)) =
+        solver.solve_anytime(requirements, Vec::new(), |_| ControlFlow::Break(()))
+    else {
+        panic!("expected the solve to be stopped");
+    };
+    let partial = reason.downcast::<PartialResult>().unwrap();
+    assert_eq!(partial.conflict_budget, 1);
+}
+
+#[test]
+fn test_marker_conditions() {
+    let mut provider =
+        BundleBoxProvider::from_packages(&[("a", 1, vec![]), ("b", 1, vec![]), ("c", 1, vec![])]);
+    let linux = provider.pool.intern_string("sys_platform == 'linux'");
+    let windows = provider.pool.intern_string("sys_platform == 'win32'");
+    provider.markers.insert(linux);
+
+    let mut requirements = provider.requirements(&["a"]);
+    for (marker, spec) in [(linux, "b"), (windows, "c")] {
+        let requirement = provider.requirements(&[spec]).remove(0).requirement;
+        requirements.push(ConditionalRequirement::new(
+            vec![Condition::Marker(marker)],
+            requirement,
+        ));
+    }
+
+    let mut solver = Solver::new(provider);
+    let problem = Problem::new().requirements(requirements);
+    let solved = solver.solve(problem).unwrap();
+    let result = transaction_to_string(solver.provider(), &solved);
+    assert_snapshot!(result, @r"
+    a=1
+    b=1
+    ");
+}
+
+#[test]
+fn test_marker_conditional_constraint() {
+    let solve = |on_windows: bool| {
+        let mut provider = BundleBoxProvider::from_packages(&[
+            ("a", 1, vec!["b"]),
+            ("b", 1, vec![]),
+            ("b", 2, vec![]),
+        ]);
+        provider.add_marker_constraint("a", 1, "sys_platform == 'win32'", "b 1");
+        if on_windows {
+            let windows = provider.pool.intern_string("sys_platform == 'win32'");
+            provider.markers.insert(windows);
+        }
+        let requirements = provider.requirements(&["a"]);
+        let mut solver = Solver::new(provider);
+        let solved = solver
+            .solve(Problem::new().requirements(requirements))
+            .unwrap();
+        transaction_to_string(solver.provider(), &solved)
+    };
+
+    // The constraint is inert unless the marker holds.
+    assert_snapshot!(solve(false), @r"
+    a=1
+    b=2
+    ");
+    assert_snapshot!(solve(true), @r"
+    a=1
+    b=1
+    ");
+}
+
+#[test]
+fn test_reduce_problem_to_conflicting_requirements() {
+    let provider = BundleBoxProvider::from_packages(&[
+        ("a", 1, vec!["d"]),
+        ("a", 2, vec!["d", "e"]),
+        ("b", 1, vec!["c 1", "d"]),
+        ("c", 1, vec![]),
+        ("c", 2, vec![]),
+        ("d", 1, vec![]),
+        ("e", 1, vec![]),
+    ]);
+    let requirements = provider.requirements(&["a", "b", "c 2", "e"]);
+    let problem = ProblemSnapshot::from_provider(provider, requirements, Vec::new()).unwrap();
+
+    // The problem is unsolvable, even though every requirement on its own can
+    // be solved.
+    let conflicting = |problem: &ProblemSnapshot| {
+        problem.solve().is_err()
+            && problem.requirements.iter().all(|requirement| {
+                let problem = ProblemSnapshot {
+                    requirements: vec![requirement.clone()],
+                    ..problem.clone()
+                };
+                problem.solve().is_ok()
+            })
+    };
+    let reduced = reduce_problem(problem, conflicting);
+
+    let provider = reduced.dependencies.provider();
+    let requirements = reduced
+        .requirements
+        .iter()
+        .map(|requirement| requirement.requirement.display(&provider).to_string())
+        .join(", ");
+    let candidates = reduced
+        .dependencies
+        .solvables
+        .iter()
+        .map(|(_, solvable)| {
+            let Dependencies::Known(deps) = &solvable.dependencies else {
+                unreachable!()
+            };
+            let requirements = deps
+                .requirements
+                .iter()
+                .map(|requirement| requirement.requirement.display(&provider).to_string())
+                .join(", ");
+            format!("{} -> [{}]", solvable.display, requirements)
+        })
+        .join("\n");
+    assert_snapshot!(format!("{requirements}\n{candidates}"), @r"
+    b *, c >=2, <3
+    b=1 -> [c >=1, <2]
+    c=1 -> []
+    c=2 -> []
+    ");
+}
+
+#[test]
+fn test_generate_problem() {
+    let params = GenParams {
+        names: 30,
+        versions_per_name: 4,
+        fan_out: 2,
+        conflict_density: 0.0,
+        requirements: 5,
+    };
+    let display_requirements = |pool: &GeneratedPool, requirements: &[ConditionalRequirement]| {
+        requirements
+            .iter()
+            .map(|requirement| requirement.requirement.display(pool).to_string())
+            .join(", ")
+    };
+
+    // The same seed yields the same problem.
+    let (pool, requirements, constraints) = generate_problem(42, params);
+    let (same_pool, same_requirements, same_constraints) = generate_problem(42, params);
+    assert_eq!(pool.to_string(), same_pool.to_string());
+    assert_eq!(
+        display_requirements(&pool, &requirements),
+        display_requirements(&same_pool, &same_requirements)
+    );
+    assert_eq!(constraints.len(), same_constraints.len());
+
+    let (other_pool, _, _) = generate_problem(43, params);
+    assert_ne!(pool.to_string(), other_pool.to_string());
+
+    // Without conflicts every generated problem is solvable.
+    for seed in 0..10 {
+        let (pool, requirements, constraints) = generate_problem(seed, params);
+        assert!(constraints.is_empty());
+        let mut solver = Solver::new(pool);
+        let problem = Problem::new()
+            .requirements(requirements)
+            .constraints(constraints);
+        assert!(solver.solve(problem).is_ok(), "seed {seed} is not solvable");
     }
 }
@@ -15,7 +15,9 @@ use rand::{
     rngs::StdRng,
     Rng, SeedableRng,
 };
-use resolvo::{snapshot::DependencySnapshot, Problem, Requirement, Solver, UnsolvableOrCancelled};
+use resolvo::{
+    snapshot::DependencySnapshot, Problem, Requirement, SolverBuilder, UnsolvableOrCancelled,
+};
 
 #[derive(Parser)]
 #[clap(version = "0.1.0", author = "Bas Zalmstra <zalmstra.bas@gmail.com>")]
@@ -34,6 +36,10 @@ struct Opts {
     /// The random seed to use for generating the requirements.
     #[clap(long, default_value = "0")]
     seed: u64,
+
+    /// Take the first solution that is found instead of the preferred one.
+    #[clap(long)]
+    first_fit: bool,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -130,7 +136,9 @@ fn main() {
 
         let problem =
             Problem::default().requirements(requirements.into_iter().map(Into::into).collect());
-        let mut solver = Solver::new(provider);
+        let mut solver = SolverBuilder::new()
+            .with_first_fit(opts.first_fit)
+            .build(provider);
         let mut records = None;
         let mut error = None;
         let result = solver.solve(problem);